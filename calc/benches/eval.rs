@@ -0,0 +1,67 @@
+use std::{hint::black_box, time::Instant};
+
+use ccalc::Context;
+
+const ITERATIONS: usize = 100_000;
+
+const EXPRESSIONS: &[&str] = &[
+	"2 * x + 1",
+	"a * x^2 + b * x + c",
+	"sqrt(x^2 + y^2) * sin(x) + cos(y) / (1 + exp(-x))",
+	"x > 0 && y > 0 ? hypot(x, y) : max(abs(x), abs(y)) * (2 * pi)",
+];
+
+// the average nanoseconds of a calculation with the inputs changing every time
+fn measure(mut calculate: impl FnMut(&[f64])) -> f64 {
+	let mut inputs = [0.0; 4];
+	let mut next = |index: usize| {
+		inputs.iter_mut().enumerate().for_each(|(slot, input)| *input = (index + slot) as f64 * 0.001 - 0.5);
+		inputs
+	};
+
+	// --- Warm-up phase ---
+	for index in 0..1000 {
+		calculate(&next(index));
+	}
+
+	// --- Measurement phase ---
+	let start = Instant::now();
+	for index in 0..ITERATIONS {
+		calculate(black_box(&next(index)));
+	}
+	let duration = start.elapsed();
+
+	// --- Reporting phase ---
+	duration.as_nanos() as f64 / ITERATIONS as f64
+}
+
+fn main() {
+	for expression in EXPRESSIONS {
+		let mut context = Context::new();
+		let compiled = ccalc::compile(expression, &context).expect("BUG: the benchmark expression is invalid");
+		let names = compiled.inputs().to_vec();
+
+		// PARSE PATH
+		let parse = measure(|inputs| {
+			names.iter().zip(inputs).for_each(|(name, input)| context.set(name, *input));
+			black_box(ccalc::eval(expression, &context).expect("BUG: the benchmark failed"));
+		});
+
+		// TREE PATH
+		let tree = measure(|inputs| {
+			names.iter().zip(inputs).for_each(|(name, input)| context.set(name, *input));
+			black_box(compiled.eval(&context).expect("BUG: the benchmark failed"));
+		});
+
+		// BYTECODE PATH
+		let bytecode = measure(|inputs| {
+			black_box(compiled.eval_slice(&inputs[..names.len()]).expect("BUG: the benchmark failed"));
+		});
+
+		println!("\"{expression}\"");
+		println!("  parsed every time      {parse:>10.2} ns");
+		println!("  tree of the compiled   {tree:>10.2} ns");
+		println!("  bytecode of the slice  {bytecode:>10.2} ns ({:.1}x the tree)", tree / bytecode);
+		assert!(compiled.is_compiled(), "`{expression}` should compile to bytecode");
+	}
+}
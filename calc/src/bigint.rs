@@ -0,0 +1,619 @@
+use std::{
+	cmp::Ordering,
+	fmt,
+	ops::{Add, Mul, Neg, Sub},
+	str::FromStr,
+};
+
+/// An integer of any size stored as its sign and magnitude
+///
+/// The magnitude is kept in little endian base 2^32 limbs without leading zero limbs so zero is an empty vector.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BigInt {
+	negative: bool,
+	magnitude: Vec<u32>,
+}
+
+fn trim(mut magnitude: Vec<u32>) -> Vec<u32> {
+	while magnitude.last() == Some(&0) {
+		magnitude.pop();
+	}
+	magnitude
+}
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+	a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+	let mut sum = Vec::with_capacity(long.len() + 1);
+	let mut carry = 0;
+
+	for (index, limb) in long.iter().enumerate() {
+		let total = *limb as u64 + *short.get(index).unwrap_or(&0) as u64 + carry;
+		sum.push(total as u32);
+		carry = total >> 32;
+	}
+	sum.push(carry as u32);
+	trim(sum)
+}
+
+// `a` has to be at least as large as `b`
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+	let mut difference = Vec::with_capacity(a.len());
+	let mut borrow = 0;
+
+	for (index, limb) in a.iter().enumerate() {
+		let total = *limb as i64 - *b.get(index).unwrap_or(&0) as i64 - borrow;
+		difference.push(total as u32);
+		borrow = i64::from(total < 0);
+	}
+	trim(difference)
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+	if a.is_empty() || b.is_empty() {
+		return Vec::new();
+	}
+
+	let mut product = vec![0u32; a.len() + b.len()];
+	for (i, x) in a.iter().enumerate() {
+		let mut carry = 0;
+		for (j, y) in b.iter().enumerate() {
+			let total = *x as u64 * *y as u64 + product[i + j] as u64 + carry;
+			product[i + j] = total as u32;
+			carry = total >> 32;
+		}
+		product[i + b.len()] = carry as u32;
+	}
+	trim(product)
+}
+
+fn div_rem_small(a: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+	let mut quotient = vec![0u32; a.len()];
+	let mut remainder = 0u64;
+
+	for (index, limb) in a.iter().enumerate().rev() {
+		let current = (remainder << 32) | *limb as u64;
+		quotient[index] = (current / divisor as u64) as u32;
+		remainder = current % divisor as u64;
+	}
+	(trim(quotient), remainder as u32)
+}
+
+// shifts by less than a limb and always keeps the limb that was shifted out at the top
+fn shl_bits(a: &[u32], shift: u32) -> Vec<u32> {
+	let mut shifted = Vec::with_capacity(a.len() + 1);
+	let mut carry = 0;
+	for limb in a {
+		shifted.push((limb << shift) | carry);
+		carry = if shift == 0 { 0 } else { limb >> (32 - shift) };
+	}
+	shifted.push(carry);
+	shifted
+}
+
+fn shr_bits(a: &[u32], shift: u32) -> Vec<u32> {
+	let mut shifted = vec![0u32; a.len()];
+	for index in 0..a.len() {
+		let high = if shift == 0 {
+			0
+		} else {
+			a.get(index + 1).map_or(0, |limb| limb << (32 - shift))
+		};
+		shifted[index] = (a[index] >> shift) | high;
+	}
+	trim(shifted)
+}
+
+// long division from Knuth's The Art of Computer Programming, volume 2, algorithm D
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+	assert!(!b.is_empty(), "BUG: division by zero should be caught by the caller");
+
+	if compare_magnitude(a, b) == Ordering::Less {
+		return (Vec::new(), a.to_vec());
+	}
+	if let [divisor] = b {
+		let (quotient, remainder) = div_rem_small(a, *divisor);
+		return (quotient, trim(vec![remainder]));
+	}
+
+	// the estimate of every quotient limb is at most two off once the top bit of the divisor is set
+	let shift = b.last().expect("BUG: empty divisor").leading_zeros();
+	let divisor = &shl_bits(b, shift)[..b.len()];
+	let mut dividend = shl_bits(a, shift);
+	let n = divisor.len();
+	let mut quotient = vec![0u32; dividend.len() - n];
+
+	for j in (0..quotient.len()).rev() {
+		let top = ((dividend[j + n] as u64) << 32) | dividend[j + n - 1] as u64;
+		let mut estimate = top / divisor[n - 1] as u64;
+		let mut remainder = top % divisor[n - 1] as u64;
+
+		while estimate >> 32 != 0 || estimate * divisor[n - 2] as u64 > ((remainder << 32) | dividend[j + n - 2] as u64) {
+			estimate -= 1;
+			remainder += divisor[n - 1] as u64;
+			if remainder >> 32 != 0 {
+				break;
+			}
+		}
+
+		let mut borrow = 0;
+		let mut carry = 0;
+		for (index, limb) in divisor.iter().enumerate() {
+			let product = estimate * *limb as u64 + carry;
+			carry = product >> 32;
+			let total = dividend[index + j] as i64 - borrow - (product & 0xFFFF_FFFF) as i64;
+			dividend[index + j] = total as u32;
+			borrow = i64::from(total < 0);
+		}
+		let total = dividend[j + n] as i64 - borrow - carry as i64;
+		dividend[j + n] = total as u32;
+
+		// the estimate was one too large so we add the divisor back once
+		if total < 0 {
+			estimate -= 1;
+			let mut carry = 0;
+			for (index, limb) in divisor.iter().enumerate() {
+				let total = dividend[index + j] as u64 + *limb as u64 + carry;
+				dividend[index + j] = total as u32;
+				carry = total >> 32;
+			}
+			dividend[j + n] = dividend[j + n].wrapping_add(carry as u32);
+		}
+		quotient[j] = estimate as u32;
+	}
+
+	(trim(quotient), shr_bits(&dividend[..n], shift))
+}
+
+impl BigInt {
+	fn from_parts(negative: bool, magnitude: Vec<u32>) -> Self {
+		let magnitude = trim(magnitude);
+		Self {
+			negative: negative && !magnitude.is_empty(),
+			magnitude,
+		}
+	}
+
+	pub fn zero() -> Self {
+		Self::default()
+	}
+
+	pub fn one() -> Self {
+		Self::from(1u64)
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.magnitude.is_empty()
+	}
+
+	pub fn is_negative(&self) -> bool {
+		self.negative
+	}
+
+	pub fn is_even(&self) -> bool {
+		self.magnitude.first().is_none_or(|limb| limb % 2 == 0)
+	}
+
+	pub fn abs(&self) -> Self {
+		Self::from_parts(false, self.magnitude.clone())
+	}
+
+	/// The number of bits needed for the magnitude
+	pub fn bits(&self) -> u64 {
+		match self.magnitude.last() {
+			Some(top) => self.magnitude.len() as u64 * 32 - top.leading_zeros() as u64,
+			None => 0,
+		}
+	}
+
+	/// The number of decimal digits without the sign, zero has one digit
+	pub fn digits(&self) -> usize {
+		self.abs().to_string().len()
+	}
+
+	pub fn shl(&self, bits: u64) -> Self {
+		let mut magnitude = vec![0u32; (bits / 32) as usize];
+		magnitude.extend(shl_bits(&self.magnitude, (bits % 32) as u32));
+		Self::from_parts(self.negative, magnitude)
+	}
+
+	/// Shifts the magnitude right so it rounds towards zero
+	pub fn shr(&self, bits: u64) -> Self {
+		let limbs = (bits / 32) as usize;
+		if limbs >= self.magnitude.len() {
+			return Self::zero();
+		}
+		Self::from_parts(self.negative, shr_bits(&self.magnitude[limbs..], (bits % 32) as u32))
+	}
+
+	fn trailing_zeros(&self) -> u64 {
+		let limbs = self.magnitude.iter().take_while(|limb| **limb == 0).count();
+		limbs as u64 * 32 + self.magnitude.get(limbs).map_or(0, |limb| limb.trailing_zeros() as u64)
+	}
+
+	/// Truncated division so the remainder takes the sign of the dividend, `None` when dividing by zero
+	pub fn div_rem(&self, divisor: &BigInt) -> Option<(BigInt, BigInt)> {
+		if divisor.is_zero() {
+			return None;
+		}
+		let (quotient, remainder) = div_rem_magnitude(&self.magnitude, &divisor.magnitude);
+		Some((Self::from_parts(self.negative != divisor.negative, quotient), Self::from_parts(self.negative, remainder)))
+	}
+
+	/// Division rounding towards negative infinity, `None` when dividing by zero
+	pub fn div_floor(&self, divisor: &BigInt) -> Option<BigInt> {
+		let (quotient, remainder) = self.div_rem(divisor)?;
+		if !remainder.is_zero() && remainder.negative != divisor.negative {
+			Some(&quotient - &BigInt::one())
+		} else {
+			Some(quotient)
+		}
+	}
+
+	/// The greatest common divisor which is never negative
+	pub fn gcd(&self, other: &BigInt) -> BigInt {
+		// binary gcd only needs shifts and subtractions which are cheap compared to divisions
+		let (mut a, mut b) = (self.abs(), other.abs());
+		if a.is_zero() {
+			return b;
+		}
+		if b.is_zero() {
+			return a;
+		}
+
+		let shift = a.trailing_zeros().min(b.trailing_zeros());
+		a = a.shr(a.trailing_zeros());
+		loop {
+			b = b.shr(b.trailing_zeros());
+			if compare_magnitude(&a.magnitude, &b.magnitude) == Ordering::Greater {
+				std::mem::swap(&mut a, &mut b);
+			}
+			b = &b - &a;
+			if b.is_zero() {
+				return a.shl(shift);
+			}
+		}
+	}
+
+	pub fn pow(&self, mut exponent: u64) -> BigInt {
+		let (mut base, mut result) = (self.clone(), BigInt::one());
+		while exponent > 0 {
+			if exponent % 2 == 1 {
+				result = &result * &base;
+			}
+			exponent /= 2;
+			if exponent > 0 {
+				base = &base * &base;
+			}
+		}
+		result
+	}
+
+	/// The largest integer whose `n`th power is at most this non-negative number
+	pub fn root(&self, n: u32) -> BigInt {
+		if self.is_zero() || n == 1 {
+			return self.clone();
+		}
+
+		// Newton's method falls towards the root from any start above it
+		let degree = BigInt::from(u64::from(n));
+		let mut root = BigInt::one().shl(self.bits().div_ceil(u64::from(n)));
+		loop {
+			let power = root.pow(u64::from(n - 1));
+			let quotient = self.div_rem(&power).expect("BUG: a power of a positive root is zero").0;
+			let sum = &(&root * &BigInt::from(u64::from(n - 1))) + &quotient;
+			let next = sum.div_rem(&degree).expect("BUG: root of degree zero").0;
+			if next >= root {
+				return root;
+			}
+			root = next;
+		}
+	}
+
+	/// The closest `f64` which is infinite when the number is too large
+	pub fn to_f64(&self) -> f64 {
+		let magnitude = self.magnitude.iter().rev().fold(0.0, |total, limb| total * 4_294_967_296.0 + *limb as f64);
+		if self.negative { -magnitude } else { magnitude }
+	}
+
+	/// `None` when the number doesn't fit
+	pub fn to_u64(&self) -> Option<u64> {
+		match (self.negative, self.magnitude.as_slice()) {
+			(true, _) => None,
+			(false, []) => Some(0),
+			(false, [low]) => Some(*low as u64),
+			(false, [low, high]) => Some(((*high as u64) << 32) | *low as u64),
+			_ => None,
+		}
+	}
+
+	/// `None` when the number doesn't fit
+	pub fn to_i128(&self) -> Option<i128> {
+		if self.magnitude.len() > 4 {
+			return None;
+		}
+		let magnitude = self.magnitude.iter().rev().fold(0u128, |total, limb| (total << 32) | *limb as u128);
+		if self.negative {
+			0i128.checked_sub_unsigned(magnitude)
+		} else {
+			i128::try_from(magnitude).ok()
+		}
+	}
+}
+
+impl From<u64> for BigInt {
+	fn from(number: u64) -> Self {
+		Self::from_parts(false, vec![number as u32, (number >> 32) as u32])
+	}
+}
+
+impl From<i128> for BigInt {
+	fn from(number: i128) -> Self {
+		let magnitude = number.unsigned_abs();
+		Self::from_parts(number < 0, (0..4).map(|limb| (magnitude >> (32 * limb)) as u32).collect())
+	}
+}
+
+impl From<i64> for BigInt {
+	fn from(number: i64) -> Self {
+		let magnitude = BigInt::from(number.unsigned_abs()).magnitude;
+		Self::from_parts(number < 0, magnitude)
+	}
+}
+
+impl FromStr for BigInt {
+	type Err = String;
+
+	/// Parses decimal digits with an optional leading `-`
+	fn from_str(text: &str) -> Result<Self, Self::Err> {
+		let (negative, digits) = match text.strip_prefix('-') {
+			Some(digits) => (true, digits),
+			None => (false, text),
+		};
+		if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+			return Err(text.to_string());
+		}
+
+		let mut magnitude: Vec<u32> = Vec::new();
+		// nine digits at a time still fit into a limb
+		for chunk in digits.as_bytes().chunks(9) {
+			let value = chunk.iter().fold(0u64, |total, digit| total * 10 + (digit - b'0') as u64);
+			let mut carry = value;
+			let factor = 10u64.pow(chunk.len() as u32);
+			for limb in magnitude.iter_mut() {
+				let total = *limb as u64 * factor + carry;
+				*limb = total as u32;
+				carry = total >> 32;
+			}
+			if carry > 0 {
+				magnitude.push(carry as u32);
+			}
+		}
+		Ok(Self::from_parts(negative, magnitude))
+	}
+}
+
+impl fmt::Display for BigInt {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_zero() {
+			return write!(f, "0");
+		}
+
+		let mut chunks = Vec::new();
+		let mut magnitude = self.magnitude.clone();
+		while !magnitude.is_empty() {
+			let (quotient, remainder) = div_rem_small(&magnitude, 1_000_000_000);
+			chunks.push(remainder);
+			magnitude = quotient;
+		}
+
+		if self.negative {
+			write!(f, "-")?;
+		}
+		let mut chunks = chunks.iter().rev();
+		write!(f, "{}", chunks.next().expect("BUG: non zero number without digits"))?;
+		chunks.try_for_each(|chunk| write!(f, "{chunk:09}"))
+	}
+}
+
+impl PartialOrd for BigInt {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Ord for BigInt {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self.negative, other.negative) {
+			(false, true) => Ordering::Greater,
+			(true, false) => Ordering::Less,
+			(false, false) => compare_magnitude(&self.magnitude, &other.magnitude),
+			(true, true) => compare_magnitude(&other.magnitude, &self.magnitude),
+		}
+	}
+}
+
+impl Neg for &BigInt {
+	type Output = BigInt;
+
+	fn neg(self) -> BigInt {
+		BigInt::from_parts(!self.negative, self.magnitude.clone())
+	}
+}
+
+impl Add for &BigInt {
+	type Output = BigInt;
+
+	fn add(self, other: &BigInt) -> BigInt {
+		if self.negative == other.negative {
+			return BigInt::from_parts(self.negative, add_magnitude(&self.magnitude, &other.magnitude));
+		}
+		match compare_magnitude(&self.magnitude, &other.magnitude) {
+			Ordering::Less => BigInt::from_parts(other.negative, sub_magnitude(&other.magnitude, &self.magnitude)),
+			_ => BigInt::from_parts(self.negative, sub_magnitude(&self.magnitude, &other.magnitude)),
+		}
+	}
+}
+
+impl Sub for &BigInt {
+	type Output = BigInt;
+
+	fn sub(self, other: &BigInt) -> BigInt {
+		self + &-other
+	}
+}
+
+impl Mul for &BigInt {
+	type Output = BigInt;
+
+	fn mul(self, other: &BigInt) -> BigInt {
+		BigInt::from_parts(self.negative != other.negative, mul_magnitude(&self.magnitude, &other.magnitude))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn big(text: &str) -> BigInt {
+		text.parse().unwrap()
+	}
+
+	#[test]
+	fn parse_display_test() {
+		for text in [
+			"0",
+			"7",
+			"-42",
+			"4294967296",
+			"-18446744073709551616",
+			"1000000000000000000000000000001",
+		] {
+			assert_eq!(big(text).to_string(), text);
+		}
+		assert_eq!(big("-0"), BigInt::zero());
+		assert_eq!(big("007").to_string(), "7");
+		assert!("1.5".parse::<BigInt>().is_err());
+		assert!("".parse::<BigInt>().is_err());
+		assert_eq!(BigInt::from(-5i64).to_string(), "-5");
+		assert_eq!(BigInt::from(u64::MAX).to_string(), "18446744073709551615");
+	}
+
+	#[test]
+	fn arithmetic_test() {
+		let (a, b) = (big("123456789012345678901234567890"), big("-987654321098765432109876543210"));
+
+		assert_eq!((&a + &b).to_string(), "-864197532086419753208641975320");
+		assert_eq!((&a - &b).to_string(), "1111111110111111111011111111100");
+		assert_eq!((&b - &b), BigInt::zero());
+		assert_eq!((&a * &b).to_string(), "-121932631137021795226185032733622923332237463801111263526900");
+		assert_eq!(big("2").pow(100).to_string(), "1267650600228229401496703205376");
+		assert_eq!(big("-3").pow(3).to_string(), "-27");
+		assert!(big("-1") < BigInt::zero() && big("-2") < big("-1") && big("10") > big("9"));
+	}
+
+	#[test]
+	fn div_rem_test() {
+		let check = |a: &str, b: &str, quotient: &str, remainder: &str| {
+			let (q, r) = big(a).div_rem(&big(b)).unwrap();
+			assert_eq!((q.to_string(), r.to_string()), (quotient.to_string(), remainder.to_string()), "{a} / {b}");
+		};
+
+		check("7", "2", "3", "1");
+		check("-7", "2", "-3", "-1");
+		check("7", "-2", "-3", "1");
+		check("1", "12345678901234567890", "0", "1");
+		check(
+			"121932631137021795226185032733622923332237463801111263526900",
+			"-987654321098765432109876543210",
+			"-123456789012345678901234567890",
+			"0",
+		);
+		check(
+			"340282366920938463463374607431768211455",
+			"18446744073709551616",
+			"18446744073709551615",
+			"18446744073709551615",
+		);
+		// these need the estimate of a quotient limb to be corrected
+		check("79228162514264337589248983040", "4294967297", "18446744069414584320", "0");
+		check(
+			"6277101735386680763835789423207666416102355444464034512895",
+			"340282366920938463463374607431768211456",
+			"18446744073709551615",
+			"340282366920938463463374607431768211455",
+		);
+		assert_eq!(big("1").div_rem(&BigInt::zero()), None);
+		assert_eq!(big("-7").div_floor(&big("2")), Some(big("-4")));
+		assert_eq!(big("7").div_floor(&big("2")), Some(big("3")));
+	}
+
+	#[test]
+	fn div_rem_random_test() {
+		// checks `a = q * b + r` with `|r| < |b|` for numbers of all kinds of lengths
+		let mut state = 0x2545F4914F6CDD1Du64;
+		let mut next = || {
+			state ^= state << 13;
+			state ^= state >> 7;
+			state ^= state << 17;
+			state
+		};
+
+		for _ in 0..200 {
+			let (a_limbs, b_limbs) = (next() % 8, next() % 5 + 1);
+			let mut number = |limbs: u64| {
+				let mut total = BigInt::zero();
+				for _ in 0..limbs {
+					total = &total.shl(32) + &BigInt::from(next() >> 32);
+				}
+				if next() % 2 == 0 { -&total } else { total }
+			};
+			let (a, b) = (number(a_limbs), number(b_limbs));
+			if b.is_zero() {
+				continue;
+			}
+
+			let (quotient, remainder) = a.div_rem(&b).unwrap();
+			assert_eq!(&(&quotient * &b) + &remainder, a);
+			assert!(remainder.abs() < b.abs());
+			assert!(remainder.is_zero() || remainder.is_negative() == a.is_negative());
+		}
+	}
+
+	#[test]
+	fn gcd_shift_test() {
+		assert_eq!(big("48").gcd(&big("-18")), big("6"));
+		assert_eq!(big("0").gcd(&big("5")), big("5"));
+		assert_eq!(big("17").gcd(&big("5")), big("1"));
+		assert_eq!(big("1267650600228229401496703205376").gcd(&big("1000000000000")), big("4096"));
+		assert_eq!(big("3").shl(70).to_string(), "3541774862152233910272");
+		assert_eq!(big("3541774862152233910272").shr(70), big("3"));
+		assert_eq!(big("-5").shr(1), big("-2"));
+		assert_eq!(big("5").shr(100), BigInt::zero());
+		assert_eq!(big("4294967296").bits(), 33);
+		assert_eq!(big("-12345").digits(), 5);
+		assert!(big("10").is_even() && !big("-3").is_even() && BigInt::zero().is_even());
+		assert_eq!(big("18446744073709551615").to_u64(), Some(u64::MAX));
+		assert_eq!(big("18446744073709551616").to_u64(), None);
+		assert_eq!(big("-170141183460469231731687303715884105728").to_i128(), Some(i128::MIN));
+		assert_eq!(big("170141183460469231731687303715884105728").to_i128(), None);
+		assert_eq!(BigInt::from(-12345678901234567890123i128), big("-12345678901234567890123"));
+		assert_eq!(big("-4294967296").to_f64(), -4294967296.0);
+	}
+
+	#[test]
+	fn root_test() {
+		assert_eq!(big("144").root(2), big("12"));
+		assert_eq!(big("143").root(2), big("11"));
+		assert_eq!(
+			big("1000000000000000000000000000000000000000000000000000000000000").root(3),
+			big("100000000000000000000")
+		);
+		assert_eq!(big("2").shl(200).root(2), big("1792728671193156477399422023278"));
+		assert_eq!(big("1").root(5), big("1"));
+		assert_eq!(BigInt::zero().root(2), BigInt::zero());
+	}
+}
@@ -0,0 +1,429 @@
+use std::cmp::Ordering;
+
+use crate::{
+	env::Environment,
+	expr::{Ast, Expr},
+	functions,
+	numeric::{FloatMode, Numeric},
+	postfix::{Postfix, PostfixError},
+	shunting::{Operator, Span, Spanned},
+	value::Value,
+};
+
+/// What a part of a program leaves on the stack, booleans are kept as `0` and `1`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+	Number,
+	Bool,
+}
+
+#[derive(Debug, Clone)]
+enum Instruction {
+	Constant(f64),
+	/// Pushes the input in this slot
+	Input(usize),
+	Unary(Operator),
+	Binary(Operator),
+	/// Calls a built-in function with this many values from the top of the stack
+	Call(functions::Implementation, usize),
+	/// Pops a boolean and jumps to the instruction when it is false
+	JumpUnless(usize),
+	Jump(usize),
+}
+
+/// An expression of numbers and booleans compiled for a stack machine that reads its variables from a slice
+///
+/// Variables are resolved to slots and everything that doesn't depend on them is calculated once when
+/// compiling. The results and errors are the same as those of `Postfix::calc` in `FloatMode`.
+#[derive(Debug, Clone)]
+pub struct Program {
+	code: Vec<Instruction>,
+	/// Where each instruction came from and where its right operand did for errors about it
+	spans: Vec<(Span, Span)>,
+	kind: Kind,
+	/// The most values on the stack at once
+	stack_size: usize,
+}
+
+/// The variables an expression reads in the order they first appear, without the built-in constants and
+/// the units converted to
+pub fn inputs(ast: &Ast) -> Vec<String> {
+	fn visit(ast: &Ast, inputs: &mut Vec<String>) {
+		match &ast.node {
+			Expr::Variable(name) if functions::constant(name).is_none() && !inputs.contains(name) => {
+				inputs.push(name.clone());
+			},
+			Expr::Number(_)
+			| Expr::Integer(..)
+			| Expr::Decimal(_)
+			| Expr::Quantity(..)
+			| Expr::Imaginary(_)
+			| Expr::Bool(_)
+			| Expr::Variable(_) => {},
+			Expr::Unary(_, operand) => visit(operand, inputs),
+			// the unit to convert to isn't calculated
+			Expr::Binary(Operator::Convert, quantity, _) => visit(quantity, inputs),
+			Expr::Binary(_, left, right) => {
+				visit(left, inputs);
+				visit(right, inputs);
+			},
+			// the variable of `sum` and `integrate` is bound by the call rather than read from outside
+			Expr::Call(name, args) if functions::is_higher_order(name) => {
+				let (body, variable) = match args.as_slice() {
+					[body, variable, bounds @ ..] => {
+						bounds.iter().for_each(|bound| visit(bound, inputs));
+						(body, &variable.node)
+					},
+					_ => return args.iter().for_each(|arg| visit(arg, inputs)),
+				};
+				let mut body_inputs = Vec::new();
+				visit(body, &mut body_inputs);
+				for name in body_inputs {
+					if !matches!(variable, Expr::Variable(bound) if *bound == name) && !inputs.contains(&name) {
+						inputs.push(name);
+					}
+				}
+			},
+			Expr::Call(_, args) | Expr::Vector(args) => args.iter().for_each(|arg| visit(arg, inputs)),
+			Expr::Conditional(condition, then, otherwise) => {
+				visit(condition, inputs);
+				visit(then, inputs);
+				visit(otherwise, inputs);
+			},
+		}
+	}
+
+	let mut inputs = Vec::new();
+	visit(ast, &mut inputs);
+	inputs
+}
+
+impl Program {
+	/// Compiles an expression that reads the inputs in this order, `None` when it needs something the
+	/// machine can't do like units, functions other than the built-in ones or mixing numbers and booleans
+	pub fn compile(ast: &Ast, inputs: &[String]) -> Option<Program> {
+		let mut compiler = Compiler {
+			inputs,
+			code: Vec::new(),
+			spans: Vec::new(),
+			depth: 0,
+			stack_size: 0,
+		};
+		let kind = compiler.expression(ast)?;
+		Some(Program {
+			code: compiler.code,
+			spans: compiler.spans,
+			kind,
+			stack_size: compiler.stack_size,
+		})
+	}
+
+	/// Runs the program with a value for every input slot
+	pub fn run(&self, inputs: &[f64]) -> Result<Value, PostfixError> {
+		let numeric = FloatMode;
+		let mut stack = Vec::with_capacity(self.stack_size);
+		let mut next = 0;
+
+		while let Some(instruction) = self.code.get(next) {
+			let (span, right) = self.spans[next];
+			let checked = |result| PostfixError::numeric(result, span);
+			next += 1;
+
+			match instruction {
+				Instruction::Constant(number) => stack.push(*number),
+				Instruction::Input(slot) => stack.push(inputs[*slot]),
+				Instruction::Unary(operator) => {
+					let operand = stack.last_mut().expect("BUG: the stack is empty");
+					*operand = match operator {
+						Operator::Negate => -*operand,
+						Operator::BitNot => numeric.not(operand).map_err(checked)?,
+						_ => 1.0 - *operand,
+					};
+				},
+				Instruction::Binary(operator) => {
+					let b = stack.pop().expect("BUG: the stack is empty");
+					let a = stack.last_mut().expect("BUG: the stack is empty");
+					*a = match operator {
+						Operator::Equal => Self::boolean(numeric.compare(a, &b) == Some(Ordering::Equal)),
+						Operator::NotEqual => Self::boolean(numeric.compare(a, &b) != Some(Ordering::Equal)),
+						Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => {
+							Self::boolean(Postfix::ordered(operator, numeric.compare(a, &b)))
+						},
+						Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::ShiftLeft | Operator::ShiftRight => {
+							numeric.bitwise(operator, a, &b).map_err(checked)?
+						},
+						operator => Postfix::arithmetic(&numeric, operator, a, &b, span, right)?,
+					};
+				},
+				Instruction::Call(function, count) => {
+					let start = stack.len() - *count;
+					let args = &stack[start..];
+					let result = function(args);
+					// like arithmetic a function of `inf` may be infinite without overflowing
+					let result = if args.iter().all(|arg| arg.is_finite()) {
+						numeric.value(result).map_err(checked)?
+					} else {
+						result
+					};
+					stack.truncate(start);
+					stack.push(result);
+				},
+				Instruction::JumpUnless(target) => {
+					if stack.pop().expect("BUG: the stack is empty") == 0.0 {
+						next = *target;
+					}
+				},
+				Instruction::Jump(target) => next = *target,
+			}
+		}
+
+		let result = stack.pop().expect("BUG: the program left nothing on the stack");
+		Ok(match self.kind {
+			Kind::Number => Value::Number(result),
+			Kind::Bool => Value::Bool(result != 0.0),
+		})
+	}
+
+	fn boolean(boolean: bool) -> f64 {
+		if boolean { 1.0 } else { 0.0 }
+	}
+}
+
+struct Compiler<'a> {
+	inputs: &'a [String],
+	code: Vec<Instruction>,
+	spans: Vec<(Span, Span)>,
+	/// How many values are on the stack after the code so far
+	depth: usize,
+	stack_size: usize,
+}
+
+impl Compiler<'_> {
+	// adds an instruction that pops and pushes this many values and returns where it is
+	fn emit(&mut self, instruction: Instruction, spans: (Span, Span), pops: usize, pushes: usize) -> usize {
+		self.depth = self.depth - pops + pushes;
+		self.stack_size = self.stack_size.max(self.depth);
+		self.code.push(instruction);
+		self.spans.push(spans);
+		self.code.len() - 1
+	}
+
+	// points a jump that was emitted before its target was known at the next instruction
+	fn patch(&mut self, jump: usize) {
+		let target = self.code.len();
+		match &mut self.code[jump] {
+			Instruction::Jump(to) | Instruction::JumpUnless(to) => *to = target,
+			instruction => unreachable!("`{instruction:?}` is not a jump"),
+		}
+	}
+
+	fn expression(&mut self, ast: &Ast) -> Option<Kind> {
+		let spans = (ast.span, ast.span);
+
+		// anything that doesn't read an input is calculated now unless it fails, then it fails when it runs
+		if Self::is_constant(ast)
+			&& let Ok(value) = Postfix::calc(ast, &Environment::new(), &FloatMode)
+		{
+			let (number, kind) = match value {
+				Value::Number(number) => (number, Kind::Number),
+				Value::Bool(boolean) => (Program::boolean(boolean), Kind::Bool),
+				Value::Quantity(_) | Value::Vector(_) | Value::Matrix(_) => return None,
+			};
+			self.emit(Instruction::Constant(number), spans, 0, 1);
+			return Some(kind);
+		}
+
+		match &ast.node {
+			Expr::Variable(name) => {
+				let slot = self.inputs.iter().position(|input| input == name)?;
+				self.emit(Instruction::Input(slot), spans, 0, 1);
+				Some(Kind::Number)
+			},
+			Expr::Unary(operator @ (Operator::Negate | Operator::BitNot), operand) => {
+				self.number(operand)?;
+				self.emit(Instruction::Unary(operator.clone()), spans, 1, 1);
+				Some(Kind::Number)
+			},
+			Expr::Unary(Operator::Not, operand) => {
+				self.boolean(operand)?;
+				self.emit(Instruction::Unary(Operator::Not), spans, 1, 1);
+				Some(Kind::Bool)
+			},
+			// `a && b` is `a ? b : false` and `a || b` is `a ? true : b`
+			Expr::Binary(Operator::And, left, right) => {
+				let otherwise = Spanned::new(Expr::Bool(false), right.span);
+				self.conditional(left, right, &otherwise, spans)
+			},
+			Expr::Binary(Operator::Or, left, right) => {
+				let then = Spanned::new(Expr::Bool(true), left.span);
+				self.conditional(left, &then, right, spans)
+			},
+			Expr::Binary(operator @ (Operator::Equal | Operator::NotEqual), left, right) => {
+				let kind = self.expression(left)?;
+				if self.expression(right)? != kind {
+					return None;
+				}
+				self.emit(Instruction::Binary(operator.clone()), (ast.span, right.span), 2, 1);
+				Some(Kind::Bool)
+			},
+			Expr::Binary(
+				operator @ (Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual),
+				left,
+				right,
+			) => {
+				self.number(left)?;
+				self.number(right)?;
+				self.emit(Instruction::Binary(operator.clone()), (ast.span, right.span), 2, 1);
+				Some(Kind::Bool)
+			},
+			// the stack only holds plain numbers
+			Expr::Binary(Operator::Convert | Operator::Dot | Operator::MatrixProduct | Operator::PlusMinus, ..) => None,
+			Expr::Binary(operator, left, right) => {
+				self.number(left)?;
+				self.number(right)?;
+				self.emit(Instruction::Binary(operator.clone()), (ast.span, right.span), 2, 1);
+				Some(Kind::Number)
+			},
+			Expr::Call(name, args) => {
+				let (arity, function) = functions::implementation(name)?;
+				if !arity.accepts(args.len()) {
+					return None;
+				}
+				for arg in args {
+					self.number(arg)?;
+				}
+				self.emit(Instruction::Call(function, args.len()), spans, args.len(), 1);
+				Some(Kind::Number)
+			},
+			Expr::Conditional(condition, then, otherwise) => self.conditional(condition, then, otherwise, spans),
+			// literals are constant, quantities need units and vectors don't fit on the stack
+			_ => None,
+		}
+	}
+
+	fn conditional(&mut self, condition: &Ast, then: &Ast, otherwise: &Ast, spans: (Span, Span)) -> Option<Kind> {
+		self.boolean(condition)?;
+		let skip_then = self.emit(Instruction::JumpUnless(0), spans, 1, 0);
+		let kind = self.expression(then)?;
+		let skip_otherwise = self.emit(Instruction::Jump(0), spans, 0, 0);
+		// only one of the branches leaves its value on the stack
+		self.depth -= 1;
+		self.patch(skip_then);
+		if self.expression(otherwise)? != kind {
+			return None;
+		}
+		self.patch(skip_otherwise);
+		Some(kind)
+	}
+
+	fn number(&mut self, ast: &Ast) -> Option<()> {
+		(self.expression(ast)? == Kind::Number).then_some(())
+	}
+
+	fn boolean(&mut self, ast: &Ast) -> Option<()> {
+		(self.expression(ast)? == Kind::Bool).then_some(())
+	}
+
+	// whether the expression only uses literals, constants and built-in functions
+	fn is_constant(ast: &Ast) -> bool {
+		match &ast.node {
+			Expr::Number(_)
+			| Expr::Integer(..)
+			| Expr::Decimal(_)
+			| Expr::Quantity(..)
+			| Expr::Imaginary(_)
+			| Expr::Bool(_) => true,
+			Expr::Variable(name) => functions::constant(name).is_some(),
+			Expr::Unary(_, operand) => Self::is_constant(operand),
+			Expr::Binary(_, left, right) => Self::is_constant(left) && Self::is_constant(right),
+			Expr::Call(name, args) => functions::is_builtin(name) && args.iter().all(Self::is_constant),
+			Expr::Vector(elements) => elements.iter().all(Self::is_constant),
+			Expr::Conditional(condition, then, otherwise) => {
+				Self::is_constant(condition) && Self::is_constant(then) && Self::is_constant(otherwise)
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shunting::{ShuntingYard, Syntax};
+
+	// the compiled result next to the result of calculating the tree
+	fn both(expression: &str, inputs: &[f64]) -> (Result<Value, PostfixError>, Result<Value, PostfixError>) {
+		let ast = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+		let names = super::inputs(&ast);
+		let mut env = Environment::new();
+		for (name, input) in names.iter().zip(inputs) {
+			env.set(name, Value::Number(*input));
+		}
+		let program = Program::compile(&ast, &names).expect("the expression should compile");
+		(program.run(inputs), Postfix::calc(&ast, &env, &FloatMode))
+	}
+
+	fn assert_same(expression: &str, inputs: &[f64]) {
+		let (compiled, tree) = both(expression, inputs);
+		assert_eq!(compiled, tree, "`{expression}` with {inputs:?}");
+	}
+
+	#[test]
+	fn run_test() {
+		assert_eq!(both("2 * (x + 4)", &[3.0]).0, Ok(Value::Number(14.0)));
+		assert_same("x ^ 2 - y / 3 % 2", &[1.5, 7.0]);
+		assert_same("sin(x) * hypot(x, y, 2) + max(x, y)", &[0.5, -2.0]);
+		assert_same("x & 6 | 1 << y ^ ~x", &[5.0, 2.0]);
+		assert_same("-x * pi + e", &[2.0]);
+		assert_same("x < y && !(x == 2) || y >= 10", &[1.0, 3.0]);
+		assert_same("x < y && !(x == 2) || y >= 10", &[2.0, 3.0]);
+		assert_same("x > 0 ? sqrt(x) : -x", &[4.0]);
+		assert_same("x > 0 ? sqrt(x) : -x", &[-4.0]);
+		assert_same("(x > 0) == (y > 0)", &[1.0, -1.0]);
+		assert_same("x != x", &[f64::NAN]);
+		assert_same("x + 1", &[f64::INFINITY]);
+	}
+
+	#[test]
+	fn run_error_test() {
+		assert_eq!(both("1 / (x - 1)", &[1.0]).0, Err(PostfixError::DivisionByZero(Span::new(5, 10))));
+		assert_same("1 / (x - 1)", &[1.0]);
+		assert_same("x % 0", &[1.0]);
+		assert_same("x ^ -1", &[0.0]);
+		assert_same("x * 1e308", &[10.0]);
+		assert_same("exp(x)", &[1000.0]);
+		assert_same("x & 1", &[0.5]);
+		// the branch that isn't taken can't fail
+		assert_same("x == 0 ? 0 : 1 / x", &[0.0]);
+		assert_same("x != 0 && 1 / x > 1", &[0.0]);
+	}
+
+	#[test]
+	fn compile_test() {
+		let compile = |expression| {
+			let ast = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+			Program::compile(&ast, &super::inputs(&ast))
+		};
+
+		// everything without an input is calculated once
+		let folded = compile("x * (2 * pi + sqrt(16))").unwrap();
+		assert_eq!(folded.code.len(), 3);
+		assert_eq!(folded.stack_size, 2);
+		// unless it fails, then it fails when it runs
+		assert!(compile("x > 0 ? x : 1 / 0").is_some());
+
+		assert!(compile("x * 3 km").is_none());
+		assert!(compile("x in m").is_none());
+		assert!(compile("f(x)").is_none());
+		assert!(compile("sin(x, x)").is_none());
+		assert!(compile("x + (x > 1)").is_none());
+		assert!(compile("x ? 1 : 2").is_none());
+		assert!(compile("x > 1 ? 1 : true").is_none());
+
+		let ast = ShuntingYard::parse("b * a + pi * b", Syntax::default()).unwrap();
+		assert_eq!(super::inputs(&ast), vec![String::from("b"), String::from("a")]);
+		// the variable a sum binds isn't an input
+		let ast = ShuntingYard::parse("sum(k * n, k, 1, 3)", Syntax::default()).unwrap();
+		assert_eq!(super::inputs(&ast), vec![String::from("n")]);
+		assert!(compile("sum(k * n, k, 1, 3)").is_none());
+	}
+}
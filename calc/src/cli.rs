@@ -0,0 +1,606 @@
+use std::{fmt, str::FromStr};
+
+use ccalc::{
+	decimal::DecimalMode, env::Environment, format::Format, integer::IntegerMode, notation::Notation, shunting::Syntax,
+};
+
+/// Which arithmetic calculations are carried out with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Mode {
+	#[default]
+	Float,
+	Rational,
+	Decimal,
+	Integer,
+	/// Complex numbers with `f64` parts
+	Complex,
+	/// Intervals with outward rounded `f64` bounds
+	Interval,
+	/// `f64` values with a standard deviation propagated to first order
+	Uncertain,
+}
+
+impl FromStr for Mode {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"float" => Ok(Mode::Float),
+			"rational" => Ok(Mode::Rational),
+			"decimal" => Ok(Mode::Decimal),
+			"integer" => Ok(Mode::Integer),
+			"complex" => Ok(Mode::Complex),
+			"interval" => Ok(Mode::Interval),
+			"uncertain" => Ok(Mode::Uncertain),
+			_ => Err(name.to_string()),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+	UnknownOption(String),
+	MissingValue(String),
+	InvalidValue {
+		option: String,
+		value: String,
+	},
+	MissingExpression(String),
+	/// Options that don't make sense together like `--rpn` and `--emit`
+	Conflict(String, String),
+	/// Options like `--json` that only change how a script is run
+	ScriptOnly(String),
+}
+
+impl fmt::Display for CliError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CliError::UnknownOption(option) => {
+				write!(f, "unknown option `{option}`, use `--` before an expression starting with `--`")
+			},
+			CliError::MissingValue(option) => write!(f, "`{option}` needs a value"),
+			CliError::InvalidValue { option, value } => write!(f, "`{value}` is not a valid value for `{option}`"),
+			CliError::MissingExpression(option) => write!(f, "`{option}` needs an expression"),
+			CliError::Conflict(option, other) => write!(f, "`{option}` can't be combined with `{other}`"),
+			CliError::ScriptOnly(option) => {
+				write!(
+					f,
+					"`{option}` only applies to a script, given with `--script`, as a `.calc` file or `-` for standard input"
+				)
+			},
+		}
+	}
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+	/// Print the parsed expression in this notation instead of calculating it
+	pub emit: Option<Notation>,
+	/// Print the derivative with respect to this variable instead of calculating the expression
+	pub diff: Option<String>,
+	/// Print the roots of the equation in this variable instead of calculating the expression
+	pub solve: Option<String>,
+	/// Draw the expression as a function of its variable instead of calculating it
+	pub plot: bool,
+	/// Where roots are searched for when they can't be solved for exactly or where the function is plotted
+	pub range: Option<(f64, f64)>,
+	pub mode: Mode,
+	/// The precision and rounding used in decimal mode
+	pub decimal: DecimalMode,
+	/// The width, signedness and overflow behaviour used in integer mode
+	pub integer: IntegerMode,
+	/// How results are printed
+	pub format: Format,
+	/// `inf` and `nan` are numbers rather than names
+	pub non_finite: bool,
+	/// Read postfix input like `3 4 +` and calculate it on a stack
+	pub rpn: bool,
+	/// How deeply user functions may call each other, the environment's default if not given
+	pub recursion_limit: Option<usize>,
+	/// The path of a script to run instead of an expression, `-` reads it from standard input
+	pub script: Option<String>,
+	/// Run the rest of a script after a statement fails
+	pub continue_on_error: bool,
+	/// Write the results of a script as JSON lines
+	pub json: bool,
+	pub expression: String,
+}
+
+impl Options {
+	/// More significant digits than this make decimal calculations too slow to be useful
+	pub const MAX_PRECISION: usize = 10_000;
+
+	/// Options come first and everything from the first argument that isn't one is the expression
+	pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, CliError> {
+		let mut options = Options::default();
+		let mut args = args.into_iter().peekable();
+
+		// `-f` is the only short option, `-f(x)` or `-foo` are still expressions
+		while let Some(arg) = args.next_if(|arg| arg.starts_with("--") || arg == "-f") {
+			// `ccalc -- -3 + 4` separates a leading minus from any flags
+			if arg == "--" {
+				break;
+			}
+
+			// both `--emit latex` and `--emit=latex` are fine
+			let (option, inline_value) = match arg.split_once('=') {
+				Some((option, value)) => (option.to_string(), Some(value.to_string())),
+				None => (arg, None),
+			};
+			let mut value =
+				|| inline_value.clone().or_else(|| args.next()).ok_or_else(|| CliError::MissingValue(option.clone()));
+
+			let invalid = |value| CliError::InvalidValue {
+				option: option.clone(),
+				value,
+			};
+
+			match option.as_str() {
+				"--emit" => options.emit = Some(value()?.parse().map_err(invalid)?),
+				"--mode" => options.mode = value()?.parse().map_err(invalid)?,
+				"--script" | "-f" => options.script = Some(value()?),
+				"--diff" => options.diff = Some(Self::identifier(value()?).map_err(invalid)?),
+				"--solve" => options.solve = Some(Self::identifier(value()?).map_err(invalid)?),
+				"--range" => {
+					let value = value()?;
+					let range = value.split_once("..").and_then(|(low, high)| Self::range(low, high));
+					options.range = Some(range.ok_or_else(|| invalid(value))?);
+				},
+				"--rounding" => options.decimal.rounding = value()?.parse().map_err(invalid)?,
+				"--overflow" => options.integer.overflow = value()?.parse().map_err(invalid)?,
+				"--output" => options.format.radix = value()?.parse().map_err(invalid)?,
+				"--format" => options.format.style = value()?.parse().map_err(invalid)?,
+				"--digits" => {
+					let value = value()?;
+					options.format.digits = match value.parse() {
+						Ok(digits @ 1..=Options::MAX_PRECISION) => Some(digits),
+						_ => return Err(invalid(value)),
+					};
+				},
+				// flags take no value
+				"--group" | "--inf-nan" | "--rpn" | "--continue-on-error" | "--json" | "--plot" if inline_value.is_some() => {
+					return Err(invalid(inline_value.clone().unwrap_or_default()));
+				},
+				"--plot" => options.plot = true,
+				"--group" => options.format.grouping = true,
+				"--inf-nan" => options.non_finite = true,
+				"--rpn" => options.rpn = true,
+				"--continue-on-error" => options.continue_on_error = true,
+				"--json" => options.json = true,
+				"--int" => {
+					let value = value()?;
+					let (bits, signed) = IntegerMode::parse_type(&value).ok_or_else(|| invalid(value.clone()))?;
+					options.integer.bits = bits;
+					options.integer.signed = signed;
+				},
+				"--recursion-limit" => {
+					let value = value()?;
+					options.recursion_limit = match value.parse() {
+						Ok(limit @ 1..=Environment::<f64>::MAX_RECURSION_LIMIT) => Some(limit),
+						_ => return Err(invalid(value)),
+					};
+				},
+				"--precision" => {
+					let value = value()?;
+					options.decimal.precision = match value.parse() {
+						Ok(precision @ 1..=Options::MAX_PRECISION) => precision,
+						_ => return Err(invalid(value)),
+					};
+				},
+				_ => return Err(CliError::UnknownOption(option)),
+			}
+		}
+
+		let mut rest = args.collect::<Vec<String>>();
+		// `ccalc --plot 'sin(x)' -10 10` ends with the range to plot
+		if options.plot
+			&& let [_, .., low, high] = rest.as_slice()
+			&& low.parse::<f64>().is_ok()
+			&& high.parse::<f64>().is_ok()
+		{
+			let range = Self::range(low, high).ok_or_else(|| CliError::InvalidValue {
+				option: String::from("--plot"),
+				value: format!("{low} {high}"),
+			})?;
+			options.range = Some(range);
+			rest.truncate(rest.len() - 2);
+		}
+
+		// `ccalc -` and `ccalc file.calc` run scripts, neither is a useful expression on its own, any other file
+		// is run with `--script`
+		match (&options.script, rest.as_slice()) {
+			(Some(script), [_, ..]) => return Err(CliError::Conflict(String::from("--script"), script.clone())),
+			(None, [path]) if path == "-" || path.ends_with(".calc") => options.script = Some(path.clone()),
+			_ => options.expression = rest.join(" "),
+		}
+
+		if let Some(script) = &options.script {
+			let conflicts = [
+				(options.rpn, "--rpn"),
+				(options.emit.is_some(), "--emit"),
+				(options.diff.is_some(), "--diff"),
+				(options.solve.is_some(), "--solve"),
+				(options.plot, "--plot"),
+			];
+			if let Some((_, option)) = conflicts.into_iter().find(|(conflict, _)| *conflict) {
+				return Err(CliError::Conflict(String::from(option), script.clone()));
+			}
+		} else if options.continue_on_error {
+			return Err(CliError::ScriptOnly(String::from("--continue-on-error")));
+		} else if options.json {
+			return Err(CliError::ScriptOnly(String::from("--json")));
+		}
+
+		// postfix input is calculated on a stack rather than parsed into a tree
+		let conflicts = [
+			(options.rpn, "--rpn", options.emit.is_some(), "--emit"),
+			(options.rpn, "--rpn", options.diff.is_some(), "--diff"),
+			(options.rpn, "--rpn", options.solve.is_some(), "--solve"),
+			// equations are solved with floats
+			(options.solve.is_some(), "--solve", options.emit.is_some(), "--emit"),
+			(options.solve.is_some(), "--solve", options.diff.is_some(), "--diff"),
+			(options.solve.is_some(), "--solve", options.mode != Mode::Float, "--mode"),
+			// plots sample the compiled expression which calculates with floats
+			(options.plot, "--plot", options.rpn, "--rpn"),
+			(options.plot, "--plot", options.emit.is_some(), "--emit"),
+			(options.plot, "--plot", options.diff.is_some(), "--diff"),
+			(options.plot, "--plot", options.solve.is_some(), "--solve"),
+			(options.plot, "--plot", options.mode != Mode::Float, "--mode"),
+		];
+		if let Some((_, option, _, other)) = conflicts.into_iter().find(|(option, _, other, _)| *option && *other) {
+			return Err(CliError::Conflict(String::from(option), String::from(other)));
+		}
+		if options.expression.trim().is_empty() {
+			if options.emit.is_some() {
+				return Err(CliError::MissingExpression(String::from("--emit")));
+			}
+			if options.diff.is_some() {
+				return Err(CliError::MissingExpression(String::from("--diff")));
+			}
+			if options.solve.is_some() {
+				return Err(CliError::MissingExpression(String::from("--solve")));
+			}
+			if options.plot {
+				return Err(CliError::MissingExpression(String::from("--plot")));
+			}
+		}
+
+		Ok(options)
+	}
+
+	// a finite range that isn't empty
+	fn range(low: &str, high: &str) -> Option<(f64, f64)> {
+		let (low, high) = (low.parse::<f64>().ok()?, high.parse::<f64>().ok()?);
+		(low < high && low.is_finite() && high.is_finite()).then_some((low, high))
+	}
+
+	// the variable of `--diff` and `--solve`
+	fn identifier(value: String) -> Result<String, String> {
+		let mut characters = value.chars();
+		let is_identifier = characters.next().is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+			&& characters.all(|character| character.is_ascii_alphanumeric() || character == '_');
+		if is_identifier { Ok(value) } else { Err(value) }
+	}
+
+	/// Integer mode reads `^` as an exclusive or like C does
+	pub fn syntax(&self) -> Syntax {
+		Syntax {
+			caret_is_xor: self.mode == Mode::Integer,
+			non_finite: self.non_finite,
+			..Syntax::default()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn parse(args: &[&str]) -> Result<Options, CliError> {
+		Options::parse(args.iter().map(|arg| arg.to_string()))
+	}
+
+	#[test]
+	fn parse_test() {
+		assert_eq!(parse(&[]), Ok(Options::default()));
+		assert_eq!(
+			parse(&["1", "+", "2"]),
+			Ok(Options {
+				expression: String::from("1 + 2"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--emit", "latex", "1/2"]),
+			Ok(Options {
+				emit: Some(Notation::Latex),
+				expression: String::from("1/2"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--emit=rpn", "--", "-3", "--4"]),
+			Ok(Options {
+				emit: Some(Notation::Postfix),
+				expression: String::from("-3 --4"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--diff", "t_1", "--emit", "latex", "t_1^2"]),
+			Ok(Options {
+				emit: Some(Notation::Latex),
+				diff: Some(String::from("t_1")),
+				expression: String::from("t_1^2"),
+				..Options::default()
+			})
+		);
+		// only leading arguments are options
+		assert_eq!(
+			parse(&["-3", "--emit", "sexpr"]),
+			Ok(Options {
+				expression: String::from("-3 --emit sexpr"),
+				..Options::default()
+			})
+		);
+	}
+
+	#[test]
+	fn parse_mode_test() {
+		use ccalc::decimal::Rounding;
+
+		assert_eq!(
+			parse(&["--mode", "decimal", "--precision=50", "--rounding", "half-up", "1/3"]),
+			Ok(Options {
+				mode: Mode::Decimal,
+				decimal: DecimalMode {
+					precision: 50,
+					rounding: Rounding::HalfUp,
+				},
+				expression: String::from("1/3"),
+				..Options::default()
+			})
+		);
+		assert_eq!(parse(&["--mode=rational"]).map(|options| options.mode), Ok(Mode::Rational));
+		assert_eq!(parse(&["--mode", "float"]).map(|options| options.mode), Ok(Mode::Float));
+		assert_eq!(parse(&["--mode=complex"]).map(|options| options.mode), Ok(Mode::Complex));
+		assert_eq!(parse(&["--mode=interval"]).map(|options| options.mode), Ok(Mode::Interval));
+		assert_eq!(parse(&["--mode", "uncertain"]).map(|options| options.mode), Ok(Mode::Uncertain));
+		assert_eq!(parse(&["--recursion-limit=50"]).map(|options| options.recursion_limit), Ok(Some(50)));
+	}
+
+	#[test]
+	fn parse_integer_test() {
+		use ccalc::{integer::Overflow, numeric::Radix};
+
+		let options = parse(&[
+			"--mode",
+			"integer",
+			"--overflow=wrapping",
+			"--int",
+			"u16",
+			"--output",
+			"hex",
+			"~0",
+		]);
+		assert_eq!(
+			options,
+			Ok(Options {
+				mode: Mode::Integer,
+				integer: IntegerMode {
+					bits: 16,
+					signed: false,
+					overflow: Overflow::Wrapping,
+				},
+				format: Format {
+					radix: Radix::Hexadecimal,
+					..Format::default()
+				},
+				expression: String::from("~0"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			options.map(|options| options.syntax()),
+			Ok(Syntax {
+				caret_is_xor: true,
+				..Syntax::default()
+			})
+		);
+		assert_eq!(parse(&["1"]).map(|options| options.syntax()), Ok(Syntax::default()));
+	}
+
+	#[test]
+	fn parse_format_test() {
+		use ccalc::format::Style;
+
+		let options = parse(&["--format", "eng", "--digits=4", "--group", "--inf-nan", "inf"]);
+		assert_eq!(
+			options,
+			Ok(Options {
+				format: Format {
+					style: Style::Engineering,
+					digits: Some(4),
+					grouping: true,
+					..Format::default()
+				},
+				non_finite: true,
+				expression: String::from("inf"),
+				..Options::default()
+			})
+		);
+		assert!(options.is_ok_and(|options| options.syntax().non_finite));
+		assert_eq!(
+			parse(&["--group=yes", "1"]),
+			Err(CliError::InvalidValue {
+				option: String::from("--group"),
+				value: String::from("yes"),
+			})
+		);
+	}
+
+	#[test]
+	fn parse_rpn_test() {
+		assert_eq!(
+			parse(&["--rpn", "3", "4", "+"]),
+			Ok(Options {
+				rpn: true,
+				expression: String::from("3 4 +"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--rpn", "--emit", "infix", "3"]),
+			Err(CliError::Conflict(String::from("--rpn"), String::from("--emit")))
+		);
+		assert_eq!(
+			parse(&["--diff=x", "--rpn", "x"]),
+			Err(CliError::Conflict(String::from("--rpn"), String::from("--diff")))
+		);
+	}
+
+	#[test]
+	fn parse_solve_test() {
+		assert_eq!(
+			parse(&["--solve", "x", "--range=-10..1e3", "x^3", "=", "2"]),
+			Ok(Options {
+				solve: Some(String::from("x")),
+				range: Some((-10.0, 1000.0)),
+				expression: String::from("x^3 = 2"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--solve", "x", "--rpn", "x"]),
+			Err(CliError::Conflict(String::from("--rpn"), String::from("--solve")))
+		);
+		assert_eq!(
+			parse(&["--solve", "x", "--mode", "rational", "x = 1"]),
+			Err(CliError::Conflict(String::from("--solve"), String::from("--mode")))
+		);
+		assert_eq!(parse(&["--solve", "x"]), Err(CliError::MissingExpression(String::from("--solve"))));
+		for range in ["1..-1", "0..inf", "1", "a..b"] {
+			assert_eq!(
+				parse(&["--range", range, "x"]),
+				Err(CliError::InvalidValue {
+					option: String::from("--range"),
+					value: String::from(range),
+				})
+			);
+		}
+	}
+
+	#[test]
+	fn parse_script_test() {
+		assert_eq!(
+			parse(&["--json", "--continue-on-error", "--mode=rational", "prices.calc"]),
+			Ok(Options {
+				mode: Mode::Rational,
+				script: Some(String::from("prices.calc")),
+				continue_on_error: true,
+				json: true,
+				..Options::default()
+			})
+		);
+		assert_eq!(parse(&["-"]).map(|options| options.script), Ok(Some(String::from("-"))));
+		// anything more than the path is an expression
+		assert_eq!(
+			parse(&["-", "1"]),
+			Ok(Options {
+				expression: String::from("- 1"),
+				..Options::default()
+			})
+		);
+		assert_eq!(parse(&["--rpn", "-"]), Err(CliError::Conflict(String::from("--rpn"), String::from("-"))));
+		assert_eq!(
+			parse(&["--emit", "latex", "a.calc"]),
+			Err(CliError::Conflict(String::from("--emit"), String::from("a.calc")))
+		);
+		// a script with any name is given with `--script`
+		assert_eq!(
+			parse(&["--json", "-f", "prices.txt"]).map(|options| (options.script, options.json)),
+			Ok((Some(String::from("prices.txt")), true))
+		);
+		assert_eq!(parse(&["--script=1"]).map(|options| options.script), Ok(Some(String::from("1"))));
+		assert_eq!(parse(&["-f", "a.txt", "1"]), Err(CliError::Conflict(String::from("--script"), String::from("a.txt"))));
+		assert_eq!(parse(&["--script"]), Err(CliError::MissingValue(String::from("--script"))));
+		assert_eq!(parse(&["-f"]), Err(CliError::MissingValue(String::from("-f"))));
+		assert_eq!(parse(&["-f(2)"]).map(|options| options.expression), Ok(String::from("-f(2)")));
+		assert_eq!(parse(&["--json", "1 + 2"]), Err(CliError::ScriptOnly(String::from("--json"))));
+		assert_eq!(parse(&["--continue-on-error"]), Err(CliError::ScriptOnly(String::from("--continue-on-error"))));
+	}
+
+	#[test]
+	fn parse_plot_test() {
+		assert_eq!(
+			parse(&["--plot", "sin(x)/x", "-10", "10"]),
+			Ok(Options {
+				plot: true,
+				range: Some((-10.0, 10.0)),
+				expression: String::from("sin(x)/x"),
+				..Options::default()
+			})
+		);
+		// without numbers at the end the range is left to `--range` or the default
+		assert_eq!(
+			parse(&["--plot", "--range=0..1", "x", "-", "1"]),
+			Ok(Options {
+				plot: true,
+				range: Some((0.0, 1.0)),
+				expression: String::from("x - 1"),
+				..Options::default()
+			})
+		);
+		assert_eq!(
+			parse(&["--plot", "x", "1", "-1"]),
+			Err(CliError::InvalidValue {
+				option: String::from("--plot"),
+				value: String::from("1 -1"),
+			})
+		);
+		assert_eq!(
+			parse(&["--plot", "--mode=decimal", "x"]),
+			Err(CliError::Conflict(String::from("--plot"), String::from("--mode")))
+		);
+		assert_eq!(parse(&["--plot", "-"]), Err(CliError::Conflict(String::from("--plot"), String::from("-"))));
+		assert_eq!(parse(&["--plot"]), Err(CliError::MissingExpression(String::from("--plot"))));
+	}
+
+	#[test]
+	fn parse_error_test() {
+		assert_eq!(parse(&["--nope", "1"]), Err(CliError::UnknownOption(String::from("--nope"))));
+		assert_eq!(parse(&["--emit"]), Err(CliError::MissingValue(String::from("--emit"))));
+		assert_eq!(
+			parse(&["--emit", "json", "1"]),
+			Err(CliError::InvalidValue {
+				option: String::from("--emit"),
+				value: String::from("json"),
+			})
+		);
+		assert_eq!(parse(&["--emit", "infix"]), Err(CliError::MissingExpression(String::from("--emit"))));
+		assert_eq!(parse(&["--diff", "x"]), Err(CliError::MissingExpression(String::from("--diff"))));
+		for (option, value) in [
+			("--mode", "quaternion"),
+			("--precision", "0"),
+			("--precision", "ten"),
+			("--rounding", "nearest"),
+			("--int", "i128"),
+			("--overflow", "saturating"),
+			("--output", "base64"),
+			("--format", "pretty"),
+			("--digits", "0"),
+			("--recursion-limit", "0"),
+			("--recursion-limit", "1000000"),
+			("--diff", "2x"),
+			("--diff", "x+y"),
+		] {
+			assert_eq!(
+				parse(&[option, value, "1"]),
+				Err(CliError::InvalidValue {
+					option: option.to_string(),
+					value: value.to_string(),
+				})
+			);
+		}
+	}
+}
@@ -0,0 +1,463 @@
+use std::{
+	cmp::Ordering,
+	f64::consts,
+	fmt,
+	ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::{
+	format::Format,
+	functions,
+	numeric::{FloatMode, Numeric, NumericError},
+};
+
+/// A number `re + im*i` with `f64` parts
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Complex {
+	pub re: f64,
+	pub im: f64,
+}
+
+impl Complex {
+	pub const I: Complex = Complex::new(0.0, 1.0);
+
+	pub const fn new(re: f64, im: f64) -> Self {
+		Self { re, im }
+	}
+
+	pub const fn real(re: f64) -> Self {
+		Self { re, im: 0.0 }
+	}
+
+	/// The number at the distance `r` from zero in the direction `phi`
+	pub fn from_polar(r: f64, phi: f64) -> Self {
+		Self::new(r * phi.cos(), r * phi.sin())
+	}
+
+	pub fn is_real(&self) -> bool {
+		self.im == 0.0
+	}
+
+	pub fn abs(self) -> f64 {
+		self.re.hypot(self.im)
+	}
+
+	/// The angle to the positive real axis between `-pi` and `pi`
+	pub fn arg(self) -> f64 {
+		// a real number has no sign in its imaginary part, `-1` is `-1-0i` after a negation but `arg(-1)` is pi
+		let im = if self.is_real() { 0.0 } else { self.im };
+		im.atan2(self.re)
+	}
+
+	pub fn conj(self) -> Self {
+		Self::new(self.re, -self.im)
+	}
+
+	pub fn exp(self) -> Self {
+		Self::from_polar(self.re.exp(), self.im)
+	}
+
+	/// The principal logarithm whose imaginary part is the argument
+	pub fn ln(self) -> Self {
+		Self::new(self.abs().ln(), self.arg())
+	}
+
+	/// The principal root with a non-negative real part
+	pub fn sqrt(self) -> Self {
+		let r = self.abs();
+		let re = ((r + self.re) / 2.0).sqrt();
+		let im = ((r - self.re) / 2.0).sqrt();
+		Self::new(re, if self.im < 0.0 { -im } else { im })
+	}
+
+	pub fn sin(self) -> Self {
+		Self::new(self.re.sin() * self.im.cosh(), self.re.cos() * self.im.sinh())
+	}
+
+	pub fn cos(self) -> Self {
+		Self::new(self.re.cos() * self.im.cosh(), -self.re.sin() * self.im.sinh())
+	}
+
+	pub fn sinh(self) -> Self {
+		Self::new(self.re.sinh() * self.im.cos(), self.re.cosh() * self.im.sin())
+	}
+
+	pub fn cosh(self) -> Self {
+		Self::new(self.re.cosh() * self.im.cos(), self.re.sinh() * self.im.sin())
+	}
+
+	// `-i ln(iz + sqrt(1 - z^2))`
+	fn asin(self) -> Self {
+		let root = (Self::real(1.0) - self * self).sqrt();
+		-Self::I * (Self::I * self + root).ln()
+	}
+
+	// `i/2 ln((i + z) / (i - z))`
+	fn atan(self) -> Self {
+		Self::new(0.0, 0.5) * ((Self::I + self) / (Self::I - self)).ln()
+	}
+
+	// each part on its own
+	fn map(self, f: impl Fn(f64) -> f64) -> Self {
+		Self::new(f(self.re), f(self.im))
+	}
+
+	fn is_finite(&self) -> bool {
+		self.re.is_finite() && self.im.is_finite()
+	}
+
+	// `3+4i`, `-i` or `2` with the parts written by `part` and the imaginary one left out when it is zero
+	fn written(&self, part: impl Fn(f64) -> String) -> String {
+		// a zero is written without the sign it may have picked up like `-(1i - 1i)`
+		let part = |number: f64| part(if number == 0.0 { 0.0 } else { number });
+		if self.re.is_nan() || self.im.is_nan() {
+			return part(f64::NAN);
+		}
+		if self.is_real() {
+			return part(self.re);
+		}
+
+		let imaginary = match part(self.im.abs()).as_str() {
+			"1" => String::from("i"),
+			magnitude => format!("{magnitude}i"),
+		};
+		let sign = if self.im < 0.0 { "-" } else { "+" };
+		match self.re {
+			0.0 if self.im < 0.0 => format!("-{imaginary}"),
+			0.0 => imaginary,
+			re => format!("{}{sign}{imaginary}", part(re)),
+		}
+	}
+}
+
+impl fmt::Display for Complex {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.written(|part| part.to_string()))
+	}
+}
+
+impl Neg for Complex {
+	type Output = Complex;
+
+	fn neg(self) -> Complex {
+		Complex::new(-self.re, -self.im)
+	}
+}
+
+impl Add for Complex {
+	type Output = Complex;
+
+	fn add(self, other: Complex) -> Complex {
+		Complex::new(self.re + other.re, self.im + other.im)
+	}
+}
+
+impl Sub for Complex {
+	type Output = Complex;
+
+	fn sub(self, other: Complex) -> Complex {
+		Complex::new(self.re - other.re, self.im - other.im)
+	}
+}
+
+impl Mul for Complex {
+	type Output = Complex;
+
+	fn mul(self, other: Complex) -> Complex {
+		Complex::new(self.re * other.re - self.im * other.im, self.re * other.im + self.im * other.re)
+	}
+}
+
+impl Div for Complex {
+	type Output = Complex;
+
+	fn div(self, other: Complex) -> Complex {
+		// a real divisor divides each part so `1/3` is the same as with `f64`
+		if other.is_real() {
+			return Complex::new(self.re / other.re, self.im / other.re);
+		}
+		let scale = other.re * other.re + other.im * other.im;
+		Complex::new((self.re * other.re + self.im * other.im) / scale, (self.im * other.re - self.re * other.im) / scale)
+	}
+}
+
+/// Complex arithmetic with `f64` parts where `sqrt(-1)` is `i` rather than NaN
+///
+/// Real numbers give the same results as in `FloatMode` wherever those have a real value, comparisons other
+/// than equality only order real numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ComplexMode;
+
+impl ComplexMode {
+	// only a finite calculation turning infinite is an overflow like in `FloatMode`
+	fn checked(result: Complex, operands: &[Complex]) -> Result<Complex, NumericError> {
+		if !result.is_finite() && !result.re.is_nan() && !result.im.is_nan() && operands.iter().all(Complex::is_finite) {
+			Err(NumericError::Overflow)
+		} else {
+			Ok(result)
+		}
+	}
+
+	// integer powers are multiplied out so `i^2` is exactly -1
+	fn integer_pow(base: Complex, exponent: i32) -> Complex {
+		let (mut result, mut square, mut remaining) = (Complex::real(1.0), base, exponent.unsigned_abs());
+		while remaining > 0 {
+			if remaining & 1 == 1 {
+				result = result * square;
+			}
+			square = square * square;
+			remaining >>= 1;
+		}
+		if exponent < 0 {
+			Complex::real(1.0) / result
+		} else {
+			result
+		}
+	}
+}
+
+impl Numeric for ComplexMode {
+	type Value = Complex;
+
+	const EXACT: bool = false;
+
+	fn value(&self, number: f64) -> Result<Complex, NumericError> {
+		FloatMode.value(number).map(Complex::real)
+	}
+
+	fn unchecked(&self, number: f64) -> Result<Complex, NumericError> {
+		Ok(Complex::real(number))
+	}
+
+	/// NaN for a number that isn't real, which no function of `f64` can take
+	fn to_f64(&self, value: &Complex) -> f64 {
+		if value.is_real() { value.re } else { f64::NAN }
+	}
+
+	fn integer(&self, integer: i128) -> Result<Complex, NumericError> {
+		Ok(Complex::real(integer as f64))
+	}
+
+	fn to_integer(&self, value: &Complex) -> Option<i128> {
+		FloatMode.to_integer(&value.re).filter(|_| value.is_real())
+	}
+
+	fn is_zero(&self, value: &Complex) -> bool {
+		value.re == 0.0 && value.im == 0.0
+	}
+
+	fn is_negative(&self, value: &Complex) -> bool {
+		value.is_real() && value.re < 0.0
+	}
+
+	fn compare(&self, a: &Complex, b: &Complex) -> Option<Ordering> {
+		if a.is_real() && b.is_real() {
+			a.re.partial_cmp(&b.re)
+		} else {
+			(a == b).then_some(Ordering::Equal)
+		}
+	}
+
+	/// Only real numbers are ordered, `3 > 2i` is an error rather than false
+	fn order(&self, a: &Complex, b: &Complex) -> Result<Option<Ordering>, NumericError> {
+		if a.is_real() && b.is_real() {
+			Ok(self.compare(a, b))
+		} else {
+			Err(NumericError::Unordered)
+		}
+	}
+
+	fn neg(&self, value: &Complex) -> Result<Complex, NumericError> {
+		Ok(-*value)
+	}
+
+	fn add(&self, a: &Complex, b: &Complex) -> Result<Complex, NumericError> {
+		Self::checked(*a + *b, &[*a, *b])
+	}
+
+	fn sub(&self, a: &Complex, b: &Complex) -> Result<Complex, NumericError> {
+		Self::checked(*a - *b, &[*a, *b])
+	}
+
+	fn mul(&self, a: &Complex, b: &Complex) -> Result<Complex, NumericError> {
+		Self::checked(*a * *b, &[*a, *b])
+	}
+
+	fn div(&self, a: &Complex, b: &Complex) -> Result<Complex, NumericError> {
+		Self::checked(*a / *b, &[*a, *b])
+	}
+
+	fn rem(&self, a: &Complex, b: &Complex) -> Result<Complex, NumericError> {
+		if a.is_real() && b.is_real() {
+			Ok(Complex::real(a.re % b.re))
+		} else {
+			Err(NumericError::NotANumber)
+		}
+	}
+
+	fn pow(&self, base: &Complex, exponent: &Complex) -> Result<Complex, NumericError> {
+		let power = match (base.is_real(), exponent.is_real()) {
+			(true, true) if !base.re.powf(exponent.re).is_nan() || base.re.is_nan() || exponent.re.is_nan() => {
+				Complex::real(base.re.powf(exponent.re))
+			},
+			(_, true) if exponent.re.fract() == 0.0 && exponent.re.abs() <= 64.0 => {
+				Self::integer_pow(*base, exponent.re as i32)
+			},
+			(_, true) if exponent.re == 0.5 => base.sqrt(),
+			// zero to a power with a positive real part is zero, anything else has no value
+			_ if self.is_zero(base) && exponent.re > 0.0 => Complex::real(0.0),
+			_ if self.is_zero(base) => return Err(NumericError::NotANumber),
+			_ => (*exponent * base.ln()).exp(),
+		};
+		Self::checked(power, &[*base, *exponent])
+	}
+
+	fn floor(&self, value: &Complex) -> Complex {
+		value.map(f64::floor)
+	}
+
+	fn format_with(&self, value: &Complex, format: Format) -> String {
+		if value.is_real() {
+			format.decimal(&self.format(value, format.radix))
+		} else {
+			value.written(|part| format.decimal(&part.to_string()))
+		}
+	}
+
+	fn imaginary_unit(&self) -> Option<Complex> {
+		Some(Complex::I)
+	}
+
+	fn parts(&self, value: &Complex) -> Vec<f64> {
+		vec![value.re, value.im]
+	}
+
+	fn joined(&self, areas: &[f64]) -> Result<Complex, NumericError> {
+		Ok(Complex::new(FloatMode.value(areas[0])?, FloatMode.value(areas[1])?))
+	}
+
+	fn function(&self, name: &str, args: &[Complex]) -> Option<Result<Complex, NumericError>> {
+		if !functions::arity(name).is_some_and(|arity| arity.accepts(args.len())) {
+			return None;
+		}
+		// real arguments go through `f64` unless the result has no real value, like `sqrt(-1)`
+		let real = args.iter().map(|arg| arg.is_real().then_some(arg.re)).collect::<Option<Vec<f64>>>();
+		if let Some(real) = real
+			&& let Some((_, call)) = functions::implementation(name)
+			&& (!call(&real).is_nan() || real.iter().any(|arg| arg.is_nan()))
+		{
+			return None;
+		}
+
+		let result = match (name, args) {
+			("sqrt", [z]) => z.sqrt(),
+			("exp", [z]) => z.exp(),
+			("ln", [z]) => z.ln(),
+			("log", [z]) => z.ln() / Complex::real(consts::LN_10),
+			("log", [z, base]) => z.ln() / base.ln(),
+			("log2", [z]) => z.ln() / Complex::real(consts::LN_2),
+			("sin", [z]) => z.sin(),
+			("cos", [z]) => z.cos(),
+			("tan", [z]) => z.sin() / z.cos(),
+			("sinh", [z]) => z.sinh(),
+			("cosh", [z]) => z.cosh(),
+			("tanh", [z]) => z.sinh() / z.cosh(),
+			("asin", [z]) => z.asin(),
+			("acos", [z]) => Complex::real(consts::FRAC_PI_2) - z.asin(),
+			("atan", [z]) => z.atan(),
+			("abs", [z]) => Complex::real(z.abs()),
+			("arg", [z]) => Complex::real(z.arg()),
+			("conj", [z]) => z.conj(),
+			("re", [z]) => Complex::real(z.re),
+			("im", [z]) => Complex::real(z.im),
+			("floor", [z]) => z.map(f64::floor),
+			("ceil", [z]) => z.map(f64::ceil),
+			("round", [z]) => z.map(f64::round),
+			("rect", [r, phi]) if r.is_real() && phi.is_real() => Complex::from_polar(r.re, phi.re),
+			// the rest only has a value for real numbers
+			_ => return Some(Err(NumericError::NotANumber)),
+		};
+		Some(Self::checked(result, args))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(actual: Complex, expected: Complex) {
+		assert!((actual - expected).abs() < 1e-12, "{actual} is not close to {expected}");
+	}
+
+	fn call(name: &str, args: &[Complex]) -> Complex {
+		ComplexMode.function(name, args).expect("the function should be complex").unwrap()
+	}
+
+	#[test]
+	fn display_test() {
+		assert_eq!(Complex::new(3.0, 4.0).to_string(), "3+4i");
+		assert_eq!(Complex::new(-5.0, -10.0).to_string(), "-5-10i");
+		assert_eq!(Complex::new(0.0, 1.0).to_string(), "i");
+		assert_eq!(Complex::new(0.0, -1.0).to_string(), "-i");
+		assert_eq!(Complex::new(0.5, -1.0).to_string(), "0.5-i");
+		assert_eq!(Complex::new(0.0, 2.5).to_string(), "2.5i");
+		assert_eq!(Complex::new(2.0, -0.0).to_string(), "2");
+		assert_eq!(Complex::new(-0.0, -0.0).to_string(), "0");
+		assert_eq!(Complex::new(1.0, f64::NAN).to_string(), "NaN");
+
+		let format = Format {
+			digits: Some(3),
+			..Format::default()
+		};
+		assert_eq!(ComplexMode.format_with(&Complex::new(1.0 / 3.0, -2.0 / 3.0), format), "0.333-0.667i");
+	}
+
+	#[test]
+	fn arithmetic_test() {
+		let mode = ComplexMode;
+		let (a, b) = (Complex::new(3.0, 4.0), Complex::new(1.0, -2.0));
+
+		assert_eq!(mode.mul(&a, &b), Ok(Complex::new(11.0, -2.0)));
+		assert_eq!(mode.div(&a, &b), Ok(Complex::new(-1.0, 2.0)));
+		assert_eq!(mode.add(&a, &b), Ok(Complex::new(4.0, 2.0)));
+		assert_eq!(mode.pow(&Complex::I, &Complex::real(2.0)), Ok(Complex::real(-1.0)));
+		assert_eq!(mode.pow(&Complex::new(1.0, 1.0), &Complex::real(-2.0)), Ok(Complex::new(0.0, -0.5)));
+		assert_eq!(mode.pow(&Complex::real(-4.0), &Complex::real(0.5)), Ok(Complex::new(0.0, 2.0)));
+		assert_eq!(mode.pow(&Complex::real(0.0), &Complex::I), Err(NumericError::NotANumber));
+		assert_eq!(mode.mul(&Complex::new(1e300, 1.0), &Complex::real(1e300)), Err(NumericError::Overflow));
+		assert_eq!(mode.rem(&Complex::I, &Complex::real(2.0)), Err(NumericError::NotANumber));
+
+		assert_eq!(mode.compare(&Complex::real(1.0), &Complex::real(2.0)), Some(Ordering::Less));
+		assert_eq!(mode.compare(&Complex::I, &Complex::I), Some(Ordering::Equal));
+		assert_eq!(mode.compare(&Complex::I, &Complex::real(2.0)), None);
+		assert_eq!(mode.order(&Complex::real(1.0), &Complex::real(2.0)), Ok(Some(Ordering::Less)));
+		assert_eq!(mode.order(&Complex::I, &Complex::I), Err(NumericError::Unordered));
+		assert_eq!(mode.to_integer(&Complex::new(2.0, 1.0)), None);
+	}
+
+	#[test]
+	fn identity_test() {
+		let z = Complex::new(0.3, -1.7);
+
+		// Euler's identity and its polar form
+		assert_close(call("exp", &[Complex::new(0.0, consts::PI)]), Complex::real(-1.0));
+		assert_close(call("rect", &[Complex::real(call("abs", &[z]).re), Complex::real(call("arg", &[z]).re)]), z);
+		assert_close(call("exp", &[call("ln", &[z])]), z);
+		assert_close(call("sqrt", &[z]) * call("sqrt", &[z]), z);
+		assert_close(call("sin", &[z]) * call("sin", &[z]) + call("cos", &[z]) * call("cos", &[z]), Complex::real(1.0));
+		assert_close(call("sin", &[call("asin", &[z])]), z);
+		assert_close(call("tan", &[call("atan", &[z])]), z);
+		assert_close(z * call("conj", &[z]), Complex::real(call("abs", &[z]).re.powi(2)));
+		assert_close(call("cosh", &[Complex::I * z]), call("cos", &[z]));
+
+		assert_eq!(call("sqrt", &[Complex::real(-1.0)]), Complex::I);
+		assert_eq!(call("ln", &[Complex::real(-1.0)]), Complex::new(0.0, consts::PI));
+		assert_eq!(call("ln", &[-Complex::real(1.0)]), Complex::new(0.0, consts::PI));
+		assert_eq!(call("abs", &[Complex::new(3.0, 4.0)]), Complex::real(5.0));
+		assert_eq!(call("im", &[Complex::new(3.0, 4.0)]), Complex::real(4.0));
+		// functions of real numbers with real results are left to `f64`
+		assert_eq!(ComplexMode.function("sqrt", &[Complex::real(4.0)]), None);
+		assert_eq!(ComplexMode.function("max", &[Complex::I, Complex::real(1.0)]), Some(Err(NumericError::NotANumber)));
+		assert_eq!(ComplexMode.function("sqrt", &[Complex::I, Complex::I]), None);
+	}
+}
@@ -0,0 +1,969 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use crate::{
+	bigint::BigInt,
+	functions,
+	numeric::{self, Numeric, NumericError},
+};
+
+/// A decimal `coefficient * 10^exponent` without trailing zeros in the coefficient
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BigDecimal {
+	coefficient: BigInt,
+	exponent: i64,
+}
+
+impl BigDecimal {
+	// the power of ten just above the leading digit, `123` has it at 3 and `0.05` at -1
+	fn top(&self) -> i64 {
+		self.exponent + self.coefficient.digits() as i64
+	}
+
+	// the coefficient mustn't end in a zero
+	fn new(coefficient: i64, exponent: i64) -> Self {
+		Self {
+			coefficient: BigInt::from(coefficient),
+			exponent,
+		}
+	}
+
+	fn one() -> Self {
+		Self::new(1, 0)
+	}
+
+	fn abs(&self) -> Self {
+		Self {
+			coefficient: self.coefficient.abs(),
+			exponent: self.exponent,
+		}
+	}
+
+	fn is_integer(&self) -> bool {
+		self.exponent >= 0
+	}
+}
+
+fn power_of_ten(exponent: u64) -> BigInt {
+	BigInt::from(10u64).pow(exponent)
+}
+
+// both coefficients scaled to the smaller exponent
+fn align(a: &BigDecimal, b: &BigDecimal) -> (BigInt, BigInt, i64) {
+	let exponent = a.exponent.min(b.exponent);
+	let scale = |value: &BigDecimal| &value.coefficient * &power_of_ten((value.exponent - exponent) as u64);
+	(scale(a), scale(b), exponent)
+}
+
+impl fmt::Display for BigDecimal {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let digits = self.coefficient.abs().to_string();
+		let sign = if self.coefficient.is_negative() { "-" } else { "" };
+		let adjusted = self.top() - 1;
+
+		// very large and very small numbers are easier to read with an exponent
+		if !(-7..=28).contains(&adjusted) {
+			let (first, rest) = digits.split_at(1);
+			let point = if rest.is_empty() { "" } else { "." };
+			return write!(f, "{sign}{first}{point}{rest}e{adjusted}");
+		}
+
+		if self.exponent >= 0 {
+			write!(f, "{sign}{digits}{}", "0".repeat(self.exponent as usize))
+		} else if adjusted >= 0 {
+			let (whole, fraction) = digits.split_at((adjusted + 1) as usize);
+			write!(f, "{sign}{whole}.{fraction}")
+		} else {
+			write!(f, "{sign}0.{}{digits}", "0".repeat((-adjusted - 1) as usize))
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Rounding {
+	/// To the nearest and to the even neighbour on a tie, also known as banker's rounding
+	#[default]
+	HalfEven,
+	/// To the nearest and away from zero on a tie
+	HalfUp,
+	/// To the nearest and towards zero on a tie
+	HalfDown,
+	/// Away from zero
+	Up,
+	/// Towards zero
+	Down,
+	/// Towards positive infinity
+	Ceiling,
+	/// Towards negative infinity
+	Floor,
+}
+
+impl fmt::Display for Rounding {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Rounding::HalfEven => write!(f, "half-even"),
+			Rounding::HalfUp => write!(f, "half-up"),
+			Rounding::HalfDown => write!(f, "half-down"),
+			Rounding::Up => write!(f, "up"),
+			Rounding::Down => write!(f, "down"),
+			Rounding::Ceiling => write!(f, "ceiling"),
+			Rounding::Floor => write!(f, "floor"),
+		}
+	}
+}
+
+impl FromStr for Rounding {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"half-even" => Ok(Rounding::HalfEven),
+			"half-up" => Ok(Rounding::HalfUp),
+			"half-down" => Ok(Rounding::HalfDown),
+			"up" => Ok(Rounding::Up),
+			"down" => Ok(Rounding::Down),
+			"ceiling" => Ok(Rounding::Ceiling),
+			"floor" => Ok(Rounding::Floor),
+			_ => Err(name.to_string()),
+		}
+	}
+}
+
+/// Decimal arithmetic rounding every result to a number of significant digits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecimalMode {
+	pub precision: usize,
+	pub rounding: Rounding,
+}
+
+impl Default for DecimalMode {
+	fn default() -> Self {
+		Self {
+			precision: 28,
+			rounding: Rounding::default(),
+		}
+	}
+}
+
+impl DecimalMode {
+	/// Results with the leading digit further from the decimal point than this overflow or become zero
+	pub const MAX_EXPONENT: i64 = 100_000;
+
+	// whether dropping `remainder` from the truncated `quotient` should round the magnitude up
+	fn rounds_up(&self, quotient: &BigInt, remainder: &BigInt, divisor: &BigInt, negative: bool) -> bool {
+		if remainder.is_zero() {
+			return false;
+		}
+
+		let half = (&remainder.abs() + &remainder.abs()).cmp(divisor);
+		match self.rounding {
+			Rounding::Up => true,
+			Rounding::Down => false,
+			Rounding::Ceiling => !negative,
+			Rounding::Floor => negative,
+			Rounding::HalfUp => half != Ordering::Less,
+			Rounding::HalfDown => half == Ordering::Greater,
+			Rounding::HalfEven => half == Ordering::Greater || (half == Ordering::Equal && !quotient.is_even()),
+		}
+	}
+
+	fn round(&self, mut coefficient: BigInt, mut exponent: i64) -> Result<BigDecimal, NumericError> {
+		if coefficient.is_zero() {
+			return Ok(BigDecimal::default());
+		}
+
+		let digits = coefficient.digits();
+		if digits > self.precision {
+			let dropped = (digits - self.precision) as u64;
+			let divisor = power_of_ten(dropped);
+			let (quotient, remainder) = coefficient.div_rem(&divisor).expect("BUG: power of ten is zero");
+			let negative = coefficient.is_negative();
+
+			coefficient = quotient.clone();
+			if self.rounds_up(&quotient, &remainder, &divisor, negative) {
+				let step = if negative { -&BigInt::one() } else { BigInt::one() };
+				coefficient = &coefficient + &step;
+			}
+			exponent += dropped as i64;
+		}
+
+		// trailing zeros carry no information so every value has a single representation
+		let ten = BigInt::from(10u64);
+		while let Some((quotient, remainder)) = coefficient.div_rem(&ten)
+			&& remainder.is_zero()
+		{
+			coefficient = quotient;
+			exponent += 1;
+		}
+
+		let value = BigDecimal { coefficient, exponent };
+		match value.top() - 1 {
+			adjusted if adjusted > Self::MAX_EXPONENT => Err(NumericError::Overflow),
+			adjusted if adjusted < -Self::MAX_EXPONENT => Ok(BigDecimal::default()),
+			_ => Ok(value),
+		}
+	}
+
+	// a precision that gives intermediate results enough guard digits for the final rounding to be right
+	fn working(&self, guard: usize) -> DecimalMode {
+		DecimalMode {
+			precision: self.precision + guard,
+			rounding: Rounding::HalfEven,
+		}
+	}
+
+	fn rounded(&self, value: BigDecimal) -> Result<BigDecimal, NumericError> {
+		self.round(value.coefficient, value.exponent)
+	}
+
+	// a non-integer power is `e^(y ln(x))` unless it's a square root, which may well be exact
+	fn root_pow(&self, base: &BigDecimal, exponent: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let doubled = self.working(1).add(exponent, exponent)?;
+		if doubled.is_integer() {
+			return self.pow(&self.working(2).root(base, 2)?, &doubled);
+		}
+		if base.coefficient.is_negative() {
+			return Err(NumericError::NotANumber);
+		}
+		if base.coefficient.is_zero() {
+			return Ok(BigDecimal::default());
+		}
+
+		// `exp` loses as many digits as its argument has before the point
+		let estimate = self.working(5).mul(exponent, &self.working(5).ln(base)?)?;
+		match estimate.top() {
+			top if top > 7 && estimate.coefficient.is_negative() => Ok(BigDecimal::default()),
+			top if top > 7 => Err(NumericError::Overflow),
+			top => {
+				let working = self.working(top.max(0) as usize + 5);
+				self.rounded(working.exp(&working.mul(exponent, &working.ln(base)?)?)?)
+			},
+		}
+	}
+}
+
+// the functions are calculated with a few guard digits and rounded once at the end
+impl DecimalMode {
+	// the sum of a series whose terms each come from the one before, up to the first that no longer shows
+	fn series(
+		&self,
+		first: BigDecimal,
+		next: impl Fn(&BigDecimal, i128) -> Result<BigDecimal, NumericError>,
+	) -> Result<BigDecimal, NumericError> {
+		let (mut sum, mut term) = (first.clone(), first);
+		for n in 1.. {
+			term = next(&term, n)?;
+			if term.coefficient.is_zero() || term.top() < sum.top() - self.precision as i64 {
+				break;
+			}
+			sum = self.add(&sum, &term)?;
+		}
+		Ok(sum)
+	}
+
+	// the integer root of the coefficient scaled to enough digits, with a sticky one for the rest like `div`
+	fn root(&self, x: &BigDecimal, n: u32) -> Result<BigDecimal, NumericError> {
+		if x.coefficient.is_negative() && n.is_multiple_of(2) {
+			return Err(NumericError::NotANumber);
+		}
+		if x.coefficient.is_zero() {
+			return Ok(BigDecimal::default());
+		}
+
+		let degree = i64::from(n);
+		let wanted = (degree * (self.precision as i64 + 2) - x.coefficient.digits() as i64).max(0);
+		// the exponent left has to divide by the degree
+		let shift = wanted + (x.exponent - wanted).rem_euclid(degree);
+		let scaled = &x.coefficient.abs() * &power_of_ten(shift as u64);
+		let root = scaled.root(n);
+		let exponent = (x.exponent - shift) / degree;
+
+		let (root, exponent) = if root.pow(u64::from(n)) == scaled {
+			(root, exponent)
+		} else {
+			(&(&root * &BigInt::from(10u64)) + &BigInt::one(), exponent - 1)
+		};
+		self.round(if x.coefficient.is_negative() { -&root } else { root }, exponent)
+	}
+
+	// the series after halving `x` until it converges quickly, squared as often afterwards
+	fn exp(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if x.coefficient.is_zero() {
+			return Ok(BigDecimal::one());
+		}
+		if x.coefficient.is_negative() {
+			// a result too small to show is the inverse of one too large to hold
+			return match self.working(2).exp(&self.neg(x)?) {
+				Ok(inverse) => self.div(&BigDecimal::one(), &inverse),
+				Err(NumericError::Overflow) => Ok(BigDecimal::default()),
+				Err(error) => Err(error),
+			};
+		}
+		// `e^(10^7)` is far beyond the largest exponent
+		if x.top() > 7 {
+			return Err(NumericError::Overflow);
+		}
+
+		let halvings = (self.to_f64(x) * 1000.0).log2().ceil().max(0.0) as u32;
+		// every squaring doubles the error of the one before
+		let working = self.working(halvings as usize / 3 + 5);
+		let reduced = working.div(x, &working.integer(1 << halvings)?)?;
+		let mut result =
+			working.series(BigDecimal::one(), |term, n| working.div(&working.mul(term, &reduced)?, &working.integer(n)?))?;
+		for _ in 0..halvings {
+			result = working.mul(&result, &result)?;
+		}
+		self.rounded(result)
+	}
+
+	// `ln(m * 10^k)` is `ln(m) + k ln(10)` with `m` between one and ten, unless `x` is close enough to one
+	// for the sum to cancel
+	fn ln(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if x.coefficient.is_negative() {
+			return Err(NumericError::NotANumber);
+		}
+		if x.coefficient.is_zero() {
+			return Err(NumericError::Overflow);
+		}
+
+		let shift = if self.near_one(x) { 0 } else { x.top() - 1 };
+		// the multiple of `ln(10)` needs as many more digits as it has before the point
+		let working = self.working(shift.unsigned_abs().to_string().len() + 3);
+		let mantissa = BigDecimal {
+			coefficient: x.coefficient.clone(),
+			exponent: x.exponent - shift,
+		};
+		let mut result = working.logarithm(&mantissa)?;
+		if shift != 0 {
+			let ten = working.logarithm(&working.integer(10)?)?;
+			result = working.add(&result, &working.mul(&working.integer(shift.into())?, &ten)?)?;
+		}
+		self.rounded(result)
+	}
+
+	fn near_one(&self, x: &BigDecimal) -> bool {
+		let (half, two) = (BigDecimal::new(5, -1), BigDecimal::new(2, 0));
+		self.compare(x, &half) != Some(Ordering::Less) && self.compare(x, &two) != Some(Ordering::Greater)
+	}
+
+	// `ln(x)` is `y + 2 atanh(z)` with `z = (x - e^y) / (x + e^y)`, the `f64` guess `y` makes `z` tiny but
+	// is left out near one where small results would lose their digits to it
+	fn logarithm(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(3);
+		let guess = if self.near_one(x) {
+			BigDecimal::default()
+		} else {
+			working.value(self.to_f64(x).ln())?
+		};
+		let power = working.exp(&guess)?;
+		let z = working.div(&working.sub(x, &power)?, &working.add(x, &power)?)?;
+
+		let square = working.mul(&z, &z)?;
+		let atanh = working.series(z.clone(), |term, n| {
+			let term = working.mul(&working.mul(term, &square)?, &working.integer(2 * n - 1)?)?;
+			working.div(&term, &working.integer(2 * n + 1)?)
+		})?;
+		working.add(&guess, &working.add(&atanh, &atanh)?)
+	}
+
+	fn log(&self, x: &BigDecimal, base: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(3);
+		let divisor = working.ln(base)?;
+		if divisor.coefficient.is_zero() {
+			return Err(NumericError::NotANumber);
+		}
+		self.div(&working.ln(x)?, &divisor)
+	}
+
+	// Machin's formula `pi = 16 atan(1/5) - 4 atan(1/239)`
+	fn pi(&self) -> Result<BigDecimal, NumericError> {
+		let working = self.working(3);
+		let arccot = |n: i128| working.atan_series(&working.div(&BigDecimal::one(), &working.integer(n)?)?);
+		let pi = working
+			.sub(&working.mul(&working.integer(16)?, &arccot(5)?)?, &working.mul(&working.integer(4)?, &arccot(239)?)?)?;
+		self.rounded(pi)
+	}
+
+	fn right_angle(&self, negative: bool) -> Result<BigDecimal, NumericError> {
+		let right = self.div(&self.pi()?, &BigDecimal::new(2, 0))?;
+		if negative { self.neg(&right) } else { Ok(right) }
+	}
+
+	// `x - x^3/3 + x^5/5 - ...` which only converges quickly for small `x`
+	fn atan_series(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let square = self.mul(x, x)?;
+		self.series(x.clone(), |term, n| {
+			let term = self.mul(&self.mul(term, &square)?, &self.integer(2 * n - 1)?)?;
+			self.neg(&self.div(&term, &self.integer(2 * n + 1)?)?)
+		})
+	}
+
+	fn atan(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		let one = BigDecimal::one();
+		let magnitude = x.abs();
+
+		let angle = if working.compare(&magnitude, &one) == Some(Ordering::Greater) {
+			// what the angle of the inverse leaves of a right angle
+			let right = working.right_angle(x.coefficient.is_negative())?;
+			working.sub(&right, &working.atan(&working.div(&one, x)?)?)?
+		} else {
+			// halving the angle three times makes the series converge quickly
+			let mut half = x.clone();
+			for _ in 0..3 {
+				let hypot = working.root(&working.add(&one, &working.mul(&half, &half)?)?, 2)?;
+				half = working.div(&half, &working.add(&one, &hypot)?)?;
+			}
+			working.mul(&working.integer(8)?, &working.atan_series(&half)?)?
+		};
+		self.rounded(angle)
+	}
+
+	fn atan2(&self, y: &BigDecimal, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if x.coefficient.is_zero() {
+			return if y.coefficient.is_zero() {
+				Ok(BigDecimal::default())
+			} else {
+				self.right_angle(y.coefficient.is_negative())
+			};
+		}
+
+		let working = self.working(5);
+		let angle = working.atan(&working.div(y, x)?)?;
+		if !x.coefficient.is_negative() {
+			return self.rounded(angle);
+		}
+		// the left half plane is half a turn away
+		let pi = working.pi()?;
+		self.rounded(if y.coefficient.is_negative() {
+			working.sub(&angle, &pi)?
+		} else {
+			working.add(&angle, &pi)?
+		})
+	}
+
+	fn asin(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		let one = BigDecimal::one();
+		let rest = working.mul(&working.sub(&one, x)?, &working.add(&one, x)?)?;
+		if rest.coefficient.is_negative() {
+			return Err(NumericError::NotANumber);
+		}
+		if rest.coefficient.is_zero() {
+			return self.right_angle(x.coefficient.is_negative());
+		}
+		self.rounded(working.atan(&working.div(x, &working.root(&rest, 2)?)?)?)
+	}
+
+	fn acos(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		self.rounded(working.sub(&working.right_angle(false)?, &working.asin(x)?)?)
+	}
+
+	// both after taking whole turns off, which needs as many more digits of `pi` as the angle has before
+	// the point
+	fn sin_cos(&self, x: &BigDecimal) -> Result<(BigDecimal, BigDecimal), NumericError> {
+		// that many digits of `pi` would take too long
+		if x.top() > 1000 {
+			return Err(NumericError::Inexact);
+		}
+
+		let working = self.working(x.top().max(0) as usize + 5);
+		let turn = working.mul(&BigDecimal::new(2, 0), &working.pi()?)?;
+		let turns = working.floor(&working.add(&working.div(x, &turn)?, &BigDecimal::new(5, -1))?);
+		let angle = working.sub(x, &working.mul(&turns, &turn)?)?;
+
+		let square = working.mul(&angle, &angle)?;
+		let sin = working.series(angle.clone(), |term, n| {
+			working.neg(&working.div(&working.mul(term, &square)?, &working.integer(2 * n * (2 * n + 1))?)?)
+		})?;
+		let cos = working.series(BigDecimal::one(), |term, n| {
+			working.neg(&working.div(&working.mul(term, &square)?, &working.integer((2 * n - 1) * 2 * n)?)?)
+		})?;
+		Ok((sin, cos))
+	}
+
+	fn tan(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let (sin, cos) = self.sin_cos(x)?;
+		if cos.coefficient.is_zero() {
+			return Err(NumericError::Overflow);
+		}
+		self.div(&sin, &cos)
+	}
+
+	fn is_small(&self, x: &BigDecimal) -> bool {
+		x.top() <= 0
+	}
+
+	fn sinh(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		// the difference of the powers cancels for small `x`
+		let sinh = if self.is_small(x) {
+			let square = working.mul(x, x)?;
+			working.series(x.clone(), |term, n| {
+				working.div(&working.mul(term, &square)?, &working.integer(2 * n * (2 * n + 1))?)
+			})?
+		} else {
+			let difference = working.sub(&working.exp(x)?, &working.exp(&working.neg(x)?)?)?;
+			working.div(&difference, &BigDecimal::new(2, 0))?
+		};
+		self.rounded(sinh)
+	}
+
+	fn cosh(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		let sum = working.add(&working.exp(x)?, &working.exp(&working.neg(x)?)?)?;
+		self.div(&sum, &BigDecimal::new(2, 0))
+	}
+
+	fn tanh(&self, x: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		if self.is_small(x) {
+			return self.div(&working.sinh(x)?, &working.cosh(x)?);
+		}
+		// `(1 - e^-2|x|) / (1 + e^-2|x|)` goes to one where the powers themselves would overflow
+		let magnitude = x.abs();
+		let power = working.exp(&working.neg(&working.add(&magnitude, &magnitude)?)?)?;
+		let one = BigDecimal::one();
+		let tanh = self.div(&working.sub(&one, &power)?, &working.add(&one, &power)?)?;
+		if x.coefficient.is_negative() {
+			self.neg(&tanh)
+		} else {
+			Ok(tanh)
+		}
+	}
+
+	fn hypot(&self, args: &[BigDecimal]) -> Result<BigDecimal, NumericError> {
+		let working = self.working(5);
+		let sum =
+			args.iter().try_fold(BigDecimal::default(), |sum, value| working.add(&sum, &working.mul(value, value)?))?;
+		self.root(&sum, 2)
+	}
+}
+
+impl Numeric for DecimalMode {
+	type Value = BigDecimal;
+
+	const EXACT: bool = true;
+
+	fn value(&self, number: f64) -> Result<BigDecimal, NumericError> {
+		if number.is_nan() {
+			return Err(NumericError::NotANumber);
+		}
+		let (coefficient, exponent) = numeric::shortest_decimal(number).ok_or(NumericError::Overflow)?;
+		self.round(coefficient, exponent)
+	}
+
+	fn literal(&self, digits: &str) -> Result<BigDecimal, NumericError> {
+		let (coefficient, exponent) = numeric::decimal_digits(digits).ok_or(NumericError::NotANumber)?;
+		self.round(coefficient, exponent)
+	}
+
+	fn to_f64(&self, value: &BigDecimal) -> f64 {
+		format!("{}e{}", value.coefficient, value.exponent).parse().expect("BUG: decimal that doesn't parse as f64")
+	}
+
+	fn integer(&self, integer: i128) -> Result<BigDecimal, NumericError> {
+		self.round(BigInt::from(integer), 0)
+	}
+
+	fn to_integer(&self, value: &BigDecimal) -> Option<i128> {
+		// an `i128` has at most 39 digits so anything further out can't fit
+		if !value.is_integer() || value.top() > 39 {
+			return None;
+		}
+		(&value.coefficient * &power_of_ten(value.exponent as u64)).to_i128()
+	}
+
+	fn is_zero(&self, value: &BigDecimal) -> bool {
+		value.coefficient.is_zero()
+	}
+
+	fn is_negative(&self, value: &BigDecimal) -> bool {
+		value.coefficient.is_negative()
+	}
+
+	fn compare(&self, a: &BigDecimal, b: &BigDecimal) -> Option<Ordering> {
+		let zero = BigInt::zero();
+		let (sign_a, sign_b) = (a.coefficient.cmp(&zero), b.coefficient.cmp(&zero));
+		if sign_a != sign_b || sign_a == Ordering::Equal {
+			return Some(sign_a.cmp(&sign_b));
+		}
+
+		// the leading digits decide unless they are in the same place
+		let by_magnitude = match a.top().cmp(&b.top()) {
+			Ordering::Equal => {
+				let (a, b, _) = align(a, b);
+				a.abs().cmp(&b.abs())
+			},
+			ordering => ordering,
+		};
+		Some(if a.coefficient.is_negative() {
+			by_magnitude.reverse()
+		} else {
+			by_magnitude
+		})
+	}
+
+	fn neg(&self, value: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		Ok(BigDecimal {
+			coefficient: -&value.coefficient,
+			exponent: value.exponent,
+		})
+	}
+
+	fn add(&self, a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if a.coefficient.is_zero() || b.coefficient.is_zero() {
+			let value = if a.coefficient.is_zero() { b } else { a };
+			return self.round(value.coefficient.clone(), value.exponent);
+		}
+
+		// an operand far below the last digit of the other only matters for which way we round so a single
+		// digit in its place keeps aligning the two cheap
+		let sticky = |small: &BigDecimal, large: &BigDecimal| {
+			let place = large.top() - self.precision as i64 - 3;
+			if small.top() <= place {
+				let digit = if small.coefficient.is_negative() {
+					-&BigInt::one()
+				} else {
+					BigInt::one()
+				};
+				BigDecimal {
+					coefficient: digit,
+					exponent: place,
+				}
+			} else {
+				small.clone()
+			}
+		};
+		let (a, b) = (sticky(a, b), sticky(b, a));
+
+		let (a, b, exponent) = align(&a, &b);
+		self.round(&a + &b, exponent)
+	}
+
+	fn sub(&self, a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		self.add(a, &self.neg(b)?)
+	}
+
+	fn mul(&self, a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		self.round(&a.coefficient * &b.coefficient, a.exponent + b.exponent)
+	}
+
+	fn div(&self, a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if a.coefficient.is_zero() {
+			return Ok(BigDecimal::default());
+		}
+
+		// scale the dividend so the quotient has two digits more than we keep
+		let shift = (self.precision + 2 + b.coefficient.digits()).saturating_sub(a.coefficient.digits());
+		let dividend = &a.coefficient * &power_of_ten(shift as u64);
+		let (quotient, remainder) =
+			dividend.div_rem(&b.coefficient).expect("BUG: division by zero should be caught by the caller");
+		let exponent = a.exponent - b.exponent - shift as i64;
+
+		if remainder.is_zero() {
+			return self.round(quotient, exponent);
+		}
+		// a trailing one stands in for everything after the last digit when rounding
+		let sticky = if quotient.is_negative() {
+			-&BigInt::one()
+		} else {
+			BigInt::one()
+		};
+		self.round(&(&quotient * &BigInt::from(10u64)) + &sticky, exponent - 1)
+	}
+
+	fn rem(&self, a: &BigDecimal, b: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if a.coefficient.is_zero() || a.top() < b.top() {
+			return Ok(a.clone());
+		}
+		// like with the quotient of a division we only go as far as the precision reaches
+		if a.top() - b.top() > self.precision as i64 {
+			return Err(NumericError::Overflow);
+		}
+
+		let (a, b, exponent) = align(a, b);
+		let (_, remainder) = a.div_rem(&b).expect("BUG: division by zero should be caught by the caller");
+		self.round(remainder, exponent)
+	}
+
+	fn pow(&self, base: &BigDecimal, exponent: &BigDecimal) -> Result<BigDecimal, NumericError> {
+		if !exponent.is_integer() {
+			return self.root_pow(base, exponent);
+		}
+
+		let one = BigDecimal::one();
+		if exponent.coefficient.is_zero() {
+			return Ok(one);
+		}
+		if base.coefficient.is_zero() {
+			return Ok(BigDecimal::default());
+		}
+
+		let power = (&exponent.coefficient.abs() * &power_of_ten(exponent.exponent as u64)).to_u64();
+		let power = match power {
+			Some(power) => power,
+			// only a magnitude of one can survive an exponent that large
+			None if base.coefficient.abs() == BigInt::one() && base.exponent == 0 => {
+				let even = exponent.exponent > 0 || exponent.coefficient.is_even();
+				return if base.coefficient.is_negative() && even {
+					self.neg(base)
+				} else {
+					Ok(base.clone())
+				};
+			},
+			None => return Err(NumericError::Overflow),
+		};
+
+		// every multiplication rounds so a few more digits keep the errors out of the result
+		let working = self.working(power.ilog10() as usize + 3);
+		// inverting first lets tiny results become zero instead of overflowing on the way
+		let mut base = if exponent.coefficient.is_negative() {
+			working.div(&one, base)?
+		} else {
+			base.clone()
+		};
+		let mut result = one;
+		let mut remaining = power;
+		while remaining > 0 {
+			if remaining % 2 == 1 {
+				result = working.mul(&result, &base)?;
+			}
+			remaining /= 2;
+			if remaining > 0 {
+				base = working.mul(&base, &base)?;
+			}
+		}
+
+		self.round(result.coefficient, result.exponent)
+	}
+
+	fn floor(&self, value: &BigDecimal) -> BigDecimal {
+		if value.is_integer() {
+			return value.clone();
+		}
+		let divisor = power_of_ten(value.exponent.unsigned_abs());
+		let floor = value.coefficient.div_floor(&divisor).expect("BUG: power of ten is zero");
+		self.round(floor, 0).expect("BUG: the floor of a decimal is larger than the decimal")
+	}
+
+	fn constant(&self, name: &str) -> Option<Result<BigDecimal, NumericError>> {
+		match name {
+			"pi" => Some(self.pi()),
+			"e" => Some(self.exp(&BigDecimal::one())),
+			"tau" => Some(self.working(2).pi().and_then(|pi| self.add(&pi, &pi))),
+			_ => None,
+		}
+	}
+
+	fn function(&self, name: &str, args: &[BigDecimal]) -> Option<Result<BigDecimal, NumericError>> {
+		if !functions::arity(name).is_some_and(|arity| arity.accepts(args.len())) {
+			return None;
+		}
+		let sin_cos = |x| self.sin_cos(x).map(|(sin, cos)| (self.rounded(sin), self.rounded(cos)));
+
+		Some(match (name, args) {
+			("sqrt", [x]) => self.root(x, 2),
+			("cbrt", [x]) => self.root(x, 3),
+			("exp", [x]) => self.exp(x),
+			("ln", [x]) => self.ln(x),
+			("log", [x]) => self.log(x, &BigDecimal::new(1, 1)),
+			("log", [x, base]) => self.log(x, base),
+			("log2", [x]) => self.log(x, &BigDecimal::new(2, 0)),
+			("sin", [x]) => sin_cos(x).and_then(|(sin, _)| sin),
+			("cos", [x]) => sin_cos(x).and_then(|(_, cos)| cos),
+			("tan", [x]) => self.tan(x),
+			("asin", [x]) => self.asin(x),
+			("acos", [x]) => self.acos(x),
+			("atan", [x]) => self.atan(x),
+			("atan2", [y, x]) => self.atan2(y, x),
+			("sinh", [x]) => self.sinh(x),
+			("cosh", [x]) => self.cosh(x),
+			("tanh", [x]) => self.tanh(x),
+			("hypot", _) => self.hypot(args),
+			// the angle of a negative number is `pi`
+			("arg", [x]) if x.coefficient.is_negative() => self.pi(),
+			_ => return None,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn decimal(text: &str) -> BigDecimal {
+		let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+		let coefficient = format!("{whole}{fraction}").parse().unwrap();
+		DecimalMode::default().round(coefficient, -(fraction.len() as i64)).unwrap()
+	}
+
+	fn mode(precision: usize, rounding: Rounding) -> DecimalMode {
+		DecimalMode { precision, rounding }
+	}
+
+	#[test]
+	fn display_test() {
+		assert_eq!(decimal("0.30").to_string(), "0.3");
+		assert_eq!(decimal("-12.5").to_string(), "-12.5");
+		assert_eq!(decimal("1200").to_string(), "1200");
+		assert_eq!(decimal("0.000001").to_string(), "0.000001");
+		assert_eq!(decimal("0").to_string(), "0");
+		assert_eq!(decimal("1000000000000000000000000000000").to_string(), "1e30");
+		assert_eq!(decimal("-0.000000012").to_string(), "-1.2e-8");
+	}
+
+	#[test]
+	fn arithmetic_test() {
+		let exact = DecimalMode::default();
+
+		assert_eq!(exact.add(&decimal("0.1"), &decimal("0.2")), Ok(decimal("0.3")));
+		assert_eq!(exact.sub(&decimal("1"), &decimal("0.99")), Ok(decimal("0.01")));
+		assert_eq!(exact.mul(&decimal("1.5"), &decimal("-0.2")), Ok(decimal("-0.3")));
+		assert_eq!(exact.div(&decimal("1"), &decimal("8")), Ok(decimal("0.125")));
+		assert_eq!(exact.div(&decimal("2"), &decimal("3")), Ok(decimal("0.6666666666666666666666666667")));
+		assert_eq!(exact.div(&decimal("-1"), &decimal("3")), Ok(decimal("-0.3333333333333333333333333333")));
+		assert_eq!(exact.rem(&decimal("7.5"), &decimal("2")), Ok(decimal("1.5")));
+		assert_eq!(exact.rem(&decimal("-7"), &decimal("3")), Ok(decimal("-1")));
+		assert_eq!(exact.rem(&decimal("1"), &decimal("300")), Ok(decimal("1")));
+		assert_eq!(exact.floor(&decimal("-2.5")), decimal("-3"));
+		assert_eq!(exact.floor(&decimal("2.5")), decimal("2"));
+		assert_eq!(exact.compare(&decimal("0.1"), &decimal("0.09")), Some(Ordering::Greater));
+		assert_eq!(exact.compare(&decimal("-10"), &decimal("-9")), Some(Ordering::Less));
+		assert_eq!(exact.compare(&decimal("-1"), &decimal("0")), Some(Ordering::Less));
+	}
+
+	#[test]
+	fn add_far_apart_test() {
+		let huge = DecimalMode::default().pow(&decimal("10"), &decimal("50000")).unwrap();
+		let tiny = decimal("0.001");
+
+		assert_eq!(mode(5, Rounding::HalfEven).add(&huge, &tiny), Ok(huge.clone()));
+		assert_eq!(
+			mode(5, Rounding::Up).add(&huge, &tiny).map(|value| value.to_string()),
+			Ok(String::from("1.0001e50000"))
+		);
+		assert_eq!(
+			mode(5, Rounding::Down).sub(&huge, &tiny).map(|value| value.to_string()),
+			Ok(String::from("9.9999e49999"))
+		);
+	}
+
+	#[test]
+	fn rounding_test() {
+		let round = |rounding, text: &str| {
+			let value = decimal(text);
+			mode(2, rounding).round(value.coefficient, value.exponent).unwrap().to_string()
+		};
+
+		let cases = [
+			(Rounding::HalfEven, ["0.12", "0.12", "0.13", "-0.12", "0.16"]),
+			(Rounding::HalfUp, ["0.12", "0.13", "0.13", "-0.13", "0.16"]),
+			(Rounding::HalfDown, ["0.12", "0.12", "0.13", "-0.12", "0.16"]),
+			(Rounding::Up, ["0.13", "0.13", "0.13", "-0.13", "0.16"]),
+			(Rounding::Down, ["0.12", "0.12", "0.12", "-0.12", "0.15"]),
+			(Rounding::Ceiling, ["0.13", "0.13", "0.13", "-0.12", "0.16"]),
+			(Rounding::Floor, ["0.12", "0.12", "0.12", "-0.13", "0.15"]),
+		];
+		for (rounding, expected) in cases {
+			let rounded = ["0.1201", "0.125", "0.1251", "-0.125", "0.1551"].map(|text| round(rounding, text));
+			assert_eq!(rounded, expected, "{rounding}");
+		}
+		assert_eq!(round(Rounding::HalfUp, "9.96"), "10");
+	}
+
+	#[test]
+	fn pow_test() {
+		let exact = DecimalMode::default();
+
+		assert_eq!(exact.pow(&decimal("1.1"), &decimal("2")), Ok(decimal("1.21")));
+		assert_eq!(exact.pow(&decimal("2"), &decimal("-2")), Ok(decimal("0.25")));
+		assert_eq!(
+			exact.pow(&decimal("2"), &decimal("100")).map(|value| value.to_string()),
+			Ok(String::from("1.267650600228229401496703205e30"))
+		);
+		assert_eq!(exact.pow(&decimal("4"), &decimal("0.5")), Ok(decimal("2")));
+		assert_eq!(exact.pow(&decimal("-1"), &decimal("100000000000000000001")), Ok(decimal("-1")));
+		assert_eq!(exact.pow(&decimal("10"), &decimal("100001")), Err(NumericError::Overflow));
+		assert_eq!(exact.pow(&decimal("10"), &decimal("-100001")), Ok(decimal("0")));
+		assert_eq!(exact.pow(&decimal("-8"), &decimal("0.5")), Err(NumericError::NotANumber));
+		assert_eq!(exact.pow(&decimal("2"), &decimal("0.5")), Ok(decimal("1.414213562373095048801688724")));
+		assert_eq!(exact.pow(&decimal("8"), &decimal("1.5")), Ok(decimal("22.62741699796952078082701959")));
+		assert_eq!(exact.pow(&decimal("10"), &decimal("0.3")), Ok(decimal("1.995262314968879601352455397")));
+	}
+
+	#[test]
+	fn function_test() {
+		let exact = mode(50, Rounding::HalfEven);
+		let call = |name, args: &[&str]| {
+			let args = args.iter().map(|arg| decimal(arg)).collect::<Vec<_>>();
+			exact.function(name, &args).map(|result| result.map(|value| value.to_string()))
+		};
+		let ok = |digits: &str| Some(Ok(String::from(digits)));
+
+		assert_eq!(call("sqrt", &["2"]), ok("1.4142135623730950488016887242096980785696718753769"));
+		assert_eq!(call("sqrt", &["0.0625"]), ok("0.25"));
+		assert_eq!(call("cbrt", &["-27"]), ok("-3"));
+		assert_eq!(call("exp", &["1"]), ok("2.7182818284590452353602874713526624977572470937"));
+		assert_eq!(call("exp", &["-1000"]), ok("5.0759588975494567652918094795743369193055992828928e-435"));
+		assert_eq!(call("ln", &["2"]), ok("0.69314718055994530941723212145817656807550013436026"));
+		assert_eq!(call("ln", &["1.0001"]), ok("0.000099995000333308335333166680951131063482064401071076"));
+		assert_eq!(call("log", &["1000"]), ok("3"));
+		assert_eq!(call("sin", &["1"]), ok("0.84147098480789650665250232163029899962256306079837"));
+		assert_eq!(call("cos", &["0"]), ok("1"));
+		assert_eq!(call("atan", &["1"]), ok("0.78539816339744830961566084581987572104929234984378"));
+		assert_eq!(call("asin", &["-1"]), ok("-1.5707963267948966192313216916397514420985846996876"));
+		assert_eq!(call("tanh", &["-100000"]), ok("-1"));
+		assert_eq!(call("hypot", &["3", "4"]), ok("5"));
+		assert_eq!(call("ln", &["0"]), Some(Err(NumericError::Overflow)));
+		assert_eq!(call("asin", &["2"]), Some(Err(NumericError::NotANumber)));
+		assert_eq!(call("exp", &["1000000"]), Some(Err(NumericError::Overflow)));
+		assert_eq!(call("floor", &["2.5"]), None);
+		assert_eq!(
+			exact.constant("pi").map(|result| result.map(|value| value.to_string())),
+			ok("3.1415926535897932384626433832795028841971693993751")
+		);
+	}
+
+	#[test]
+	fn f64_test() {
+		let exact = DecimalMode::default();
+
+		assert_eq!(exact.value(0.1), Ok(decimal("0.1")));
+		assert_eq!(exact.value(1e300).map(|value| value.to_string()), Ok(String::from("1e300")));
+		assert_eq!(exact.value(f64::NEG_INFINITY), Err(NumericError::Overflow));
+		assert_eq!(exact.to_f64(&decimal("-0.125")), -0.125);
+		assert_eq!(exact.to_f64(&exact.pow(&decimal("10"), &decimal("400")).unwrap()), f64::INFINITY);
+		assert_eq!(mode(3, Rounding::Down).value(2.0 / 3.0), Ok(decimal("0.666")));
+		assert_eq!(exact.literal("12345678901234567890.123"), Ok(decimal("12345678901234567890.123")));
+		assert_eq!(mode(3, Rounding::Down).literal("0.66699999999999999999"), Ok(decimal("0.666")));
+		assert_eq!(exact.literal("1e-99999999999999999999"), Ok(decimal("0")));
+	}
+
+	#[test]
+	fn integer_test() {
+		let exact = DecimalMode::default();
+
+		assert_eq!(exact.integer(-12300), Ok(decimal("-12300")));
+		assert_eq!(mode(2, Rounding::HalfEven).integer(125), Ok(decimal("120")));
+		assert_eq!(exact.to_integer(&decimal("-12300")), Some(-12300));
+		assert_eq!(exact.to_integer(&decimal("1.5")), None);
+		assert_eq!(exact.to_integer(&exact.value(1e300).unwrap()), None);
+	}
+
+	#[test]
+	fn from_str_test() {
+		for rounding in [
+			Rounding::HalfEven,
+			Rounding::HalfUp,
+			Rounding::HalfDown,
+			Rounding::Up,
+			Rounding::Down,
+			Rounding::Ceiling,
+			Rounding::Floor,
+		] {
+			assert_eq!(rounding.to_string().parse(), Ok(rounding));
+		}
+		assert_eq!("nearest".parse::<Rounding>(), Err(String::from("nearest")));
+	}
+}
@@ -0,0 +1,295 @@
+use std::fmt;
+
+use crate::{
+	expr::{self, Ast, Expr},
+	functions,
+	shunting::{Operator, Span},
+	units::Symbol,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum DiffError {
+	NotDifferentiable(String, Span),
+	UnknownFunction(String, Span),
+}
+
+impl DiffError {
+	pub fn span(&self) -> Span {
+		match self {
+			DiffError::NotDifferentiable(_, span) | DiffError::UnknownFunction(_, span) => *span,
+		}
+	}
+}
+
+impl fmt::Display for DiffError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			DiffError::NotDifferentiable(what, _) => write!(f, "`{what}` can't be differentiated"),
+			DiffError::UnknownFunction(name, _) => write!(f, "unknown function `{name}`"),
+		}
+	}
+}
+
+/// Whether the variable appears anywhere in the expression
+pub fn depends_on(ast: &Ast, variable: &str) -> bool {
+	match &ast.node {
+		// `2t^2` is a product when its unit is the variable
+		Expr::Variable(name) | Expr::Quantity(_, Symbol { name, .. }) => name == variable,
+		Expr::Imaginary(_) => variable == "i",
+		Expr::Number(_) | Expr::Integer(..) | Expr::Decimal(_) | Expr::Bool(_) => false,
+		Expr::Unary(_, operand) => depends_on(operand, variable),
+		Expr::Binary(_, left, right) => depends_on(left, variable) || depends_on(right, variable),
+		Expr::Call(_, args) | Expr::Vector(args) => args.iter().any(|arg| depends_on(arg, variable)),
+		Expr::Conditional(condition, then, otherwise) => {
+			depends_on(condition, variable) || depends_on(then, variable) || depends_on(otherwise, variable)
+		},
+	}
+}
+
+/// The derivative of an expression with respect to one variable, every other variable is held constant
+///
+/// The result is built by applying the rules mechanically so it is full of terms like `x^(2 - 1)*1` and
+/// should be simplified before it is shown.
+pub fn derivative(ast: &Ast, variable: &str) -> Result<Ast, DiffError> {
+	let d = |ast: &Ast| derivative(ast, variable);
+	let depends = |ast: &Ast| depends_on(ast, variable);
+	let build = Builder(ast.span);
+
+	if !depends(ast) {
+		return Ok(build.number(0.0));
+	}
+
+	Ok(match &ast.node {
+		Expr::Variable(_) => build.number(1.0),
+		Expr::Quantity(number, symbol) => d(&expr::product(*number, symbol, ast.span))?,
+		Expr::Imaginary(number) => d(&expr::imaginary_product(*number, ast.span))?,
+		Expr::Unary(Operator::Negate, operand) => build.negate(d(operand)?),
+		Expr::Binary(operator @ (Operator::Plus | Operator::Minus), left, right) => {
+			build.binary(operator.clone(), d(left)?, d(right)?)
+		},
+		Expr::Binary(Operator::Times, left, right) => {
+			build.binary(Operator::Plus, build.times(d(left)?, (**right).clone()), build.times((**left).clone(), d(right)?))
+		},
+		Expr::Binary(Operator::Divided, left, right) => {
+			let numerator = build.binary(
+				Operator::Minus,
+				build.times(d(left)?, (**right).clone()),
+				build.times((**left).clone(), d(right)?),
+			);
+			build.binary(Operator::Divided, numerator, build.power((**right).clone(), build.number(2.0)))
+		},
+		// the remainder only jumps where the quotient changes and follows the dividend everywhere else
+		Expr::Binary(Operator::Modulo, left, right) if !depends(right) => d(left)?,
+		Expr::Binary(Operator::Power, base, exponent) if !depends(exponent) => {
+			let lowered =
+				build.power((**base).clone(), build.binary(Operator::Minus, (**exponent).clone(), build.number(1.0)));
+			build.times(build.times((**exponent).clone(), lowered), d(base)?)
+		},
+		Expr::Binary(Operator::Power, base, exponent) if !depends(base) => {
+			build.times(build.times(ast.clone(), build.call("ln", vec![(**base).clone()])), d(exponent)?)
+		},
+		// `a^b = e^(b*ln(a))` in general
+		Expr::Binary(Operator::Power, base, exponent) => {
+			let logarithm = build.times(d(exponent)?, build.call("ln", vec![(**base).clone()]));
+			let ratio = build.binary(Operator::Divided, build.times((**exponent).clone(), d(base)?), (**base).clone());
+			build.times(ast.clone(), build.binary(Operator::Plus, logarithm, ratio))
+		},
+		Expr::Unary(operator, _) | Expr::Binary(operator, _, _) => {
+			return Err(DiffError::NotDifferentiable(operator.to_string(), ast.span));
+		},
+		// the derivative is taken piece by piece and is undefined where the condition changes
+		Expr::Conditional(condition, then, otherwise) => {
+			build.node(Expr::Conditional(condition.clone(), Box::new(d(then)?), Box::new(d(otherwise)?)))
+		},
+		Expr::Call(name, args) => call(name, args, variable, ast.span)?,
+		Expr::Vector(elements) => {
+			build.node(Expr::Vector(elements.iter().map(d).collect::<Result<Vec<Ast>, DiffError>>()?))
+		},
+		Expr::Number(_) | Expr::Integer(..) | Expr::Decimal(_) | Expr::Bool(_) => {
+			unreachable!("constants don't depend on anything")
+		},
+	})
+}
+
+// the chain rule with the derivatives of the built-in functions
+fn call(name: &str, args: &[Ast], variable: &str, span: Span) -> Result<Ast, DiffError> {
+	let d = |ast: &Ast| derivative(ast, variable);
+	let build = Builder(span);
+	let call = |name: &str, arg: &Ast| build.call(name, vec![arg.clone()]);
+	let square = |ast: Ast| build.power(ast, build.number(2.0));
+	let reciprocal = |ast: Ast| build.binary(Operator::Divided, build.number(1.0), ast);
+	// `1 - u^2` and `1 + u^2` for the inverse trigonometric functions
+	let one_and_square = |operator, arg: &Ast| build.binary(operator, build.number(1.0), square(arg.clone()));
+
+	let outer = match (name, args) {
+		("sin", [arg]) => call("cos", arg),
+		("cos", [arg]) => build.negate(call("sin", arg)),
+		("tan", [arg]) => reciprocal(square(call("cos", arg))),
+		("asin", [arg]) => reciprocal(call("sqrt", &one_and_square(Operator::Minus, arg))),
+		("acos", [arg]) => build.negate(reciprocal(call("sqrt", &one_and_square(Operator::Minus, arg)))),
+		("atan", [arg]) => reciprocal(one_and_square(Operator::Plus, arg)),
+		("sinh", [arg]) => call("cosh", arg),
+		("cosh", [arg]) => call("sinh", arg),
+		("tanh", [arg]) => reciprocal(square(call("cosh", arg))),
+		("sqrt", [arg]) => reciprocal(build.times(build.number(2.0), call("sqrt", arg))),
+		("cbrt", [arg]) => reciprocal(build.times(build.number(3.0), square(call("cbrt", arg)))),
+		("exp", [arg]) => call("exp", arg),
+		("ln", [arg]) => reciprocal(arg.clone()),
+		("log", [arg]) => reciprocal(build.times(arg.clone(), call("ln", &build.number(10.0)))),
+		("log2", [arg]) => reciprocal(build.times(arg.clone(), call("ln", &build.number(2.0)))),
+		("abs", [arg]) => build.binary(Operator::Divided, arg.clone(), call("abs", arg)),
+		// these are flat everywhere except at the steps
+		("floor" | "ceil" | "round", [_]) => return Ok(build.number(0.0)),
+		// a logarithm to any base is a quotient of natural logarithms
+		("log", [arg, base]) => {
+			return d(&build.binary(Operator::Divided, call("ln", arg), call("ln", base)));
+		},
+		("atan2", [y, x]) => {
+			let numerator = build.binary(Operator::Minus, build.times(x.clone(), d(y)?), build.times(y.clone(), d(x)?));
+			let denominator = build.binary(Operator::Plus, square(x.clone()), square(y.clone()));
+			return Ok(build.binary(Operator::Divided, numerator, denominator));
+		},
+		("hypot", [first, rest @ ..]) => {
+			let mut numerator = build.times(first.clone(), d(first)?);
+			for arg in rest {
+				numerator = build.binary(Operator::Plus, numerator, build.times(arg.clone(), d(arg)?));
+			}
+			return Ok(build.binary(Operator::Divided, numerator, build.call(name, args.to_vec())));
+		},
+		_ if functions::is_builtin(name) => return Err(DiffError::NotDifferentiable(name.to_string(), span)),
+		_ => return Err(DiffError::UnknownFunction(name.to_string(), span)),
+	};
+
+	Ok(build.times(outer, d(&args[0])?))
+}
+
+/// Builds expression nodes that all point at the same part of the source
+#[derive(Clone, Copy)]
+pub struct Builder(pub Span);
+
+impl Builder {
+	pub fn node(&self, expr: Expr) -> Ast {
+		Ast::new(expr, self.0)
+	}
+
+	pub fn number(&self, number: f64) -> Ast {
+		self.node(Expr::Number(number))
+	}
+
+	pub fn negate(&self, operand: Ast) -> Ast {
+		self.node(Expr::Unary(Operator::Negate, Box::new(operand)))
+	}
+
+	pub fn binary(&self, operator: Operator, left: Ast, right: Ast) -> Ast {
+		self.node(Expr::Binary(operator, Box::new(left), Box::new(right)))
+	}
+
+	pub fn times(&self, left: Ast, right: Ast) -> Ast {
+		self.binary(Operator::Times, left, right)
+	}
+
+	pub fn power(&self, base: Ast, exponent: Ast) -> Ast {
+		self.binary(Operator::Power, base, exponent)
+	}
+
+	pub fn call(&self, name: &str, args: Vec<Ast>) -> Ast {
+		self.node(Expr::Call(name.to_string(), args))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		env::Environment,
+		notation::Notation,
+		numeric::FloatMode,
+		postfix::Postfix,
+		shunting::{ShuntingYard, Syntax},
+		simplify::simplify,
+		value::Value,
+	};
+
+	fn parse(expression: &str) -> Ast {
+		ShuntingYard::parse(expression, Syntax::default()).unwrap()
+	}
+
+	fn diff(expression: &str, variable: &str) -> Result<String, DiffError> {
+		derivative(&parse(expression), variable).map(|derivative| Notation::Infix.print(&simplify(&derivative)))
+	}
+
+	fn at(ast: &Ast, x: f64) -> f64 {
+		let mut env = Environment::new();
+		env.set("x", Value::Number(x));
+		env.set("y", Value::Number(0.7));
+		match Postfix::calc(ast, &env, &FloatMode) {
+			Ok(Value::Number(number)) => number,
+			result => panic!("{} at x = {x} is {result:?}", Notation::Infix.print(ast)),
+		}
+	}
+
+	#[test]
+	fn derivative_test() {
+		assert_eq!(diff("x^2 * sin(x)", "x"), Ok(String::from("2*x*sin(x) + x^2*cos(x)")));
+		assert_eq!(diff("x^3 + 2*x^2 - 5*x + 7", "x"), Ok(String::from("3*x^2 + 4*x - 5")));
+		assert_eq!(diff("ln(x^2 + 1)", "x"), Ok(String::from("2*x/(x^2 + 1)")));
+		assert_eq!(diff("1 / x", "x"), Ok(String::from("-1/x^2")));
+		assert_eq!(diff("e^(2*x)", "x"), Ok(String::from("2*e^(2*x)")));
+		assert_eq!(diff("x^x", "x"), Ok(String::from("x^x*(ln(x) + 1)")));
+		assert_eq!(diff("x*y^2 + y", "y"), Ok(String::from("2*x*y + 1")));
+		assert_eq!(diff("x*y^2 + sin(pi)", "z"), Ok(String::from("0")));
+		assert_eq!(diff("x > 0 ? x^2 : -x", "x"), Ok(String::from("x > 0 ? 2*x : -1")));
+		// the variable hides a unit of the same name
+		assert_eq!(diff("2t^2 + 3 s", "t"), Ok(String::from("4*t")));
+		assert_eq!(diff("3i + i^2", "i"), Ok(String::from("2*i + 3")));
+	}
+
+	#[test]
+	fn derivative_error_test() {
+		assert_eq!(diff("1 + min(x, 2)", "x"), Err(DiffError::NotDifferentiable(String::from("min"), Span::new(4, 13))));
+		assert_eq!(diff("x & 1", "x"), Err(DiffError::NotDifferentiable(String::from("&"), Span::new(0, 5))));
+		assert_eq!(diff("x % x", "x"), Err(DiffError::NotDifferentiable(String::from("%"), Span::new(0, 5))));
+		assert_eq!(diff("f(x)", "x"), Err(DiffError::UnknownFunction(String::from("f"), Span::new(0, 4))));
+		// nothing to differentiate when the variable isn't there
+		assert_eq!(diff("min(y, 2)", "x"), Ok(String::from("0")));
+	}
+
+	// every derivative is compared against a central difference at a few points
+	#[test]
+	fn finite_difference_test() {
+		let expressions = [
+			"x^2 * sin(x)",
+			"3*x^4 - 2*x^3 + x - 8",
+			"sin(x) / x",
+			"cos(x^2) * exp(-x)",
+			"tan(x) + atan(x) + asin(x / 3) + acos(x / 4)",
+			"sinh(x) * cosh(x) - tanh(x)",
+			"sqrt(x) + cbrt(x) - x^0.3",
+			"ln(x) + log(x) + log2(x) + log(x, 3)",
+			"x^x + 2^x + x^y",
+			"abs(x - 1) * hypot(x, y, 2)",
+			"atan2(x, y) + atan2(y, x^2)",
+			"1 / (x^2 + y) - x / (1 - x)^2",
+			"(x - 1)^3 / (x + 1)^2 * -x",
+			"x % 0.25 + floor(x) * x",
+			"x < 1 ? x^2 : 2*x - 1",
+			"e^(x*y) * y^2",
+		];
+		let points = [0.35, 0.8, 1.3, 2.2];
+		let h = 1e-6;
+
+		for expression in expressions {
+			let ast = parse(expression);
+			let derivative = simplify(&derivative(&ast, "x").unwrap());
+			for x in points {
+				let expected = (at(&ast, x + h) - at(&ast, x - h)) / (2.0 * h);
+				let actual = at(&derivative, x);
+				assert!(
+					(actual - expected).abs() <= 1e-5 * expected.abs().max(1.0),
+					"d/dx {expression} = {} is {actual} at x = {x} but should be about {expected}",
+					Notation::Infix.print(&derivative)
+				);
+			}
+		}
+	}
+}
@@ -0,0 +1,389 @@
+use std::{
+	io::{self, IsTerminal, Read, Write},
+	process::{Command, Stdio},
+};
+
+#[derive(Debug, Default, PartialEq)]
+struct LineBuffer {
+	chars: Vec<char>,
+	cursor: usize,
+}
+
+impl LineBuffer {
+	fn insert(&mut self, item: char) {
+		self.chars.insert(self.cursor, item);
+		self.cursor += 1;
+	}
+
+	fn backspace(&mut self) {
+		if self.cursor > 0 {
+			self.cursor -= 1;
+			self.chars.remove(self.cursor);
+		}
+	}
+
+	fn delete(&mut self) {
+		if self.cursor < self.chars.len() {
+			self.chars.remove(self.cursor);
+		}
+	}
+
+	fn left(&mut self) {
+		self.cursor = self.cursor.saturating_sub(1);
+	}
+
+	fn right(&mut self) {
+		self.cursor = (self.cursor + 1).min(self.chars.len());
+	}
+
+	fn home(&mut self) {
+		self.cursor = 0;
+	}
+
+	fn end(&mut self) {
+		self.cursor = self.chars.len();
+	}
+
+	fn replace(&mut self, text: &str) {
+		self.chars = text.chars().collect();
+		self.cursor = self.chars.len();
+	}
+
+	fn text(&self) -> String {
+		self.chars.iter().collect()
+	}
+}
+
+#[derive(Debug, Default)]
+struct History {
+	entries: Vec<String>,
+	// entries.len() means we are not browsing the history but editing a fresh line
+	position: usize,
+	// the fresh line so we can come back to it after browsing
+	draft: String,
+}
+
+impl History {
+	fn push(&mut self, line: &str) {
+		if !line.trim().is_empty() && self.entries.last().map(String::as_str) != Some(line) {
+			self.entries.push(line.to_string());
+		}
+		self.position = self.entries.len();
+		self.draft.clear();
+	}
+
+	fn previous(&mut self, current: &str) -> Option<&str> {
+		if self.position == 0 {
+			return None;
+		}
+		if self.position == self.entries.len() {
+			self.draft = current.to_string();
+		}
+		self.position -= 1;
+		Some(&self.entries[self.position])
+	}
+
+	fn next(&mut self) -> Option<&str> {
+		if self.position >= self.entries.len() {
+			return None;
+		}
+		self.position += 1;
+		if self.position == self.entries.len() {
+			Some(&self.draft)
+		} else {
+			Some(&self.entries[self.position])
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+enum Key {
+	Char(char),
+	Enter,
+	Backspace,
+	Delete,
+	Left,
+	Right,
+	Home,
+	End,
+	Up,
+	Down,
+	ClearLine,
+	Interrupt,
+	Eof,
+	Ignored,
+}
+
+fn read_byte(input: &mut impl Read) -> io::Result<Option<u8>> {
+	let mut byte = [0];
+	match input.read(&mut byte)? {
+		0 => Ok(None),
+		_ => Ok(Some(byte[0])),
+	}
+}
+
+fn read_key(input: &mut impl Read) -> io::Result<Key> {
+	let Some(byte) = read_byte(input)? else {
+		return Ok(Key::Eof);
+	};
+
+	Ok(match byte {
+		b'\n' | b'\r' => Key::Enter,
+		127 | 8 => Key::Backspace,
+		1 => Key::Home,
+		5 => Key::End,
+		3 => Key::Interrupt,
+		4 => Key::Eof,
+		21 => Key::ClearLine,
+		// escape sequences like `ESC [ A` for the arrow keys
+		27 => match (read_byte(input)?, read_byte(input)?) {
+			(Some(b'[' | b'O'), Some(b'A')) => Key::Up,
+			(Some(b'[' | b'O'), Some(b'B')) => Key::Down,
+			(Some(b'[' | b'O'), Some(b'C')) => Key::Right,
+			(Some(b'[' | b'O'), Some(b'D')) => Key::Left,
+			(Some(b'[' | b'O'), Some(b'H')) => Key::Home,
+			(Some(b'[' | b'O'), Some(b'F')) => Key::End,
+			(Some(b'['), Some(b'3')) => {
+				read_byte(input)?;
+				Key::Delete
+			},
+			_ => Key::Ignored,
+		},
+		0..=31 => Key::Ignored,
+		_ => {
+			// the first byte of a utf-8 character tells us how many more follow
+			let mut bytes = vec![byte];
+			let length = match byte {
+				0xF0.. => 4,
+				0xE0.. => 3,
+				0xC0.. => 2,
+				_ => 1,
+			};
+			while bytes.len() < length {
+				match read_byte(input)? {
+					Some(byte) => bytes.push(byte),
+					None => return Ok(Key::Eof),
+				}
+			}
+			match std::str::from_utf8(&bytes).ok().and_then(|text| text.chars().next()) {
+				Some(item) => Key::Char(item),
+				None => Key::Ignored,
+			}
+		},
+	})
+}
+
+/// Reads lines with cursor movement and history when attached to a terminal and plain lines otherwise
+pub struct Editor {
+	history: History,
+	// the terminal settings to restore, `None` when we are not in raw mode
+	saved_settings: Option<String>,
+}
+
+impl Editor {
+	pub fn new() -> Self {
+		let saved_settings = if io::stdin().is_terminal() {
+			Self::enable_raw_mode()
+		} else {
+			None
+		};
+
+		Self {
+			history: History::default(),
+			saved_settings,
+		}
+	}
+
+	// there is no raw mode in std so we lean on `stty` and fall back to plain lines without it
+	fn enable_raw_mode() -> Option<String> {
+		let saved = Command::new("stty").arg("-g").stdin(Stdio::inherit()).output().ok()?;
+		if !saved.status.success() {
+			return None;
+		}
+
+		let status = Command::new("stty").args(["-icanon", "-echo", "-isig", "min", "1"]).status().ok()?;
+		if !status.success() {
+			return None;
+		}
+
+		Some(String::from_utf8_lossy(&saved.stdout).trim().to_string())
+	}
+
+	/// Returns `None` once the input is exhausted
+	pub fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+		if self.saved_settings.is_none() {
+			return Self::read_plain_line(prompt);
+		}
+
+		let mut stdin = io::stdin().lock();
+		let mut stdout = io::stdout().lock();
+		let mut line = LineBuffer::default();
+		Self::render(&mut stdout, prompt, &line)?;
+
+		loop {
+			match read_key(&mut stdin)? {
+				Key::Char(item) => line.insert(item),
+				Key::Enter => {
+					writeln!(stdout)?;
+					let text = line.text();
+					self.history.push(&text);
+					return Ok(Some(text));
+				},
+				Key::Backspace => line.backspace(),
+				Key::Delete => line.delete(),
+				Key::Left => line.left(),
+				Key::Right => line.right(),
+				Key::Home => line.home(),
+				Key::End => line.end(),
+				Key::Up => {
+					if let Some(entry) = self.history.previous(&line.text()) {
+						line.replace(entry);
+					}
+				},
+				Key::Down => {
+					if let Some(entry) = self.history.next() {
+						line.replace(entry);
+					}
+				},
+				Key::ClearLine => line.replace(""),
+				Key::Interrupt => {
+					writeln!(stdout, "^C")?;
+					self.history.push("");
+					return Ok(Some(String::new()));
+				},
+				Key::Eof if line.chars.is_empty() => {
+					writeln!(stdout)?;
+					return Ok(None);
+				},
+				Key::Eof | Key::Ignored => {},
+			}
+			Self::render(&mut stdout, prompt, &line)?;
+		}
+	}
+
+	fn read_plain_line(prompt: &str) -> io::Result<Option<String>> {
+		if io::stdout().is_terminal() {
+			print!("{prompt}");
+			io::stdout().flush()?;
+		}
+
+		let mut line = String::new();
+		if io::stdin().read_line(&mut line)? == 0 {
+			return Ok(None);
+		}
+		Ok(Some(line.trim_end_matches(['\n', '\r']).to_string()))
+	}
+
+	fn render(stdout: &mut impl Write, prompt: &str, line: &LineBuffer) -> io::Result<()> {
+		// redraw the whole line and clear whatever was left over from a longer one
+		write!(stdout, "\r{prompt}{}\x1b[K", line.text())?;
+		let after_cursor = line.chars.len() - line.cursor;
+		if after_cursor > 0 {
+			write!(stdout, "\x1b[{after_cursor}D")?;
+		}
+		stdout.flush()
+	}
+}
+
+impl Drop for Editor {
+	fn drop(&mut self) {
+		if let Some(settings) = &self.saved_settings {
+			let _ = Command::new("stty").arg(settings).status();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn line_buffer_test() {
+		let mut line = LineBuffer::default();
+
+		"1+3".chars().for_each(|item| line.insert(item));
+		assert_eq!(line.text(), "1+3");
+		assert_eq!(line.cursor, 3);
+
+		line.left();
+		line.insert('2');
+		assert_eq!(line.text(), "1+23");
+		assert_eq!(line.cursor, 3);
+
+		line.home();
+		line.left();
+		line.delete();
+		assert_eq!(line.text(), "+23");
+		assert_eq!(line.cursor, 0);
+
+		line.backspace();
+		assert_eq!(line.text(), "+23");
+		line.end();
+		line.right();
+		line.backspace();
+		assert_eq!(line.text(), "+2");
+		line.delete();
+		assert_eq!(line.text(), "+2");
+
+		line.replace("ans * 2");
+		assert_eq!(line.text(), "ans * 2");
+		assert_eq!(line.cursor, 7);
+	}
+
+	#[test]
+	fn history_test() {
+		let mut history = History::default();
+		assert_eq!(history.previous("draft"), None);
+
+		history.push("1 + 1");
+		history.push("2 + 2");
+		history.push("2 + 2");
+		history.push("  ");
+		assert_eq!(history.entries, vec!["1 + 1", "2 + 2"]);
+
+		assert_eq!(history.previous("draft"), Some("2 + 2"));
+		assert_eq!(history.previous("2 + 2"), Some("1 + 1"));
+		assert_eq!(history.previous("1 + 1"), None);
+		assert_eq!(history.next(), Some("2 + 2"));
+		assert_eq!(history.next(), Some("draft"));
+		assert_eq!(history.next(), None);
+
+		history.previous("other");
+		history.push("3 + 3");
+		assert_eq!(history.position, 3);
+		assert_eq!(history.next(), None);
+	}
+
+	#[test]
+	fn read_key_test() {
+		let mut input = &b"a\x1b[A\x1b[B\x1b[C\x1b[D\x1b[3~\x7f\x01\x05\x15\x03\x04\n"[..];
+		let keys = std::iter::from_fn(|| match read_key(&mut input).unwrap() {
+			Key::Eof if input.is_empty() => None,
+			key => Some(key),
+		})
+		.collect::<Vec<Key>>();
+
+		assert_eq!(
+			keys,
+			vec![
+				Key::Char('a'),
+				Key::Up,
+				Key::Down,
+				Key::Right,
+				Key::Left,
+				Key::Delete,
+				Key::Backspace,
+				Key::Home,
+				Key::End,
+				Key::ClearLine,
+				Key::Interrupt,
+				Key::Eof,
+				Key::Enter,
+			]
+		);
+
+		let mut input = "π".as_bytes();
+		assert_eq!(read_key(&mut input).unwrap(), Key::Char('π'));
+		assert_eq!(read_key(&mut input).unwrap(), Key::Eof);
+	}
+}
@@ -0,0 +1,164 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{expr::Ast, functions::Arity};
+
+/// A function defined with `name(parameters) = body`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserFunction {
+	pub parameters: Vec<String>,
+	pub body: Ast,
+}
+
+/// Calls a native function, an error is a message about what went wrong
+pub type NativeCall<V> = Arc<dyn Fn(&[V]) -> Result<V, String> + Send + Sync>;
+
+/// A function the program embedding the calculator provides, called with the values of its arguments
+#[derive(Clone)]
+pub struct NativeFunction<V> {
+	pub arity: Arity,
+	pub call: NativeCall<V>,
+}
+
+impl<V> fmt::Debug for NativeFunction<V> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("NativeFunction").field("arity", &self.arity).finish_non_exhaustive()
+	}
+}
+
+// closures can't be compared so only copies of the same function are equal
+impl<V> PartialEq for NativeFunction<V> {
+	fn eq(&self, other: &Self) -> bool {
+		self.arity == other.arity && Arc::ptr_eq(&self.call, &other.call)
+	}
+}
+
+/// Variables holding values of whichever numeric mode is in use and the functions defined alongside them
+#[derive(Debug, PartialEq)]
+pub struct Environment<V = f64> {
+	variables: HashMap<String, V>,
+	functions: HashMap<String, UserFunction>,
+	natives: HashMap<String, NativeFunction<V>>,
+	/// How many calls of user functions can be nested before giving up
+	pub recursion_limit: usize,
+	/// How deeply the parts of an expression or of the body of a function can be nested while calculating, at
+	/// most `MAX_DEPTH_LIMIT`
+	pub depth_limit: usize,
+	/// How many parts of an expression can be calculated before giving up, the calls of user functions included
+	pub step_limit: Option<usize>,
+}
+
+impl<V> Default for Environment<V> {
+	fn default() -> Self {
+		Self {
+			variables: HashMap::new(),
+			functions: HashMap::new(),
+			natives: HashMap::new(),
+			recursion_limit: Self::DEFAULT_RECURSION_LIMIT,
+			depth_limit: Self::MAX_DEPTH_LIMIT,
+			step_limit: None,
+		}
+	}
+}
+
+impl<V> Environment<V> {
+	pub const DEFAULT_RECURSION_LIMIT: usize = 200;
+	/// Deeper recursion than this is a mistake rather than a calculation worth waiting for
+	pub const MAX_RECURSION_LIMIT: usize = 1_000;
+	/// Trees deeper than this would overflow the stack of the command line while they are printed or simplified
+	pub const MAX_DEPTH_LIMIT: usize = 1_000;
+}
+
+impl<V: Clone> Environment<V> {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn get(&self, name: &str) -> Option<V> {
+		self.variables.get(name).cloned()
+	}
+
+	pub fn set(&mut self, name: &str, value: V) {
+		self.variables.insert(name.to_string(), value);
+	}
+
+	/// Removes all variables and functions except native ones
+	pub fn clear(&mut self) {
+		self.variables.clear();
+		self.functions.clear();
+	}
+
+	pub fn native(&self, name: &str) -> Option<&NativeFunction<V>> {
+		self.natives.get(name)
+	}
+
+	pub fn define_native(&mut self, name: &str, function: NativeFunction<V>) {
+		self.natives.insert(name.to_string(), function);
+	}
+
+	pub fn function(&self, name: &str) -> Option<&UserFunction> {
+		self.functions.get(name)
+	}
+
+	pub fn define(&mut self, name: &str, function: UserFunction) {
+		self.functions.insert(name.to_string(), function);
+	}
+
+	/// All functions sorted by name
+	pub fn functions(&self) -> Vec<(&str, &UserFunction)> {
+		let mut functions =
+			self.functions.iter().map(|(name, function)| (name.as_str(), function)).collect::<Vec<(&str, &UserFunction)>>();
+		functions.sort_by_key(|(name, _)| *name);
+		functions
+	}
+
+	/// All variables sorted by name
+	pub fn variables(&self) -> Vec<(&str, V)> {
+		let mut variables =
+			self.variables.iter().map(|(name, value)| (name.as_str(), value.clone())).collect::<Vec<(&str, V)>>();
+		variables.sort_by_key(|(name, _)| *name);
+		variables
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn environment_test() {
+		let mut env = Environment::new();
+		assert_eq!(env.get("x"), None);
+
+		env.set("x", 1.0);
+		env.set("b", 2.0);
+		env.set("x", 3.0);
+		assert_eq!(env.get("x"), Some(3.0));
+		assert_eq!(env.variables(), vec![("b", 2.0), ("x", 3.0)]);
+
+		env.clear();
+		assert_eq!(env.get("x"), None);
+		assert!(env.variables().is_empty());
+	}
+
+	#[test]
+	fn functions_test() {
+		use crate::{expr::Expr, shunting::Span};
+
+		let mut env = Environment::<f64>::new();
+		let function = |parameter: &str| UserFunction {
+			parameters: vec![parameter.to_string()],
+			body: Ast::new(Expr::Variable(parameter.to_string()), Span::new(0, 1)),
+		};
+		assert_eq!(env.function("f"), None);
+
+		env.define("g", function("y"));
+		env.define("f", function("x"));
+		assert_eq!(env.function("f"), Some(&function("x")));
+		assert_eq!(env.functions(), vec![("f", &function("x")), ("g", &function("y"))]);
+
+		env.set("x", 1.0);
+		env.clear();
+		assert_eq!(env.function("f"), None);
+		assert!(env.functions().is_empty());
+	}
+}
@@ -0,0 +1,75 @@
+use crate::{
+	numeric::Radix,
+	shunting::{Operator, Span, Spanned},
+	units::Symbol,
+};
+
+/// An expression tree where every node remembers the part of the source it came from
+pub type Ast = Spanned<Expr>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+	Number(f64),
+	/// An integer literal that keeps the radix it was written in
+	Integer(u64, Radix),
+	/// A decimal literal with more digits than an `f64` holds, exact modes calculate with all of them
+	Decimal(String),
+	/// A number with a unit like `3 km`
+	Quantity(f64, Symbol),
+	/// An imaginary literal like `4i`
+	Imaginary(f64),
+	Bool(bool),
+	Variable(String),
+	/// A prefix operator like the `-` in `-x`
+	Unary(Operator, Box<Ast>),
+	Binary(Operator, Box<Ast>, Box<Ast>),
+	Call(String, Vec<Ast>),
+	/// `condition ? then : otherwise`
+	Conditional(Box<Ast>, Box<Ast>, Box<Ast>),
+	/// `[a, b, c]`, a vector of vectors of the same length is a matrix
+	Vector(Vec<Ast>),
+}
+
+impl Expr {
+	/// How tightly the expression binds, anything that never needs parentheses binds tightest
+	pub fn precedence(&self) -> u8 {
+		match self {
+			Expr::Unary(operator, _) | Expr::Binary(operator, _, _) => operator.precedence(),
+			Expr::Conditional(..) => Operator::Conditional.precedence(),
+			// a negative number reads like a negation
+			Expr::Number(number) | Expr::Imaginary(number) if number.is_sign_negative() => Operator::Negate.precedence(),
+			Expr::Decimal(digits) if digits.starts_with('-') => Operator::Negate.precedence(),
+			// `(3 m)^2` is nine square metres while `3 m^2` is three of them
+			Expr::Quantity(..) => Operator::Power.precedence(),
+			Expr::Number(_)
+			| Expr::Integer(..)
+			| Expr::Decimal(_)
+			| Expr::Imaginary(_)
+			| Expr::Bool(_)
+			| Expr::Variable(_)
+			| Expr::Call(..)
+			| Expr::Vector(_) => u8::MAX,
+		}
+	}
+}
+
+/// The product `2 * t^2` a quantity like `2t^2` reads as where a variable of the same name hides the unit
+pub fn product(number: f64, symbol: &Symbol, span: Span) -> Ast {
+	let node = |expr| Box::new(Ast::new(expr, span));
+	let variable = node(Expr::Variable(symbol.name.clone()));
+	let factor = if symbol.power == 1 {
+		variable
+	} else {
+		node(Expr::Binary(Operator::Power, variable, node(Expr::Number(symbol.power.into()))))
+	};
+	Ast::new(Expr::Binary(Operator::Times, node(Expr::Number(number)), factor), span)
+}
+
+/// The product `2 * i` an imaginary number like `2i` reads as where a variable `i` hides the imaginary unit
+pub fn imaginary_product(number: f64, span: Span) -> Ast {
+	let symbol = Symbol {
+		name: String::from("i"),
+		power: 1,
+	};
+	product(number, &symbol, span)
+}
@@ -0,0 +1,256 @@
+use std::str::FromStr;
+
+use crate::numeric::Radix;
+
+/// How decimal results are written
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Style {
+	/// However the numeric mode prints its numbers
+	#[default]
+	Auto,
+	/// Never with an exponent like `1500000`
+	Fixed,
+	/// One digit before the point like `1.5e6`
+	Scientific,
+	/// An exponent that is a multiple of three like `1.5e6` or `150e3`
+	Engineering,
+}
+
+impl FromStr for Style {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"auto" => Ok(Style::Auto),
+			"fixed" => Ok(Style::Fixed),
+			"scientific" | "sci" => Ok(Style::Scientific),
+			"engineering" | "eng" => Ok(Style::Engineering),
+			_ => Err(name.to_string()),
+		}
+	}
+}
+
+/// The options results are printed with
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Format {
+	/// The radix integer results are printed in
+	pub radix: Radix,
+	pub style: Style,
+	/// Rounds to this many significant digits
+	pub digits: Option<usize>,
+	/// Separates thousands with commas
+	pub grouping: bool,
+}
+
+impl Format {
+	/// Rewrites a decimal number like `-1.25e-7` as the options ask and leaves anything else like
+	/// fractions, `inf` and other radixes as it is
+	pub fn decimal(&self, text: &str) -> String {
+		let parsed = Self::parse(text);
+		// a zero is written without the sign it may have picked up like `0 * -1`, whatever the mode and format
+		let negative_zero = parsed.as_ref().is_some_and(|(negative, digits, ..)| *negative && digits == "0");
+		if self.style == Style::Auto && self.digits.is_none() && !self.grouping && !negative_zero {
+			return text.to_string();
+		}
+		let Some((negative, mut digits, mut exponent, written_with_exponent)) = parsed else {
+			return text.to_string();
+		};
+
+		// the zeros rounding leaves are significant, `9.99` to two digits is `1.0e1` rather than `1e1`
+		let rounded = self.digits.is_some_and(|significant| Self::round(&mut digits, &mut exponent, significant));
+		while !rounded && digits.len() > 1 && digits.ends_with('0') {
+			digits.pop();
+			exponent += 1;
+		}
+		if digits == "0" {
+			exponent = 0;
+		}
+
+		// the position of the point counted from the first digit
+		let point = digits.len() as i64 + exponent;
+		let scientific = match self.style {
+			Style::Fixed => None,
+			Style::Scientific => Some(point - 1),
+			Style::Engineering => Some((point - 1).div_euclid(3) * 3),
+			Style::Auto => written_with_exponent.then_some(point - 1),
+		};
+		let (whole, fraction, shown_exponent) = match scientific {
+			Some(shown) => {
+				let (whole, fraction) = Self::place(&digits, point - shown);
+				(whole, fraction, Some(if digits == "0" { 0 } else { shown }))
+			},
+			None => {
+				let (whole, fraction) = Self::place(&digits, point);
+				(whole, fraction, None)
+			},
+		};
+
+		let whole = if self.grouping { Self::group(&whole) } else { whole };
+		let sign = if negative && digits != "0" { "-" } else { "" };
+		let fraction = if fraction.is_empty() {
+			fraction
+		} else {
+			format!(".{fraction}")
+		};
+		match shown_exponent {
+			Some(shown) => format!("{sign}{whole}{fraction}e{shown}"),
+			None => format!("{sign}{whole}{fraction}"),
+		}
+	}
+
+	// the sign, the significant digits without leading zeros, the exponent of the last digit and whether
+	// there was an exponent, `None` for anything but a plain decimal
+	fn parse(text: &str) -> Option<(bool, String, i64, bool)> {
+		let (negative, unsigned) = match text.strip_prefix('-') {
+			Some(unsigned) => (true, unsigned),
+			None => (false, text),
+		};
+		let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+			Some((mantissa, exponent)) => (mantissa, Some(exponent.strip_prefix('+').unwrap_or(exponent).parse().ok()?)),
+			None => (unsigned, None),
+		};
+		let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+		let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+		if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+			return None;
+		}
+
+		let digits = format!("{whole}{fraction}");
+		let digits = digits.trim_start_matches('0');
+		let digits = if digits.is_empty() { "0" } else { digits };
+		Some((negative, digits.to_string(), exponent.unwrap_or(0) - fraction.len() as i64, exponent.is_some()))
+	}
+
+	// rounds half away from zero to the significant digits, whether any of the digits dropped weren't zero
+	fn round(digits: &mut String, exponent: &mut i64, significant: usize) -> bool {
+		if digits.len() <= significant {
+			return false;
+		}
+		let dropped = digits[significant..].bytes().any(|digit| digit != b'0');
+		let round_up = digits.as_bytes()[significant] >= b'5';
+		*exponent += (digits.len() - significant) as i64;
+		digits.truncate(significant);
+		if round_up {
+			let mut bytes = digits.clone().into_bytes();
+			match bytes.iter().rposition(|byte| *byte != b'9') {
+				Some(last) => {
+					bytes[last] += 1;
+					bytes[last + 1..].fill(b'0');
+				},
+				None => {
+					// `999` becomes `1000` which keeps the significant digits by moving the exponent
+					bytes.fill(b'0');
+					bytes.insert(0, b'1');
+					bytes.pop();
+					*exponent += 1;
+				},
+			}
+			*digits = String::from_utf8(bytes).expect("BUG: digits aren't ASCII");
+		}
+		dropped
+	}
+
+	// splits the digits into the whole and fractional part with this many digits before the point
+	fn place(digits: &str, point: i64) -> (String, String) {
+		if point <= 0 {
+			(String::from("0"), format!("{}{digits}", "0".repeat(point.unsigned_abs() as usize)))
+		} else if point as usize >= digits.len() {
+			(format!("{digits}{}", "0".repeat(point as usize - digits.len())), String::new())
+		} else {
+			let (whole, fraction) = digits.split_at(point as usize);
+			(whole.to_string(), fraction.to_string())
+		}
+	}
+
+	fn group(whole: &str) -> String {
+		let mut grouped = String::new();
+		for (index, digit) in whole.chars().enumerate() {
+			if index > 0 && (whole.len() - index).is_multiple_of(3) {
+				grouped.push(',');
+			}
+			grouped.push(digit);
+		}
+		grouped
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn format(style: Style, digits: Option<usize>, grouping: bool) -> Format {
+		Format {
+			style,
+			digits,
+			grouping,
+			..Format::default()
+		}
+	}
+
+	#[test]
+	fn style_test() {
+		let fixed = format(Style::Fixed, None, false);
+		assert_eq!(fixed.decimal("1e21"), "1000000000000000000000");
+		assert_eq!(fixed.decimal("-1.25e-3"), "-0.00125");
+		assert_eq!(fixed.decimal("0.5"), "0.5");
+
+		let scientific = format(Style::Scientific, None, false);
+		assert_eq!(scientific.decimal("1500000"), "1.5e6");
+		assert_eq!(scientific.decimal("0.00125"), "1.25e-3");
+		assert_eq!(scientific.decimal("-7"), "-7e0");
+		assert_eq!(scientific.decimal("0"), "0e0");
+
+		let engineering = format(Style::Engineering, None, false);
+		assert_eq!(engineering.decimal("150000"), "150e3");
+		assert_eq!(engineering.decimal("1500000"), "1.5e6");
+		assert_eq!(engineering.decimal("0.00125"), "1.25e-3");
+		assert_eq!(engineering.decimal("0.000125"), "125e-6");
+		assert_eq!(engineering.decimal("12"), "12e0");
+	}
+
+	#[test]
+	fn digits_test() {
+		let three = format(Style::Auto, Some(3), false);
+		assert_eq!(three.decimal("3.14159"), "3.14");
+		assert_eq!(three.decimal("2.71828"), "2.72");
+		assert_eq!(three.decimal("-0.0012345"), "-0.00123");
+		assert_eq!(three.decimal("123456"), "123000");
+		assert_eq!(three.decimal("9.999"), "10.0");
+		assert_eq!(three.decimal("1.2000000000000002"), "1.20");
+		assert_eq!(three.decimal("1.2345e300"), "1.23e300");
+		assert_eq!(three.decimal("2.5"), "2.5");
+		assert_eq!(format(Style::Auto, Some(1), false).decimal("0.25"), "0.3");
+		// a carry into a new digit keeps as many digits
+		let scientific = format(Style::Scientific, Some(2), false);
+		assert_eq!(scientific.decimal("9.99"), "1.0e1");
+		assert_eq!(scientific.decimal("-99.9"), "-1.0e2");
+		assert_eq!(scientific.decimal("0.000996"), "1.0e-3");
+		assert_eq!(scientific.decimal("9.9"), "9.9e0");
+		assert_eq!(scientific.decimal("1000"), "1e3");
+		assert_eq!(format(Style::Engineering, Some(3), false).decimal("999.7"), "1.00e3");
+	}
+
+	#[test]
+	fn grouping_test() {
+		let grouping = format(Style::Auto, None, true);
+		assert_eq!(grouping.decimal("1234567.891"), "1,234,567.891");
+		assert_eq!(grouping.decimal("-123456"), "-123,456");
+		assert_eq!(grouping.decimal("999"), "999");
+		assert_eq!(format(Style::Fixed, Some(2), true).decimal("1e7"), "10,000,000");
+	}
+
+	#[test]
+	fn untouched_test() {
+		let format = format(Style::Scientific, Some(3), true);
+		assert_eq!(format.decimal("1/3"), "1/3");
+		assert_eq!(format.decimal("inf"), "inf");
+		assert_eq!(format.decimal("NaN"), "NaN");
+		assert_eq!(format.decimal("0xff"), "0xff");
+		assert_eq!(Format::default().decimal("0.30000000000000004"), "0.30000000000000004");
+		assert_eq!(Format::default().decimal("-0"), "0");
+		assert_eq!(Format::default().decimal("-0.0"), "0");
+		assert_eq!(format.decimal("-0e-5"), "0e0");
+		assert_eq!("eng".parse(), Ok(Style::Engineering));
+		assert_eq!("pretty".parse::<Style>(), Err(String::from("pretty")));
+	}
+}
@@ -0,0 +1,464 @@
+use std::{cmp::Ordering, f64::consts, fmt};
+
+use crate::{numeric::Numeric, postfix::PostfixError, shunting::Span};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+	Exact(usize),
+	Between(usize, usize),
+	AtLeast(usize),
+}
+
+impl Arity {
+	pub fn accepts(&self, count: usize) -> bool {
+		match *self {
+			Arity::Exact(arity) => count == arity,
+			Arity::Between(min, max) => (min..=max).contains(&count),
+			Arity::AtLeast(min) => count >= min,
+		}
+	}
+}
+
+impl fmt::Display for Arity {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let plural = |count: usize| if count == 1 { "argument" } else { "arguments" };
+
+		match *self {
+			Arity::Exact(arity) => write!(f, "exactly {arity} {}", plural(arity)),
+			Arity::Between(min, max) => write!(f, "{min} to {max} {}", plural(max)),
+			Arity::AtLeast(min) => write!(f, "at least {min} {}", plural(min)),
+		}
+	}
+}
+
+struct Function {
+	name: &'static str,
+	arity: Arity,
+	// only ever called with a number of arguments the arity accepts
+	call: Implementation,
+}
+
+const FUNCTIONS: &[Function] = &[
+	Function {
+		name: "sin",
+		arity: Arity::Exact(1),
+		call: |args| args[0].sin(),
+	},
+	Function {
+		name: "cos",
+		arity: Arity::Exact(1),
+		call: |args| args[0].cos(),
+	},
+	Function {
+		name: "tan",
+		arity: Arity::Exact(1),
+		call: |args| args[0].tan(),
+	},
+	Function {
+		name: "asin",
+		arity: Arity::Exact(1),
+		call: |args| args[0].asin(),
+	},
+	Function {
+		name: "acos",
+		arity: Arity::Exact(1),
+		call: |args| args[0].acos(),
+	},
+	Function {
+		name: "atan",
+		arity: Arity::Exact(1),
+		call: |args| args[0].atan(),
+	},
+	Function {
+		name: "atan2",
+		arity: Arity::Exact(2),
+		call: |args| args[0].atan2(args[1]),
+	},
+	Function {
+		name: "sinh",
+		arity: Arity::Exact(1),
+		call: |args| args[0].sinh(),
+	},
+	Function {
+		name: "cosh",
+		arity: Arity::Exact(1),
+		call: |args| args[0].cosh(),
+	},
+	Function {
+		name: "tanh",
+		arity: Arity::Exact(1),
+		call: |args| args[0].tanh(),
+	},
+	Function {
+		name: "sqrt",
+		arity: Arity::Exact(1),
+		call: |args| args[0].sqrt(),
+	},
+	Function {
+		name: "cbrt",
+		arity: Arity::Exact(1),
+		call: |args| args[0].cbrt(),
+	},
+	Function {
+		name: "exp",
+		arity: Arity::Exact(1),
+		call: |args| args[0].exp(),
+	},
+	Function {
+		name: "ln",
+		arity: Arity::Exact(1),
+		call: |args| args[0].ln(),
+	},
+	// base 10 unless a base is given as second argument
+	Function {
+		name: "log",
+		arity: Arity::Between(1, 2),
+		call: |args| match args {
+			[number] => number.log10(),
+			[number, base, ..] => number.log(*base),
+			[] => unreachable!("log takes at least one argument"),
+		},
+	},
+	Function {
+		name: "log2",
+		arity: Arity::Exact(1),
+		call: |args| args[0].log2(),
+	},
+	Function {
+		name: "abs",
+		arity: Arity::Exact(1),
+		call: |args| args[0].abs(),
+	},
+	Function {
+		name: "floor",
+		arity: Arity::Exact(1),
+		call: |args| args[0].floor(),
+	},
+	Function {
+		name: "ceil",
+		arity: Arity::Exact(1),
+		call: |args| args[0].ceil(),
+	},
+	Function {
+		name: "round",
+		arity: Arity::Exact(1),
+		call: |args| args[0].round(),
+	},
+	Function {
+		name: "min",
+		arity: Arity::AtLeast(1),
+		call: |args| args.iter().copied().fold(f64::INFINITY, f64::min),
+	},
+	Function {
+		name: "max",
+		arity: Arity::AtLeast(1),
+		call: |args| args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+	},
+	// the length of a vector in as many dimensions as there are arguments
+	Function {
+		name: "hypot",
+		arity: Arity::AtLeast(2),
+		call: |args| args.iter().copied().reduce(f64::hypot).unwrap_or_default(),
+	},
+	// the parts of complex numbers, which real numbers have too
+	Function {
+		name: "arg",
+		arity: Arity::Exact(1),
+		call: |args| 0f64.atan2(args[0]),
+	},
+	Function {
+		name: "conj",
+		arity: Arity::Exact(1),
+		call: |args| args[0],
+	},
+	Function {
+		name: "re",
+		arity: Arity::Exact(1),
+		call: |args| args[0],
+	},
+	Function {
+		name: "im",
+		arity: Arity::Exact(1),
+		call: |args| if args[0].is_nan() { f64::NAN } else { 0.0 },
+	},
+	// the number at a distance and angle from zero, only real when the angle is a multiple of pi
+	Function {
+		name: "rect",
+		arity: Arity::Exact(2),
+		call: |args| {
+			if args[1].sin() == 0.0 {
+				args[0] * args[1].cos()
+			} else {
+				f64::NAN
+			}
+		},
+	},
+];
+
+const CONSTANTS: &[(&str, f64)] = &[("pi", consts::PI), ("e", consts::E), ("tau", consts::TAU)];
+
+pub fn constant(name: &str) -> Option<f64> {
+	CONSTANTS.iter().find(|(constant, _)| *constant == name).map(|(_, value)| *value)
+}
+
+/// Functions of a whole matrix rather than of each element, or with a vector as result like `polar`, they have
+/// no `f64` implementation
+const MATRIX_FUNCTIONS: &[(&str, Arity)] = &[
+	("polar", Arity::Exact(1)),
+	("transpose", Arity::Exact(1)),
+	("det", Arity::Exact(1)),
+	("inv", Arity::Exact(1)),
+];
+
+/// Functions like `sum(k^2, k, 1, 10)` that bind the variable named by their second argument and calculate their
+/// first argument for many values of it
+const HIGHER_ORDER_FUNCTIONS: &[(&str, Arity)] = &[("integrate", Arity::Exact(4)), ("sum", Arity::Exact(4))];
+
+pub fn is_builtin(name: &str) -> bool {
+	FUNCTIONS.iter().any(|function| function.name == name) || is_matrix_function(name) || is_higher_order(name)
+}
+
+pub fn is_matrix_function(name: &str) -> bool {
+	MATRIX_FUNCTIONS.iter().any(|(function, _)| *function == name)
+}
+
+pub fn is_higher_order(name: &str) -> bool {
+	HIGHER_ORDER_FUNCTIONS.iter().any(|(function, _)| *function == name)
+}
+
+pub fn arity(name: &str) -> Option<Arity> {
+	FUNCTIONS
+		.iter()
+		.map(|function| (function.name, function.arity))
+		.chain(MATRIX_FUNCTIONS.iter().copied())
+		.chain(HIGHER_ORDER_FUNCTIONS.iter().copied())
+		.find(|(function, _)| *function == name)
+		.map(|(_, arity)| arity)
+}
+
+/// How a built-in function is calculated with `f64`
+pub type Implementation = fn(&[f64]) -> f64;
+
+/// The `f64` implementation of a built-in function, only to be called with as many arguments as its arity accepts
+pub fn implementation(name: &str) -> Option<(Arity, Implementation)> {
+	FUNCTIONS.iter().find(|function| function.name == name).map(|function| (function.arity, function.call))
+}
+
+pub fn call(name: &str, args: &[f64], span: Span) -> Result<f64, PostfixError> {
+	let function = FUNCTIONS
+		.iter()
+		.find(|function| function.name == name)
+		.ok_or_else(|| PostfixError::UnknownFunction(name.to_string(), span))?;
+
+	if !function.arity.accepts(args.len()) {
+		return Err(PostfixError::ArityMismatch {
+			name: name.to_string(),
+			expected: function.arity,
+			found: args.len(),
+			span,
+		});
+	}
+
+	Ok((function.call)(args))
+}
+
+/// Calls a function in any numeric mode
+///
+/// Exact modes calculate the functions with exact results themselves and leave the others to their
+/// `function` hook, everything else is calculated with `f64` and converted back.
+pub fn call_numeric<N: Numeric>(
+	numeric: &N,
+	name: &str,
+	args: &[N::Value],
+	span: Span,
+) -> Result<N::Value, PostfixError> {
+	let checked = |result| PostfixError::numeric(result, span);
+
+	if let Some(result) = numeric.function(name, args) {
+		return result.map_err(checked);
+	}
+
+	if N::EXACT {
+		let larger = |a: &N::Value, b: &N::Value| numeric.compare(a, b) == Some(Ordering::Greater);
+		let abs = |value: &N::Value| {
+			if numeric.is_negative(value) {
+				numeric.neg(value).map_err(checked)
+			} else {
+				Ok(value.clone())
+			}
+		};
+		let is_integer = |value: &N::Value| numeric.compare(&numeric.floor(value), value) == Some(Ordering::Equal);
+
+		match (name, args) {
+			("abs", [value]) => return abs(value),
+			("floor", [value]) => return Ok(numeric.floor(value)),
+			("ceil", [value]) if is_integer(value) => return Ok(value.clone()),
+			("ceil", [value]) => {
+				let one = numeric.integer(1).map_err(checked)?;
+				return numeric.add(&numeric.floor(value), &one).map_err(checked);
+			},
+			("round", [value]) if is_integer(value) => return Ok(value.clone()),
+			// halves round away from zero like `f64::round`
+			("round", [value]) => {
+				let half = numeric.value(0.5).map_err(checked)?;
+				let rounded = numeric.floor(&numeric.add(&abs(value)?, &half).map_err(checked)?);
+				return if numeric.is_negative(value) {
+					numeric.neg(&rounded).map_err(checked)
+				} else {
+					Ok(rounded)
+				};
+			},
+			("conj" | "re", [value]) => return Ok(value.clone()),
+			("im", [_]) => return numeric.integer(0).map_err(checked),
+			("rect", [radius, angle]) if numeric.is_zero(angle) => return Ok(radius.clone()),
+			("min", [first, rest @ ..]) => {
+				return Ok(rest.iter().fold(first, |min, value| if larger(min, value) { value } else { min }).clone());
+			},
+			("max", [first, rest @ ..]) => {
+				return Ok(rest.iter().fold(first, |max, value| if larger(value, max) { value } else { max }).clone());
+			},
+			_ => {},
+		}
+	}
+
+	let args = args.iter().map(|arg| numeric.to_f64(arg)).collect::<Vec<f64>>();
+	// like arithmetic a function of `inf` may be infinite without overflowing
+	let result = call(name, &args, span)?;
+	if args.iter().any(|arg| !arg.is_finite()) {
+		numeric.unchecked(result).map_err(checked)
+	} else {
+		numeric.value(result).map_err(checked)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn arity_test() {
+		assert!(Arity::Exact(1).accepts(1));
+		assert!(!Arity::Exact(1).accepts(2));
+		assert!(Arity::Between(1, 2).accepts(2));
+		assert!(!Arity::Between(1, 2).accepts(0));
+		assert!(Arity::AtLeast(1).accepts(100));
+		assert!(!Arity::AtLeast(1).accepts(0));
+
+		assert_eq!(Arity::Exact(1).to_string(), "exactly 1 argument");
+		assert_eq!(Arity::Between(1, 2).to_string(), "1 to 2 arguments");
+		assert_eq!(Arity::AtLeast(2).to_string(), "at least 2 arguments");
+	}
+
+	#[test]
+	fn constant_test() {
+		assert_eq!(constant("pi"), Some(consts::PI));
+		assert_eq!(constant("e"), Some(consts::E));
+		assert_eq!(constant("tau"), Some(consts::TAU));
+		assert_eq!(constant("phi"), None);
+		assert!(is_builtin("hypot"));
+		assert!(!is_builtin("pi"));
+	}
+
+	#[test]
+	fn call_test() {
+		assert_eq!(call("sqrt", &[16.0], Span::default()), Ok(4.0));
+		assert_eq!(call("abs", &[-2.5], Span::default()), Ok(2.5));
+		assert_eq!(call("floor", &[2.7], Span::default()), Ok(2.0));
+		assert_eq!(call("ceil", &[2.1], Span::default()), Ok(3.0));
+		assert_eq!(call("round", &[2.5], Span::default()), Ok(3.0));
+		assert_eq!(call("exp", &[0.0], Span::default()), Ok(1.0));
+		assert_eq!(call("ln", &[consts::E], Span::default()), Ok(1.0));
+		assert_eq!(call("log", &[1000.0], Span::default()), Ok(3.0));
+		assert_eq!(call("log", &[8.0, 2.0], Span::default()), Ok(3.0));
+		assert_eq!(call("log2", &[8.0], Span::default()), Ok(3.0));
+		assert_eq!(call("min", &[3.0, 1.0, 2.0], Span::default()), Ok(1.0));
+		assert_eq!(call("max", &[3.0], Span::default()), Ok(3.0));
+		assert_eq!(call("max", &[1.0, 2.0, 3.0], Span::default()), Ok(3.0));
+		assert_eq!(call("hypot", &[3.0, 4.0], Span::default()), Ok(5.0));
+		assert_eq!(call("hypot", &[2.0, 3.0, 6.0], Span::default()), Ok(7.0));
+		assert_eq!(call("atan2", &[1.0, 1.0], Span::default()), Ok(consts::FRAC_PI_4));
+		assert_eq!(call("sin", &[0.0], Span::default()), Ok(0.0));
+		assert_eq!(call("cos", &[0.0], Span::default()), Ok(1.0));
+		assert!((call("tan", &[consts::FRAC_PI_4], Span::default()).unwrap() - 1.0).abs() < 1e-12);
+		assert!((call("asin", &[1.0], Span::default()).unwrap() - consts::FRAC_PI_2).abs() < 1e-12);
+	}
+
+	#[test]
+	fn call_error_test() {
+		assert_eq!(
+			call("nope", &[1.0], Span::default()),
+			Err(PostfixError::UnknownFunction(String::from("nope"), Span::default()))
+		);
+		assert_eq!(
+			call("sin", &[1.0, 2.0], Span::default()),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("sin"),
+				expected: Arity::Exact(1),
+				found: 2,
+				span: Span::default(),
+			})
+		);
+		assert_eq!(
+			call("max", &[], Span::default()),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("max"),
+				expected: Arity::AtLeast(1),
+				found: 0,
+				span: Span::default(),
+			})
+		);
+		assert_eq!(
+			call("log", &[1.0, 2.0, 3.0], Span::default()),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("log"),
+				expected: Arity::Between(1, 2),
+				found: 3,
+				span: Span::default(),
+			})
+		);
+	}
+
+	#[test]
+	fn call_numeric_test() {
+		use crate::{
+			numeric::FloatMode,
+			rational::{BigRational, RationalMode},
+		};
+
+		let exact = RationalMode;
+		let rational = |number: f64| exact.value(number).unwrap();
+		let call = |name, args: &[f64]| {
+			let args = args.iter().map(|arg| rational(*arg)).collect::<Vec<BigRational>>();
+			call_numeric(&exact, name, &args, Span::default()).map(|value| value.to_string())
+		};
+
+		assert_eq!(call("abs", &[-0.5]), Ok(String::from("1/2")));
+		assert_eq!(call("floor", &[-2.5]), Ok(String::from("-3")));
+		assert_eq!(call("ceil", &[2.1]), Ok(String::from("3")));
+		assert_eq!(call("round", &[2.5]), Ok(String::from("3")));
+		assert_eq!(call("round", &[-2.5]), Ok(String::from("-3")));
+		assert_eq!(call("round", &[-2.4]), Ok(String::from("-2")));
+		assert_eq!(call("min", &[0.3, 0.1, 0.2]), Ok(String::from("1/10")));
+		assert_eq!(call("max", &[0.3, 0.1, 0.2]), Ok(String::from("3/10")));
+		assert_eq!(call("sqrt", &[0.25]), Ok(String::from("1/2")));
+		assert_eq!(call("sqrt", &[-1.0]), Err(PostfixError::NotANumber(Span::default())));
+		assert_eq!(call("exp", &[1000.0]), Err(PostfixError::Inexact(Span::default())));
+		assert_eq!(call("re", &[0.1]), Ok(String::from("1/10")));
+		assert_eq!(call("im", &[0.1]), Ok(String::from("0")));
+		assert_eq!(
+			call("max", &[]),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("max"),
+				expected: Arity::AtLeast(1),
+				found: 0,
+				span: Span::default(),
+			})
+		);
+
+		assert_eq!(call_numeric(&FloatMode, "abs", &[-2.0], Span::default()), Ok(2.0));
+		assert_eq!(
+			call_numeric(&FloatMode, "exp", &[1000.0], Span::default()),
+			Err(PostfixError::Overflow(Span::default()))
+		);
+	}
+}
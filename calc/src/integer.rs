@@ -0,0 +1,349 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use crate::{
+	numeric::{Numeric, NumericError, Radix},
+	shunting::Operator,
+};
+
+/// What happens to a result that doesn't fit into the width of the integers
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Overflow {
+	#[default]
+	Checked,
+	/// Keeps the low bits like two's complement hardware does
+	Wrapping,
+}
+
+impl FromStr for Overflow {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"checked" => Ok(Overflow::Checked),
+			"wrapping" => Ok(Overflow::Wrapping),
+			_ => Err(name.to_string()),
+		}
+	}
+}
+
+/// Whole numbers of a fixed width like the integer types of C or Rust
+///
+/// Values are kept in an `i128` which holds every width up to 64 bits with room to spare.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntegerMode {
+	pub bits: u32,
+	pub signed: bool,
+	pub overflow: Overflow,
+}
+
+impl Default for IntegerMode {
+	fn default() -> Self {
+		Self {
+			bits: 64,
+			signed: true,
+			overflow: Overflow::default(),
+		}
+	}
+}
+
+impl IntegerMode {
+	/// Reads a type like `i32` or `u8` as its width and signedness
+	pub fn parse_type(name: &str) -> Option<(u32, bool)> {
+		let (signed, bits) = match name.split_at_checked(1)? {
+			("i", bits) => (true, bits),
+			("u", bits) => (false, bits),
+			_ => return None,
+		};
+		match bits.parse() {
+			Ok(bits @ (8 | 16 | 32 | 64)) => Some((bits, signed)),
+			_ => None,
+		}
+	}
+
+	fn min(&self) -> i128 {
+		if self.signed { -(1 << (self.bits - 1)) } else { 0 }
+	}
+
+	fn max(&self) -> i128 {
+		if self.signed {
+			(1 << (self.bits - 1)) - 1
+		} else {
+			(1 << self.bits) - 1
+		}
+	}
+
+	// the low bits read as a number of this width
+	fn wrap(&self, value: i128) -> i128 {
+		let modulus = 1 << self.bits;
+		let value = value.rem_euclid(modulus);
+		if value > self.max() { value - modulus } else { value }
+	}
+
+	// `checked` is `None` when even an `i128` overflowed, `wrapping` is the result modulo 2^128
+	fn fit(&self, checked: Option<i128>, wrapping: i128) -> Result<i128, NumericError> {
+		match self.overflow {
+			Overflow::Checked => {
+				checked.filter(|value| (self.min()..=self.max()).contains(value)).ok_or(NumericError::Overflow)
+			},
+			Overflow::Wrapping => Ok(self.wrap(wrapping)),
+		}
+	}
+
+	// checked shifts take amounts from 0 to below the width while wrapping ones only look at the low bits
+	fn shift_amount(&self, amount: i128) -> Result<u32, NumericError> {
+		match self.overflow {
+			Overflow::Checked if (0..self.bits as i128).contains(&amount) => Ok(amount as u32),
+			Overflow::Checked => Err(NumericError::InvalidShift),
+			Overflow::Wrapping => Ok(amount.rem_euclid(self.bits as i128) as u32),
+		}
+	}
+}
+
+impl Numeric for IntegerMode {
+	type Value = i128;
+
+	const EXACT: bool = true;
+
+	fn value(&self, number: f64) -> Result<i128, NumericError> {
+		if number.is_nan() {
+			return Err(NumericError::NotANumber);
+		}
+		if number.is_infinite() || number.abs() >= 2f64.powi(127) {
+			return Err(NumericError::Overflow);
+		}
+		if number.fract() != 0.0 {
+			return Err(NumericError::NotAnInteger);
+		}
+		self.integer(number as i128)
+	}
+
+	fn to_f64(&self, value: &i128) -> f64 {
+		*value as f64
+	}
+
+	fn integer(&self, integer: i128) -> Result<i128, NumericError> {
+		self.fit(Some(integer), integer)
+	}
+
+	fn to_integer(&self, value: &i128) -> Option<i128> {
+		Some(*value)
+	}
+
+	// `0xFF` is -1 as an `i8` just like `0xFF as i8` in Rust
+	fn bit_pattern(&self, pattern: u64) -> Result<i128, NumericError> {
+		let pattern = pattern as i128;
+		if pattern >> self.bits == 0 {
+			Ok(self.wrap(pattern))
+		} else {
+			self.integer(pattern)
+		}
+	}
+
+	fn is_zero(&self, value: &i128) -> bool {
+		*value == 0
+	}
+
+	fn is_negative(&self, value: &i128) -> bool {
+		*value < 0
+	}
+
+	fn compare(&self, a: &i128, b: &i128) -> Option<Ordering> {
+		Some(a.cmp(b))
+	}
+
+	fn neg(&self, value: &i128) -> Result<i128, NumericError> {
+		self.fit(value.checked_neg(), value.wrapping_neg())
+	}
+
+	fn add(&self, a: &i128, b: &i128) -> Result<i128, NumericError> {
+		self.fit(a.checked_add(*b), a.wrapping_add(*b))
+	}
+
+	fn sub(&self, a: &i128, b: &i128) -> Result<i128, NumericError> {
+		self.fit(a.checked_sub(*b), a.wrapping_sub(*b))
+	}
+
+	fn mul(&self, a: &i128, b: &i128) -> Result<i128, NumericError> {
+		self.fit(a.checked_mul(*b), a.wrapping_mul(*b))
+	}
+
+	/// Truncated towards zero like in C
+	fn div(&self, a: &i128, b: &i128) -> Result<i128, NumericError> {
+		self.fit(a.checked_div(*b), a.wrapping_div(*b))
+	}
+
+	fn rem(&self, a: &i128, b: &i128) -> Result<i128, NumericError> {
+		self.fit(a.checked_rem(*b), a.wrapping_rem(*b))
+	}
+
+	fn pow(&self, base: &i128, exponent: &i128) -> Result<i128, NumericError> {
+		// a negative power is a fraction that truncates to zero unless the base is one
+		if *exponent < 0 {
+			return Ok(match base {
+				1 => 1,
+				-1 if exponent % 2 == 0 => 1,
+				-1 => -1,
+				_ => 0,
+			});
+		}
+
+		let (mut checked, mut wrapping) = (Some(1i128), 1i128);
+		let (mut square_checked, mut square_wrapping) = (Some(*base), *base);
+		let mut exponent = *exponent;
+		while exponent > 0 {
+			if exponent % 2 == 1 {
+				checked = checked.zip(square_checked).and_then(|(result, square)| result.checked_mul(square));
+				wrapping = wrapping.wrapping_mul(square_wrapping);
+			}
+			exponent /= 2;
+			// squaring past the last bit could overflow where the result doesn't
+			if exponent > 0 {
+				square_checked = square_checked.and_then(|square| square.checked_mul(square));
+				square_wrapping = square_wrapping.wrapping_mul(square_wrapping);
+			}
+		}
+
+		// a base of zero or one stays in range however often it's multiplied
+		if base.abs() <= 1 {
+			checked = Some(wrapping);
+		}
+		self.fit(checked, wrapping)
+	}
+
+	fn floor(&self, value: &i128) -> i128 {
+		*value
+	}
+
+	fn bitwise(&self, operator: &Operator, a: &i128, b: &i128) -> Result<i128, NumericError> {
+		match operator {
+			// both operands are sign extended so the result is too
+			Operator::BitAnd => Ok(a & b),
+			Operator::BitOr => Ok(a | b),
+			Operator::BitXor => Ok(a ^ b),
+			Operator::ShiftLeft => {
+				let amount = self.shift_amount(*b)?;
+				self.fit(a.checked_mul(1 << amount), a.wrapping_shl(amount))
+			},
+			// shifting in the sign bit for signed integers and zeros for unsigned ones
+			Operator::ShiftRight => Ok(a >> self.shift_amount(*b)?),
+			_ => unreachable!("`{operator}` is not a bitwise operator"),
+		}
+	}
+
+	fn not(&self, value: &i128) -> Result<i128, NumericError> {
+		Ok(self.wrap(!value))
+	}
+
+	/// Negative numbers are written as their two's complement
+	fn format(&self, value: &i128, radix: Radix) -> String {
+		match radix {
+			Radix::Decimal => value.to_string(),
+			_ => radix.format(((*value as u128) << (128 - self.bits)) >> (128 - self.bits)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn mode(name: &str, overflow: Overflow) -> IntegerMode {
+		let (bits, signed) = IntegerMode::parse_type(name).unwrap();
+		IntegerMode { bits, signed, overflow }
+	}
+
+	#[test]
+	fn parse_type_test() {
+		assert_eq!(IntegerMode::parse_type("i64"), Some((64, true)));
+		assert_eq!(IntegerMode::parse_type("u8"), Some((8, false)));
+		assert_eq!(IntegerMode::parse_type("i128"), None);
+		assert_eq!(IntegerMode::parse_type("f32"), None);
+		assert_eq!(IntegerMode::parse_type(""), None);
+		assert_eq!("wrapping".parse(), Ok(Overflow::Wrapping));
+	}
+
+	#[test]
+	fn checked_test() {
+		let i8 = mode("i8", Overflow::Checked);
+		let u8 = mode("u8", Overflow::Checked);
+
+		assert_eq!(i8.add(&100, &27), Ok(127));
+		assert_eq!(i8.add(&100, &28), Err(NumericError::Overflow));
+		assert_eq!(i8.neg(&-128), Err(NumericError::Overflow));
+		assert_eq!(i8.div(&-128, &-1), Err(NumericError::Overflow));
+		assert_eq!(i8.div(&-7, &2), Ok(-3));
+		assert_eq!(i8.rem(&-7, &2), Ok(-1));
+		assert_eq!(u8.sub(&1, &2), Err(NumericError::Overflow));
+		assert_eq!(u8.mul(&15, &17), Ok(255));
+		assert_eq!(u8.integer(256), Err(NumericError::Overflow));
+		assert_eq!(u8.value(2.5), Err(NumericError::NotAnInteger));
+		assert_eq!(IntegerMode::default().mul(&(1 << 62), &2), Err(NumericError::Overflow));
+	}
+
+	#[test]
+	fn wrapping_test() {
+		let i8 = mode("i8", Overflow::Wrapping);
+		let u64 = mode("u64", Overflow::Wrapping);
+
+		assert_eq!(i8.add(&100, &28), Ok(-128));
+		assert_eq!(i8.neg(&-128), Ok(-128));
+		assert_eq!(i8.mul(&16, &16), Ok(0));
+		assert_eq!(u64.sub(&0, &1), Ok(u64::MAX as i128));
+		assert_eq!(u64.mul(&(u64::MAX as i128), &(u64::MAX as i128)), Ok(1));
+		assert_eq!(u64.integer(-1), Ok(u64::MAX as i128));
+	}
+
+	#[test]
+	fn pow_test() {
+		let i64 = IntegerMode::default();
+		let u8 = mode("u8", Overflow::Wrapping);
+
+		assert_eq!(i64.pow(&3, &4), Ok(81));
+		assert_eq!(i64.pow(&2, &62), Ok(1 << 62));
+		assert_eq!(i64.pow(&2, &63), Err(NumericError::Overflow));
+		assert_eq!(i64.pow(&-2, &63), Ok(i64::MIN as i128));
+		assert_eq!(i64.pow(&-1, &i128::MAX), Ok(-1));
+		assert_eq!(i64.pow(&0, &1_000_000), Ok(0));
+		assert_eq!(i64.pow(&2, &-1), Ok(0));
+		assert_eq!(i64.pow(&-1, &-3), Ok(-1));
+		assert_eq!(u8.pow(&3, &5), Ok(243));
+		assert_eq!(u8.pow(&3, &6), Ok(729 % 256));
+		assert_eq!(u8.pow(&2, &1_000_000), Ok(0));
+	}
+
+	#[test]
+	fn bitwise_test() {
+		let i64 = IntegerMode::default();
+		let u8 = mode("u8", Overflow::Checked);
+		let i8 = mode("i8", Overflow::Wrapping);
+
+		assert_eq!(i64.bitwise(&Operator::BitAnd, &0xFF, &0b1010), Ok(0b1010));
+		assert_eq!(i64.bitwise(&Operator::BitXor, &-1, &5), Ok(-6));
+		assert_eq!(i64.bitwise(&Operator::ShiftRight, &-16, &2), Ok(-4));
+		assert_eq!(i64.bitwise(&Operator::ShiftLeft, &1, &62), Ok(1 << 62));
+		assert_eq!(i64.bitwise(&Operator::ShiftLeft, &1, &63), Err(NumericError::Overflow));
+		assert_eq!(i64.bitwise(&Operator::ShiftLeft, &1, &64), Err(NumericError::InvalidShift));
+		assert_eq!(i64.bitwise(&Operator::ShiftRight, &1, &-1), Err(NumericError::InvalidShift));
+		assert_eq!(u8.bitwise(&Operator::ShiftLeft, &1, &7), Ok(128));
+		assert_eq!(u8.not(&0), Ok(255));
+		assert_eq!(i8.bitwise(&Operator::ShiftLeft, &1, &7), Ok(-128));
+		assert_eq!(i8.bitwise(&Operator::ShiftLeft, &3, &9), Ok(6));
+		assert_eq!(i8.not(&0), Ok(-1));
+	}
+
+	#[test]
+	fn bit_pattern_format_test() {
+		let i8 = mode("i8", Overflow::Checked);
+		let i64 = IntegerMode::default();
+
+		assert_eq!(i8.bit_pattern(0xFF), Ok(-1));
+		assert_eq!(i8.bit_pattern(0x7F), Ok(127));
+		assert_eq!(i8.bit_pattern(0x100), Err(NumericError::Overflow));
+		assert_eq!(i64.bit_pattern(u64::MAX), Ok(-1));
+		assert_eq!(i8.format(&-1, Radix::Hexadecimal), "0xff");
+		assert_eq!(i8.format(&-128, Radix::Binary), "0b10000000");
+		assert_eq!(i8.format(&-1, Radix::Decimal), "-1");
+		assert_eq!(i64.format(&-2, Radix::Octal), "0o1777777777777777777776");
+		assert_eq!(i64.format(&255, Radix::Hexadecimal), "0xff");
+	}
+}
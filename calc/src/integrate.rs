@@ -0,0 +1,100 @@
+/// How often an interval is halved at most, deep enough for any integrand that is smooth between its samples
+const MAX_DEPTH: usize = 48;
+
+/// How many intervals are split at most so an integrand that never settles like `sin(1/x)` still ends
+const MAX_STEPS: usize = 100_000;
+
+/// The definite integral of a function from `a` to `b` with adaptive Simpson quadrature
+///
+/// Intervals are halved until Simpson's rule on the halves agrees with the rule on the whole to within the
+/// tolerance, so smooth stretches take few samples and steep ones many. Errors of the function end the
+/// integration.
+pub fn simpson<E>(f: impl FnMut(f64) -> Result<f64, E>, a: f64, b: f64, tolerance: f64) -> Result<f64, E> {
+	if a == b {
+		return Ok(0.0);
+	}
+	let mut quadrature = Quadrature { f, budget: MAX_STEPS };
+	let (start, end) = (quadrature.sample(a)?, quadrature.sample(b)?);
+	let interval = quadrature.interval(start, end)?;
+	quadrature.step(interval, tolerance, MAX_DEPTH)
+}
+
+struct Quadrature<F> {
+	f: F,
+	/// How many more intervals may be split
+	budget: usize,
+}
+
+// the samples at the ends and the middle of an interval and Simpson's rule over it
+struct Interval {
+	start: (f64, f64),
+	middle: (f64, f64),
+	end: (f64, f64),
+	area: f64,
+}
+
+impl<E, F: FnMut(f64) -> Result<f64, E>> Quadrature<F> {
+	fn sample(&mut self, x: f64) -> Result<(f64, f64), E> {
+		Ok((x, (self.f)(x)?))
+	}
+
+	fn interval(&mut self, start: (f64, f64), end: (f64, f64)) -> Result<Interval, E> {
+		let middle = self.sample((start.0 + end.0) / 2.0)?;
+		let area = (end.0 - start.0) / 6.0 * (start.1 + 4.0 * middle.1 + end.1);
+		Ok(Interval {
+			start,
+			middle,
+			end,
+			area,
+		})
+	}
+
+	fn step(&mut self, whole: Interval, tolerance: f64, depth: usize) -> Result<f64, E> {
+		let left = self.interval(whole.start, whole.middle)?;
+		let right = self.interval(whole.middle, whole.end)?;
+		let difference = left.area + right.area - whole.area;
+
+		// the difference is fifteen times the error of the halves, which cancels most of it
+		if depth == 0 || self.budget == 0 || difference.abs() <= 15.0 * tolerance || !difference.is_finite() {
+			return Ok(left.area + right.area + difference / 15.0);
+		}
+		self.budget -= 1;
+		Ok(self.step(left, tolerance / 2.0, depth - 1)? + self.step(right, tolerance / 2.0, depth - 1)?)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{convert::Infallible, f64::consts::PI};
+
+	use super::*;
+
+	fn integrate(f: impl Fn(f64) -> f64, a: f64, b: f64) -> f64 {
+		simpson(|x| Ok::<f64, Infallible>(f(x)), a, b, 1e-10).unwrap()
+	}
+
+	fn assert_close(actual: f64, expected: f64) {
+		assert!((actual - expected).abs() < 1e-9, "{actual} is not close to {expected}");
+	}
+
+	#[test]
+	fn simpson_test() {
+		assert_close(integrate(f64::sin, 0.0, PI), 2.0);
+		// cubics are exact with a single step
+		assert_eq!(integrate(|x| x * x * x - x, 0.0, 2.0), 2.0);
+		assert_close(integrate(|x| (-x * x).exp(), -10.0, 10.0), PI.sqrt());
+		assert_close(integrate(f64::sqrt, 0.0, 1.0), 2.0 / 3.0);
+		// the bounds can be either way round
+		assert_close(integrate(|x| x, 1.0, 0.0), -0.5);
+		assert_eq!(integrate(|x| x, 3.0, 3.0), 0.0);
+		// a jump only makes the intervals around it smaller
+		assert_close(integrate(f64::floor, 0.0, 3.5), 4.5);
+		assert!(integrate(|x| (1.0 / x).sin(), 1e-9, 1.0).is_finite());
+	}
+
+	#[test]
+	fn simpson_error_test() {
+		let f = |x: f64| if x > 0.5 { Err(x) } else { Ok(x) };
+		assert!(simpson(f, 0.0, 1.0, 1e-10).is_err());
+	}
+}
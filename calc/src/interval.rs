@@ -0,0 +1,654 @@
+use std::{cmp::Ordering, f64::consts, fmt, ops::Neg};
+
+use crate::{
+	bigint::BigInt,
+	format::Format,
+	functions,
+	numeric::{self, FloatMode, Numeric, NumericError},
+};
+
+/// The real numbers from `low` to `high`, both included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+	pub low: f64,
+	pub high: f64,
+}
+
+/// Every real number, which is what dividing by an interval around zero gives
+const ENTIRE: Interval = Interval::new(f64::NEG_INFINITY, f64::INFINITY);
+
+impl Interval {
+	pub const fn new(low: f64, high: f64) -> Self {
+		Self { low, high }
+	}
+
+	pub const fn point(number: f64) -> Self {
+		Self::new(number, number)
+	}
+
+	/// The decimal a number prints as, which is a single point when the `f64` holds it exactly and the `f64` on
+	/// either side of it otherwise, like for `0.1`
+	pub fn around(number: f64) -> Self {
+		if is_exact(number) {
+			Self::point(number)
+		} else {
+			Self::new(number.next_down(), number.next_up())
+		}
+	}
+
+	pub fn is_point(&self) -> bool {
+		self.low == self.high
+	}
+
+	pub fn contains(&self, number: f64) -> bool {
+		self.low <= number && number <= self.high
+	}
+
+	/// The number halfway between the bounds, NaN when both of them are infinite
+	pub fn midpoint(&self) -> f64 {
+		if self.is_point() {
+			self.low
+		} else {
+			// halves first so the sum of two large bounds doesn't overflow
+			self.low / 2.0 + self.high / 2.0
+		}
+	}
+
+	fn is_finite(&self) -> bool {
+		self.low.is_finite() && self.high.is_finite()
+	}
+
+	fn is_nan(&self) -> bool {
+		self.low.is_nan() || self.high.is_nan()
+	}
+
+	// the smallest interval holding both
+	fn hull(self, other: Interval) -> Interval {
+		Interval::new(self.low.min(other.low), self.high.max(other.high))
+	}
+
+	// the results of the standard library are off by less than an ulp so one more on each side holds the exact one
+	fn widened(self) -> Interval {
+		Interval::new(self.low.next_down(), self.high.next_up())
+	}
+
+	fn abs(self) -> Interval {
+		if self.low >= 0.0 {
+			self
+		} else if self.high <= 0.0 {
+			-self
+		} else {
+			Interval::new(0.0, self.high.max(-self.low))
+		}
+	}
+
+	// `[1, 2]` or a single number for a point, with the bounds written by `bound`
+	fn written(&self, bound: impl Fn(f64) -> String) -> String {
+		if self.is_nan() {
+			bound(f64::NAN)
+		} else if self.is_point() {
+			bound(self.low)
+		} else {
+			format!("[{}, {}]", bound(self.low), bound(self.high))
+		}
+	}
+}
+
+impl fmt::Display for Interval {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.written(written_bound))
+	}
+}
+
+// very small bounds like the `5e-324` that zero widens to are written with an exponent instead of hundreds of zeros,
+// and so are very large ones
+fn written_bound(bound: f64) -> String {
+	if bound != 0.0 && bound.is_finite() && !(1e-7..1e21).contains(&bound.abs()) {
+		format!("{bound:e}")
+	} else {
+		bound.to_string()
+	}
+}
+
+impl Neg for Interval {
+	type Output = Interval;
+
+	fn neg(self) -> Interval {
+		Interval::new(-self.high, -self.low)
+	}
+}
+
+// the shortest decimal of a binary fraction like `0.5` is the number itself, `c / 10^k` is one when `5^k` divides `c`
+fn is_exact(number: f64) -> bool {
+	let Some((coefficient, exponent)) = numeric::shortest_decimal(number) else {
+		return true;
+	};
+	exponent >= 0
+		|| coefficient
+			.div_rem(&BigInt::from(5u64).pow(exponent.unsigned_abs()))
+			.is_some_and(|(_, remainder)| remainder.is_zero())
+}
+
+// the exact result lies `error` away from the rounded one, the bound on that side moves out by an ulp
+fn enclosure(rounded: f64, error: f64) -> Interval {
+	Interval::new(
+		if error < 0.0 { rounded.next_down() } else { rounded },
+		if error > 0.0 { rounded.next_up() } else { rounded },
+	)
+}
+
+// two more sums find the rounding error of a sum exactly
+fn sum(a: f64, b: f64) -> Interval {
+	let rounded = a + b;
+	let b_part = rounded - a;
+	enclosure(rounded, (a - (rounded - b_part)) + (b - b_part))
+}
+
+// the fused multiply-add finds the rounding error of a product exactly
+fn product(a: f64, b: f64) -> Interval {
+	let rounded = a * b;
+	// zero times an infinite bound is zero, the bound only stands for finite numbers
+	if rounded.is_nan() && !a.is_nan() && !b.is_nan() {
+		return Interval::point(0.0);
+	}
+	enclosure(rounded, a.mul_add(b, -rounded))
+}
+
+// `a - q*b` is exact and over `b` it has the sign of the rounding error of `q = a/b`
+fn quotient(a: f64, b: f64) -> Interval {
+	let rounded = a / b;
+	enclosure(rounded, -rounded.mul_add(b, -a) / b)
+}
+
+// `a - r^2` has the sign of the rounding error of `r = sqrt(a)`
+fn root(a: f64) -> Interval {
+	let rounded = a.sqrt();
+	enclosure(rounded, -rounded.mul_add(rounded, -a))
+}
+
+fn multiply(a: Interval, b: Interval) -> Interval {
+	[
+		product(a.low, b.low),
+		product(a.low, b.high),
+		product(a.high, b.low),
+		product(a.high, b.high),
+	]
+	.into_iter()
+	.reduce(Interval::hull)
+	.expect("BUG: a product without corners")
+}
+
+// squared and multiplied out from both ends, even powers of an interval around zero start at zero
+fn integer_pow(base: Interval, exponent: u32) -> Interval {
+	let power = |number: f64| {
+		let (mut result, mut square, mut remaining) = (Interval::point(1.0), Interval::point(number), exponent);
+		while remaining > 0 {
+			if remaining & 1 == 1 {
+				result = multiply(result, square);
+			}
+			square = multiply(square, square);
+			remaining >>= 1;
+		}
+		result
+	};
+	let base = if exponent.is_multiple_of(2) { base.abs() } else { base };
+	Interval::new(power(base.low).low, power(base.high).high)
+}
+
+// a function that only grows, calculated by the standard library
+fn increasing(x: Interval, f: fn(f64) -> f64) -> Interval {
+	Interval::new(f(x.low), f(x.high)).widened()
+}
+
+fn decreasing(x: Interval, f: fn(f64) -> f64) -> Interval {
+	Interval::new(f(x.high), f(x.low)).widened()
+}
+
+// whether `x` holds `point` plus a whole number of periods, a little slack makes up for the rounding of pi
+fn reaches(x: Interval, point: f64, period: f64) -> bool {
+	if (x.high - x.low).partial_cmp(&period) != Some(Ordering::Less) {
+		return true;
+	}
+	let slack = (x.low.abs() + x.high.abs() + period) * 4.0 * f64::EPSILON;
+	let periods = ((x.low - slack - point) / period).ceil();
+	point + periods * period <= x.high + slack
+}
+
+// the sine and cosine have a maximum at `peak` and every turn from it and a minimum half a turn further on
+fn periodic(x: Interval, f: fn(f64) -> f64, peak: f64) -> Interval {
+	let ends = Interval::point(f(x.low)).hull(Interval::point(f(x.high))).widened();
+	Interval::new(
+		if reaches(x, peak + consts::PI, consts::TAU) {
+			-1.0
+		} else {
+			ends.low.max(-1.0)
+		},
+		if reaches(x, peak, consts::TAU) {
+			1.0
+		} else {
+			ends.high.min(1.0)
+		},
+	)
+}
+
+// the angles of the points of a box are extreme at its corners unless it holds zero or crosses the negative x axis
+// where the angle jumps from pi to -pi
+fn angle(y: Interval, x: Interval) -> Interval {
+	if (x.low < 0.0 && y.low < 0.0 && y.high >= 0.0) || (x.contains(0.0) && y.contains(0.0)) {
+		return Interval::new(-consts::PI, consts::PI).widened();
+	}
+	[(y.low, x.low), (y.low, x.high), (y.high, x.low), (y.high, x.high)]
+		.into_iter()
+		.map(|(y, x)| Interval::point(y.atan2(x)))
+		.reduce(Interval::hull)
+		.expect("BUG: a box without corners")
+		.widened()
+}
+
+/// Interval arithmetic whose bounds are rounded outwards so the exact result always lies between them
+///
+/// Decimals that `f64` can't hold and constants are widened to the `f64` on either side of them. Comparing
+/// intervals that overlap is an error since the comparison holds for some of their numbers and not for others.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct IntervalMode;
+
+impl IntervalMode {
+	// only a finite calculation turning infinite is an overflow like in `FloatMode`
+	fn checked(result: Interval, operands: &[Interval]) -> Result<Interval, NumericError> {
+		if !result.is_finite() && !result.is_nan() && operands.iter().all(Interval::is_finite) {
+			Err(NumericError::Overflow)
+		} else {
+			Ok(result)
+		}
+	}
+}
+
+impl Numeric for IntervalMode {
+	type Value = Interval;
+
+	const EXACT: bool = false;
+
+	fn value(&self, number: f64) -> Result<Interval, NumericError> {
+		FloatMode.value(number).map(Interval::around)
+	}
+
+	fn unchecked(&self, number: f64) -> Result<Interval, NumericError> {
+		Ok(Interval::around(number))
+	}
+
+	/// A literal with more digits than the `f64` next to it holds lies between that and its neighbours
+	fn literal(&self, digits: &str) -> Result<Interval, NumericError> {
+		let number = digits.parse::<f64>().map_err(|_| NumericError::NotANumber)?;
+		if numeric::decimal_digits(digits) == numeric::decimal_digits(&number.to_string()) {
+			Ok(Interval::around(number))
+		} else {
+			Ok(Interval::point(number).widened())
+		}
+	}
+
+	/// The midpoint, functions of `f64` only get to see that
+	fn to_f64(&self, value: &Interval) -> f64 {
+		value.midpoint()
+	}
+
+	fn integer(&self, integer: i128) -> Result<Interval, NumericError> {
+		let number = integer as f64;
+		if number as i128 == integer {
+			Ok(Interval::point(number))
+		} else {
+			Ok(Interval::new(number.next_down(), number.next_up()))
+		}
+	}
+
+	fn to_integer(&self, value: &Interval) -> Option<i128> {
+		FloatMode.to_integer(&value.low).filter(|_| value.is_point())
+	}
+
+	fn is_zero(&self, value: &Interval) -> bool {
+		value.low == 0.0 && value.high == 0.0
+	}
+
+	fn is_negative(&self, value: &Interval) -> bool {
+		value.high < 0.0
+	}
+
+	fn compare(&self, a: &Interval, b: &Interval) -> Option<Ordering> {
+		if a.high < b.low {
+			Some(Ordering::Less)
+		} else if a.low > b.high {
+			Some(Ordering::Greater)
+		} else {
+			(a.is_point() && a == b).then_some(Ordering::Equal)
+		}
+	}
+
+	/// Intervals that overlap are neither smaller nor larger than each other, unlike `NaN` that isn't an error
+	fn order(&self, a: &Interval, b: &Interval) -> Result<Option<Ordering>, NumericError> {
+		match self.compare(a, b) {
+			None if !a.is_nan() && !b.is_nan() => Err(NumericError::Indeterminate),
+			ordering => Ok(ordering),
+		}
+	}
+
+	fn neg(&self, value: &Interval) -> Result<Interval, NumericError> {
+		Ok(-*value)
+	}
+
+	fn add(&self, a: &Interval, b: &Interval) -> Result<Interval, NumericError> {
+		Self::checked(Interval::new(sum(a.low, b.low).low, sum(a.high, b.high).high), &[*a, *b])
+	}
+
+	fn sub(&self, a: &Interval, b: &Interval) -> Result<Interval, NumericError> {
+		Self::checked(Interval::new(sum(a.low, -b.high).low, sum(a.high, -b.low).high), &[*a, *b])
+	}
+
+	fn mul(&self, a: &Interval, b: &Interval) -> Result<Interval, NumericError> {
+		Self::checked(multiply(*a, *b), &[*a, *b])
+	}
+
+	fn div(&self, a: &Interval, b: &Interval) -> Result<Interval, NumericError> {
+		if !b.contains(0.0) {
+			let corners = [
+				quotient(a.low, b.low),
+				quotient(a.low, b.high),
+				quotient(a.high, b.low),
+				quotient(a.high, b.high),
+			];
+			let result = corners.into_iter().reduce(Interval::hull).expect("BUG: a quotient without corners");
+			return Self::checked(result, &[*a, *b]);
+		}
+
+		// dividing by numbers close to zero gets arbitrarily large, when the divisor has zero inside the two sides
+		// of the result are joined into every number
+		let (negative, positive) = (a.high < 0.0, a.low > 0.0);
+		Ok(match (negative, positive) {
+			_ if self.is_zero(b) => return Err(NumericError::NotANumber),
+			(true, _) if b.low == 0.0 => Interval::new(f64::NEG_INFINITY, quotient(a.high, b.high).high),
+			(true, _) if b.high == 0.0 => Interval::new(quotient(a.high, b.low).low, f64::INFINITY),
+			(_, true) if b.low == 0.0 => Interval::new(quotient(a.low, b.high).low, f64::INFINITY),
+			(_, true) if b.high == 0.0 => Interval::new(f64::NEG_INFINITY, quotient(a.low, b.low).high),
+			_ => ENTIRE,
+		})
+	}
+
+	fn rem(&self, a: &Interval, b: &Interval) -> Result<Interval, NumericError> {
+		if a.is_point() && b.is_point() {
+			return Ok(Interval::point(a.low % b.low));
+		}
+		// between two multiples of the divisor the remainder grows with the dividend
+		if b.is_point() && (a.low >= 0.0 || a.high <= 0.0) && (a.low / b.low).trunc() == (a.high / b.low).trunc() {
+			let (low, high) = (a.low % b.low, a.high % b.low);
+			if low <= high {
+				return Ok(Interval::new(low, high));
+			}
+		}
+		// otherwise it is smaller than the divisor and takes the sign of the dividend
+		let largest = b.abs().high;
+		Ok(Interval::new(if a.low < 0.0 { -largest } else { 0.0 }, if a.high > 0.0 { largest } else { 0.0 }))
+	}
+
+	fn pow(&self, base: &Interval, exponent: &Interval) -> Result<Interval, NumericError> {
+		if exponent.is_point() && exponent.low.fract() == 0.0 && exponent.low.abs() <= f64::from(u32::MAX) {
+			let power = integer_pow(*base, exponent.low.abs() as u32);
+			return if exponent.low < 0.0 {
+				self.div(&Interval::point(1.0), &Self::checked(power, &[*base])?)
+			} else {
+				Self::checked(power, &[*base])
+			};
+		}
+		// a negative number has no real power unless the exponent is an integer
+		if base.low < 0.0 {
+			return Err(NumericError::NotANumber);
+		}
+		if *exponent == Interval::point(0.5) {
+			return Ok(Interval::new(root(base.low).low, root(base.high).high));
+		}
+
+		// the power of a positive number grows or shrinks with each operand so it is extreme at the corners
+		let power = [
+			(base.low, exponent.low),
+			(base.low, exponent.high),
+			(base.high, exponent.low),
+			(base.high, exponent.high),
+		]
+		.into_iter()
+		.map(|(base, exponent)| Interval::point(base.powf(exponent)))
+		.reduce(Interval::hull)
+		.expect("BUG: a power without corners")
+		.widened();
+		// zero to a negative power is infinite like a division by zero
+		if base.contains(0.0) && exponent.low < 0.0 {
+			Ok(Interval::new(power.low.max(0.0), power.high))
+		} else {
+			Self::checked(Interval::new(power.low.max(0.0), power.high), &[*base, *exponent])
+		}
+	}
+
+	fn floor(&self, value: &Interval) -> Interval {
+		Interval::new(value.low.floor(), value.high.floor())
+	}
+
+	fn format_with(&self, value: &Interval, format: Format) -> String {
+		if value.is_point() {
+			format.decimal(&self.format(value, format.radix))
+		} else {
+			value.written(|bound| format.decimal(&written_bound(bound)))
+		}
+	}
+
+	fn plus_minus(&self, value: &Interval, error: &Interval) -> Option<Result<Interval, NumericError>> {
+		let error = error.abs().high;
+		Some(Self::checked(Interval::new(sum(value.low, -error).low, sum(value.high, error).high), &[*value]))
+	}
+
+	fn parts(&self, value: &Interval) -> Vec<f64> {
+		vec![value.low, value.high]
+	}
+
+	/// The areas under the bounds enclose the area as far as the integration is accurate
+	fn joined(&self, areas: &[f64]) -> Result<Interval, NumericError> {
+		let (low, high) = (FloatMode.value(areas[0])?, FloatMode.value(areas[1])?);
+		Ok(Interval::new(low.min(high), low.max(high)).widened())
+	}
+
+	fn function(&self, name: &str, args: &[Interval]) -> Option<Result<Interval, NumericError>> {
+		if !functions::arity(name).is_some_and(|arity| arity.accepts(args.len())) {
+			return None;
+		}
+		let within = |x: &Interval, low: f64, high: f64| low <= x.low && x.high <= high;
+
+		let result = match (name, args) {
+			("sqrt", [x]) if x.low >= 0.0 => Interval::new(root(x.low).low, root(x.high).high),
+			("cbrt", [x]) => increasing(*x, f64::cbrt),
+			("exp", [x]) => {
+				let exp = increasing(*x, f64::exp);
+				Interval::new(exp.low.max(0.0), exp.high)
+			},
+			("ln", [x]) if x.low >= 0.0 => increasing(*x, f64::ln),
+			("log", [x]) if x.low >= 0.0 => increasing(*x, f64::log10),
+			("log", [x, base]) if x.low >= 0.0 && base.low >= 0.0 => {
+				return Some(self.div(&increasing(*x, f64::ln), &increasing(*base, f64::ln)));
+			},
+			("log2", [x]) if x.low >= 0.0 => increasing(*x, f64::log2),
+			("sin", [x]) => periodic(*x, f64::sin, consts::FRAC_PI_2),
+			("cos", [x]) => periodic(*x, f64::cos, 0.0),
+			// the tangent goes through every number at each of its poles
+			("tan", [x]) if reaches(*x, consts::FRAC_PI_2, consts::PI) => return Some(Ok(ENTIRE)),
+			("tan", [x]) => increasing(*x, f64::tan),
+			("asin", [x]) if within(x, -1.0, 1.0) => increasing(*x, f64::asin),
+			("acos", [x]) if within(x, -1.0, 1.0) => decreasing(*x, f64::acos),
+			("atan", [x]) => increasing(*x, f64::atan),
+			("atan2", [y, x]) => angle(*y, *x),
+			("sinh", [x]) => increasing(*x, f64::sinh),
+			("cosh", [x]) => {
+				let cosh = increasing(x.abs(), f64::cosh);
+				Interval::new(cosh.low.max(1.0), cosh.high)
+			},
+			("tanh", [x]) => increasing(*x, f64::tanh),
+			("abs", [x]) => x.abs(),
+			("floor", [x]) => self.floor(x),
+			("ceil", [x]) => Interval::new(x.low.ceil(), x.high.ceil()),
+			("round", [x]) => Interval::new(x.low.round(), x.high.round()),
+			("min", _) => args
+				.iter()
+				.copied()
+				.reduce(|a, b| Interval::new(a.low.min(b.low), a.high.min(b.high)))
+				.expect("BUG: checked by the arity"),
+			("max", _) => args
+				.iter()
+				.copied()
+				.reduce(|a, b| Interval::new(a.low.max(b.low), a.high.max(b.high)))
+				.expect("BUG: checked by the arity"),
+			("hypot", _) => {
+				let lengths = args.iter().map(|arg| arg.abs());
+				let hypot = lengths
+					.reduce(|a, b| Interval::new(a.low.hypot(b.low), a.high.hypot(b.high)))
+					.expect("BUG: checked by the arity");
+				let hypot = hypot.widened();
+				Interval::new(hypot.low.max(0.0), hypot.high)
+			},
+			("arg", [x]) if x.low >= 0.0 => Interval::point(0.0),
+			("arg", [x]) if x.high < 0.0 => Interval::around(consts::PI),
+			("arg", [_]) => Interval::new(0.0, consts::PI.next_up()),
+			("conj" | "re", [x]) => *x,
+			("im", [_]) => Interval::point(0.0),
+			// only real at multiples of pi
+			("rect", [r, phi]) if phi.is_point() && phi.low.sin() == 0.0 => {
+				return Some(self.mul(r, &periodic(*phi, f64::cos, 0.0)));
+			},
+			// the rest has no real value for these arguments, like the root of a negative number
+			_ if functions::implementation(name).is_some() => return Some(Err(NumericError::NotANumber)),
+			_ => return None,
+		};
+		Some(Self::checked(result, args))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn call(name: &str, args: &[Interval]) -> Result<Interval, NumericError> {
+		IntervalMode.function(name, args).expect("the function should have an interval version")
+	}
+
+	// the bounds hold the exact value and are at most a few ulps apart
+	fn assert_tight(interval: Interval, exact: f64) {
+		assert!(interval.contains(exact), "{interval} doesn't hold {exact}");
+		assert!(interval.high - interval.low <= 4.0 * f64::EPSILON * exact.abs().max(1.0), "{interval} is too wide");
+	}
+
+	#[test]
+	fn display_test() {
+		assert_eq!(Interval::new(1.0, 2.5).to_string(), "[1, 2.5]");
+		assert_eq!(Interval::point(-3.0).to_string(), "-3");
+		assert_eq!(Interval::new(f64::NAN, 1.0).to_string(), "NaN");
+		assert_eq!(ENTIRE.to_string(), "[-inf, inf]");
+
+		let format = Format {
+			digits: Some(3),
+			..Format::default()
+		};
+		assert_eq!(IntervalMode.format_with(&Interval::new(1.0 / 3.0, 2.0 / 3.0), format), "[0.333, 0.667]");
+	}
+
+	#[test]
+	fn rounding_test() {
+		let mode = IntervalMode;
+		let literal = |number| mode.unchecked(number).unwrap();
+
+		// decimals that `f64` holds are points, the others are widened to the `f64` on either side
+		assert_eq!(literal(0.5), Interval::point(0.5));
+		assert_eq!(literal(1e21), Interval::point(1e21));
+		assert_eq!(literal(0.1), Interval::new(0.1f64.next_down(), 0.1f64.next_up()));
+		// so are literals with more digits than the `f64` closest to them
+		let long = mode.literal("12345678901234567890.5").unwrap();
+		assert!(!long.is_point() && long.contains(12345678901234567890.5));
+		assert_eq!(mode.literal("0.1"), Ok(literal(0.1)));
+		assert_eq!(mode.integer(1 << 60), Ok(Interval::point(2f64.powi(60))));
+		assert!(mode.integer((1 << 60) + 1).unwrap().contains(2f64.powi(60)));
+
+		// exact results stay points and rounded ones are rounded outwards
+		assert_eq!(mode.add(&literal(1.0), &literal(2.0)), Ok(Interval::point(3.0)));
+		assert_eq!(mode.div(&literal(1.0), &literal(4.0)), Ok(Interval::point(0.25)));
+		let third = mode.div(&literal(1.0), &literal(3.0)).unwrap();
+		assert_eq!(third.high, third.low.next_up());
+		assert!(mode.mul(&third, &literal(3.0)).unwrap().contains(1.0));
+		let sum = mode.add(&literal(0.1), &literal(0.2)).unwrap();
+		assert!(sum.contains(0.3) && !sum.contains(0.1 + 0.2 + 1e-15));
+		assert_eq!(mode.add(&literal(1.0), &literal(1e-30)).unwrap(), Interval::new(1.0, 1f64.next_up()));
+		assert_eq!(mode.sub(&literal(1.0), &literal(1e-30)).unwrap(), Interval::new(1f64.next_down(), 1.0));
+		assert_tight(call("sqrt", &[literal(2.0)]).unwrap(), consts::SQRT_2);
+		assert_eq!(call("sqrt", &[literal(16.0)]), Ok(Interval::point(4.0)));
+	}
+
+	#[test]
+	fn arithmetic_test() {
+		let mode = IntervalMode;
+		let (a, b) = (Interval::new(-1.0, 2.0), Interval::new(3.0, 4.0));
+
+		assert_eq!(mode.add(&a, &b), Ok(Interval::new(2.0, 6.0)));
+		assert_eq!(mode.sub(&a, &b), Ok(Interval::new(-5.0, -1.0)));
+		assert_eq!(mode.mul(&a, &b), Ok(Interval::new(-4.0, 8.0)));
+		assert_eq!(mode.mul(&a, &a), Ok(Interval::new(-2.0, 4.0)));
+		assert_eq!(mode.div(&b, &Interval::new(1.0, 2.0)), Ok(Interval::new(1.5, 4.0)));
+		assert_eq!(mode.pow(&a, &Interval::point(2.0)), Ok(Interval::new(0.0, 4.0)));
+		assert_eq!(mode.pow(&a, &Interval::point(3.0)), Ok(Interval::new(-1.0, 8.0)));
+		assert_eq!(mode.pow(&b, &Interval::point(-1.0)), Ok(Interval::new(0.25, 1.0 / 3.0f64.next_down())));
+		assert!(mode.pow(&b, &Interval::new(0.5, 2.0)).unwrap().contains(16.0));
+		assert_eq!(mode.pow(&a, &Interval::point(0.5)), Err(NumericError::NotANumber));
+		assert_eq!(mode.rem(&Interval::new(7.0, 8.0), &Interval::point(3.0)), Ok(Interval::new(1.0, 2.0)));
+		assert_eq!(mode.rem(&Interval::new(2.0, 4.0), &Interval::point(3.0)), Ok(Interval::new(0.0, 3.0)));
+		assert_eq!(mode.floor(&Interval::new(-0.5, 1.5)), Interval::new(-1.0, 1.0));
+		assert_eq!(mode.mul(&Interval::new(1.0, 1e300), &Interval::point(1e10)), Err(NumericError::Overflow));
+
+		assert_eq!(mode.compare(&a, &b), Some(Ordering::Less));
+		assert_eq!(mode.compare(&b, &a), Some(Ordering::Greater));
+		assert_eq!(mode.compare(&a, &Interval::new(1.0, 5.0)), None);
+		assert_eq!(mode.compare(&Interval::point(2.0), &Interval::point(2.0)), Some(Ordering::Equal));
+		assert_eq!(mode.compare(&a, &a), None);
+		assert_eq!(mode.plus_minus(&Interval::point(2.0), &Interval::point(-0.5)), Some(Ok(Interval::new(1.5, 2.5))));
+	}
+
+	#[test]
+	fn division_by_zero_test() {
+		let mode = IntervalMode;
+		let divide = |a: (f64, f64), b: (f64, f64)| mode.div(&Interval::new(a.0, a.1), &Interval::new(b.0, b.1));
+
+		assert_eq!(divide((1.0, 2.0), (0.0, 4.0)), Ok(Interval::new(0.25, f64::INFINITY)));
+		assert_eq!(divide((1.0, 2.0), (-4.0, 0.0)), Ok(Interval::new(f64::NEG_INFINITY, -0.25)));
+		assert_eq!(divide((-2.0, -1.0), (0.0, 4.0)), Ok(Interval::new(f64::NEG_INFINITY, -0.25)));
+		assert_eq!(divide((-2.0, -1.0), (-4.0, 0.0)), Ok(Interval::new(0.25, f64::INFINITY)));
+		assert_eq!(divide((1.0, 2.0), (-1.0, 1.0)), Ok(ENTIRE));
+		assert_eq!(divide((-1.0, 2.0), (1.0, 2.0)), Ok(Interval::new(-1.0, 2.0)));
+		assert_eq!(divide((-1.0, 2.0), (0.0, 2.0)), Ok(ENTIRE));
+		assert_eq!(divide((1.0, 2.0), (0.0, 0.0)), Err(NumericError::NotANumber));
+	}
+
+	#[test]
+	fn function_test() {
+		let point = Interval::point;
+
+		assert_eq!(call("sin", &[Interval::new(0.0, 3.0)]).map(|sine| sine.high), Ok(1.0));
+		assert_eq!(call("sin", &[Interval::new(-10.0, 10.0)]), Ok(Interval::new(-1.0, 1.0)));
+		assert_eq!(call("cos", &[Interval::new(3.0, 3.5)]).map(|cosine| cosine.low), Ok(-1.0));
+		assert_tight(call("sin", &[point(0.5)]).unwrap(), 0.5f64.sin());
+		assert_eq!(call("tan", &[Interval::new(1.0, 2.0)]), Ok(ENTIRE));
+		assert!(call("tan", &[Interval::new(-1.0, 1.0)]).unwrap().contains(1f64.tan()));
+		assert_eq!(call("abs", &[Interval::new(-3.0, 2.0)]), Ok(Interval::new(0.0, 3.0)));
+		assert_eq!(call("cosh", &[Interval::new(-1.0, 1.0)]).map(|cosh| cosh.low), Ok(1.0));
+		assert_eq!(call("min", &[Interval::new(1.0, 4.0), Interval::new(2.0, 3.0)]), Ok(Interval::new(1.0, 3.0)));
+		assert_eq!(call("max", &[Interval::new(1.0, 4.0), point(2.0)]), Ok(Interval::new(2.0, 4.0)));
+		assert_eq!(
+			call("atan2", &[Interval::new(-1.0, 1.0), Interval::new(-2.0, -1.0)]).map(|angle| angle.high > 3.0),
+			Ok(true)
+		);
+		assert!(call("atan2", &[point(1.0), Interval::new(1.0, 2.0)]).unwrap().contains(consts::FRAC_PI_4));
+		assert!(call("exp", &[point(1.0)]).unwrap().contains(consts::E));
+		assert_tight(call("ln", &[point(consts::E)]).unwrap(), 1.0);
+		assert!(call("log", &[point(8.0), point(2.0)]).unwrap().contains(3.0));
+		assert_eq!(call("sqrt", &[Interval::new(-1.0, 4.0)]), Err(NumericError::NotANumber));
+		assert_eq!(call("asin", &[Interval::new(0.0, 2.0)]), Err(NumericError::NotANumber));
+		assert_eq!(IntervalMode.function("transpose", &[point(1.0)]), None);
+	}
+}
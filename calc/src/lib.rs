@@ -0,0 +1,566 @@
+//! A calculator for expressions like `2 * (3 + 4)` with variables, functions, units and several kinds of
+//! arithmetic
+//!
+//! ```
+//! use ccalc::{Arity, Context, Value};
+//!
+//! let mut context = Context::new();
+//! context.set("rate", 0.25);
+//! context.set_function("clamp", Arity::Exact(3), |args| Ok(args[0].max(args[1]).min(args[2])));
+//!
+//! assert_eq!(ccalc::eval("clamp(80 * rate, 0, 10)", &context), Ok(Value::Number(10.0)));
+//! ```
+//!
+//! The modules are public so the `ccalc` binary can be built on them, only the items at the root are meant
+//! to stay stable.
+
+use std::{fmt, sync::Arc};
+
+pub mod bigint;
+pub mod bytecode;
+pub mod complex;
+pub mod decimal;
+pub mod diff;
+pub mod env;
+pub mod expr;
+pub mod format;
+pub mod functions;
+pub mod integer;
+pub mod integrate;
+pub mod interval;
+pub mod matrix;
+pub mod notation;
+pub mod numeric;
+pub mod postfix;
+pub mod rational;
+pub mod report;
+pub mod shunting;
+pub mod simplify;
+pub mod solve;
+pub mod uncertain;
+pub mod units;
+pub mod value;
+
+use crate::env::{Environment, NativeFunction, UserFunction};
+use crate::{
+	bytecode::Program,
+	postfix::Postfix,
+	shunting::{ShuntingYard, Statement},
+};
+pub use crate::{
+	complex::ComplexMode,
+	decimal::DecimalMode,
+	expr::{Ast, Expr},
+	functions::Arity,
+	integer::IntegerMode,
+	interval::IntervalMode,
+	numeric::{FloatMode, Numeric},
+	postfix::PostfixError,
+	rational::RationalMode,
+	shunting::{ShuntingYardError, Span, Syntax},
+	solve::{Solution, Solver},
+	uncertain::UncertainMode,
+	value::Value,
+};
+
+/// Everything that can go wrong with an expression
+#[derive(Debug, PartialEq)]
+pub enum CalcError {
+	Parse(ShuntingYardError),
+	Calc(PostfixError),
+	/// `Context::define` was given something other than `name = expression` or `name(parameters) = expression`
+	NotADefinition(Span),
+	/// `Context::define` would hide a built-in function or constant
+	Reserved(String, Span),
+	/// `CompiledExpr::eval_slice` was given a different number of inputs than the expression has variables
+	WrongInputs {
+		expected: usize,
+		found: usize,
+	},
+}
+
+impl CalcError {
+	/// Where in the expression it went wrong, `None` when the expression is empty
+	pub fn span(&self) -> Option<Span> {
+		match self {
+			CalcError::Parse(error) => error.span(),
+			CalcError::Calc(error) => Some(error.span()),
+			CalcError::NotADefinition(span) | CalcError::Reserved(_, span) => Some(*span),
+			CalcError::WrongInputs { .. } => None,
+		}
+	}
+
+	/// The message with the expression below it and the part that went wrong underlined
+	pub fn report(&self, source: &str) -> String {
+		report::render(self, self.span(), source)
+	}
+}
+
+impl fmt::Display for CalcError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CalcError::Parse(error) => write!(f, "{error}"),
+			CalcError::Calc(error) => write!(f, "{error}"),
+			CalcError::NotADefinition(_) => write!(f, "expected `name = expression` or `name(parameters) = expression`"),
+			CalcError::Reserved(name, _) => write!(f, "`{name}` is built in and can't be redefined"),
+			CalcError::WrongInputs { expected, found } => {
+				let values = if *expected == 1 { "value" } else { "values" };
+				write!(f, "the expression reads {expected} {values} but {found} were given")
+			},
+		}
+	}
+}
+
+impl std::error::Error for CalcError {}
+
+impl From<ShuntingYardError> for CalcError {
+	fn from(error: ShuntingYardError) -> Self {
+		CalcError::Parse(error)
+	}
+}
+
+impl From<PostfixError> for CalcError {
+	fn from(error: PostfixError) -> Self {
+		CalcError::Calc(error)
+	}
+}
+
+/// Bounds on the work an expression may cause so untrusted input can't exhaust memory or the CPU
+///
+/// These don't bound the size of results, the modes do that themselves: a fraction has at most
+/// `RationalMode::MAX_BITS` bits in its numerator and denominator, a decimal's exponent is at most
+/// `DecimalMode::MAX_EXPONENT` either way and a float overflows to infinity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+	/// The most numbers, names, operators and parentheses an expression may have
+	pub max_tokens: usize,
+	/// How deeply operators and calls may be nested, `1 + 2 * 3` is three levels deep
+	pub max_depth: usize,
+	/// How many operators, calls and operands may be calculated, counting every call of a defined function
+	pub max_steps: usize,
+}
+
+impl Limits {
+	pub const UNLIMITED: Limits = Limits {
+		max_tokens: usize::MAX,
+		max_depth: usize::MAX,
+		max_steps: usize::MAX,
+	};
+}
+
+impl Default for Limits {
+	fn default() -> Self {
+		Self {
+			max_tokens: 10_000,
+			max_depth: 200,
+			max_steps: 1_000_000,
+		}
+	}
+}
+
+/// The variables, functions and limits expressions are calculated with in one of the numeric modes
+#[derive(Debug)]
+pub struct Context<N: Numeric = FloatMode> {
+	numeric: N,
+	syntax: Syntax,
+	limits: Limits,
+	env: Environment<Value<N::Value>>,
+}
+
+impl Context {
+	/// A context calculating with `f64`
+	pub fn new() -> Self {
+		Self::with_mode(FloatMode)
+	}
+}
+
+impl Default for Context {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<N: Numeric> Context<N> {
+	pub fn with_mode(numeric: N) -> Self {
+		let mut context = Self {
+			numeric,
+			syntax: Syntax::default(),
+			limits: Limits::default(),
+			env: Environment::new(),
+		};
+		context.set_limits(Limits::default());
+		context
+	}
+
+	pub fn numeric(&self) -> &N {
+		&self.numeric
+	}
+
+	/// The grammar expressions are read with, its limits are replaced by those of the context
+	pub fn syntax(&self) -> Syntax {
+		Syntax {
+			max_tokens: Some(self.limits.max_tokens),
+			max_depth: Some(self.limits.max_depth),
+			..self.syntax
+		}
+	}
+
+	pub fn set_syntax(&mut self, syntax: Syntax) {
+		self.syntax = syntax;
+	}
+
+	pub fn limits(&self) -> Limits {
+		self.limits
+	}
+
+	pub fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+		self.env.depth_limit = limits.max_depth;
+		self.env.step_limit = Some(limits.max_steps);
+	}
+
+	/// How many calls of defined functions can be nested, up to `Environment::MAX_RECURSION_LIMIT`
+	pub fn set_recursion_limit(&mut self, limit: usize) {
+		self.env.recursion_limit = limit.min(Environment::<f64>::MAX_RECURSION_LIMIT);
+	}
+
+	pub fn get(&self, name: &str) -> Option<Value<N::Value>> {
+		self.env.get(name)
+	}
+
+	pub fn set(&mut self, name: &str, value: impl Into<Value<N::Value>>) {
+		self.env.set(name, value.into());
+	}
+
+	/// Adds a function of plain numbers, passing a boolean, a quantity with units or a vector to it is an error
+	pub fn set_function<F>(&mut self, name: &str, arity: Arity, function: F)
+	where
+		F: Fn(&[N::Value]) -> Result<N::Value, String> + Send + Sync + 'static,
+	{
+		let call = move |args: &[Value<N::Value>]| {
+			let numbers = args
+				.iter()
+				.map(|arg| match arg {
+					Value::Number(number) => Ok(number.clone()),
+					Value::Bool(_) => Err(String::from("expected a number but found a boolean")),
+					Value::Quantity(_) => Err(String::from("expected a plain number but found a quantity")),
+					Value::Vector(_) | Value::Matrix(_) => Err(String::from("expected a number but found a vector")),
+				})
+				.collect::<Result<Vec<N::Value>, String>>()?;
+			function(&numbers).map(Value::Number)
+		};
+		self.env.define_native(
+			name,
+			NativeFunction {
+				arity,
+				call: Arc::new(call),
+			},
+		);
+	}
+
+	/// Sets a variable with `name = expression` or defines a function with `name(parameters) = expression`
+	pub fn define(&mut self, definition: &str) -> Result<(), CalcError> {
+		let reserved = |name: &str| functions::is_builtin(name) || functions::constant(name).is_some();
+
+		match ShuntingYard::parse_statement(definition, self.syntax())? {
+			Statement::Assignment(name, _) | Statement::Definition(name, ..) if reserved(&name.node) => {
+				Err(CalcError::Reserved(name.node, name.span))
+			},
+			Statement::Assignment(name, ast) => {
+				let value = Postfix::calc(&ast, &self.env, &self.numeric)?;
+				self.env.set(&name.node, value);
+				Ok(())
+			},
+			Statement::Definition(name, parameters, body) => {
+				let function = UserFunction {
+					parameters: parameters.into_iter().map(|parameter| parameter.node).collect(),
+					body,
+				};
+				self.env.define(&name.node, function);
+				Ok(())
+			},
+			Statement::Expression(ast) => Err(CalcError::NotADefinition(ast.span)),
+		}
+	}
+}
+
+/// An expression that was parsed once and can be calculated any number of times
+///
+/// Expressions of numbers and booleans are also compiled to a `Program` that `eval_slice` runs with the
+/// variables given by position, which is much faster than calculating the tree with a context.
+#[derive(Debug, Clone)]
+pub struct CompiledExpr {
+	ast: Ast,
+	inputs: Vec<String>,
+	program: Option<Program>,
+}
+
+impl CompiledExpr {
+	pub fn new(ast: Ast) -> Self {
+		let inputs = bytecode::inputs(&ast);
+		let program = Program::compile(&ast, &inputs);
+		Self { ast, inputs, program }
+	}
+
+	pub fn ast(&self) -> &Ast {
+		&self.ast
+	}
+
+	/// The variables `eval_slice` takes in order, everything but the built-in constants
+	pub fn inputs(&self) -> &[String] {
+		&self.inputs
+	}
+
+	/// Whether `eval_slice` runs compiled code rather than falling back to the tree
+	pub fn is_compiled(&self) -> bool {
+		self.program.is_some()
+	}
+
+	pub fn eval<N: Numeric>(&self, context: &Context<N>) -> Result<Value<N::Value>, CalcError> {
+		Ok(Postfix::calc(&self.ast, &context.env, &context.numeric)?)
+	}
+
+	/// Calculates the expression in `FloatMode` with a value for each of the `inputs`
+	pub fn eval_slice(&self, inputs: &[f64]) -> Result<Value, CalcError> {
+		if inputs.len() != self.inputs.len() {
+			return Err(CalcError::WrongInputs {
+				expected: self.inputs.len(),
+				found: inputs.len(),
+			});
+		}
+		match &self.program {
+			Some(program) => Ok(program.run(inputs)?),
+			None => {
+				let mut env = Environment::new();
+				for (name, input) in self.inputs.iter().zip(inputs) {
+					env.set(name, Value::Number(*input));
+				}
+				Ok(Postfix::calc(&self.ast, &env, &FloatMode)?)
+			},
+		}
+	}
+}
+
+/// Reads an expression into a tree without calculating it
+pub fn parse(source: &str, syntax: Syntax) -> Result<Ast, CalcError> {
+	Ok(ShuntingYard::parse(source, syntax)?)
+}
+
+/// Reads an expression with the grammar and limits of the context to calculate it later
+pub fn compile<N: Numeric>(source: &str, context: &Context<N>) -> Result<CompiledExpr, CalcError> {
+	parse(source, context.syntax()).map(CompiledExpr::new)
+}
+
+/// Reads and calculates an expression
+pub fn eval<N: Numeric>(source: &str, context: &Context<N>) -> Result<Value<N::Value>, CalcError> {
+	compile(source, context)?.eval(context)
+}
+
+/// Finds the real values of a variable that solve an equation like `2*x^2 - 3*x = 5`, an expression without
+/// `=` is solved for zero
+pub fn solve(source: &str, variable: &str, solver: &Solver, context: &Context) -> Result<Solution, CalcError> {
+	let (left, right) = ShuntingYard::parse_equation(source, context.syntax())?;
+	Ok(solver.solve(&left, &right, variable, &context.env)?)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::thread;
+
+	use super::*;
+
+	#[test]
+	fn eval_test() {
+		let mut context = Context::new();
+		context.set("x", 3.0);
+		context.define("f(n) = n < 2 ? n : f(n - 1) + f(n - 2)").unwrap();
+		context.define("y = f(10) + x").unwrap();
+
+		assert_eq!(eval("2 * (x + 4)", &context), Ok(Value::Number(14.0)));
+		assert_eq!(context.get("y"), Some(Value::Number(58.0)));
+		assert_eq!(eval("3 km in m", &context).map(|value| value.to_string()), Ok(String::from("3000 m")));
+		assert_eq!(eval("1 / 0", &context), Err(CalcError::Calc(PostfixError::DivisionByZero(Span::new(4, 5)))));
+
+		let compiled = compile("x ^ 2", &context).unwrap();
+		context.set("x", 5.0);
+		assert_eq!(compiled.eval(&context), Ok(Value::Number(25.0)));
+
+		let mut exact = Context::with_mode(RationalMode);
+		exact.define("third = 1 / 3").unwrap();
+		assert_eq!(eval("third + 1/6", &exact).map(|value| value.to_string()), Ok(String::from("1/2")));
+		// literals longer than an `f64` keeps stay exact
+		let exact_eval = |expression| eval(expression, &exact).map(|value| value.to_string());
+		assert_eq!(exact_eval("123456789012345678901234567890"), Ok(String::from("123456789012345678901234567890")));
+		assert_eq!(
+			exact_eval("99999999999999999999 * 99999999999999999999"),
+			Ok(String::from("9999999999999999999800000000000000000001"))
+		);
+		assert_eq!(exact_eval("0.1000000000000000000001 - 0.1"), Ok(String::from("1/10000000000000000000000")));
+		let decimal = Context::with_mode(DecimalMode::default());
+		assert_eq!(
+			eval("12345678901234567890.123 + 1", &decimal).map(|value| value.to_string()),
+			Ok(String::from("12345678901234567891.123"))
+		);
+		assert_eq!(eval("12345678901234567890.123", &context), Ok(Value::Number(12345678901234567890.123)));
+	}
+
+	#[test]
+	fn eval_slice_test() {
+		let context = Context::new();
+		let compiled = compile("a * x^2 + b * x + pi", &context).unwrap();
+		assert!(compiled.is_compiled());
+		assert_eq!(compiled.inputs(), ["a", "x", "b"]);
+		assert_eq!(compiled.eval_slice(&[2.0, 3.0, 1.0]), Ok(Value::Number(21.0 + std::f64::consts::PI)));
+		assert_eq!(compiled.eval_slice(&[1.0]), Err(CalcError::WrongInputs { expected: 3, found: 1 }));
+
+		// units fall back to calculating the tree
+		let fallback = compile("(x * 1 km in m) > 1500 m", &context).unwrap();
+		assert!(!fallback.is_compiled());
+		assert_eq!(fallback.eval_slice(&[2.0]), Ok(Value::Bool(true)));
+		assert_eq!(
+			compile("x / y", &context).unwrap().eval_slice(&[1.0, 0.0]),
+			Err(CalcError::Calc(PostfixError::DivisionByZero(Span::new(4, 5))))
+		);
+	}
+
+	#[test]
+	fn solve_test() {
+		let mut context = Context::new();
+		context.set("c", 5.0);
+		assert_eq!(solve("2*x^2 - 3*x = c", "x", &Solver::default(), &context), Ok(Solution::Roots(vec![-1.0, 2.5])));
+		// the variable hides the hour
+		assert_eq!(solve("3h = 6", "h", &Solver::default(), &context), Ok(Solution::Roots(vec![2.0])));
+		assert_eq!(
+			solve("x = = 1", "x", &Solver::default(), &context),
+			Err(CalcError::Parse(ShuntingYardError::MisplacedAssignment(Span::new(4, 5))))
+		);
+	}
+
+	#[test]
+	fn native_function_test() {
+		let mut context = Context::new();
+		context.set_function("clamp", Arity::Exact(3), |args| Ok(args[0].max(args[1]).min(args[2])));
+		context.set_function("fail", Arity::AtLeast(0), |_| Err(String::from("on purpose")));
+
+		assert_eq!(eval("clamp(15, 0, 10)", &context), Ok(Value::Number(10.0)));
+		assert_eq!(
+			eval("clamp(1, 2)", &context),
+			Err(CalcError::Calc(PostfixError::ArityMismatch {
+				name: String::from("clamp"),
+				expected: Arity::Exact(3),
+				found: 2,
+				span: Span::new(0, 11),
+			}))
+		);
+		assert_eq!(
+			eval("fail()", &context),
+			Err(CalcError::Calc(PostfixError::FunctionFailed(
+				String::from("fail"),
+				String::from("on purpose"),
+				Span::new(0, 6)
+			)))
+		);
+		assert_eq!(
+			eval("clamp(1 m, 0, 1)", &context).map_err(|error| error.to_string()),
+			Err(String::from("`clamp` failed: expected a plain number but found a quantity"))
+		);
+	}
+
+	#[test]
+	fn limits_test() {
+		let mut context = Context::new();
+		context.set_limits(Limits {
+			max_tokens: 5,
+			max_depth: 3,
+			max_steps: 100,
+		});
+
+		assert_eq!(eval("1 + 2 + 3", &context), Ok(Value::Number(6.0)));
+		assert_eq!(
+			eval("1 + 2 + 3 + 4", &context),
+			Err(CalcError::Parse(ShuntingYardError::TooManyTokens(5, Span::new(10, 11))))
+		);
+		assert_eq!(
+			eval("-(-(-(-1)))", &context),
+			Err(CalcError::Parse(ShuntingYardError::TooManyTokens(5, Span::new(5, 6))))
+		);
+
+		context.set_limits(Limits {
+			max_depth: 3,
+			..Limits::default()
+		});
+		assert_eq!(eval("1 + 2 * 3", &context), Ok(Value::Number(7.0)));
+		assert_eq!(eval("1 + 2 * -3", &context), Err(CalcError::Parse(ShuntingYardError::TooDeep(3, Span::new(2, 3)))));
+
+		context.set_limits(Limits {
+			max_steps: 100,
+			..Limits::default()
+		});
+		context.define("f(n) = n < 1 ? 0 : 1 + f(n - 1)").unwrap();
+		assert_eq!(eval("f(5)", &context), Ok(Value::Number(5.0)));
+		assert_eq!(
+			eval("f(50)", &context).map_err(|error| error.to_string()),
+			Err(String::from("the calculation takes more than 100 steps in function `f`"))
+		);
+
+		// every body starts over at the top level so the calls nest as deeply as the recursion limit allows
+		context.set_limits(Limits::default());
+		assert_eq!(eval("f(199)", &context), Ok(Value::Number(199.0)));
+		assert_eq!(
+			eval("f(200)", &context).map_err(|error| error.to_string()),
+			Err(String::from("more than 200 nested function calls in function `f`"))
+		);
+	}
+
+	#[test]
+	fn default_limits_test() {
+		// as deep as the default limits allow in every mode on a thread with the default stack size
+		fn deepest<N: Numeric>(numeric: N) -> Vec<Result<String, String>> {
+			let mut context = Context::with_mode(numeric);
+			context.define("f(n) = n < 1 ? 0 : 1 + f(n - 1)").unwrap();
+			let depth = context.limits().max_depth;
+			let expressions = [
+				format!("{}1", "-".repeat(depth - 1)),
+				format!("{}0.5{}", "sin(".repeat(depth - 1), ")".repeat(depth - 1)),
+				format!("{}1", "1 + ".repeat(depth - 1)),
+				String::from("f(199)"),
+			];
+			let result = |expression: &String| eval(expression, &context).map(|value| value.to_string());
+			expressions.iter().map(|expression| result(expression).map_err(|error| error.to_string())).collect()
+		}
+
+		let results = thread::Builder::new()
+			.stack_size(2 * 1024 * 1024)
+			.spawn(|| {
+				[
+					deepest(FloatMode),
+					deepest(RationalMode),
+					deepest(DecimalMode::default()),
+					deepest(ComplexMode),
+					deepest(IntervalMode),
+					deepest(UncertainMode),
+				]
+			})
+			.unwrap()
+			.join()
+			.unwrap();
+		for results in results {
+			assert_eq!(results[0], Ok(String::from("-1")));
+			assert_eq!(results[2], Ok(String::from("200")));
+			assert_eq!(results[3], Ok(String::from("199")));
+		}
+	}
+
+	#[test]
+	fn define_error_test() {
+		let mut context = Context::new();
+
+		assert_eq!(context.define("x + 1"), Err(CalcError::NotADefinition(Span::new(0, 5))));
+		assert_eq!(context.define("pi = 3"), Err(CalcError::Reserved(String::from("pi"), Span::new(0, 2))));
+		assert_eq!(context.define("sin(x) = x"), Err(CalcError::Reserved(String::from("sin"), Span::new(0, 3))));
+		assert_eq!(
+			CalcError::Calc(PostfixError::DivisionByZero(Span::new(4, 5))).report("1 / 0"),
+			"Error: division by zero at column 5\n  1 / 0\n      ^"
+		);
+	}
+}
@@ -1,18 +1,304 @@
-use std::env::args;
+use std::{
+	env::args,
+	fs::File,
+	io::{self, BufReader},
+	process::ExitCode,
+	thread,
+};
 
-mod postfix;
-mod shunting;
+mod cli;
+mod editor;
+mod plot;
+mod repl;
+mod rpn;
+mod script;
 
-use crate::{postfix::Postfix, shunting::ShuntingYard};
+use ccalc::{
+	CalcError, CompiledExpr, ComplexMode, Context, FloatMode, IntervalMode, Limits, Numeric, RationalMode, Solution,
+	Solver, UncertainMode, Value, diff, format::Format, notation::Notation, report, shunting::Syntax, simplify::simplify,
+};
 
-fn main() {
-	let expression = args().skip(1).collect::<Vec<String>>().join(" ");
+use crate::{
+	cli::{Mode, Options},
+	plot::Plot,
+	repl::Repl,
+	rpn::Rpn,
+	script::{Output, Script, Status},
+};
 
-	match ShuntingYard::infix_to_postfix(&expression) {
-		Ok(ast) => match Postfix::calc(ast) {
-			Ok(result) => println!("{result}"),
-			Err(error) => println!("Error: {error:?}"),
+/// Returned when an expression can't be calculated, so scripts can tell it apart from a false comparison
+const ERROR: u8 = 2;
+
+/// Where functions are plotted when the command line doesn't say
+const PLOT_RANGE: (f64, f64) = (-10.0, 10.0);
+
+/// Enough to walk the deepest trees the parser allows, even in debug builds
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+// a false result exits with 1 so `ccalc 'x > 2' && ...` works like `test` does
+fn calculate<N: Numeric>(
+	numeric: N,
+	expression: &CompiledExpr,
+	format: Format,
+) -> Result<(String, ExitCode), CalcError> {
+	// the expression comes from whoever runs the calculator so it isn't limited
+	let mut context = Context::with_mode(numeric);
+	context.set_limits(Limits::UNLIMITED);
+	let result = expression.eval(&context)?;
+	let code = if result == Value::Bool(false) {
+		ExitCode::FAILURE
+	} else {
+		ExitCode::SUCCESS
+	};
+	Ok((result.format(context.numeric(), format), code))
+}
+
+fn run<N: Numeric>(mut repl: Repl<N>, recursion_limit: Option<usize>) -> io::Result<()> {
+	if let Some(limit) = recursion_limit {
+		repl.set_recursion_limit(limit);
+	}
+	repl.run()
+}
+
+// statements are read a line at a time so `ccalc -` answers each line of a pipe as it arrives
+fn script<N: Numeric>(mut repl: Repl<N>, options: &Options, path: &str) -> ExitCode {
+	if let Some(limit) = options.recursion_limit {
+		repl.set_recursion_limit(limit);
+	}
+	let output = if options.json { Output::Json } else { Output::Plain };
+	let mut script = Script::new(repl, output, options.continue_on_error);
+
+	let status = if path == "-" {
+		script.run(io::stdin().lock(), &mut io::stdout().lock())
+	} else {
+		match File::open(path) {
+			Ok(file) => script.run(BufReader::new(file), &mut io::stdout().lock()),
+			Err(error) => Err(error),
+		}
+	};
+	match status {
+		Ok(Status::Success) => ExitCode::SUCCESS,
+		Ok(Status::False) => ExitCode::FAILURE,
+		Ok(Status::Failed) => ExitCode::from(ERROR),
+		Err(error) => {
+			eprintln!("Error: can't read `{path}`: {error}");
+			ExitCode::from(ERROR)
+		},
+	}
+}
+
+// the stack is interactive without an expression and printed from the bottom to the top after one
+fn stack<N: Numeric>(mut rpn: Rpn<N>, recursion_limit: Option<usize>, expression: &str) -> ExitCode {
+	if let Some(limit) = recursion_limit {
+		rpn.set_recursion_limit(limit);
+	}
+	if expression.trim().is_empty() {
+		if let Err(error) = rpn.run() {
+			eprintln!("Error: {error}");
+		}
+		return ExitCode::SUCCESS;
+	}
+
+	match rpn.eval_line(expression) {
+		Ok(()) => {
+			if !rpn.stack().is_empty() {
+				println!("{}", rpn.results());
+			}
+			if rpn.stack().last() == Some(&Value::Bool(false)) {
+				ExitCode::FAILURE
+			} else {
+				ExitCode::SUCCESS
+			}
+		},
+		Err(error) => {
+			println!("{}", report::render(&error, error.span(), expression));
+			ExitCode::from(ERROR)
+		},
+	}
+}
+
+// every root on its own line, having none exits with 1 like a false comparison
+fn solve(expression: &str, variable: &str, solver: &Solver, syntax: Syntax, format: Format) -> ExitCode {
+	let mut context = Context::new();
+	context.set_syntax(syntax);
+	context.set_limits(Limits::UNLIMITED);
+
+	match ccalc::solve(expression, variable, solver, &context) {
+		Ok(Solution::Roots(roots)) if roots.is_empty() => {
+			println!("no real roots");
+			ExitCode::FAILURE
+		},
+		Ok(Solution::Roots(roots)) => {
+			for root in roots {
+				println!("{variable} = {}", Value::Number(root).format(&FloatMode, format));
+			}
+			ExitCode::SUCCESS
+		},
+		Ok(Solution::Identity) => {
+			println!("every {variable} is a root");
+			ExitCode::SUCCESS
+		},
+		Err(error) => {
+			println!("{}", error.report(expression));
+			ExitCode::from(ERROR)
+		},
+	}
+}
+
+// the variable is sampled at every column, a constant is a flat line
+fn plot(expression: &str, syntax: Syntax, range: (f64, f64)) -> ExitCode {
+	let compiled = match ccalc::parse(expression, syntax) {
+		Ok(ast) => CompiledExpr::new(ast),
+		Err(error) => {
+			println!("{}", error.report(expression));
+			return ExitCode::from(ERROR);
 		},
-		Err(error) => println!("Error: {error:?}"),
+	};
+	if let [_, _, ..] = compiled.inputs() {
+		println!("Error: can't plot more than one variable but there are {}", compiled.inputs().join(", "));
+		return ExitCode::from(ERROR);
 	}
+
+	let f = |x: f64| {
+		let inputs = if compiled.inputs().is_empty() {
+			&[][..]
+		} else {
+			&[x][..]
+		};
+		match compiled.eval_slice(inputs) {
+			Ok(Value::Number(y)) => Some(y),
+			_ => None,
+		}
+	};
+	match Plot::default().render(f, range) {
+		Some(chart) => {
+			println!("{chart}");
+			ExitCode::SUCCESS
+		},
+		None => {
+			println!("Error: `{expression}` has no finite value between {} and {}", range.0, range.1);
+			ExitCode::from(ERROR)
+		},
+	}
+}
+
+fn calculator() -> ExitCode {
+	let options = match Options::parse(args().skip(1)) {
+		Ok(options) => options,
+		Err(error) => {
+			eprintln!("Error: {error}");
+			return ExitCode::from(ERROR);
+		},
+	};
+	let (syntax, format) = (options.syntax(), options.format);
+	if let Some(path) = &options.script {
+		return match options.mode {
+			Mode::Float => script(Repl::new(FloatMode, syntax, format), &options, path),
+			Mode::Rational => script(Repl::new(RationalMode, syntax, format), &options, path),
+			Mode::Decimal => script(Repl::new(options.decimal, syntax, format), &options, path),
+			Mode::Integer => script(Repl::new(options.integer, syntax, format), &options, path),
+			Mode::Complex => script(Repl::new(ComplexMode, syntax, format), &options, path),
+			Mode::Interval => script(Repl::new(IntervalMode, syntax, format), &options, path),
+			Mode::Uncertain => script(Repl::new(UncertainMode, syntax, format), &options, path),
+		};
+	}
+
+	let expression = options.expression;
+
+	if options.rpn {
+		let limit = options.recursion_limit;
+		return match options.mode {
+			Mode::Float => stack(Rpn::new(FloatMode, syntax, format), limit, &expression),
+			Mode::Rational => stack(Rpn::new(RationalMode, syntax, format), limit, &expression),
+			Mode::Decimal => stack(Rpn::new(options.decimal, syntax, format), limit, &expression),
+			Mode::Integer => stack(Rpn::new(options.integer, syntax, format), limit, &expression),
+			Mode::Complex => stack(Rpn::new(ComplexMode, syntax, format), limit, &expression),
+			Mode::Interval => stack(Rpn::new(IntervalMode, syntax, format), limit, &expression),
+			Mode::Uncertain => stack(Rpn::new(UncertainMode, syntax, format), limit, &expression),
+		};
+	}
+
+	if expression.trim().is_empty() {
+		let limit = options.recursion_limit;
+		let result = match options.mode {
+			Mode::Float => run(Repl::new(FloatMode, syntax, format), limit),
+			Mode::Rational => run(Repl::new(RationalMode, syntax, format), limit),
+			Mode::Decimal => run(Repl::new(options.decimal, syntax, format), limit),
+			Mode::Integer => run(Repl::new(options.integer, syntax, format), limit),
+			Mode::Complex => run(Repl::new(ComplexMode, syntax, format), limit),
+			Mode::Interval => run(Repl::new(IntervalMode, syntax, format), limit),
+			Mode::Uncertain => run(Repl::new(UncertainMode, syntax, format), limit),
+		};
+		if let Err(error) = result {
+			eprintln!("Error: {error}");
+		}
+		return ExitCode::SUCCESS;
+	}
+
+	if options.plot {
+		return plot(&expression, syntax, options.range.unwrap_or(PLOT_RANGE));
+	}
+
+	if let Some(variable) = &options.solve {
+		let mut solver = Solver::default();
+		if let Some(range) = options.range {
+			solver.range = range;
+		}
+		return solve(&expression, variable, &solver, syntax, format);
+	}
+
+	let ast = match ccalc::parse(&expression, syntax) {
+		Ok(ast) => ast,
+		Err(error) => {
+			println!("{}", error.report(&expression));
+			return ExitCode::from(ERROR);
+		},
+	};
+
+	if let Some(variable) = &options.diff {
+		return match diff::derivative(&ast, variable) {
+			Ok(derivative) => {
+				println!("{}", options.emit.unwrap_or(Notation::Infix).print(&simplify(&derivative)));
+				ExitCode::SUCCESS
+			},
+			Err(error) => {
+				println!("{}", report::render(&error, Some(error.span()), &expression));
+				ExitCode::from(ERROR)
+			},
+		};
+	}
+
+	if let Some(notation) = options.emit {
+		println!("{}", notation.print(&ast));
+		return ExitCode::SUCCESS;
+	}
+
+	let compiled = CompiledExpr::new(ast);
+	let result = match options.mode {
+		Mode::Float => calculate(FloatMode, &compiled, format),
+		Mode::Rational => calculate(RationalMode, &compiled, format),
+		Mode::Decimal => calculate(options.decimal, &compiled, format),
+		Mode::Integer => calculate(options.integer, &compiled, format),
+		Mode::Complex => calculate(ComplexMode, &compiled, format),
+		Mode::Interval => calculate(IntervalMode, &compiled, format),
+		Mode::Uncertain => calculate(UncertainMode, &compiled, format),
+	};
+	match result {
+		Ok((result, code)) => {
+			println!("{result}");
+			code
+		},
+		Err(error) => {
+			println!("{}", error.report(&expression));
+			ExitCode::from(ERROR)
+		},
+	}
+}
+
+fn main() -> ExitCode {
+	// the passes over the trees recurse on the stack so they get a larger one than the main thread has
+	let calculator =
+		thread::Builder::new().stack_size(STACK_SIZE).spawn(calculator).expect("BUG: failed to spawn the calculator");
+	// a panic has already been reported by the time it is joined
+	calculator.join().unwrap_or(ExitCode::from(ERROR))
 }
@@ -0,0 +1,292 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::numeric::{Numeric, NumericError};
+
+/// The shape of a value for errors about values that don't fit together
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+	Scalar,
+	Vector(usize),
+	/// Rows and columns
+	Matrix(usize, usize),
+}
+
+impl fmt::Display for Shape {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Shape::Scalar => write!(f, "a number"),
+			Shape::Vector(length) => write!(f, "a vector of {length}"),
+			Shape::Matrix(rows, columns) => write!(f, "a {rows}x{columns} matrix"),
+		}
+	}
+}
+
+/// A matrix of numbers stored row after row
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<N = f64> {
+	rows: usize,
+	columns: usize,
+	elements: Vec<N>,
+}
+
+impl<N> Matrix<N> {
+	/// `None` when the rows aren't all the same length
+	pub fn from_rows(rows: Vec<Vec<N>>) -> Option<Self> {
+		let columns = rows.first().map_or(0, Vec::len);
+		if rows.iter().any(|row| row.len() != columns) {
+			return None;
+		}
+		Some(Self {
+			rows: rows.len(),
+			columns,
+			elements: rows.into_iter().flatten().collect(),
+		})
+	}
+
+	pub fn rows(&self) -> usize {
+		self.rows
+	}
+
+	pub fn columns(&self) -> usize {
+		self.columns
+	}
+
+	pub fn shape(&self) -> Shape {
+		Shape::Matrix(self.rows, self.columns)
+	}
+
+	pub fn row(&self, row: usize) -> &[N] {
+		&self.elements[row * self.columns..(row + 1) * self.columns]
+	}
+
+	pub fn get(&self, row: usize, column: usize) -> &N {
+		&self.elements[row * self.columns + column]
+	}
+
+	/// The elements row after row
+	pub fn elements(&self) -> &[N] {
+		&self.elements
+	}
+
+	/// A matrix of the same shape with every element replaced
+	pub fn map<M, E>(&self, f: impl FnMut(&N) -> Result<M, E>) -> Result<Matrix<M>, E> {
+		Ok(Matrix {
+			rows: self.rows,
+			columns: self.columns,
+			elements: self.elements.iter().map(f).collect::<Result<Vec<M>, E>>()?,
+		})
+	}
+
+	/// Combines the elements at the same places of a matrix of the same shape
+	pub fn zip<M, E>(&self, other: &Matrix<N>, mut f: impl FnMut(&N, &N) -> Result<M, E>) -> Result<Matrix<M>, E> {
+		Ok(Matrix {
+			rows: self.rows,
+			columns: self.columns,
+			elements: self.elements.iter().zip(&other.elements).map(|(a, b)| f(a, b)).collect::<Result<Vec<M>, E>>()?,
+		})
+	}
+}
+
+impl<N: Clone> Matrix<N> {
+	pub fn transpose(&self) -> Self {
+		let elements = (0..self.columns)
+			.flat_map(|column| (0..self.rows).map(move |row| (row, column)))
+			.map(|(row, column)| self.get(row, column).clone())
+			.collect();
+		Self {
+			rows: self.columns,
+			columns: self.rows,
+			elements,
+		}
+	}
+
+	fn column(&self, column: usize) -> Vec<N> {
+		(0..self.rows).map(|row| self.get(row, column).clone()).collect()
+	}
+
+	// the matrix without one of its rows and columns
+	fn minor(&self, skipped_row: usize, skipped_column: usize) -> Self {
+		let elements = (0..self.rows)
+			.filter(|row| *row != skipped_row)
+			.flat_map(|row| (0..self.columns).filter(|column| *column != skipped_column).map(move |column| (row, column)))
+			.map(|(row, column)| self.get(row, column).clone())
+			.collect();
+		Self {
+			rows: self.rows - 1,
+			columns: self.columns - 1,
+			elements,
+		}
+	}
+}
+
+/// The sum of the products of the elements at the same places, both have to be the same length
+pub fn dot<N: Numeric>(numeric: &N, a: &[N::Value], b: &[N::Value]) -> Result<N::Value, NumericError> {
+	a.iter().zip(b).try_fold(numeric.integer(0)?, |sum, (a, b)| numeric.add(&sum, &numeric.mul(a, b)?))
+}
+
+/// The matrix product, the columns of `a` have to be as many as the rows of `b`
+pub fn product<N: Numeric>(
+	numeric: &N,
+	a: &Matrix<N::Value>,
+	b: &Matrix<N::Value>,
+) -> Result<Matrix<N::Value>, NumericError> {
+	let columns = (0..b.columns).map(|column| b.column(column)).collect::<Vec<Vec<N::Value>>>();
+	let mut elements = Vec::with_capacity(a.rows * b.columns);
+	for row in 0..a.rows {
+		for column in &columns {
+			elements.push(dot(numeric, a.row(row), column)?);
+		}
+	}
+	Ok(Matrix {
+		rows: a.rows,
+		columns: b.columns,
+		elements,
+	})
+}
+
+/// The determinant of a square matrix
+///
+/// It is calculated with the fraction-free elimination of Bareiss, where every division is exact, so integer
+/// mode gets the exact determinant too.
+pub fn determinant<N: Numeric>(numeric: &N, matrix: &Matrix<N::Value>) -> Result<N::Value, NumericError> {
+	let size = matrix.rows;
+	if size == 0 {
+		return numeric.integer(1);
+	}
+	let mut rows = (0..size).map(|row| matrix.row(row).to_vec()).collect::<Vec<Vec<N::Value>>>();
+	let mut negative = false;
+	let mut previous = numeric.integer(1)?;
+
+	for k in 0..size - 1 {
+		// the largest pivot keeps float rounding errors small, any that isn't zero would do otherwise
+		let pivot = (k..size)
+			.filter(|row| !numeric.is_zero(&rows[*row][k]))
+			.map(|row| Ok((row, abs(numeric, &rows[row][k])?)))
+			.collect::<Result<Vec<(usize, N::Value)>, NumericError>>()?
+			.into_iter()
+			.max_by(|(_, a), (_, b)| numeric.compare(a, b).unwrap_or(Ordering::Equal))
+			.map(|(row, _)| row);
+		let Some(pivot) = pivot else {
+			return numeric.integer(0);
+		};
+		if pivot != k {
+			rows.swap(pivot, k);
+			negative = !negative;
+		}
+
+		for i in k + 1..size {
+			for j in k + 1..size {
+				let product = numeric.sub(&numeric.mul(&rows[i][j], &rows[k][k])?, &numeric.mul(&rows[i][k], &rows[k][j])?)?;
+				rows[i][j] = numeric.div(&product, &previous)?;
+			}
+		}
+		previous = rows[k][k].clone();
+	}
+
+	let determinant = rows[size - 1][size - 1].clone();
+	if negative {
+		numeric.neg(&determinant)
+	} else {
+		Ok(determinant)
+	}
+}
+
+/// The inverse of a square matrix, `None` when it is singular
+///
+/// Every element is its cofactor divided by the determinant so each mode divides the way it always does.
+pub fn inverse<N: Numeric>(numeric: &N, matrix: &Matrix<N::Value>) -> Result<Option<Matrix<N::Value>>, NumericError> {
+	let whole = determinant(numeric, matrix)?;
+	if numeric.is_zero(&whole) {
+		return Ok(None);
+	}
+	let size = matrix.rows;
+	if size == 1 {
+		let one = numeric.integer(1)?;
+		return Ok(Some(matrix.map(|element| numeric.div(&one, element))?));
+	}
+
+	// the adjugate is the transposed matrix of cofactors
+	let mut elements = Vec::with_capacity(size * size);
+	for row in 0..size {
+		for column in 0..size {
+			let minor = determinant(numeric, &matrix.minor(column, row))?;
+			// zero stays positive so floats don't print `-0`
+			let cofactor = if (row + column).is_multiple_of(2) || numeric.is_zero(&minor) {
+				minor
+			} else {
+				numeric.neg(&minor)?
+			};
+			elements.push(numeric.div(&cofactor, &whole)?);
+		}
+	}
+	Ok(Some(Matrix {
+		rows: size,
+		columns: size,
+		elements,
+	}))
+}
+
+fn abs<N: Numeric>(numeric: &N, value: &N::Value) -> Result<N::Value, NumericError> {
+	if numeric.is_negative(value) {
+		numeric.neg(value)
+	} else {
+		Ok(value.clone())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{integer::IntegerMode, numeric::FloatMode, rational::RationalMode};
+
+	fn matrix<N: Numeric>(numeric: &N, rows: &[&[i128]]) -> Matrix<N::Value> {
+		let rows = rows.iter().map(|row| row.iter().map(|element| numeric.integer(*element).unwrap()).collect()).collect();
+		Matrix::from_rows(rows).unwrap()
+	}
+
+	#[test]
+	fn from_rows_test() {
+		let matrix = Matrix::from_rows(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]).unwrap();
+		assert_eq!(matrix.shape(), Shape::Matrix(2, 3));
+		assert_eq!(matrix.row(1), &[4.0, 5.0, 6.0]);
+		assert_eq!(matrix.transpose().row(2), &[3.0, 6.0]);
+		assert_eq!(Matrix::from_rows(vec![vec![1.0], vec![2.0, 3.0]]), None);
+	}
+
+	#[test]
+	fn determinant_test() {
+		assert_eq!(determinant(&FloatMode, &matrix(&FloatMode, &[&[1, 2], &[3, 4]])), Ok(-2.0));
+		assert_eq!(determinant(&FloatMode, &matrix(&FloatMode, &[&[0, 1], &[1, 0]])), Ok(-1.0));
+		assert_eq!(determinant(&FloatMode, &matrix(&FloatMode, &[&[1, 2], &[2, 4]])), Ok(0.0));
+
+		// every division of the elimination comes out even so integers stay exact
+		let integer = IntegerMode::default();
+		let rows: &[&[i128]] = &[&[2, -3, 1, 5], &[4, 0, -2, 1], &[1, 7, 3, -4], &[-6, 2, 5, 3]];
+		assert_eq!(determinant(&integer, &matrix(&integer, rows)), Ok(485));
+		assert_eq!(determinant(&integer, &Matrix::from_rows(Vec::new()).unwrap()), Ok(1));
+	}
+
+	#[test]
+	fn inverse_test() {
+		let rational = RationalMode;
+		let inverse = inverse(&rational, &matrix(&rational, &[&[1, 2], &[3, 4]])).unwrap().unwrap();
+		let printed = inverse.elements().iter().map(ToString::to_string).collect::<Vec<String>>();
+		assert_eq!(printed, ["-2", "1", "3/2", "-1/2"]);
+		assert_eq!(
+			product(&rational, &inverse, &matrix(&rational, &[&[1, 2], &[3, 4]])),
+			Ok(matrix(&rational, &[&[1, 0], &[0, 1]]))
+		);
+
+		assert_eq!(super::inverse(&FloatMode, &matrix(&FloatMode, &[&[1, 2], &[2, 4]])), Ok(None));
+		let quarter = Matrix::from_rows(vec![vec![0.25]]).unwrap();
+		assert_eq!(super::inverse(&FloatMode, &matrix(&FloatMode, &[&[4]])), Ok(Some(quarter)));
+	}
+
+	#[test]
+	fn product_test() {
+		let a = matrix(&FloatMode, &[&[1, 2, 3], &[4, 5, 6]]);
+		let b = matrix(&FloatMode, &[&[7, 8], &[9, 10], &[11, 12]]);
+		assert_eq!(product(&FloatMode, &a, &b), Ok(matrix(&FloatMode, &[&[58, 64], &[139, 154]])));
+		assert_eq!(dot(&FloatMode, &[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), Ok(32.0));
+	}
+}
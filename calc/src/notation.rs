@@ -0,0 +1,427 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+	expr::{Ast, Expr},
+	numeric::Radix,
+	shunting::Operator,
+};
+
+/// The ways an expression tree can be written back out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Notation {
+	Infix,
+	Prefix,
+	Postfix,
+	Sexpr,
+	Latex,
+}
+
+impl Notation {
+	pub fn print(&self, ast: &Ast) -> String {
+		match self {
+			Notation::Infix => infix(ast),
+			Notation::Prefix => prefix(ast),
+			Notation::Postfix => postfix(ast),
+			Notation::Sexpr => sexpr(ast),
+			Notation::Latex => latex(ast),
+		}
+	}
+}
+
+impl fmt::Display for Notation {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Notation::Infix => write!(f, "infix"),
+			Notation::Prefix => write!(f, "prefix"),
+			Notation::Postfix => write!(f, "postfix"),
+			Notation::Sexpr => write!(f, "sexpr"),
+			Notation::Latex => write!(f, "latex"),
+		}
+	}
+}
+
+impl FromStr for Notation {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"infix" => Ok(Notation::Infix),
+			"prefix" | "polish" => Ok(Notation::Prefix),
+			"postfix" | "rpn" => Ok(Notation::Postfix),
+			"sexpr" => Ok(Notation::Sexpr),
+			"latex" => Ok(Notation::Latex),
+			_ => Err(name.to_string()),
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+	Left,
+	Right,
+}
+
+// parentheses are only needed where leaving them out would parse into a different tree
+fn needs_parentheses(operator: &Operator, child: &Expr, side: Side) -> bool {
+	// nothing can pull a prefix operator apart from its right so `2^-x` and `2 * -x` are fine
+	if side == Side::Right && matches!(child, Expr::Unary(..)) {
+		return false;
+	}
+
+	let (precedence, child_precedence) = (operator.precedence(), child.precedence());
+	match side {
+		Side::Left => child_precedence < precedence || (child_precedence == precedence && operator.is_right_associative()),
+		Side::Right => {
+			child_precedence < precedence || (child_precedence == precedence && !operator.is_right_associative())
+		},
+	}
+}
+
+// prefix and postfix notations have no parentheses so variadic calls carry their number of arguments
+fn call_name(name: &str, arity: usize) -> String {
+	if arity == 1 {
+		name.to_string()
+	} else {
+		format!("{name}/{arity}")
+	}
+}
+
+fn infix(ast: &Ast) -> String {
+	let operand = |operator: &Operator, child: &Ast, side: Side| {
+		if needs_parentheses(operator, &child.node, side) {
+			format!("({})", infix(child))
+		} else {
+			infix(child)
+		}
+	};
+
+	match &ast.node {
+		Expr::Number(number) => number.to_string(),
+		Expr::Imaginary(number) => format!("{number}i"),
+		Expr::Integer(integer, radix) => radix.format(*integer as u128),
+		Expr::Decimal(digits) => digits.clone(),
+		Expr::Quantity(number, symbol) => format!("{number} {symbol}"),
+		Expr::Bool(boolean) => boolean.to_string(),
+		Expr::Variable(name) => name.clone(),
+		Expr::Unary(operator, child) => format!("{operator}{}", operand(operator, child, Side::Right)),
+		Expr::Binary(operator, left, right) => {
+			let (left, right) = (operand(operator, left, Side::Left), operand(operator, right, Side::Right));
+			match operator {
+				Operator::Times | Operator::Divided | Operator::Modulo | Operator::Power => {
+					format!("{left}{operator}{right}")
+				},
+				_ => format!("{left} {operator} {right}"),
+			}
+		},
+		Expr::Call(name, args) => format!("{name}({})", args.iter().map(infix).collect::<Vec<String>>().join(", ")),
+		// the middle is enclosed by `?` and `:` so it never needs parentheses
+		Expr::Conditional(condition, then, otherwise) => format!(
+			"{} ? {} : {}",
+			operand(&Operator::Conditional, condition, Side::Left),
+			infix(then),
+			operand(&Operator::Conditional, otherwise, Side::Right)
+		),
+		Expr::Vector(elements) => format!("[{}]", elements.iter().map(infix).collect::<Vec<String>>().join(", ")),
+	}
+}
+
+fn prefix(ast: &Ast) -> String {
+	match &ast.node {
+		Expr::Number(number) => number.to_string(),
+		Expr::Imaginary(number) => format!("{number}i"),
+		Expr::Integer(integer, radix) => radix.format(*integer as u128),
+		Expr::Decimal(digits) => digits.clone(),
+		Expr::Quantity(number, symbol) => format!("{number}{symbol}"),
+		Expr::Bool(boolean) => boolean.to_string(),
+		Expr::Variable(name) => name.clone(),
+		Expr::Unary(Operator::Negate, child) => format!("neg {}", prefix(child)),
+		Expr::Unary(operator, child) => format!("{operator} {}", prefix(child)),
+		Expr::Binary(operator, left, right) => format!("{operator} {} {}", prefix(left), prefix(right)),
+		Expr::Call(name, args) => {
+			let mut words = vec![call_name(name, args.len())];
+			words.extend(args.iter().map(prefix));
+			words.join(" ")
+		},
+		Expr::Conditional(condition, then, otherwise) => {
+			format!("{} {} {} {}", Operator::Conditional, prefix(condition), prefix(then), prefix(otherwise))
+		},
+		Expr::Vector(elements) => {
+			let mut words = vec![call_name("vector", elements.len())];
+			words.extend(elements.iter().map(prefix));
+			words.join(" ")
+		},
+	}
+}
+
+fn postfix(ast: &Ast) -> String {
+	match &ast.node {
+		Expr::Number(number) => number.to_string(),
+		Expr::Imaginary(number) => format!("{number}i"),
+		Expr::Integer(integer, radix) => radix.format(*integer as u128),
+		Expr::Decimal(digits) => digits.clone(),
+		Expr::Quantity(number, symbol) => format!("{number}{symbol}"),
+		Expr::Bool(boolean) => boolean.to_string(),
+		Expr::Variable(name) => name.clone(),
+		Expr::Unary(Operator::Negate, child) => format!("{} neg", postfix(child)),
+		Expr::Unary(operator, child) => format!("{} {operator}", postfix(child)),
+		Expr::Binary(operator, left, right) => format!("{} {} {operator}", postfix(left), postfix(right)),
+		Expr::Call(name, args) => {
+			let mut words = args.iter().map(postfix).collect::<Vec<String>>();
+			words.push(call_name(name, args.len()));
+			words.join(" ")
+		},
+		Expr::Conditional(condition, then, otherwise) => {
+			format!("{} {} {} {}", postfix(condition), postfix(then), postfix(otherwise), Operator::Conditional)
+		},
+		Expr::Vector(elements) => {
+			let mut words = elements.iter().map(postfix).collect::<Vec<String>>();
+			words.push(call_name("vector", elements.len()));
+			words.join(" ")
+		},
+	}
+}
+
+fn sexpr(ast: &Ast) -> String {
+	match &ast.node {
+		Expr::Number(number) => number.to_string(),
+		Expr::Imaginary(number) => format!("{number}i"),
+		Expr::Integer(integer, radix) => radix.format(*integer as u128),
+		Expr::Decimal(digits) => digits.clone(),
+		Expr::Quantity(number, symbol) => format!("{number}{symbol}"),
+		Expr::Bool(boolean) => boolean.to_string(),
+		Expr::Variable(name) => name.clone(),
+		Expr::Unary(operator, child) => format!("({operator} {})", sexpr(child)),
+		Expr::Binary(operator, left, right) => format!("({operator} {} {})", sexpr(left), sexpr(right)),
+		Expr::Call(name, args) if args.is_empty() => format!("({name})"),
+		Expr::Call(name, args) => format!("({name} {})", args.iter().map(sexpr).collect::<Vec<String>>().join(" ")),
+		Expr::Conditional(condition, then, otherwise) => {
+			format!("({} {} {} {})", Operator::Conditional, sexpr(condition), sexpr(then), sexpr(otherwise))
+		},
+		Expr::Vector(elements) if elements.is_empty() => String::from("(vector)"),
+		Expr::Vector(elements) => format!("(vector {})", elements.iter().map(sexpr).collect::<Vec<String>>().join(" ")),
+	}
+}
+
+fn latex(ast: &Ast) -> String {
+	let operand = |operator: &Operator, child: &Ast, side: Side| {
+		// a fraction is drawn as a block so it never needs parentheses around it
+		let is_fraction = matches!(child.node, Expr::Binary(Operator::Divided, ..));
+		if needs_parentheses(operator, &child.node, side) && !(is_fraction && *operator != Operator::Power) {
+			format!("\\left({}\\right)", latex(child))
+		} else {
+			latex(child)
+		}
+	};
+
+	match &ast.node {
+		Expr::Number(number) => number.to_string(),
+		Expr::Imaginary(number) => format!("{number}i"),
+		Expr::Integer(integer, Radix::Decimal) => integer.to_string(),
+		Expr::Decimal(digits) => digits.clone(),
+		Expr::Integer(integer, radix) => format!("\\mathtt{{{}}}", radix.format(*integer as u128)),
+		Expr::Quantity(number, symbol) if symbol.power == 1 => format!("{number}\\,\\mathrm{{{}}}", symbol.name),
+		Expr::Quantity(number, symbol) => format!("{number}\\,\\mathrm{{{}}}^{{{}}}", symbol.name, symbol.power),
+		Expr::Bool(boolean) => format!("\\text{{{boolean}}}"),
+		Expr::Variable(name) => match name.as_str() {
+			"pi" | "tau" => format!("\\{name}"),
+			_ => name.clone(),
+		},
+		Expr::Unary(Operator::BitNot, child) => format!("\\sim {}", operand(&Operator::BitNot, child, Side::Right)),
+		Expr::Unary(Operator::Not, child) => format!("\\lnot {}", operand(&Operator::Not, child, Side::Right)),
+		Expr::Unary(operator, child) => format!("{operator}{}", operand(operator, child, Side::Right)),
+		Expr::Binary(Operator::Divided, left, right) => format!("\\frac{{{}}}{{{}}}", latex(left), latex(right)),
+		// the exponent is raised so braces are all it needs
+		Expr::Binary(Operator::Power, left, right) => {
+			format!("{}^{{{}}}", operand(&Operator::Power, left, Side::Left), latex(right))
+		},
+		Expr::Binary(operator, left, right) => {
+			let symbol = match operator {
+				Operator::Times => String::from("\\cdot"),
+				Operator::Modulo => String::from("\\bmod"),
+				Operator::BitAnd => String::from("\\mathbin{\\&}"),
+				Operator::BitOr => String::from("\\mid"),
+				Operator::BitXor => String::from("\\oplus"),
+				Operator::ShiftLeft => String::from("\\ll"),
+				Operator::ShiftRight => String::from("\\gg"),
+				Operator::Equal => String::from("="),
+				Operator::NotEqual => String::from("\\neq"),
+				Operator::LessEqual => String::from("\\leq"),
+				Operator::GreaterEqual => String::from("\\geq"),
+				Operator::And => String::from("\\land"),
+				Operator::Or => String::from("\\lor"),
+				Operator::Convert => String::from("\\rightarrow"),
+				Operator::Dot => String::from("\\cdot"),
+				Operator::MatrixProduct => String::from("\\,"),
+				Operator::PlusMinus => String::from("\\pm"),
+				_ => operator.to_string(),
+			};
+			format!("{} {symbol} {}", operand(operator, left, Side::Left), operand(operator, right, Side::Right))
+		},
+		Expr::Call(name, args) => {
+			let args = args.iter().map(latex).collect::<Vec<String>>();
+			match (name.as_str(), args.as_slice()) {
+				("sqrt", [arg]) => format!("\\sqrt{{{arg}}}"),
+				("cbrt", [arg]) => format!("\\sqrt[3]{{{arg}}}"),
+				("abs", [arg]) => format!("\\left|{arg}\\right|"),
+				("floor", [arg]) => format!("\\left\\lfloor {arg}\\right\\rfloor"),
+				("ceil", [arg]) => format!("\\left\\lceil {arg}\\right\\rceil"),
+				("log", [arg, base]) => format!("\\log_{{{base}}}\\left({arg}\\right)"),
+				("log2", [arg]) => format!("\\log_{{2}}\\left({arg}\\right)"),
+				_ => {
+					let name = match name.as_str() {
+						"sin" | "cos" | "tan" | "sinh" | "cosh" | "tanh" | "exp" | "ln" | "log" | "min" | "max" => {
+							format!("\\{name}")
+						},
+						"asin" | "acos" | "atan" => format!("\\arc{}", &name[1..]),
+						_ => format!("\\operatorname{{{name}}}"),
+					};
+					format!("{name}\\left({}\\right)", args.join(", "))
+				},
+			}
+		},
+		Expr::Conditional(condition, then, otherwise) => format!(
+			"\\begin{{cases}} {} & \\text{{if }} {} \\\\ {} & \\text{{otherwise}} \\end{{cases}}",
+			latex(then),
+			latex(condition),
+			latex(otherwise)
+		),
+		Expr::Vector(elements) => {
+			let row = |elements: &[Ast]| elements.iter().map(latex).collect::<Vec<String>>().join(" & ");
+			// a vector of vectors is drawn as a matrix with one row per vector
+			let rows = elements
+				.iter()
+				.map(|element| match &element.node {
+					Expr::Vector(row_elements) => Some(row(row_elements)),
+					_ => None,
+				})
+				.collect::<Option<Vec<String>>>()
+				.filter(|rows| !rows.is_empty());
+			match rows {
+				Some(rows) => format!("\\begin{{bmatrix}} {} \\end{{bmatrix}}", rows.join(" \\\\ ")),
+				None => format!("\\begin{{bmatrix}} {} \\end{{bmatrix}}", row(elements)),
+			}
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shunting::{ShuntingYard, Syntax};
+
+	fn print(notation: Notation, expression: &str) -> String {
+		notation.print(&ShuntingYard::parse(expression, Syntax::default()).unwrap())
+	}
+
+	#[test]
+	fn from_str_test() {
+		let notations = [
+			Notation::Infix,
+			Notation::Prefix,
+			Notation::Postfix,
+			Notation::Sexpr,
+			Notation::Latex,
+		];
+		for notation in notations {
+			assert_eq!(notation.to_string().parse(), Ok(notation));
+		}
+		assert_eq!("rpn".parse(), Ok(Notation::Postfix));
+		assert_eq!("json".parse::<Notation>(), Err(String::from("json")));
+	}
+
+	#[test]
+	fn infix_test() {
+		let infix = |expression| print(Notation::Infix, expression);
+
+		assert_eq!(infix("((1 + 2)) * 3"), "(1 + 2)*3");
+		assert_eq!(infix("1 + (2 * 3)"), "1 + 2*3");
+		assert_eq!(infix("(1 - 2) - 3"), "1 - 2 - 3");
+		assert_eq!(infix("1 - (2 - 3)"), "1 - (2 - 3)");
+		assert_eq!(infix("1 - (2 + 3)"), "1 - (2 + 3)");
+		assert_eq!(infix("8 / (4 / 2)"), "8/(4/2)");
+		assert_eq!(infix("(2 ^ 3) ^ 2"), "(2^3)^2");
+		assert_eq!(infix("2 ^ (3 ^ 2)"), "2^3^2");
+		assert_eq!(infix("-(2 ^ 2)"), "-2^2");
+		assert_eq!(infix("(-2) ^ 2"), "(-2)^2");
+		assert_eq!(infix("-(1 + x)"), "-(1 + x)");
+		assert_eq!(infix("2 ^ (-1)"), "2^-1");
+		assert_eq!(infix("3 - (-2)"), "3 - -2");
+		assert_eq!(infix("2*x*sin(x) + x^2*cos(x)"), "2*x*sin(x) + x^2*cos(x)");
+		assert_eq!(infix("max(1, (2), 3 % 2) + f()"), "max(1, 2, 3%2) + f()");
+		assert_eq!(infix("(0xFF & ~x) | (1 << (2 + 0b11))"), "0xff & ~x | 1 << 2 + 0b11");
+		assert_eq!(infix("(a | b) & (c >> 1 >> 2)"), "(a | b) & c >> 1 >> 2");
+		assert_eq!(infix("(cpu>80)&&(mem<90) ? 1 : 0"), "cpu > 80 && mem < 90 ? 1 : 0");
+		assert_eq!(infix("!(a == b) || (c && true)"), "!(a == b) || c && true");
+		assert_eq!(infix("(a ? b : c) ? (d ? e : f) : (g ? h : i)"), "(a ? b : c) ? d ? e : f : g ? h : i");
+		assert_eq!(infix("(3 km) / (20min) to (km / h)"), "3 km/20 min in km/h");
+		assert_eq!(infix("(2 m) ^ 2 + 2 m^2"), "(2 m)^2 + 2 m^2");
+	}
+
+	#[test]
+	fn infix_round_trip_test() {
+		let expressions = [
+			"1 - (2 - (3 - 4))",
+			"((1 + 2) * (3 - 4)) / -(5 ^ 6 ^ 7)",
+			"-(-(2)) ^ -(3) % (4 * 5)",
+			"hypot(1 - x, (2 / (y / 3)))",
+			"(2 ^ 3) ^ (4 ^ 5) - (6 - 7) * 8",
+			"~(1 & 2) | 3 << (4 >> 5) - 0o17",
+			"(a || b) && !(c != (d < e)) ? (f ? 1 : 2) : 3 >= 4",
+			"(((1 km) ^ 2) / 3 s^-1 in (m ^ 2 * Hz)) == x ? 1 : 2",
+			"[[1, -2], [x, (3 . y)]] @ -[1, 2] . ([3] + 4)",
+		];
+
+		for expression in expressions {
+			let tree = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+			let printed = Notation::Infix.print(&tree);
+			assert_eq!(print(Notation::Sexpr, &printed), Notation::Sexpr.print(&tree), "{expression} printed as {printed}");
+		}
+	}
+
+	#[test]
+	fn prefix_postfix_test() {
+		assert_eq!(print(Notation::Prefix, "3 + 4 * (2 - 1)"), "+ 3 * 4 - 2 1");
+		assert_eq!(print(Notation::Postfix, "3 + 4 * (2 - 1)"), "3 4 2 1 - * +");
+		assert_eq!(print(Notation::Prefix, "-sqrt(x) - max(1, 2, 3)"), "- neg sqrt x max/3 1 2 3");
+		assert_eq!(print(Notation::Postfix, "-sqrt(x) - max(1, 2, 3)"), "x sqrt neg 1 2 3 max/3 -");
+		assert_eq!(print(Notation::Postfix, "f()"), "f/0");
+		assert_eq!(print(Notation::Prefix, "!a && b > 1 ? x : y"), "?: && ! a > b 1 x y");
+		assert_eq!(print(Notation::Postfix, "!a && b > 1 ? x : y"), "a ! b 1 > && x y ?:");
+		assert_eq!(print(Notation::Prefix, "[1, x] . []"), ". vector/2 1 x vector/0");
+		assert_eq!(print(Notation::Postfix, "[[1], [x]]"), "1 vector x vector vector/2");
+	}
+
+	#[test]
+	fn sexpr_test() {
+		assert_eq!(print(Notation::Sexpr, "3 + 4 * (2 - 1)"), "(+ 3 (* 4 (- 2 1)))");
+		assert_eq!(print(Notation::Sexpr, "-x ^ 2 % 3"), "(% (- (^ x 2)) 3)");
+		assert_eq!(print(Notation::Sexpr, "atan2(y, x) + f()"), "(+ (atan2 y x) (f))");
+		assert_eq!(print(Notation::Sexpr, "x >= 0 ? x : -x"), "(?: (>= x 0) x (- x))");
+	}
+
+	#[test]
+	fn latex_test() {
+		let latex = |expression| print(Notation::Latex, expression);
+
+		assert_eq!(latex("(1 + 2) / (3 * x)"), "\\frac{1 + 2}{3 \\cdot x}");
+		assert_eq!(latex("(1 / 2) * 3"), "\\frac{1}{2} \\cdot 3");
+		assert_eq!(latex("(1 / 2) ^ (x + 1)"), "\\left(\\frac{1}{2}\\right)^{x + 1}");
+		assert_eq!(latex("2 * pi * r ^ 2"), "2 \\cdot \\pi \\cdot r^{2}");
+		assert_eq!(latex("-(a - b) % 3"), "-\\left(a - b\\right) \\bmod 3");
+		assert_eq!(latex("sqrt(abs(x)) + sin(x)"), "\\sqrt{\\left|x\\right|} + \\sin\\left(x\\right)");
+		assert_eq!(latex("log(x, 2) - atan(y)"), "\\log_{2}\\left(x\\right) - \\arctan\\left(y\\right)");
+		assert_eq!(latex("hypot(3, 4)"), "\\operatorname{hypot}\\left(3, 4\\right)");
+		assert_eq!(latex("9.81 m/s^2 in ft"), "\\frac{9.81\\,\\mathrm{m}}{s^{2}} \\rightarrow ft");
+		assert_eq!(latex("5 m^2"), "5\\,\\mathrm{m}^{2}");
+		assert_eq!(latex("~(0xF0 & x) | y << 2"), "\\sim \\left(\\mathtt{0xf0} \\mathbin{\\&} x\\right) \\mid y \\ll 2");
+		assert_eq!(latex("!(a <= b) || c != true"), "\\lnot \\left(a \\leq b\\right) \\lor c \\neq \\text{true}");
+		assert_eq!(
+			latex("x >= 0 ? x : -x"),
+			"\\begin{cases} x & \\text{if } x \\geq 0 \\\\ -x & \\text{otherwise} \\end{cases}"
+		);
+		assert_eq!(
+			latex("[[1, 2], [3, 4]] @ [x, y] . [1, 0]"),
+			"\\begin{bmatrix} 1 & 2 \\\\ 3 & 4 \\end{bmatrix} \\, \\begin{bmatrix} x & y \\end{bmatrix} \\cdot \\begin{bmatrix} 1 & 0 \\end{bmatrix}"
+		);
+	}
+}
@@ -0,0 +1,386 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use crate::{bigint::BigInt, format::Format, integer::IntegerMode, shunting::Operator};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumericError {
+	Overflow,
+	/// The result has no value in this mode, like `sqrt(-1)` when calculating exactly
+	NotANumber,
+	/// A fraction where only whole numbers work like in bitwise operators
+	NotAnInteger,
+	/// Shifting by a negative amount or by at least the width of the integer
+	InvalidShift,
+	/// A result like `sqrt(2)` that an exact mode can't hold, or one beyond what it calculates to its precision
+	Inexact,
+	/// A function of a value with an error where it has no slope to carry the error, like `abs(0 ± 1)`
+	NotDifferentiable,
+	/// A comparison that holds for some of the values and not for others, like `(1 ± 1) > 0.5`
+	Indeterminate,
+	/// A comparison of values that have no order, like `3 > 2i`
+	Unordered,
+}
+
+/// The base integers are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Radix {
+	Binary,
+	Octal,
+	#[default]
+	Decimal,
+	Hexadecimal,
+}
+
+impl Radix {
+	pub fn base(self) -> u32 {
+		match self {
+			Radix::Binary => 2,
+			Radix::Octal => 8,
+			Radix::Decimal => 10,
+			Radix::Hexadecimal => 16,
+		}
+	}
+
+	/// The digits of a magnitude with the prefix literals of this radix start with
+	pub fn format(self, magnitude: u128) -> String {
+		match self {
+			Radix::Binary => format!("0b{magnitude:b}"),
+			Radix::Octal => format!("0o{magnitude:o}"),
+			Radix::Decimal => magnitude.to_string(),
+			Radix::Hexadecimal => format!("0x{magnitude:x}"),
+		}
+	}
+}
+
+impl FromStr for Radix {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		match name {
+			"bin" => Ok(Radix::Binary),
+			"oct" => Ok(Radix::Octal),
+			"dec" => Ok(Radix::Decimal),
+			"hex" => Ok(Radix::Hexadecimal),
+			_ => Err(name.to_string()),
+		}
+	}
+}
+
+/// The arithmetic a calculation is carried out with
+///
+/// An instance holds the settings of its mode like the precision of decimals. Division by zero is
+/// caught before any of these are called.
+pub trait Numeric {
+	type Value: Clone + fmt::Debug + fmt::Display + PartialEq;
+
+	/// Exact modes never round through `f64`, a function they can't calculate exactly or to their precision is
+	/// an error
+	const EXACT: bool;
+
+	/// Converts a literal or the result of a function that can only be calculated with `f64`
+	fn value(&self, number: f64) -> Result<Self::Value, NumericError>;
+
+	/// Converts a number as it is, which may be `inf` or `nan` where the mode has them
+	fn unchecked(&self, number: f64) -> Result<Self::Value, NumericError> {
+		self.value(number)
+	}
+
+	/// Converts a decimal literal like `6.02e23` from its digits, exact modes keep every one of them
+	fn literal(&self, digits: &str) -> Result<Self::Value, NumericError> {
+		self.unchecked(digits.parse().map_err(|_| NumericError::NotANumber)?)
+	}
+
+	fn to_f64(&self, value: &Self::Value) -> f64;
+	fn integer(&self, integer: i128) -> Result<Self::Value, NumericError>;
+	/// `None` when the value has a fraction or doesn't fit
+	fn to_integer(&self, value: &Self::Value) -> Option<i128>;
+
+	/// Converts a literal like `0xFF` which stands for its bits in modes where integers have a width
+	fn bit_pattern(&self, pattern: u64) -> Result<Self::Value, NumericError> {
+		self.integer(pattern.into())
+	}
+
+	fn is_zero(&self, value: &Self::Value) -> bool;
+	fn is_negative(&self, value: &Self::Value) -> bool;
+	/// `None` when the values have no order like `NaN` and anything else
+	fn compare(&self, a: &Self::Value, b: &Self::Value) -> Option<Ordering>;
+
+	/// The order `<` and the other comparisons go by, an error where the values don't decide it
+	fn order(&self, a: &Self::Value, b: &Self::Value) -> Result<Option<Ordering>, NumericError> {
+		Ok(self.compare(a, b))
+	}
+
+	fn neg(&self, value: &Self::Value) -> Result<Self::Value, NumericError>;
+	fn add(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, NumericError>;
+	fn sub(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, NumericError>;
+	fn mul(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, NumericError>;
+	fn div(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, NumericError>;
+	/// Truncated like in C so the result takes the sign of the dividend
+	fn rem(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, NumericError>;
+	fn pow(&self, base: &Self::Value, exponent: &Self::Value) -> Result<Self::Value, NumericError>;
+	/// The largest integer not greater than the value
+	fn floor(&self, value: &Self::Value) -> Self::Value;
+
+	/// Calculates `&`, `|`, `^`, `<<` and `>>` on 64 bit signed integers unless the mode has its own
+	fn bitwise(&self, operator: &Operator, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, NumericError> {
+		let integer = IntegerMode::default();
+		let operand = |value| integer.integer(self.to_integer(value).ok_or(NumericError::NotAnInteger)?);
+		self.integer(integer.bitwise(operator, &operand(a)?, &operand(b)?)?)
+	}
+
+	/// Flips all bits of a 64 bit signed integer unless the mode has its own
+	fn not(&self, value: &Self::Value) -> Result<Self::Value, NumericError> {
+		let integer = IntegerMode::default();
+		let operand = integer.integer(self.to_integer(value).ok_or(NumericError::NotAnInteger)?)?;
+		self.integer(integer.not(&operand)?)
+	}
+
+	/// Writes integers in the radix with a sign in front and everything else in decimal
+	fn format(&self, value: &Self::Value, radix: Radix) -> String {
+		match self.to_integer(value) {
+			Some(integer) if radix != Radix::Decimal => {
+				let sign = if integer < 0 { "-" } else { "" };
+				format!("{sign}{}", radix.format(integer.unsigned_abs()))
+			},
+			_ => value.to_string(),
+		}
+	}
+
+	/// Writes a value in the radix and style of the format, modes with numbers made of parts style each of them
+	fn format_with(&self, value: &Self::Value, format: Format) -> String {
+		format.decimal(&self.format(value, format.radix))
+	}
+
+	/// The square root of -1 that `i` and literals like `2i` stand for, `None` in modes without one
+	fn imaginary_unit(&self) -> Option<Self::Value> {
+		None
+	}
+
+	/// `value ± error` in modes whose numbers carry how far off they may be, `None` in the others
+	fn plus_minus(&self, _value: &Self::Value, _error: &Self::Value) -> Option<Result<Self::Value, NumericError>> {
+		None
+	}
+
+	/// The value of a built-in constant like `pi` the mode has its own version of, `None` leaves it to `f64`
+	fn constant(&self, _name: &str) -> Option<Result<Self::Value, NumericError>> {
+		None
+	}
+
+	/// The numbers `integrate` integrates one by one to get the area of a value, like the real and imaginary part
+	fn parts(&self, value: &Self::Value) -> Vec<f64> {
+		vec![self.to_f64(value)]
+	}
+
+	/// Joins the areas of the parts of the values of an integrand
+	fn joined(&self, areas: &[f64]) -> Result<Self::Value, NumericError> {
+		self.value(areas[0])
+	}
+
+	/// Calculates a built-in function the mode has its own version of, `None` leaves it to `f64`
+	fn function(&self, _name: &str, _args: &[Self::Value]) -> Option<Result<Self::Value, NumericError>> {
+		None
+	}
+}
+
+/// The digits of the shortest decimal that reads back as the same `f64` as `(coefficient, exponent)` which
+/// stands for `coefficient * 10^exponent`, `None` when the number isn't finite
+pub fn shortest_decimal(number: f64) -> Option<(BigInt, i64)> {
+	if !number.is_finite() {
+		return None;
+	}
+
+	// `Display` for `f64` prints the shortest round trip digits and never switches to an exponent
+	let text = number.to_string();
+	let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+	let coefficient = format!("{whole}{fraction}").parse().expect("BUG: f64 printed as something other than digits");
+	Some((coefficient, -(fraction.len() as i64)))
+}
+
+/// The value of a decimal literal like `-1.25e3` as `(coefficient, exponent)` without trailing zeros in the
+/// coefficient, `None` when it isn't one
+pub fn decimal_digits(literal: &str) -> Option<(BigInt, i64)> {
+	let (mantissa, exponent) = literal.split_once(['e', 'E']).unwrap_or((literal, "0"));
+	let magnitude = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+	if magnitude.is_empty() || !magnitude.bytes().all(|digit| digit.is_ascii_digit()) {
+		return None;
+	}
+	// an exponent beyond an `i64` is far out of reach of every mode anyway
+	let exponent = exponent.parse::<i64>().unwrap_or(if exponent.starts_with('-') {
+		i64::MIN / 2
+	} else {
+		i64::MAX / 2
+	});
+
+	let (sign, mantissa) = mantissa.strip_prefix('-').map_or(("", mantissa), |mantissa| ("-", mantissa));
+	let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+	let digits = format!("{whole}{fraction}");
+	let significant = digits.trim_end_matches('0');
+	if significant.trim_start_matches('0').is_empty() {
+		return digits.parse::<BigInt>().ok().map(|_| (BigInt::zero(), 0));
+	}
+	let coefficient = format!("{sign}{significant}").parse().ok()?;
+	let shift = (digits.len() - significant.len()) as i64 - fraction.len() as i64;
+	Some((coefficient, exponent.saturating_add(shift)))
+}
+
+/// Plain `f64` arithmetic which is fast but rounds in binary
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FloatMode;
+
+impl FloatMode {
+	// only a finite calculation turning infinite is an overflow, `inf + 1` is still `inf`
+	fn checked(result: f64, operands: &[f64]) -> Result<f64, NumericError> {
+		if result.is_infinite() && operands.iter().all(|operand| operand.is_finite()) {
+			Err(NumericError::Overflow)
+		} else {
+			Ok(result)
+		}
+	}
+}
+
+impl Numeric for FloatMode {
+	type Value = f64;
+
+	const EXACT: bool = false;
+
+	fn value(&self, number: f64) -> Result<f64, NumericError> {
+		Self::checked(number, &[])
+	}
+
+	fn unchecked(&self, number: f64) -> Result<f64, NumericError> {
+		Ok(number)
+	}
+
+	fn to_f64(&self, value: &f64) -> f64 {
+		*value
+	}
+
+	fn integer(&self, integer: i128) -> Result<f64, NumericError> {
+		Ok(integer as f64)
+	}
+
+	fn to_integer(&self, value: &f64) -> Option<i128> {
+		// every `f64` from 2^53 on is an integer but only those below 2^127 fit
+		(value.fract() == 0.0 && value.abs() < 2f64.powi(127)).then_some(*value as i128)
+	}
+
+	fn is_zero(&self, value: &f64) -> bool {
+		*value == 0.0
+	}
+
+	fn is_negative(&self, value: &f64) -> bool {
+		*value < 0.0
+	}
+
+	fn compare(&self, a: &f64, b: &f64) -> Option<Ordering> {
+		a.partial_cmp(b)
+	}
+
+	fn neg(&self, value: &f64) -> Result<f64, NumericError> {
+		Ok(-value)
+	}
+
+	fn add(&self, a: &f64, b: &f64) -> Result<f64, NumericError> {
+		Self::checked(a + b, &[*a, *b])
+	}
+
+	fn sub(&self, a: &f64, b: &f64) -> Result<f64, NumericError> {
+		Self::checked(a - b, &[*a, *b])
+	}
+
+	fn mul(&self, a: &f64, b: &f64) -> Result<f64, NumericError> {
+		Self::checked(a * b, &[*a, *b])
+	}
+
+	fn div(&self, a: &f64, b: &f64) -> Result<f64, NumericError> {
+		Self::checked(a / b, &[*a, *b])
+	}
+
+	fn rem(&self, a: &f64, b: &f64) -> Result<f64, NumericError> {
+		Ok(a % b)
+	}
+
+	fn pow(&self, base: &f64, exponent: &f64) -> Result<f64, NumericError> {
+		Self::checked(base.powf(*exponent), &[*base, *exponent])
+	}
+
+	fn floor(&self, value: &f64) -> f64 {
+		value.floor()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn float_mode_test() {
+		let float = FloatMode;
+
+		assert_eq!(float.add(&0.1, &0.2), Ok(0.30000000000000004));
+		assert_eq!(float.rem(&-7.0, &3.0), Ok(-1.0));
+		assert_eq!(float.pow(&2.0, &0.5), Ok(std::f64::consts::SQRT_2));
+		assert_eq!(float.mul(&1e308, &10.0), Err(NumericError::Overflow));
+		assert_eq!(float.pow(&10.0, &400.0), Err(NumericError::Overflow));
+		assert_eq!(float.value(f64::INFINITY), Err(NumericError::Overflow));
+		assert!(float.value(f64::NAN).unwrap().is_nan());
+		assert_eq!(float.unchecked(f64::INFINITY), Ok(f64::INFINITY));
+		assert_eq!(float.literal("12345678901234567890.125"), Ok(12345678901234567890.125));
+		assert_eq!(float.add(&f64::INFINITY, &1.0), Ok(f64::INFINITY));
+		assert_eq!(float.mul(&f64::NEG_INFINITY, &2.0), Ok(f64::NEG_INFINITY));
+		assert_eq!(float.compare(&-1.0, &2.0), Some(Ordering::Less));
+		assert_eq!(float.compare(&-0.0, &0.0), Some(Ordering::Equal));
+		assert_eq!(float.compare(&f64::NAN, &f64::NAN), None);
+	}
+
+	#[test]
+	fn bitwise_test() {
+		let float = FloatMode;
+
+		assert_eq!(float.bitwise(&Operator::BitAnd, &12.0, &10.0), Ok(8.0));
+		assert_eq!(float.bitwise(&Operator::ShiftLeft, &-3.0, &2.0), Ok(-12.0));
+		assert_eq!(float.not(&0.0), Ok(-1.0));
+		assert_eq!(float.bitwise(&Operator::BitOr, &1.5, &1.0), Err(NumericError::NotAnInteger));
+		assert_eq!(float.bitwise(&Operator::BitOr, &1e19, &1.0), Err(NumericError::Overflow));
+		assert_eq!(float.bitwise(&Operator::ShiftRight, &1.0, &64.0), Err(NumericError::InvalidShift));
+	}
+
+	#[test]
+	fn format_test() {
+		let float = FloatMode;
+
+		assert_eq!(float.format(&255.0, Radix::Hexadecimal), "0xff");
+		assert_eq!(float.format(&-5.0, Radix::Binary), "-0b101");
+		assert_eq!(float.format(&8.0, Radix::Octal), "0o10");
+		assert_eq!(float.format(&2.5, Radix::Hexadecimal), "2.5");
+		assert_eq!(float.format(&1e300, Radix::Hexadecimal), 1e300.to_string());
+		assert_eq!(float.format_with(&-0.0, Format::default()), "0");
+		assert_eq!("hex".parse(), Ok(Radix::Hexadecimal));
+		assert_eq!("base64".parse::<Radix>(), Err(String::from("base64")));
+	}
+
+	#[test]
+	fn shortest_decimal_test() {
+		let decimal = |number| shortest_decimal(number).map(|(coefficient, exponent)| (coefficient.to_string(), exponent));
+
+		assert_eq!(decimal(0.1), Some((String::from("1"), -1)));
+		assert_eq!(decimal(-12.5), Some((String::from("-125"), -1)));
+		assert_eq!(decimal(3.0), Some((String::from("3"), 0)));
+		assert_eq!(decimal(1e21), Some((String::from("1000000000000000000000"), 0)));
+		assert_eq!(decimal(f64::NAN), None);
+	}
+
+	#[test]
+	fn decimal_digits_test() {
+		let digits = |literal| decimal_digits(literal).map(|(coefficient, exponent)| (coefficient.to_string(), exponent));
+
+		assert_eq!(digits("123456789012345678901234567890"), Some((String::from("12345678901234567890123456789"), 1)));
+		assert_eq!(digits("-12.500e-3"), Some((String::from("-125"), -4)));
+		assert_eq!(digits("0.0010"), Some((String::from("1"), -3)));
+		assert_eq!(digits(".5"), Some((String::from("5"), -1)));
+		assert_eq!(digits("0e5"), Some((String::from("0"), 0)));
+		assert_eq!(digits("1e-99999999999999999999"), Some((String::from("1"), i64::MIN / 2)));
+		assert_eq!(digits("1.2.3"), None);
+		assert_eq!(digits("1e"), None);
+	}
+}
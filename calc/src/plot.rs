@@ -0,0 +1,149 @@
+use ccalc::{
+	FloatMode, Value,
+	format::{Format, Style},
+};
+
+/// Draws a function of one variable with characters, one sample for every column
+pub struct Plot {
+	/// Columns of the chart without the labels on its left
+	pub width: usize,
+	pub height: usize,
+}
+
+impl Default for Plot {
+	fn default() -> Self {
+		Self { width: 72, height: 20 }
+	}
+}
+
+impl Plot {
+	/// The chart of `f` from `low` to `high` with the axes where they are in range, `None` if `f` has no finite
+	/// value there
+	///
+	/// Points where `f` is `None` or not finite are left out. The vertical range fits the values that are.
+	pub fn render(&self, f: impl Fn(f64) -> Option<f64>, (low, high): (f64, f64)) -> Option<String> {
+		let (width, height) = (self.width.max(2), self.height.max(2));
+		// the ends are weighed rather than the width taken so `high - low` can't overflow however wide the range is
+		let x = |column: usize| {
+			let share = column as f64 / (width - 1) as f64;
+			low * (1.0 - share) + high * share
+		};
+		let points = (0..width).map(|column| f(x(column)).filter(|y| y.is_finite())).collect::<Vec<Option<f64>>>();
+
+		let values = points.iter().flatten();
+		let bottom = values.clone().copied().reduce(f64::min)?;
+		let top = values.copied().reduce(f64::max)?;
+		// a constant is drawn across the middle
+		let (bottom, top) = if bottom == top {
+			(bottom - 1.0, top + 1.0)
+		} else {
+			(bottom, top)
+		};
+		let row = |y: f64| ((top / 2.0 - y / 2.0) / (top / 2.0 - bottom / 2.0) * (height - 1) as f64).round() as usize;
+
+		let mut grid = vec![vec![' '; width]; height];
+		let zero_row = (bottom <= 0.0 && 0.0 <= top).then(|| row(0.0));
+		let zero_column = (low <= 0.0 && 0.0 <= high)
+			.then(|| (-low / 2.0 / (high / 2.0 - low / 2.0) * (width - 1) as f64).round() as usize);
+		if let Some(zero) = zero_row {
+			grid[zero].fill('-');
+		}
+		if let Some(zero) = zero_column {
+			for line in &mut grid {
+				line[zero] = if line[zero] == '-' { '+' } else { '|' };
+			}
+		}
+		for (column, y) in points.into_iter().enumerate() {
+			if let Some(y) = y {
+				grid[row(y)][column] = '*';
+			}
+		}
+
+		let mut labels = vec![String::new(); height];
+		labels[0] = label(top);
+		labels[height - 1] = label(bottom);
+		if let Some(zero) = zero_row {
+			labels[zero] = label(0.0);
+		}
+		let margin = labels.iter().map(String::len).max().unwrap_or_default();
+
+		let mut chart = String::new();
+		for (label, line) in labels.iter().zip(grid) {
+			let line = line.into_iter().collect::<String>();
+			chart.push_str(format!("{label:>margin$} |{line}").trim_end());
+			chart.push('\n');
+		}
+		chart.push_str(&format!("{:margin$} +{}\n", "", "-".repeat(width)));
+		let (low, high) = (label(low), label(high));
+		let gap = width.saturating_sub(low.len() + high.len()).max(1);
+		chart.push_str(&format!("{:margin$}  {low}{:gap$}{high}", "", ""));
+		Some(chart)
+	}
+}
+
+// short enough to fit the margin, with an exponent when the number is very large or small
+fn label(number: f64) -> String {
+	let magnitude = number.abs();
+	let format = Format {
+		style: if magnitude == 0.0 || (1e-4..1e6).contains(&magnitude) {
+			Style::Auto
+		} else {
+			Style::Scientific
+		},
+		digits: Some(4),
+		..Format::default()
+	};
+	Value::Number(number).format(&FloatMode, format)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn render_test() {
+		let plot = Plot { width: 9, height: 5 };
+
+		assert_eq!(
+			plot.render(|x| Some(x * x), (-2.0, 2.0)).unwrap(),
+			"\
+4 |*   |   *
+  |    |
+  | *  |  *
+  |  * | *
+0 |---***---
+  +---------
+   -2      2"
+		);
+		// a constant fills the middle and a pole is left out
+		assert_eq!(
+			plot.render(|x| (x != 0.0).then_some(0.5), (-1.0, 1.0)).unwrap(),
+			[
+				" 1.5 |    |",
+				"     |    |",
+				"     |****|****",
+				"   0 |----+----",
+				"-0.5 |    |",
+				"     +---------",
+				"      -1      1",
+			]
+			.join("\n")
+		);
+		assert_eq!(plot.render(|_| None, (0.0, 1.0)), None);
+		assert_eq!(plot.render(|x| Some(x.ln()), (-2.0, -1.0)), None);
+
+		// ranges wider than the largest float are still spread over the columns and rows
+		let wide = Plot { width: 5, height: 3 };
+		assert_eq!(
+			wide.render(Some, (-1e308, 1e308)).unwrap(),
+			[
+				" 1e308 |  | *",
+				"     0 |--**-",
+				"-1e308 |**|",
+				"       +-----",
+				"        -1e308 1e308",
+			]
+			.join("\n")
+		);
+	}
+}
@@ -1,134 +1,1514 @@
-use crate::shunting::{Ast, Operator, Token};
+use std::{cell::Cell, cmp::Ordering, fmt};
+
+use crate::{
+	env::{Environment, UserFunction},
+	expr::{self, Ast, Expr},
+	functions::{self, Arity},
+	integrate,
+	matrix::{self, Matrix, Shape},
+	notation::Notation,
+	numeric::{Numeric, NumericError, Radix},
+	shunting::{Operator, Span, Spanned},
+	units::{self, Dimension},
+	value::{Quantity, Value},
+};
 
 #[derive(Debug, PartialEq)]
 pub enum PostfixError {
-	TooManyOperands,
-	NotEnoughOperands,
-	DivisionByZero,
+	DivisionByZero(Span),
+	Overflow(Span),
+	NotANumber(Span),
+	NotAnInteger(Span),
+	InvalidShift(Span),
+	/// A result an exact mode can't hold like `sqrt(2)` in the rational mode
+	Inexact(Span),
+	/// An uncertain argument where the function jumps or turns, like `floor(2 ± 0.1)`
+	NotDifferentiable(Span),
+	/// A comparison of intervals that overlap, which holds for some of their numbers and not for others
+	Indeterminate(Span),
+	/// A comparison of complex numbers that have no order, like `3 > 2i`
+	Unordered(Span),
+	/// A negative error after `±`
+	NegativeError(Span),
+	ModuloByZero(Span),
+	ExpectedNumber(Span),
+	ExpectedBool(Span),
+	RecursionLimit(usize, Span),
+	/// The calculation nested deeper than the environment allows, the span is where it gave up
+	TooDeep(usize, Span),
+	/// The calculation took more steps than the environment allows, the span is where it gave up
+	StepLimit(usize, Span),
+	/// A native function reported an error with this message
+	FunctionFailed(String, String, Span),
+	/// An error while calculating the body of a function, the span is where it was called
+	InFunction(String, Box<PostfixError>, Span),
+	UnknownIdentifier(String, Span),
+	UnknownFunction(String, Span),
+	UnknownUnit(String, Span),
+	/// Units of different dimensions where they have to match, like adding metres and seconds
+	UnitMismatch(Dimension, Dimension, Span),
+	/// A quantity with units where only a plain number makes sense, like the argument of `sin`
+	UnexpectedUnit(Dimension, Span),
+	InvalidUnitPower(Span),
+	NotAUnit(Span),
+	/// Vectors or matrices whose shapes don't allow the operation, like adding vectors of different lengths
+	ShapeMismatch(Shape, Shape, Span),
+	/// A vector or matrix where only a single number makes sense, like the argument of `max`
+	ExpectedScalar(Shape, Span),
+	NotSquare(Shape, Span),
+	Singular(Span),
+	/// A matrix literal whose rows aren't all vectors of the same length
+	RaggedMatrix(Span),
+	/// Something other than a name where `sum` or `integrate` expect the variable they bind
+	ExpectedVariable(Span),
+	/// An imaginary number in a mode without them
+	NotComplex(Span),
+	/// A value with an error in a mode whose numbers are exact
+	NotUncertain(Span),
+	ArityMismatch {
+		name: String,
+		expected: Arity,
+		found: usize,
+		span: Span,
+	},
+}
+
+impl PostfixError {
+	pub fn numeric(error: NumericError, span: Span) -> Self {
+		match error {
+			NumericError::Overflow => PostfixError::Overflow(span),
+			NumericError::NotANumber => PostfixError::NotANumber(span),
+			NumericError::NotAnInteger => PostfixError::NotAnInteger(span),
+			NumericError::InvalidShift => PostfixError::InvalidShift(span),
+			NumericError::Inexact => PostfixError::Inexact(span),
+			NumericError::NotDifferentiable => PostfixError::NotDifferentiable(span),
+			NumericError::Indeterminate => PostfixError::Indeterminate(span),
+			NumericError::Unordered => PostfixError::Unordered(span),
+		}
+	}
+
+	pub fn span(&self) -> Span {
+		match self {
+			PostfixError::DivisionByZero(span)
+			| PostfixError::Overflow(span)
+			| PostfixError::NotANumber(span)
+			| PostfixError::NotAnInteger(span)
+			| PostfixError::InvalidShift(span)
+			| PostfixError::Inexact(span)
+			| PostfixError::NotDifferentiable(span)
+			| PostfixError::Indeterminate(span)
+			| PostfixError::Unordered(span)
+			| PostfixError::NegativeError(span)
+			| PostfixError::ModuloByZero(span)
+			| PostfixError::ExpectedNumber(span)
+			| PostfixError::ExpectedBool(span)
+			| PostfixError::RecursionLimit(_, span)
+			| PostfixError::TooDeep(_, span)
+			| PostfixError::StepLimit(_, span)
+			| PostfixError::FunctionFailed(_, _, span)
+			| PostfixError::InFunction(_, _, span)
+			| PostfixError::UnknownIdentifier(_, span)
+			| PostfixError::UnknownFunction(_, span)
+			| PostfixError::UnknownUnit(_, span)
+			| PostfixError::UnitMismatch(_, _, span)
+			| PostfixError::UnexpectedUnit(_, span)
+			| PostfixError::InvalidUnitPower(span)
+			| PostfixError::NotAUnit(span)
+			| PostfixError::ShapeMismatch(_, _, span)
+			| PostfixError::ExpectedScalar(_, span)
+			| PostfixError::NotSquare(_, span)
+			| PostfixError::Singular(span)
+			| PostfixError::RaggedMatrix(span)
+			| PostfixError::ExpectedVariable(span)
+			| PostfixError::NotComplex(span)
+			| PostfixError::NotUncertain(span)
+			| PostfixError::ArityMismatch { span, .. } => *span,
+		}
+	}
+}
+
+impl fmt::Display for PostfixError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PostfixError::DivisionByZero(_) => write!(f, "division by zero"),
+			PostfixError::Overflow(_) => write!(f, "the result is too large"),
+			PostfixError::NotANumber(_) => write!(f, "the result is not a number"),
+			PostfixError::NotAnInteger(_) => write!(f, "the result is not an integer"),
+			PostfixError::InvalidShift(_) => write!(f, "the shift amount is out of range"),
+			PostfixError::Inexact(_) => write!(f, "the result can't be calculated exactly in this mode"),
+			PostfixError::NotDifferentiable(_) => write!(f, "the error can't be carried where the function has no slope"),
+			PostfixError::Indeterminate(_) => write!(f, "the comparison holds for some values of the intervals but not all"),
+			PostfixError::Unordered(_) => write!(f, "complex numbers have no order"),
+			PostfixError::NegativeError(_) => write!(f, "the error after `±` can't be negative"),
+			PostfixError::ModuloByZero(_) => write!(f, "modulo by zero"),
+			PostfixError::ExpectedNumber(_) => write!(f, "expected a number but found a boolean"),
+			PostfixError::ExpectedBool(_) => write!(f, "expected a boolean but found a number"),
+			PostfixError::RecursionLimit(limit, _) => write!(f, "more than {limit} nested function calls"),
+			PostfixError::TooDeep(limit, _) => write!(f, "the calculation is nested deeper than {limit} levels"),
+			PostfixError::StepLimit(limit, _) => write!(f, "the calculation takes more than {limit} steps"),
+			PostfixError::FunctionFailed(name, message, _) => write!(f, "`{name}` failed: {message}"),
+			PostfixError::InFunction(name, error, _) => write!(f, "{error} in function `{name}`"),
+			PostfixError::UnknownIdentifier(name, _) => write!(f, "unknown identifier `{name}`"),
+			PostfixError::UnknownFunction(name, _) => write!(f, "unknown function `{name}`"),
+			PostfixError::UnknownUnit(name, _) => write!(f, "unknown unit `{name}`"),
+			PostfixError::UnitMismatch(a, b, _) => write!(f, "{} and {} are incompatible", units(a), units(b)),
+			PostfixError::UnexpectedUnit(dimension, _) => {
+				write!(f, "expected a plain number but found a quantity in `{dimension}`")
+			},
+			PostfixError::InvalidUnitPower(_) => write!(f, "the units can't be raised to this power"),
+			PostfixError::NotAUnit(_) => write!(f, "expected a unit like `km/h`"),
+			PostfixError::ShapeMismatch(a, b, _) => write!(f, "{a} and {b} don't fit together"),
+			PostfixError::ExpectedScalar(shape, _) => write!(f, "expected a number but found {shape}"),
+			PostfixError::NotSquare(shape, _) => write!(f, "expected a square matrix but found {shape}"),
+			PostfixError::Singular(_) => write!(f, "the matrix is singular and has no inverse"),
+			PostfixError::RaggedMatrix(_) => write!(f, "the rows of a matrix have to be vectors of the same length"),
+			PostfixError::ExpectedVariable(_) => write!(f, "expected the name of the variable to bind"),
+			PostfixError::NotComplex(_) => write!(f, "imaginary numbers need the complex mode"),
+			PostfixError::NotUncertain(_) => write!(f, "`±` needs the interval or uncertain mode"),
+			PostfixError::ArityMismatch {
+				name, expected, found, ..
+			} => {
+				write!(f, "`{name}` takes {expected} but was given {found}")
+			},
+		}
+	}
+}
+
+fn units(dimension: &Dimension) -> String {
+	if dimension.is_dimensionless() {
+		String::from("a plain number")
+	} else {
+		format!("`{dimension}`")
+	}
+}
+
+// the magnitude in SI base units of a number or quantity found at the span
+fn magnitude<V>(value: Value<V>, span: Span) -> Result<(V, Dimension), PostfixError> {
+	match value {
+		Value::Number(number) => Ok((number, Dimension::NONE)),
+		Value::Quantity(quantity) => Ok((quantity.magnitude, quantity.dimension)),
+		Value::Bool(_) => Err(PostfixError::ExpectedNumber(span)),
+		Value::Vector(_) | Value::Matrix(_) => Err(PostfixError::ExpectedScalar(shape(&value), span)),
+	}
+}
+
+// a number without units for the elements of vectors and matrices
+fn plain<V>(value: Value<V>, span: Span) -> Result<V, PostfixError> {
+	match magnitude(value, span)? {
+		(number, units) if units.is_dimensionless() => Ok(number),
+		(_, units) => Err(PostfixError::UnexpectedUnit(units, span)),
+	}
+}
+
+// the truth of a condition found at the span
+fn boolean<V>(value: Value<V>, span: Span) -> Result<bool, PostfixError> {
+	match value {
+		Value::Bool(boolean) => Ok(boolean),
+		Value::Number(_) | Value::Quantity(_) | Value::Vector(_) | Value::Matrix(_) => {
+			Err(PostfixError::ExpectedBool(span))
+		},
+	}
+}
+
+fn shape<V>(value: &Value<V>) -> Shape {
+	match value {
+		Value::Vector(elements) => Shape::Vector(elements.len()),
+		Value::Matrix(matrix) => matrix.shape(),
+		Value::Number(_) | Value::Bool(_) | Value::Quantity(_) => Shape::Scalar,
+	}
+}
+
+// the parameters of the function being called are looked up before the variables of the environment
+#[derive(Clone)]
+struct Scope<'a, V> {
+	env: &'a Environment<V>,
+	locals: Vec<(&'a str, V)>,
+	depth: usize,
+	/// Shared by all calls so the step limit covers the whole calculation
+	steps: &'a Cell<usize>,
+}
+
+impl<'a, V: Clone> Scope<'a, V> {
+	fn get(&self, name: &str) -> Option<V> {
+		match self.locals.iter().find(|(local, _)| *local == name) {
+			Some((_, value)) => Some(value.clone()),
+			None => self.env.get(name),
+		}
+	}
+
+	// the scope of the body of `sum` or `integrate` with its variable hiding everything of the same name
+	fn bind(&self, variable: &'a str, value: V) -> Self {
+		let mut locals = vec![(variable, value)];
+		locals.extend(self.locals.iter().cloned());
+		Self { locals, ..self.clone() }
+	}
+}
+
+// a node of the tree, the index of the scope its names are looked up in and how deep it is in the expression
+// or the body of the function it belongs to
+#[derive(Clone, Copy)]
+struct Node<'a> {
+	ast: &'a Ast,
+	scope: usize,
+	level: usize,
+}
+
+impl<'a> Node<'a> {
+	fn operand(self, ast: &'a Ast) -> Task<'a> {
+		Task::Eval(Node {
+			ast,
+			level: self.level + 1,
+			..self
+		})
+	}
+}
+
+// what is left of a calculation, kept on the heap so neither a deep expression nor deep recursion can overflow
+// the stack
+enum Task<'a> {
+	/// Calculates a leaf, or the operands of a node ahead of combining them
+	Eval(Node<'a>),
+	/// Combines the values of the operands of a node, the last of them on top
+	Combine(Node<'a>),
+	/// Calculates the operand of `&&`, `||` or `?:` the value on top picks
+	Choose(Node<'a>),
+	/// Calculates the terms of a `sum` from a whole number up to the last
+	Term(Node<'a>, i128, i128),
+	/// Adds the term of a `sum` on top to the total under it
+	Accumulate(Node<'a>),
+	/// Leaves the body of the function called at the span
+	Return(&'a str, Span),
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Postfix {}
 
+/// More terms than this are a mistake rather than a sum that is worth waiting for
+const MAX_TERMS: i128 = 1_000_000;
+
+/// The absolute error `integrate` aims for
+const TOLERANCE: f64 = 1e-10;
+
 impl Postfix {
-	pub fn calc(equation: Ast) -> Result<f64, PostfixError> {
-		let mut stack = Vec::new();
+	pub fn calc<N: Numeric>(
+		ast: &Ast,
+		env: &Environment<Value<N::Value>>,
+		numeric: &N,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let steps = Cell::new(0);
+		let scope = Scope {
+			env,
+			locals: Vec::new(),
+			depth: 0,
+			steps: &steps,
+		};
+		Self::eval(ast, scope, 0, numeric)
+	}
+
+	/// Calculates an operator or call whose operands are values already, like those on the stack of the RPN
+	/// mode. The AST names them by their position as `0`, `1` and so on which no identifier can be.
+	pub fn apply<N: Numeric>(
+		ast: &Ast,
+		operands: Vec<Value<N::Value>>,
+		env: &Environment<Value<N::Value>>,
+		numeric: &N,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let names = (0..operands.len()).map(|index| index.to_string()).collect::<Vec<String>>();
+		let steps = Cell::new(0);
+		let scope = Scope {
+			env,
+			locals: names.iter().map(String::as_str).zip(operands).collect(),
+			depth: 0,
+			steps: &steps,
+		};
+		Self::eval(ast, scope, 0, numeric)
+	}
+
+	/// Calculates an expression with a variable bound to a value ahead of the environment, like the unknown
+	/// of an equation while it is being solved
+	pub fn calc_with<N: Numeric>(
+		ast: &Ast,
+		variable: &str,
+		value: Value<N::Value>,
+		env: &Environment<Value<N::Value>>,
+		numeric: &N,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let steps = Cell::new(0);
+		let scope = Scope {
+			env,
+			locals: vec![(variable, value)],
+			depth: 0,
+			steps: &steps,
+		};
+		Self::eval(ast, scope, 0, numeric)
+	}
+
+	// a tree `level` deep in the expression or body it belongs to
+	fn eval<'a, N: Numeric>(
+		ast: &'a Ast,
+		scope: Scope<'a, Value<N::Value>>,
+		level: usize,
+		numeric: &'a N,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let calculation = Calculation {
+			numeric,
+			scopes: vec![scope],
+			tasks: vec![Task::Eval(Node { ast, scope: 0, level })],
+			values: Vec::new(),
+		};
+		calculation.run()
+	}
 
-		for item in equation {
-			match item {
-				Token::Number(number) => {
-					stack.push(number);
+	// a vector of numbers or a matrix when every element is a vector of the same length
+	fn vector<V>(values: Vec<Value<V>>, elements: &[Ast]) -> Result<Value<V>, PostfixError> {
+		if !matches!(values.first(), Some(Value::Vector(_))) {
+			return values
+				.into_iter()
+				.zip(elements)
+				.map(|(value, element)| match value {
+					Value::Vector(_) | Value::Matrix(_) => Err(PostfixError::RaggedMatrix(element.span)),
+					value => plain(value, element.span),
+				})
+				.collect::<Result<_, _>>()
+				.map(Value::Vector);
+		}
+
+		let rows = values
+			.into_iter()
+			.zip(elements)
+			.map(|(value, element)| match value {
+				Value::Vector(row) => Ok(row),
+				_ => Err(PostfixError::RaggedMatrix(element.span)),
+			})
+			.collect::<Result<Vec<Vec<V>>, _>>()?;
+		match rows.iter().position(|row| row.len() != rows[0].len()) {
+			Some(ragged) => Err(PostfixError::RaggedMatrix(elements[ragged].span)),
+			None => Ok(Value::Matrix(Matrix::from_rows(rows).expect("BUG: the rows have the same length"))),
+		}
+	}
+
+	// element by element arithmetic where a single number goes with every element of the other operand
+	fn broadcast<N: Numeric>(
+		numeric: &N,
+		operator: &Operator,
+		a: Value<N::Value>,
+		b: Value<N::Value>,
+		span: Span,
+		(left, right): (Span, Span),
+	) -> Result<Value<N::Value>, PostfixError> {
+		let element = |a: &N::Value, b: &N::Value| Self::arithmetic(numeric, operator, a, b, span, right);
+		match (a, b) {
+			(Value::Vector(a), Value::Vector(b)) if a.len() == b.len() => {
+				a.iter().zip(&b).map(|(a, b)| element(a, b)).collect::<Result<_, _>>().map(Value::Vector)
+			},
+			(Value::Matrix(a), Value::Matrix(b)) if a.shape() == b.shape() => a.zip(&b, element).map(Value::Matrix),
+			(a @ (Value::Vector(_) | Value::Matrix(_)), b @ (Value::Vector(_) | Value::Matrix(_))) => {
+				Err(PostfixError::ShapeMismatch(shape(&a), shape(&b), span))
+			},
+			(Value::Vector(a), b) => {
+				let b = plain(b, right)?;
+				a.iter().map(|a| element(a, &b)).collect::<Result<_, _>>().map(Value::Vector)
+			},
+			(Value::Matrix(a), b) => {
+				let b = plain(b, right)?;
+				a.map(|a| element(a, &b)).map(Value::Matrix)
+			},
+			(a, Value::Vector(b)) => {
+				let a = plain(a, left)?;
+				b.iter().map(|b| element(&a, b)).collect::<Result<_, _>>().map(Value::Vector)
+			},
+			(a, Value::Matrix(b)) => {
+				let a = plain(a, left)?;
+				b.map(|b| element(&a, b)).map(Value::Matrix)
+			},
+			_ => unreachable!("one of the operands is a vector or matrix"),
+		}
+	}
+
+	// a vector is a row on the left of a matrix and a column on its right, and `v @ w` is the dot product
+	fn matrix_product<N: Numeric>(
+		numeric: &N,
+		a: Value<N::Value>,
+		b: Value<N::Value>,
+		span: Span,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let checked = |error| PostfixError::numeric(error, span);
+		let row = |elements: Vec<N::Value>| Matrix::from_rows(vec![elements]).expect("BUG: a single row is never ragged");
+		match (a, b) {
+			(Value::Vector(a), Value::Vector(b)) if a.len() == b.len() => {
+				matrix::dot(numeric, &a, &b).map(Value::Number).map_err(checked)
+			},
+			(Value::Matrix(a), Value::Matrix(b)) if a.columns() == b.rows() => {
+				matrix::product(numeric, &a, &b).map(Value::Matrix).map_err(checked)
+			},
+			(Value::Matrix(a), Value::Vector(b)) if a.columns() == b.len() => {
+				let product = matrix::product(numeric, &a, &row(b).transpose()).map_err(checked)?;
+				Ok(Value::Vector(product.transpose().elements().to_vec()))
+			},
+			(Value::Vector(a), Value::Matrix(b)) if a.len() == b.rows() => {
+				let product = matrix::product(numeric, &row(a), &b).map_err(checked)?;
+				Ok(Value::Vector(product.elements().to_vec()))
+			},
+			(a, b) => Err(PostfixError::ShapeMismatch(shape(&a), shape(&b), span)),
+		}
+	}
+
+	// `transpose`, `det` and `inv` of the argument at `arg`, or `polar` which makes a vector of a number
+	fn matrix_call<N: Numeric>(
+		numeric: &N,
+		name: &str,
+		value: Value<N::Value>,
+		arg: Span,
+		span: Span,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let checked = |error| PostfixError::numeric(error, span);
+		if name == "polar" {
+			let number = [plain(value, arg)?];
+			let part = |name| functions::call_numeric(numeric, name, &number, span);
+			return Ok(Value::Vector(vec![part("abs")?, part("arg")?]));
+		}
+		let matrix = match value {
+			Value::Matrix(matrix) => matrix,
+			// a vector is a single row
+			Value::Vector(elements) if name == "transpose" => {
+				let row = Matrix::from_rows(vec![elements]).expect("BUG: a single row is never ragged");
+				return Ok(Value::Matrix(row.transpose()));
+			},
+			Value::Vector(_) => return Err(PostfixError::NotSquare(shape(&value), arg)),
+			// a number is its own transpose
+			value => {
+				let number = plain(value, arg)?;
+				return if name == "transpose" {
+					Ok(Value::Number(number))
+				} else {
+					Err(PostfixError::NotSquare(Shape::Scalar, arg))
+				};
+			},
+		};
+
+		match name {
+			"transpose" => Ok(Value::Matrix(matrix.transpose())),
+			_ if matrix.rows() != matrix.columns() => Err(PostfixError::NotSquare(matrix.shape(), arg)),
+			"det" => matrix::determinant(numeric, &matrix).map(Value::Number).map_err(checked),
+			_ => match matrix::inverse(numeric, &matrix).map_err(checked)? {
+				Some(inverse) => Ok(Value::Matrix(inverse)),
+				None => Err(PostfixError::Singular(span)),
+			},
+		}
+	}
+
+	fn all_equal<N: Numeric>(numeric: &N, a: &[N::Value], b: &[N::Value]) -> bool {
+		a.len() == b.len() && a.iter().zip(b).all(|(a, b)| numeric.compare(a, b) == Some(Ordering::Equal))
+	}
+
+	/// Calculates `+`, `-`, `*`, `/`, `%` or `^` where `right` is the span of the right operand
+	pub fn arithmetic<N: Numeric>(
+		numeric: &N,
+		operator: &Operator,
+		a: &N::Value,
+		b: &N::Value,
+		span: Span,
+		right: Span,
+	) -> Result<N::Value, PostfixError> {
+		let checked = |result: Result<N::Value, NumericError>| result.map_err(|error| PostfixError::numeric(error, span));
+		match operator {
+			Operator::Plus => checked(numeric.add(a, b)),
+			Operator::Minus => checked(numeric.sub(a, b)),
+			Operator::Times => checked(numeric.mul(a, b)),
+			Operator::Divided if numeric.is_zero(b) => Err(PostfixError::DivisionByZero(right)),
+			Operator::Divided => checked(numeric.div(a, b)),
+			Operator::Modulo if numeric.is_zero(b) => Err(PostfixError::ModuloByZero(right)),
+			Operator::Modulo => checked(numeric.rem(a, b)),
+			// a negative power of zero is a division by zero in disguise
+			Operator::Power if numeric.is_zero(a) && numeric.is_negative(b) => Err(PostfixError::DivisionByZero(span)),
+			Operator::Power => checked(numeric.pow(a, b)),
+			_ => unreachable!("`{operator}` is not an arithmetic operator"),
+		}
+	}
+
+	/// Whether `<`, `<=`, `>` or `>=` holds for two values ordered like this
+	pub fn ordered(operator: &Operator, ordering: Option<Ordering>) -> bool {
+		// nothing is ordered against NaN so every comparison with it is false
+		ordering.is_some_and(|ordering| match operator {
+			Operator::Less => ordering.is_lt(),
+			Operator::LessEqual => ordering.is_le(),
+			Operator::Greater => ordering.is_gt(),
+			_ => ordering.is_ge(),
+		})
+	}
+
+	// the units of what a built-in function returns, most of them only take plain numbers
+	fn call_units(name: &str, args: &[(Dimension, Span)], span: Span) -> Result<Dimension, PostfixError> {
+		let Some(&(first, _)) = args.first() else {
+			return Ok(Dimension::NONE);
+		};
+
+		match name {
+			"abs" | "conj" | "re" | "im" | "floor" | "ceil" | "round" | "min" | "max" | "hypot" | "atan2" => {
+				if let Some(&(other, span)) = args.iter().find(|(units, _)| *units != first) {
+					return Err(PostfixError::UnitMismatch(first, other, span));
+				}
+				// an angle is a ratio of its sides
+				Ok(if name == "atan2" { Dimension::NONE } else { first })
+			},
+			"sqrt" => first.root(2).ok_or(PostfixError::InvalidUnitPower(span)),
+			"cbrt" => first.root(3).ok_or(PostfixError::InvalidUnitPower(span)),
+			_ => match args.iter().find(|(units, _)| !units.is_dimensionless()) {
+				Some(&(units, span)) => Err(PostfixError::UnexpectedUnit(units, span)),
+				None => Ok(Dimension::NONE),
+			},
+		}
+	}
+
+	fn same_units(a: Dimension, b: Dimension, span: Span) -> Result<Dimension, PostfixError> {
+		if a == b {
+			Ok(a)
+		} else {
+			Err(PostfixError::UnitMismatch(a, b, span))
+		}
+	}
+
+	// the body only sees its parameters and the variables of the environment, not those of its caller
+	fn call<'a, V: Clone>(
+		name: &str,
+		function: &'a UserFunction,
+		args: Vec<V>,
+		scope: &Scope<'a, V>,
+		span: Span,
+	) -> Result<Scope<'a, V>, PostfixError> {
+		if args.len() != function.parameters.len() {
+			return Err(PostfixError::ArityMismatch {
+				name: name.to_string(),
+				expected: Arity::Exact(function.parameters.len()),
+				found: args.len(),
+				span,
+			});
+		}
+		if scope.depth >= scope.env.recursion_limit {
+			return Err(PostfixError::RecursionLimit(scope.env.recursion_limit, span));
+		}
+
+		Ok(Scope {
+			env: scope.env,
+			locals: function.parameters.iter().map(String::as_str).zip(args).collect(),
+			depth: scope.depth + 1,
+			steps: scope.steps,
+		})
+	}
+
+	// the body, variable and bounds of `sum` or `integrate`, whose shape was checked before calculating them
+	fn higher_order(ast: &Ast) -> (&Ast, &str, &Ast, &Ast) {
+		match &ast.node {
+			Expr::Call(_, args) => match args.as_slice() {
+				[
+					body,
+					Spanned {
+						node: Expr::Variable(variable),
+						..
+					},
+					from,
+					to,
+				] => (body, variable, from, to),
+				_ => unreachable!("the arguments of `sum` and `integrate` are checked first"),
+			},
+			_ => unreachable!("`sum` and `integrate` are calls"),
+		}
+	}
+}
+
+// the values of the nodes calculated so far and the tasks left to do
+struct Calculation<'a, N: Numeric> {
+	numeric: &'a N,
+	/// The scope of the expression and those of the function bodies and sums being calculated in it
+	scopes: Vec<Scope<'a, Value<N::Value>>>,
+	tasks: Vec<Task<'a>>,
+	values: Vec<Value<N::Value>>,
+}
+
+impl<'a, N: Numeric> Calculation<'a, N> {
+	fn run(mut self) -> Result<Value<N::Value>, PostfixError> {
+		while let Some(task) = self.tasks.pop() {
+			let done = match task {
+				Task::Eval(node) => self.visit(node),
+				Task::Combine(node) => self.combine(node),
+				Task::Choose(node) => self.choose(node),
+				Task::Term(node, k, last) => self.term(node, k, last),
+				Task::Accumulate(node) => self.accumulate(node),
+				Task::Return(..) => {
+					self.scopes.pop();
+					Ok(())
 				},
-				Token::Operator(Operator::Plus) => {
-					if stack.len() < 2 {
-						return Err(PostfixError::NotEnoughOperands);
-					}
-					let (operand_b, operand_a) = (stack.pop(), stack.pop());
-					stack.push(operand_a.unwrap() + operand_b.unwrap());
+			};
+			// spans in the bodies point into their definitions so the error is moved to the outermost call
+			if let Err(error) = done {
+				return Err(self.tasks.iter().rev().fold(error, |error, task| match (task, error) {
+					(Task::Return(_, span), PostfixError::InFunction(name, error, _)) => {
+						PostfixError::InFunction(name, error, *span)
+					},
+					(Task::Return(name, span), error) => PostfixError::InFunction(name.to_string(), Box::new(error), *span),
+					(_, error) => error,
+				}));
+			}
+		}
+		Ok(self.values.pop().expect("BUG: a calculation ends with its value"))
+	}
+
+	fn operand(&mut self) -> Value<N::Value> {
+		self.values.pop().expect("BUG: the operand was calculated")
+	}
+
+	// the values of the last operands calculated, the first of them first
+	fn operands(&mut self, count: usize) -> Vec<Value<N::Value>> {
+		self.values.split_off(self.values.len() - count)
+	}
+
+	// the operands are calculated in order ahead of the node that combines them
+	fn defer(&mut self, node: Node<'a>, operands: impl DoubleEndedIterator<Item = &'a Ast>) {
+		self.tasks.push(Task::Combine(node));
+		self.tasks.extend(operands.rev().map(|operand| node.operand(operand)));
+	}
+
+	fn visit(&mut self, node: Node<'a>) -> Result<(), PostfixError> {
+		let (ast, scope, numeric) = (node.ast, &self.scopes[node.scope], self.numeric);
+		let limit = scope.env.depth_limit.min(Environment::<()>::MAX_DEPTH_LIMIT);
+		if node.level >= limit {
+			return Err(PostfixError::TooDeep(limit, ast.span));
+		}
+		let steps = scope.steps.get() + 1;
+		scope.steps.set(steps);
+		if let Some(limit) = scope.env.step_limit
+			&& steps > limit
+		{
+			return Err(PostfixError::StepLimit(limit, ast.span));
+		}
+		let checked =
+			|result: Result<N::Value, NumericError>| result.map_err(|error| PostfixError::numeric(error, ast.span));
+
+		let value = match &ast.node {
+			Expr::Number(number) => Value::Number(checked(numeric.unchecked(*number))?),
+			Expr::Decimal(digits) => Value::Number(checked(numeric.literal(digits))?),
+			Expr::Integer(integer, Radix::Decimal) => Value::Number(checked(numeric.integer((*integer).into()))?),
+			Expr::Integer(pattern, _) => Value::Number(checked(numeric.bit_pattern(*pattern))?),
+			// a variable hides a unit of the same name like it does without a number
+			Expr::Quantity(number, symbol) if scope.get(&symbol.name).is_some() => {
+				Postfix::eval(&expr::product(*number, symbol, ast.span), scope.clone(), node.level + 1, numeric)?
+			},
+			Expr::Quantity(number, symbol) => {
+				let unit = symbol.unit().ok_or(PostfixError::InvalidUnitPower(ast.span))?;
+				let magnitude = checked(numeric.unchecked(*number).and_then(|number| unit.magnitude(numeric, &number)))?;
+				Value::quantity(magnitude, unit.dimension)
+			},
+			Expr::Imaginary(number) if scope.get("i").is_some() => {
+				Postfix::eval(&expr::imaginary_product(*number, ast.span), scope.clone(), node.level + 1, numeric)?
+			},
+			Expr::Imaginary(number) => {
+				let unit = numeric.imaginary_unit().ok_or(PostfixError::NotComplex(ast.span))?;
+				Value::Number(checked(numeric.unchecked(*number).and_then(|number| numeric.mul(&number, &unit)))?)
+			},
+			Expr::Bool(boolean) => Value::Bool(*boolean),
+			// variables and constants shadow units of the same name
+			Expr::Variable(name) => match (scope.get(name), functions::constant(name), units::unit(name)) {
+				(Some(value), ..) => value,
+				(None, Some(constant), _) => {
+					Value::Number(checked(numeric.constant(name).unwrap_or_else(|| numeric.value(constant)))?)
 				},
-				Token::Operator(Operator::Minus) => {
-					if stack.len() < 2 {
-						return Err(PostfixError::NotEnoughOperands);
-					}
-					let (operand_b, operand_a) = (stack.pop(), stack.pop());
-					stack.push(operand_a.unwrap() - operand_b.unwrap());
+				(None, None, _) if name == "i" => {
+					numeric.imaginary_unit().map(Value::Number).ok_or(PostfixError::NotComplex(ast.span))?
 				},
-				Token::Operator(Operator::Times) => {
-					if stack.len() < 2 {
-						return Err(PostfixError::NotEnoughOperands);
-					}
-					let (operand_b, operand_a) = (stack.pop(), stack.pop());
-					stack.push(operand_a.unwrap() * operand_b.unwrap());
+				(None, None, Some(unit)) => {
+					let magnitude = checked(numeric.integer(1).and_then(|one| unit.magnitude(numeric, &one)))?;
+					Value::quantity(magnitude, unit.dimension)
 				},
-				Token::Operator(Operator::Divided) => {
-					if stack.len() < 2 {
-						return Err(PostfixError::NotEnoughOperands);
-					}
-					let (operand_b, operand_a) = (stack.pop(), stack.pop());
-					if operand_b == Some(0.0) {
-						return Err(PostfixError::DivisionByZero);
-					}
-					stack.push(operand_a.unwrap() / operand_b.unwrap());
+				(None, None, None) => return Err(PostfixError::UnknownIdentifier(name.clone(), ast.span)),
+			},
+			Expr::Unary(Operator::Negate | Operator::BitNot | Operator::Not, operand) => {
+				self.defer(node, [&**operand].into_iter());
+				return Ok(());
+			},
+			Expr::Unary(operator, _) => unreachable!("`{operator}` is not a prefix operator"),
+			// the right operand or a branch is only calculated when the value of the first operand picks it
+			Expr::Binary(Operator::And | Operator::Or, first, _) | Expr::Conditional(first, ..) => {
+				self.tasks.push(Task::Choose(node));
+				self.tasks.push(node.operand(first));
+				return Ok(());
+			},
+			// the right side of a conversion is the unit rather than a value
+			Expr::Binary(Operator::Convert, left, _) => {
+				self.defer(node, [&**left].into_iter());
+				return Ok(());
+			},
+			Expr::Binary(_, left, right) => {
+				self.defer(node, [&**left, &**right].into_iter());
+				return Ok(());
+			},
+			Expr::Call(name, args) => {
+				let operands = match (scope.env.function(name), scope.env.native(name)) {
+					(Some(_), _) => args.as_slice(),
+					(None, Some(native)) if !native.arity.accepts(args.len()) => {
+						return Err(PostfixError::ArityMismatch {
+							name: name.clone(),
+							expected: native.arity,
+							found: args.len(),
+							span: ast.span,
+						});
+					},
+					(None, None) if functions::is_higher_order(name) => {
+						let [_, variable, _, _] = args.as_slice() else {
+							return Err(PostfixError::ArityMismatch {
+								name: name.clone(),
+								expected: Arity::Exact(4),
+								found: args.len(),
+								span: ast.span,
+							});
+						};
+						if !matches!(variable.node, Expr::Variable(_)) {
+							return Err(PostfixError::ExpectedVariable(variable.span));
+						}
+						// the area is only ever approximated
+						if name == "integrate" && N::EXACT {
+							return Err(PostfixError::Inexact(ast.span));
+						}
+						// the body is calculated for every term or sample once the bounds are known
+						&args[2..]
+					},
+					(None, None) if functions::is_matrix_function(name) && args.len() != 1 => {
+						return Err(PostfixError::ArityMismatch {
+							name: name.clone(),
+							expected: Arity::Exact(1),
+							found: args.len(),
+							span: ast.span,
+						});
+					},
+					_ => args.as_slice(),
+				};
+				self.defer(node, operands.iter());
+				return Ok(());
+			},
+			Expr::Vector(elements) => {
+				self.defer(node, elements.iter());
+				return Ok(());
+			},
+		};
+		self.values.push(value);
+		Ok(())
+	}
+
+	fn choose(&mut self, node: Node<'a>) -> Result<(), PostfixError> {
+		let value = self.operand();
+		match &node.ast.node {
+			Expr::Binary(operator, left, right) => {
+				let left = boolean(value, left.span)?;
+				// a false left side decides `&&` and a true one `||`
+				if left == (*operator == Operator::Or) {
+					self.values.push(Value::Bool(left));
+				} else {
+					self.tasks.push(Task::Combine(node));
+					self.tasks.push(node.operand(right));
+				}
+			},
+			Expr::Conditional(condition, then, otherwise) => {
+				let branch = if boolean(value, condition.span)? {
+					then
+				} else {
+					otherwise
+				};
+				self.tasks.push(node.operand(branch));
+			},
+			_ => unreachable!("only `&&`, `||` and `?:` pick an operand"),
+		}
+		Ok(())
+	}
+
+	fn combine(&mut self, node: Node<'a>) -> Result<(), PostfixError> {
+		let (ast, numeric) = (node.ast, self.numeric);
+		let checked =
+			|result: Result<N::Value, NumericError>| result.map_err(|error| PostfixError::numeric(error, ast.span));
+
+		let value = match &ast.node {
+			Expr::Unary(Operator::Negate, operand) => match self.operand() {
+				Value::Vector(elements) => {
+					Value::Vector(elements.iter().map(|element| checked(numeric.neg(element))).collect::<Result<_, _>>()?)
 				},
-				Token::OpenParenthesis | Token::CloseParenthesis => {
-					unreachable!("Postfix notations should not contain parenthesis")
+				Value::Matrix(matrix) => Value::Matrix(matrix.map(|element| checked(numeric.neg(element)))?),
+				value => {
+					let (value, units) = magnitude(value, operand.span)?;
+					Value::quantity(checked(numeric.neg(&value))?, units)
 				},
-			}
+			},
+			Expr::Unary(Operator::BitNot, operand) => {
+				Value::Number(checked(numeric.not(&plain(self.operand(), operand.span)?))?)
+			},
+			Expr::Unary(_, operand) => Value::Bool(!boolean(self.operand(), operand.span)?),
+			// the left side didn't decide so the right one does
+			Expr::Binary(Operator::And | Operator::Or, _, right) => Value::Bool(boolean(self.operand(), right.span)?),
+			Expr::Binary(Operator::Convert, left, right) => {
+				let (magnitude, units) = magnitude(self.operand(), left.span)?;
+				let unit = units::target(right)?;
+				if unit.dimension != units {
+					return Err(PostfixError::UnitMismatch(units, unit.dimension, ast.span));
+				}
+				let converted = checked(unit.amount(numeric, &magnitude))?;
+				Value::Quantity(Quantity {
+					magnitude,
+					dimension: units,
+					converted: Some((converted, Notation::Infix.print(right))),
+				})
+			},
+			Expr::Binary(operator, left, right) => {
+				let (b, a) = (self.operand(), self.operand());
+				self.binary(operator, (a, b), ast.span, (left.span, right.span))?
+			},
+			Expr::Call(name, args) => {
+				let env = self.scopes[node.scope].env;
+				match (env.function(name), env.native(name)) {
+					(Some(function), _) => {
+						let args = self.operands(args.len());
+						let scope = Postfix::call(name, function, args, &self.scopes[node.scope], ast.span)?;
+						self.scopes.push(scope);
+						self.tasks.push(Task::Return(name, ast.span));
+						// the body starts at the top, how deeply the calls nest is up to the recursion limit
+						self.tasks.push(Task::Eval(Node {
+							ast: &function.body,
+							scope: self.scopes.len() - 1,
+							level: 0,
+						}));
+						return Ok(());
+					},
+					(None, Some(native)) => {
+						let args = self.operands(args.len());
+						(native.call)(&args).map_err(|message| PostfixError::FunctionFailed(name.clone(), message, ast.span))?
+					},
+					(None, None) if name == "sum" => return self.sum(node),
+					(None, None) if functions::is_higher_order(name) => self.integrate(node)?,
+					(None, None) if functions::is_matrix_function(name) => {
+						let value = self.operand();
+						Postfix::matrix_call(numeric, name, value, args[0].span, ast.span)?
+					},
+					(None, None) => {
+						let values = self.operands(args.len());
+						Self::builtin(numeric, name, values, args, ast.span)?
+					},
+				}
+			},
+			Expr::Vector(elements) => {
+				let values = self.operands(elements.len());
+				Postfix::vector(values, elements)?
+			},
+			expr => unreachable!("`{expr:?}` has no operands"),
+		};
+		self.values.push(value);
+		Ok(())
+	}
+
+	fn binary(
+		&self,
+		operator: &Operator,
+		(a, b): (Value<N::Value>, Value<N::Value>),
+		span: Span,
+		(left, right): (Span, Span),
+	) -> Result<Value<N::Value>, PostfixError> {
+		let numeric = self.numeric;
+		let checked = |result: Result<N::Value, NumericError>| result.map_err(|error| PostfixError::numeric(error, span));
+		match operator {
+			Operator::Equal | Operator::NotEqual => {
+				let equal = match (a, b) {
+					(Value::Bool(a), Value::Bool(b)) => a == b,
+					(Value::Bool(_), _) => return Err(PostfixError::ExpectedBool(right)),
+					// arrays are equal when they have the same shape and elements
+					(Value::Vector(a), Value::Vector(b)) => Postfix::all_equal(numeric, &a, &b),
+					(Value::Matrix(a), Value::Matrix(b)) => {
+						a.shape() == b.shape() && Postfix::all_equal(numeric, a.elements(), b.elements())
+					},
+					(a, b) => {
+						let ((a, a_units), (b, b_units)) = (magnitude(a, left)?, magnitude(b, right)?);
+						Postfix::same_units(a_units, b_units, span)?;
+						numeric.compare(&a, &b) == Some(Ordering::Equal)
+					},
+				};
+				Ok(Value::Bool(equal == (*operator == Operator::Equal)))
+			},
+			Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => {
+				let ((a, a_units), (b, b_units)) = (magnitude(a, left)?, magnitude(b, right)?);
+				Postfix::same_units(a_units, b_units, span)?;
+				let ordering = numeric.order(&a, &b).map_err(|error| PostfixError::numeric(error, span))?;
+				Ok(Value::Bool(Postfix::ordered(operator, ordering)))
+			},
+			Operator::Plus | Operator::Minus | Operator::Times | Operator::Divided | Operator::Modulo | Operator::Power => {
+				if matches!(a, Value::Vector(_) | Value::Matrix(_)) || matches!(b, Value::Vector(_) | Value::Matrix(_)) {
+					return Postfix::broadcast(numeric, operator, a, b, span, (left, right));
+				}
+				let ((operand_a, a_units), (operand_b, b_units)) = (magnitude(a, left)?, magnitude(b, right)?);
+				let (a, b) = (&operand_a, &operand_b);
+				let units = match operator {
+					Operator::Times => a_units.checked_mul(b_units).ok_or(PostfixError::Overflow(span))?,
+					Operator::Divided => a_units.checked_div(b_units).ok_or(PostfixError::Overflow(span))?,
+					Operator::Power if !b_units.is_dimensionless() => {
+						return Err(PostfixError::UnexpectedUnit(b_units, right));
+					},
+					Operator::Power => a_units.pow(numeric.to_f64(b)).ok_or(PostfixError::InvalidUnitPower(span))?,
+					_ => Postfix::same_units(a_units, b_units, span)?,
+				};
+				let magnitude = Postfix::arithmetic(numeric, operator, a, b, span, right)?;
+				Ok(Value::quantity(magnitude, units))
+			},
+			Operator::BitAnd | Operator::BitOr | Operator::BitXor | Operator::ShiftLeft | Operator::ShiftRight => {
+				checked(numeric.bitwise(operator, &plain(a, left)?, &plain(b, right)?)).map(Value::Number)
+			},
+			Operator::PlusMinus => {
+				let ((value, units), (error, error_units)) = (magnitude(a, left)?, magnitude(b, right)?);
+				let units = Postfix::same_units(units, error_units, span)?;
+				let uncertain = numeric.plus_minus(&value, &error).ok_or(PostfixError::NotUncertain(span))?;
+				if numeric.is_negative(&error) {
+					return Err(PostfixError::NegativeError(right));
+				}
+				Ok(Value::quantity(checked(uncertain)?, units))
+			},
+			Operator::Dot => match (a, b) {
+				(Value::Vector(a), Value::Vector(b)) if a.len() == b.len() => {
+					checked(matrix::dot(numeric, &a, &b)).map(Value::Number)
+				},
+				(a, b) => Err(PostfixError::ShapeMismatch(shape(&a), shape(&b), span)),
+			},
+			Operator::MatrixProduct => Postfix::matrix_product(numeric, a, b, span),
+			operator => unreachable!("`{operator}` is handled above or isn't a binary operator"),
+		}
+	}
+
+	// a built-in function of numbers, those of one number are applied to every element of an array
+	fn builtin(
+		numeric: &N,
+		name: &str,
+		values: Vec<Value<N::Value>>,
+		args: &[Ast],
+		span: Span,
+	) -> Result<Value<N::Value>, PostfixError> {
+		let element = |element: &N::Value| functions::call_numeric(numeric, name, std::slice::from_ref(element), span);
+		match values.as_slice() {
+			[Value::Vector(elements)] if functions::arity(name) == Some(Arity::Exact(1)) => {
+				return elements.iter().map(element).collect::<Result<_, _>>().map(Value::Vector);
+			},
+			[Value::Matrix(matrix)] if functions::arity(name) == Some(Arity::Exact(1)) => {
+				return matrix.map(element).map(Value::Matrix);
+			},
+			_ => {},
 		}
 
-		if stack.len() > 1 {
-			Err(PostfixError::TooManyOperands)
-		} else {
-			Ok(stack[0])
+		let (magnitudes, units): (Vec<N::Value>, Vec<(Dimension, Span)>) = values
+			.into_iter()
+			.zip(args)
+			.map(|(value, arg)| magnitude(value, arg.span).map(|(magnitude, units)| (magnitude, (units, arg.span))))
+			.collect::<Result<Vec<_>, PostfixError>>()?
+			.into_iter()
+			.unzip();
+		let result = functions::call_numeric(numeric, name, &magnitudes, span)?;
+		Ok(Value::quantity(result, Postfix::call_units(name, &units, span)?))
+	}
+
+	// every whole number from one bound to the other, an empty sum when the first is larger
+	fn sum(&mut self, node: Node<'a>) -> Result<(), PostfixError> {
+		let (numeric, span) = (self.numeric, node.ast.span);
+		let checked = |result: Result<N::Value, NumericError>| result.map_err(|error| PostfixError::numeric(error, span));
+		let (_, _, from, to) = Postfix::higher_order(node.ast);
+		// a bound with an error isn't a whole number either
+		let bound = |value, ast: &Ast| {
+			numeric
+				.to_integer(&plain(value, ast.span)?)
+				.filter(|bound| bound.unsigned_abs() < 1 << 100)
+				.ok_or(PostfixError::NotAnInteger(ast.span))
+		};
+		let (last, first) = (self.operand(), self.operand());
+		let (first, last) = (bound(first, from)?, bound(last, to)?);
+		if last - first >= MAX_TERMS {
+			return Err(PostfixError::FunctionFailed(String::from("sum"), format!("more than {MAX_TERMS} terms"), span));
+		}
+
+		self.values.push(Value::Number(checked(numeric.integer(0))?));
+		self.tasks.push(Task::Term(node, first, last));
+		Ok(())
+	}
+
+	// the body of a sum with its variable bound to `k`, then the terms after it
+	fn term(&mut self, node: Node<'a>, k: i128, last: i128) -> Result<(), PostfixError> {
+		if k > last {
+			return Ok(());
 		}
+		let (body, variable, ..) = Postfix::higher_order(node.ast);
+		let k_value = self.numeric.integer(k).map_err(|error| PostfixError::numeric(error, node.ast.span))?;
+		let scope = self.scopes[node.scope].bind(variable, Value::Number(k_value));
+		self.scopes.push(scope);
+		self.tasks.push(Task::Term(node, k + 1, last));
+		self.tasks.push(Task::Accumulate(node));
+		self.tasks.push(Task::Eval(Node {
+			ast: body,
+			scope: self.scopes.len() - 1,
+			level: node.level + 1,
+		}));
+		Ok(())
+	}
+
+	fn accumulate(&mut self, node: Node<'a>) -> Result<(), PostfixError> {
+		let (body, ..) = Postfix::higher_order(node.ast);
+		self.scopes.pop();
+		let term = plain(self.operand(), body.span)?;
+		let Value::Number(total) = self.operand() else {
+			unreachable!("the total of a sum is a number")
+		};
+		let total = self.numeric.add(&total, &term).map_err(|error| PostfixError::numeric(error, node.ast.span))?;
+		self.values.push(Value::Number(total));
+		Ok(())
+	}
+
+	fn integrate(&mut self, node: Node<'a>) -> Result<Value<N::Value>, PostfixError> {
+		let (numeric, span) = (self.numeric, node.ast.span);
+		let checked = |error| PostfixError::numeric(error, span);
+		let (body, variable, from, to) = Postfix::higher_order(node.ast);
+		let bound = |value, ast: &Ast| match numeric.to_f64(&plain(value, ast.span)?) {
+			bound if bound.is_finite() => Ok(bound),
+			_ => Err(PostfixError::FunctionFailed(
+				String::from("integrate"),
+				String::from("the bounds have to be finite"),
+				ast.span,
+			)),
+		};
+		let (b, a) = (self.operand(), self.operand());
+		let (a, b) = (bound(a, from)?, bound(b, to)?);
+
+		// the integrand is sampled with floats whatever the mode, each part of its values like the error on its own
+		let scope = &self.scopes[node.scope];
+		let sample = |x: f64| {
+			let scope = scope.bind(variable, Value::Number(numeric.value(x).map_err(checked)?));
+			plain(Postfix::eval(body, scope, node.level + 1, numeric)?, body.span)
+		};
+		let areas = (0..numeric.parts(&sample(a)?).len())
+			.map(|part| integrate::simpson(|x| Ok(numeric.parts(&sample(x)?)[part]), a, b, TOLERANCE))
+			.collect::<Result<Vec<f64>, PostfixError>>()?;
+		numeric.joined(&areas).map(Value::Number).map_err(checked)
 	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::{
+		numeric::FloatMode,
+		shunting::{ShuntingYard, Syntax},
+	};
+
+	fn calc(expression: &str) -> Result<Value, PostfixError> {
+		Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &Environment::new(), &FloatMode)
+	}
 
 	#[test]
 	fn calc_test() {
-		assert_eq!(Postfix::calc(vec![Token::Number(5.0), Token::Number(10.0), Token::Operator(Operator::Plus)]), Ok(15.0));
+		assert_eq!(calc("5 + 10"), Ok(Value::Number(15.0)));
+		assert_eq!(calc("5 - 10"), Ok(Value::Number(-5.0)));
+		assert_eq!(calc("5 * 10"), Ok(Value::Number(50.0)));
+		assert_eq!(calc("5 / 10"), Ok(Value::Number(0.5)));
+		assert_eq!(calc("5"), Ok(Value::Number(5.0)));
+		assert_eq!(calc("3 + 4 * (2 - 10)"), Ok(Value::Number(-29.0)));
+		assert_eq!(calc("2 ^ 10"), Ok(Value::Number(1024.0)));
+		assert_eq!(calc("10 % 3"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("max(1, 3, 2)"), Ok(Value::Number(3.0)));
+		assert_eq!(calc("cos(pi)"), Ok(Value::Number(-1.0)));
+		assert_eq!(calc("-5"), Ok(Value::Number(-5.0)));
+		assert_eq!(calc("~6 & 0xF | 1 << 4"), Ok(Value::Number(25.0)));
+
+		assert_eq!(calc("5 / 0"), Err(PostfixError::DivisionByZero(Span::new(4, 5))));
+		assert_eq!(calc("10 % (1 - 1)"), Err(PostfixError::ModuloByZero(Span::new(6, 11))));
+		assert_eq!(calc("1 + 0^-2"), Err(PostfixError::DivisionByZero(Span::new(4, 8))));
+		assert_eq!(calc("1 + 2.5 & 1"), Err(PostfixError::NotAnInteger(Span::new(0, 11))));
+		assert_eq!(
+			calc("sin(2, 3)"),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("sin"),
+				expected: Arity::Exact(1),
+				found: 2,
+				span: Span::new(0, 9),
+			})
+		);
+	}
+
+	#[test]
+	fn calc_logical_test() {
+		let t = Ok(Value::Bool(true));
+		let f = Ok(Value::Bool(false));
+
+		assert_eq!(calc("3 > 2"), t);
+		assert_eq!(calc("2 >= 3"), f);
+		assert_eq!(calc("1 + 1 == 2 && 3 != 4"), t);
+		assert_eq!(calc("!(1 < 2) || 5 <= 4"), f);
+		assert_eq!(calc("true == (1 > 2)"), f);
+		assert_eq!(calc("85 > 80 && 70 < 90 ? 1 : 0"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("2 > 3 ? 1 : 2 > 1 ? 2 : 3"), Ok(Value::Number(2.0)));
+		assert_eq!(calc("0.1 + 0.2 == 0.3"), f);
+		// NaN isn't ordered against anything, not even itself
+		assert_eq!(calc("sqrt(-1) == sqrt(-1)"), f);
+		assert_eq!(calc("sqrt(-1) != sqrt(-1)"), t);
+		assert_eq!(calc("sqrt(-1) < 1 || sqrt(-1) >= 1"), f);
+
+		// branches and operands that aren't needed are never evaluated
+		assert_eq!(calc("false && 1 / 0 > 1"), f);
+		assert_eq!(calc("true || 1 / 0 > 1"), t);
+		assert_eq!(calc("1 > 0 ? 2 : 1 / 0"), Ok(Value::Number(2.0)));
+		assert_eq!(calc("1 < 0 ? 2 : 1 / 0"), Err(PostfixError::DivisionByZero(Span::new(16, 17))));
+
+		assert_eq!(calc("1 + (2 > 1)"), Err(PostfixError::ExpectedNumber(Span::new(5, 10))));
+		assert_eq!(calc("1 && true"), Err(PostfixError::ExpectedBool(Span::new(0, 1))));
+		assert_eq!(calc("!3"), Err(PostfixError::ExpectedBool(Span::new(1, 2))));
+		assert_eq!(calc("2 ? 1 : 0"), Err(PostfixError::ExpectedBool(Span::new(0, 1))));
+		assert_eq!(calc("true < false"), Err(PostfixError::ExpectedNumber(Span::new(0, 4))));
+		assert_eq!(calc("1 == true"), Err(PostfixError::ExpectedNumber(Span::new(5, 9))));
+		assert_eq!(calc("true == 1"), Err(PostfixError::ExpectedBool(Span::new(8, 9))));
+		assert_eq!(calc("sqrt(1 > 0)"), Err(PostfixError::ExpectedNumber(Span::new(5, 10))));
+	}
+
+	#[test]
+	fn calc_user_function_test() {
+		use crate::shunting::Statement;
+
+		let mut env = Environment::new();
+		env.set("a", Value::Number(10.0));
+		let definitions = [
+			"f(x, y) = x^2 + y",
+			"fact(n) = n <= 1 ? 1 : n * fact(n - 1)",
+			"scaled(x) = a * x",
+			"pick(c, x, y) = c ? x : y",
+			"inverse(x) = 1 / x",
+			"twice(x) = 2 * inverse(x)",
+			"forever(x) = forever(x + 1)",
+			"grams(g) = 2g",
+		];
+		for definition in definitions {
+			match ShuntingYard::parse_statement(definition, Syntax::default()).unwrap() {
+				Statement::Definition(name, parameters, body) => env.define(
+					&name.node,
+					UserFunction {
+						parameters: parameters.into_iter().map(|parameter| parameter.node).collect(),
+						body,
+					},
+				),
+				_ => unreachable!("`{definition}` is a definition"),
+			}
+		}
+		env.recursion_limit = 20;
+
+		let calc =
+			|expression| Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &env, &FloatMode);
+
+		assert_eq!(calc("f(3, 4)"), Ok(Value::Number(13.0)));
+		assert_eq!(calc("fact(5)"), Ok(Value::Number(120.0)));
+		assert_eq!(calc("f(fact(3), -a)"), Ok(Value::Number(26.0)));
+		assert_eq!(calc("scaled(2)"), Ok(Value::Number(20.0)));
+		assert_eq!(calc("pick(1 > 2, a, f(1, 1))"), Ok(Value::Number(2.0)));
+		// parameters hide variables of the same name only inside the body
+		assert_eq!(calc("scaled(a) + a"), Ok(Value::Number(110.0)));
+		assert_eq!(calc("grams(3)"), Ok(Value::Number(6.0)));
+
 		assert_eq!(
-			Postfix::calc(vec![
-				Token::Number(5.0),
-				Token::Number(10.0),
-				Token::Operator(Operator::Minus)
-			]),
-			Ok(-5.0)
+			calc("f(1)"),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("f"),
+				expected: Arity::Exact(2),
+				found: 1,
+				span: Span::new(0, 4),
+			})
 		);
 		assert_eq!(
-			Postfix::calc(vec![
-				Token::Number(5.0),
-				Token::Number(10.0),
-				Token::Operator(Operator::Times)
-			]),
-			Ok(50.0)
+			calc("1 + twice(0)"),
+			Err(PostfixError::InFunction(
+				String::from("inverse"),
+				Box::new(PostfixError::DivisionByZero(Span::new(17, 18))),
+				Span::new(4, 12)
+			))
 		);
 		assert_eq!(
-			Postfix::calc(vec![
-				Token::Number(5.0),
-				Token::Number(10.0),
-				Token::Operator(Operator::Divided)
-			]),
-			Ok(0.5)
+			calc("forever(0)"),
+			Err(PostfixError::InFunction(
+				String::from("forever"),
+				Box::new(PostfixError::RecursionLimit(20, Span::new(13, 27))),
+				Span::new(0, 10)
+			))
 		);
-		assert_eq!(Postfix::calc(vec![Token::Number(5.0)]), Ok(5.0));
+		assert_eq!(calc("fact(20)"), Ok(Value::Number(2432902008176640000.0)));
+		assert!(calc("fact(21)").is_err());
 		assert_eq!(
-			Postfix::calc(vec![
-				Token::Number(3.0),
-				Token::Number(4.0),
-				Token::Number(2.0),
-				Token::Number(10.0),
-				Token::Operator(Operator::Minus),
-				Token::Operator(Operator::Times),
-				Token::Operator(Operator::Plus),
-			]),
-			Ok(-29.0)
+			calc("pick(1, 2, 3)"),
+			Err(PostfixError::InFunction(
+				String::from("pick"),
+				Box::new(PostfixError::ExpectedBool(Span::new(16, 17))),
+				Span::new(0, 13)
+			))
 		);
 
+		// the depth counts within an expression and every body on its own, the calls are up to the recursion limit
+		env.depth_limit = 40;
+		let calc =
+			|expression: &str| Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &env, &FloatMode);
+		assert_eq!(calc("fact(20)"), Ok(Value::Number(2432902008176640000.0)));
+		assert_eq!(calc(&format!("{}fact(3)", "-".repeat(38))), Ok(Value::Number(6.0)));
+		assert_eq!(calc(&format!("{}1", "-".repeat(50))), Err(PostfixError::TooDeep(40, Span::new(40, 51))));
+	}
+
+	#[test]
+	fn calc_integer_test() {
+		use crate::integer::{IntegerMode, Overflow};
+
+		let calc = |expression, numeric: &IntegerMode| {
+			let ast = ShuntingYard::parse(
+				expression,
+				Syntax {
+					caret_is_xor: true,
+					..Syntax::default()
+				},
+			)
+			.unwrap();
+			Postfix::calc(&ast, &Environment::new(), numeric)
+		};
+		let i64 = IntegerMode::default();
+		let u8 = IntegerMode {
+			bits: 8,
+			signed: false,
+			overflow: Overflow::Wrapping,
+		};
+
+		assert_eq!(calc("0xFF & 0b1010 << 2", &i64), Ok(Value::Number(0b101000)));
+		assert_eq!(calc("0xF0 ^ 0x3C | 1", &i64), Ok(Value::Number(0xCD)));
+		assert_eq!(calc("7 / 2 + 2 ** 3 - -7 % 3", &i64), Ok(Value::Number(12)));
+		assert_eq!(calc("0xFFFFFFFFFFFFFFFF", &i64), Ok(Value::Number(-1)));
+		assert_eq!(calc("9223372036854775807 - 1", &i64), Ok(Value::Number(i64::MAX as i128 - 1)));
+		assert_eq!(calc("sqrt(16) + abs(-3)", &i64), Ok(Value::Number(7)));
+		assert_eq!(calc("~0", &u8), Ok(Value::Number(255)));
+		assert_eq!(calc("200 + 100", &u8), Ok(Value::Number(44)));
+
+		assert_eq!(calc("1 + 9223372036854775807", &i64), Err(PostfixError::Overflow(Span::new(0, 23))));
+		assert_eq!(calc("-0x8000000000000000", &i64), Err(PostfixError::Overflow(Span::new(0, 19))));
+		assert_eq!(calc("1 << 64", &i64), Err(PostfixError::InvalidShift(Span::new(0, 7))));
+		assert_eq!(calc("1 + 2.5", &i64), Err(PostfixError::NotAnInteger(Span::new(4, 7))));
+		assert_eq!(calc("sqrt(2)", &i64), Err(PostfixError::NotAnInteger(Span::new(0, 7))));
+		assert_eq!(calc("1 / 0", &u8), Err(PostfixError::DivisionByZero(Span::new(4, 5))));
+	}
+
+	#[test]
+	fn calc_vector_test() {
+		let calc = |expression| calc(expression).map(|value| value.to_string());
+
+		assert_eq!(calc("[1, 2, 3] . [4, 5, 6]"), Ok(String::from("32")));
+		assert_eq!(calc("det([[1, 2], [3, 4]])"), Ok(String::from("-2")));
+		assert_eq!(calc("inv([[2, 0], [1, 4]])"), Ok(String::from("[[0.5, 0], [-0.125, 0.25]]")));
+		assert_eq!(calc("[[1, 2], [3, 4]] @ [1, 1]"), Ok(String::from("[3, 7]")));
+		assert_eq!(calc("[1, 1] @ [[1, 2], [3, 4]]"), Ok(String::from("[4, 6]")));
+		assert_eq!(calc("transpose([[1, 2, 3], [4, 5, 6]])"), Ok(String::from("[[1, 4], [2, 5], [3, 6]]")));
+		assert_eq!(calc("transpose([1, 2])"), Ok(String::from("[[1], [2]]")));
+		// arithmetic goes element by element and a number goes with every element
+		assert_eq!(calc("2 * [1, 2] ^ 2 - 1"), Ok(String::from("[1, 7]")));
+		assert_eq!(calc("[[1, 2], [3, 4]] * [[2, 2], [2, 2]] / 2"), Ok(String::from("[[1, 2], [3, 4]]")));
+		assert_eq!(calc("-sqrt([4, 9])"), Ok(String::from("[-2, -3]")));
+		assert_eq!(calc("[1, 2] == [1, 2] && [1, 2] != [1, 2, 3]"), Ok(String::from("true")));
+		assert_eq!(calc("[]"), Ok(String::from("[]")));
+
+		let vector = |length| Shape::Vector(length);
+		assert_eq!(calc("[1, 2] + [1, 2, 3]"), Err(PostfixError::ShapeMismatch(vector(2), vector(3), Span::new(0, 18))));
 		assert_eq!(
-			Postfix::calc(vec![Token::Number(10.0), Token::Operator(Operator::Plus)]),
-			Err(PostfixError::NotEnoughOperands)
+			calc("[[1, 2]] @ [[1, 2]]"),
+			Err(PostfixError::ShapeMismatch(Shape::Matrix(1, 2), Shape::Matrix(1, 2), Span::new(0, 19)))
 		);
+		assert_eq!(calc("2 . [1]"), Err(PostfixError::ShapeMismatch(Shape::Scalar, vector(1), Span::new(0, 7))));
+		assert_eq!(calc("det([1, 2])"), Err(PostfixError::NotSquare(vector(2), Span::new(4, 10))));
+		assert_eq!(calc("inv([[1, 2, 3]])"), Err(PostfixError::NotSquare(Shape::Matrix(1, 3), Span::new(4, 15))));
+		assert_eq!(calc("inv([[1, 2], [2, 4]])"), Err(PostfixError::Singular(Span::new(0, 21))));
+		assert_eq!(calc("[[1, 2], [3]]"), Err(PostfixError::RaggedMatrix(Span::new(9, 12))));
+		assert_eq!(calc("[1, [2]]"), Err(PostfixError::RaggedMatrix(Span::new(4, 7))));
 		assert_eq!(
-			Postfix::calc(vec![
-				Token::Number(5.0),
-				Token::Number(10.0),
-				Token::Operator(Operator::Plus),
-				Token::Number(7.0)
-			]),
-			Err(PostfixError::TooManyOperands)
+			calc("[1, 2 m]"),
+			Err(PostfixError::UnexpectedUnit(units::unit("m").unwrap().dimension, Span::new(4, 7)))
 		);
+		assert_eq!(calc("[1] * true"), Err(PostfixError::ExpectedNumber(Span::new(6, 10))));
+		assert_eq!(calc("max([1, 2])"), Err(PostfixError::ExpectedScalar(vector(2), Span::new(4, 10))));
+		assert_eq!(calc("[1, 0] / 0"), Err(PostfixError::DivisionByZero(Span::new(9, 10))));
+	}
+
+	#[test]
+	fn calc_higher_order_test() {
+		let calc = |expression| calc(expression).map(|value| value.to_string());
+
+		assert_eq!(calc("sum(k^2, k, 1, 100)"), Ok(String::from("338350")));
+		assert_eq!(calc("sum(k, k, 3, 1)"), Ok(String::from("0")));
+		assert_eq!(calc("sum(sum(i * j, j, 1, i), i, 1, 3)"), Ok(String::from("25")));
+		assert_eq!(calc("integrate(3 * x^2, x, 0, 2)"), Ok(String::from("8")));
+		assert_eq!(calc("sum(2s, s, 1, 3) + integrate(2m, m, 0, 1)"), Ok(String::from("13")));
+		assert_eq!(calc("round(1e6 * integrate(sin(x), x, 0, pi))"), Ok(String::from("2000000")));
+		// the bound variable hides one of the same name only inside the call
+		let mut env = Environment::new();
+		env.set("x", Value::Number(5.0));
+		let ast = ShuntingYard::parse("sum(x, x, 1, 2) + x", Syntax::default()).unwrap();
+		assert_eq!(Postfix::calc(&ast, &env, &FloatMode), Ok(Value::Number(8.0)));
+
+		assert_eq!(calc("sum(k, 2, 1, 3)"), Err(PostfixError::ExpectedVariable(Span::new(7, 8))));
+		assert_eq!(calc("sum(k, k, 1.5, 3)"), Err(PostfixError::NotAnInteger(Span::new(10, 13))));
+		assert_eq!(
+			calc("sum(k, k, 0, 1e7)"),
+			Err(PostfixError::FunctionFailed(String::from("sum"), String::from("more than 1000000 terms"), Span::new(0, 17)))
+		);
+		assert_eq!(
+			calc("integrate(x, x, 0, sqrt(-1))"),
+			Err(PostfixError::FunctionFailed(
+				String::from("integrate"),
+				String::from("the bounds have to be finite"),
+				Span::new(19, 27)
+			))
+		);
+		assert_eq!(calc("integrate(1 / x, x, 0, 1)"), Err(PostfixError::DivisionByZero(Span::new(14, 15))));
+	}
+
+	#[test]
+	fn calc_complex_test() {
+		use crate::complex::{Complex, ComplexMode};
+
+		let complex = |expression| {
+			let ast = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+			Postfix::calc(&ast, &Environment::new(), &ComplexMode).map(|value| value.to_string())
+		};
+
+		assert_eq!(complex("(3+4i) * (1-2i)"), Ok(String::from("11-2i")));
+		assert_eq!(complex("sqrt(-1)"), Ok(String::from("i")));
+		assert_eq!(complex("i^2 == -1"), Ok(String::from("true")));
+		assert_eq!(complex("abs(3 + 4i) + arg(-1) / pi"), Ok(String::from("6")));
+		assert_eq!(complex("conj(3 + 4i) - re(3 + 4i) + im(3 + 4i) * i"), Ok(String::from("0")));
+		assert_eq!(complex("polar(-2)"), Ok(String::from("[2, 3.141592653589793]")));
+		assert_eq!(complex("[1 + i, 2] . [1, i]"), Ok(String::from("1+3i")));
+		assert_eq!(complex("integrate(x * i, x, 0, 1)"), Ok(String::from("0.5i")));
+		// real numbers calculate like floats
+		assert_eq!(complex("sqrt(2)"), Ok(2f64.sqrt().to_string()));
+		assert_eq!(complex("2i == 2i && 2i != 3"), Ok(String::from("true")));
+		assert_eq!(complex("-(1i - 1i)"), Ok(String::from("0")));
+		assert_eq!(complex("-(1 - 1) + 2i"), Ok(String::from("2i")));
+
+		// a variable `i` hides the imaginary unit, `2i` is then a product like `2x`
+		let mut env = Environment::new();
+		env.set("i", Value::Number(Complex::real(3.0)));
+		let ast = ShuntingYard::parse("i + 2i", Syntax::default()).unwrap();
+		assert_eq!(Postfix::calc(&ast, &env, &ComplexMode), Ok(Value::Number(Complex::real(9.0))));
+		assert_eq!(complex("sum(2i, i, 1, 3) + 2i"), Ok(String::from("12+2i")));
+
+		assert_eq!(complex("i % 2"), Err(PostfixError::NotANumber(Span::new(0, 5))));
+		assert_eq!(complex("max(i, 2)"), Err(PostfixError::NotANumber(Span::new(0, 9))));
+		assert_eq!(complex("3 > 2i"), Err(PostfixError::Unordered(Span::new(0, 6))));
+		assert_eq!(complex("2i <= 2i"), Err(PostfixError::Unordered(Span::new(0, 8))));
+		assert_eq!(calc("1 + 2i"), Err(PostfixError::NotComplex(Span::new(4, 6))));
+		assert_eq!(calc("sqrt(i)"), Err(PostfixError::NotComplex(Span::new(5, 6))));
+	}
+
+	#[test]
+	fn calc_uncertain_test() {
+		use crate::{interval::IntervalMode, uncertain::UncertainMode};
+
+		let interval = |expression| {
+			let ast = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+			Postfix::calc(&ast, &Environment::new(), &IntervalMode).map(|value| value.to_string())
+		};
+		let uncertain = |expression| {
+			let ast = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+			Postfix::calc(&ast, &Environment::new(), &UncertainMode).map(|value| value.to_string())
+		};
+
+		assert_eq!(interval("(9.81 ± 0.02) * (2.0 ± 0.1)"), Ok(String::from("[18.600999999999992, 20.64300000000001]")));
+		assert_eq!(interval("1 / (0 ± 1)"), Ok(String::from("[-inf, inf]")));
+		assert_eq!(interval("1 / (1 +/- 1)"), Ok(String::from("[0.5, inf]")));
+		assert_eq!(interval("sin(pi) < 1e-15 && sin(pi) > -1e-15"), Ok(String::from("true")));
+		assert_eq!(interval("(2 ± 0.5) m + 30 cm"), Ok(String::from("[1.7999999999999998, 2.8000000000000003] m")));
+		assert_eq!(interval("1 + 2"), Ok(String::from("3")));
+		assert_eq!(interval("0.1 + 0.2"), Ok(String::from("[0.29999999999999993, 0.3000000000000001]")));
+		assert_eq!(interval("10 ± 5%"), Ok(String::from("[9.5, 10.5]")));
+		assert_eq!(interval("(1 ± 1) < 3"), Ok(String::from("true")));
+
+		assert_eq!(uncertain("(9.81 ± 0.02) * (2.0 ± 0.1)"), Ok(String::from("19.62 ± 0.98")));
+		assert_eq!(uncertain("sqrt(4 ± 0.4)"), Ok(String::from("2.00 ± 0.10")));
+		assert_eq!(uncertain("(2 ± 0.5) m + 30 cm"), Ok(String::from("2.30 ± 0.50 m")));
+		assert_eq!(uncertain("(2 +/- 0.1)^2"), Ok(String::from("4.00 ± 0.40")));
+		assert_eq!(uncertain("1 / (0 ± 1)"), Err(PostfixError::DivisionByZero(Span::new(5, 11))));
+
+		// the error of an integrand is integrated along with its value
+		assert_eq!(uncertain("integrate(x * (1 ± 0.1), x, 0, 1)"), Ok(String::from("0.500 ± 0.050")));
 		assert_eq!(
-			Postfix::calc(vec![
-				Token::Number(5.0),
-				Token::Number(0.0),
-				Token::Operator(Operator::Divided)
-			]),
-			Err(PostfixError::DivisionByZero)
+			interval("integrate(x * (1 ± 0.1), x, 0, 1)"),
+			Ok(String::from("[0.44999999999999984, 0.5500000000000002]"))
 		);
+		assert_eq!(uncertain("sum(k, k, 1, 3 ± 1)"), Err(PostfixError::NotAnInteger(Span::new(13, 19))));
+
+		// overlapping intervals are smaller and larger than each other depending on where in them we look
+		assert_eq!(interval("(1 ± 1) < (1.5 ± 1)"), Err(PostfixError::Indeterminate(Span::new(1, 20))));
+		assert_eq!(interval("(1 ± 1) > 0.5"), Err(PostfixError::Indeterminate(Span::new(1, 14))));
+		assert_eq!(interval("(1 ± 1) == 5"), Ok(String::from("false")));
+		assert_eq!(interval("2 ± -1"), Err(PostfixError::NegativeError(Span::new(5, 7))));
+		assert_eq!(uncertain("2 ± -1"), Err(PostfixError::NegativeError(Span::new(5, 7))));
+		assert_eq!(interval("sinh(1 ± 1)"), Ok(String::from("[-5e-324, 3.6268604078470195]")));
+		assert_eq!(calc("9.81 ± 0.02"), Err(PostfixError::NotUncertain(Span::new(0, 12))));
+	}
+
+	#[test]
+	fn calc_units_test() {
+		let calc = |expression| calc(expression).map(|value| value.to_string());
+		let (metres, seconds) = (units::unit("m").unwrap().dimension, units::unit("s").unwrap().dimension);
+
+		assert_eq!(calc("3 km / 20 min in km/h"), Ok(String::from("9 km/h")));
+		assert_eq!(calc("3 km / 20 min"), Ok(String::from("2.5 m/s")));
+		assert_eq!(calc("2 h + 30 min to min"), Ok(String::from("150 min")));
+		assert_eq!(calc("12 inch in ft"), Ok(String::from("1 ft")));
+		assert_eq!(calc("70 kg * 9.81 m/s^2"), Ok(String::from("686.7 N")));
+		assert_eq!(calc("(3 m)^2 + 5 m^2"), Ok(String::from("14 m^2")));
+		assert_eq!(calc("sqrt(16 m^2) - abs(-1 m)"), Ok(String::from("3 m")));
+		assert_eq!(calc("1 / 4 s"), Ok(String::from("0.25 s^-1")));
+		assert_eq!(calc("pi / 2 in deg"), Ok(String::from("90 deg")));
+		assert_eq!(calc("(1 km in m) * 2"), Ok(String::from("2000 m")));
+		// units cancel out into a plain number
+		assert_eq!(calc("6 km / 300 m"), Ok(String::from("20")));
+		assert_eq!(calc("3 m == 300 cm && 1 ft < 1 m"), Ok(String::from("true")));
+		assert_eq!(calc("max(1 m, 2 ft, 3 inch)"), Ok(String::from("1 m")));
+
+		assert_eq!(calc("3 m + 2 s"), Err(PostfixError::UnitMismatch(metres, seconds, Span::new(0, 9))));
+		assert_eq!(calc("3 m in s"), Err(PostfixError::UnitMismatch(metres, seconds, Span::new(0, 8))));
+		assert_eq!(calc("1 + 2 m"), Err(PostfixError::UnitMismatch(Dimension::NONE, metres, Span::new(0, 7))));
+		assert_eq!(calc("1 s < 1 m"), Err(PostfixError::UnitMismatch(seconds, metres, Span::new(0, 9))));
+		assert_eq!(calc("min(1 s, 1 m)"), Err(PostfixError::UnitMismatch(seconds, metres, Span::new(9, 12))));
+		assert_eq!(calc("sin(3 m)"), Err(PostfixError::UnexpectedUnit(metres, Span::new(4, 7))));
+		assert_eq!(calc("2 ^ 1 s"), Err(PostfixError::UnexpectedUnit(seconds, Span::new(4, 7))));
+		assert_eq!(calc("1 m & 1"), Err(PostfixError::UnexpectedUnit(metres, Span::new(0, 3))));
+		assert_eq!(calc("sqrt(2 m)"), Err(PostfixError::InvalidUnitPower(Span::new(0, 9))));
+		assert_eq!(calc("2 m ^ 0.5"), Err(PostfixError::InvalidUnitPower(Span::new(0, 9))));
+		assert_eq!(calc("1 m in x"), Err(PostfixError::UnknownUnit(String::from("x"), Span::new(7, 8))));
+		assert_eq!(calc("1 m in 0 m"), Err(PostfixError::NotAUnit(Span::new(7, 10))));
+		assert_eq!(calc("1 m in m + cm"), Err(PostfixError::NotAUnit(Span::new(7, 13))));
+	}
+
+	#[test]
+	fn calc_exact_units_test() {
+		let calc = |expression| {
+			let ast = ShuntingYard::parse(expression, Syntax::default()).unwrap();
+			Postfix::calc(&ast, &Environment::new(), &crate::rational::RationalMode).map(|value| value.to_string())
+		};
+
+		assert_eq!(calc("1 mi / 3 in km"), Ok(String::from("8382/15625 km")));
+		assert_eq!(calc("1 ft + 1 inch in inch"), Ok(String::from("13 inch")));
+		assert_eq!(calc("0.1 km + 20 cm"), Ok(String::from("501/5 m")));
+	}
+
+	#[test]
+	fn calc_environment_test() {
+		let mut env = Environment::new();
+		env.set("x", Value::Number(4.0));
+		env.set("ans", Value::Number(2.0));
+		env.set("m", Value::Number(3.0));
+
+		let calc =
+			|expression| Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &env, &FloatMode);
+
+		assert_eq!(calc("x * ans"), Ok(Value::Number(8.0)));
+		assert_eq!(calc("x > ans ? x : ans"), Ok(Value::Number(4.0)));
+		assert_eq!(calc("pi"), Ok(Value::Number(std::f64::consts::PI)));
+		// variables shadow units of the same name, after a number too
+		assert_eq!(calc("m * 2 m"), Ok(Value::Number(18.0)));
+		assert_eq!(calc("3m^2 + 1 m/x"), Ok(Value::Number(27.75)));
+		assert_eq!(calc("2 cm"), Ok(Value::quantity(0.02, units::unit("m").unwrap().dimension)));
+		assert_eq!(calc("x + y"), Err(PostfixError::UnknownIdentifier(String::from("y"), Span::new(4, 5))));
 	}
 }
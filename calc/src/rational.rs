@@ -0,0 +1,385 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+	bigint::BigInt,
+	functions,
+	numeric::{self, Numeric, NumericError},
+};
+
+/// A fraction in lowest terms with a positive denominator
+#[derive(Debug, Clone, PartialEq)]
+pub struct BigRational {
+	numerator: BigInt,
+	denominator: BigInt,
+}
+
+impl BigRational {
+	// the denominator must not be zero
+	fn new(numerator: BigInt, denominator: BigInt) -> Self {
+		let divisor = numerator.gcd(&denominator);
+		let divisor = if denominator.is_negative() { -&divisor } else { divisor };
+		let divide = |number: &BigInt| number.div_rem(&divisor).expect("BUG: fraction with a zero denominator").0;
+
+		Self {
+			numerator: divide(&numerator),
+			denominator: divide(&denominator),
+		}
+	}
+
+	pub fn from_integer(integer: BigInt) -> Self {
+		Self {
+			numerator: integer,
+			denominator: BigInt::one(),
+		}
+	}
+
+	pub fn is_integer(&self) -> bool {
+		self.denominator == BigInt::one()
+	}
+}
+
+impl fmt::Display for BigRational {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.is_integer() {
+			write!(f, "{}", self.numerator)
+		} else {
+			write!(f, "{}/{}", self.numerator, self.denominator)
+		}
+	}
+}
+
+/// Exact fractions of integers of any size, only bounded so a calculation can't take forever
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RationalMode;
+
+impl RationalMode {
+	/// Results with a numerator or denominator larger than this are an overflow
+	pub const MAX_BITS: u64 = 65_536;
+
+	fn checked(value: BigRational) -> Result<BigRational, NumericError> {
+		if value.numerator.bits() > Self::MAX_BITS || value.denominator.bits() > Self::MAX_BITS {
+			Err(NumericError::Overflow)
+		} else {
+			Ok(value)
+		}
+	}
+
+	// `coefficient * 10^exponent`, a power of ten has more bits than its exponent so a larger one can't fit
+	fn decimal(coefficient: BigInt, exponent: i64) -> Result<BigRational, NumericError> {
+		if exponent.unsigned_abs() > Self::MAX_BITS {
+			return Err(NumericError::Overflow);
+		}
+		let scale = BigInt::from(10u64).pow(exponent.unsigned_abs());
+
+		Self::checked(if exponent < 0 {
+			BigRational::new(coefficient, scale)
+		} else {
+			BigRational::from_integer(&coefficient * &scale)
+		})
+	}
+
+	// truncates the quotient towards zero
+	fn truncated_quotient(a: &BigRational, b: &BigRational) -> BigInt {
+		let numerator = &a.numerator * &b.denominator;
+		let denominator = &a.denominator * &b.numerator;
+		numerator.div_rem(&denominator).expect("BUG: division by zero should be caught by the caller").0
+	}
+
+	// the `n`th root which is only rational when the numerator and denominator are both `n`th powers
+	fn root(value: &BigRational, n: u32) -> Result<BigRational, NumericError> {
+		if value.numerator.is_negative() && n.is_multiple_of(2) {
+			return Err(NumericError::NotANumber);
+		}
+		let exact = |integer: &BigInt| {
+			// below `2^n` the root is zero or one which are their own roots
+			if u64::from(n) >= integer.bits() {
+				return (integer.bits() <= 1).then(|| integer.clone());
+			}
+			let root = integer.root(n);
+			(root.pow(u64::from(n)) == *integer).then_some(root)
+		};
+
+		let numerator = exact(&value.numerator.abs()).ok_or(NumericError::Inexact)?;
+		let denominator = exact(&value.denominator).ok_or(NumericError::Inexact)?;
+		let numerator = if value.numerator.is_negative() {
+			-&numerator
+		} else {
+			numerator
+		};
+		Ok(BigRational::new(numerator, denominator))
+	}
+
+	// `base^(p/q)` is the `q`th root raised to `p`, so it's exact when the root is
+	fn root_pow(&self, base: &BigRational, exponent: &BigRational) -> Result<BigRational, NumericError> {
+		// like `f64::powf` there's no real power of a negative base, not even an odd root
+		if base.numerator.is_negative() {
+			return Err(NumericError::NotANumber);
+		}
+		let degree = exponent.denominator.to_u64().and_then(|degree| u32::try_from(degree).ok());
+		let root = Self::root(base, degree.ok_or(NumericError::Inexact)?)?;
+		self.pow(&root, &BigRational::from_integer(exponent.numerator.clone()))
+	}
+}
+
+impl Numeric for RationalMode {
+	type Value = BigRational;
+
+	const EXACT: bool = true;
+
+	fn value(&self, number: f64) -> Result<BigRational, NumericError> {
+		if number.is_nan() {
+			return Err(NumericError::NotANumber);
+		}
+		let (coefficient, exponent) = numeric::shortest_decimal(number).ok_or(NumericError::Overflow)?;
+		Self::decimal(coefficient, exponent)
+	}
+
+	fn literal(&self, digits: &str) -> Result<BigRational, NumericError> {
+		let (coefficient, exponent) = numeric::decimal_digits(digits).ok_or(NumericError::NotANumber)?;
+		Self::decimal(coefficient, exponent)
+	}
+
+	fn to_f64(&self, value: &BigRational) -> f64 {
+		// divide with enough bits to spare for a full mantissa and move the point back afterwards
+		let shift = 64 + value.denominator.bits() as i64 - value.numerator.bits() as i64;
+		let quotient = if shift >= 0 {
+			value.numerator.shl(shift as u64).div_rem(&value.denominator)
+		} else {
+			value.numerator.div_rem(&value.denominator.shl(shift.unsigned_abs()))
+		};
+		let quotient = quotient.expect("BUG: fraction with a zero denominator").0.to_f64();
+
+		// scaling in two steps keeps the intermediate power of two finite
+		let half = (shift / 2) as i32;
+		quotient * 2f64.powi(-half) * 2f64.powi(half - shift as i32)
+	}
+
+	fn integer(&self, integer: i128) -> Result<BigRational, NumericError> {
+		Ok(BigRational::from_integer(BigInt::from(integer)))
+	}
+
+	fn to_integer(&self, value: &BigRational) -> Option<i128> {
+		if value.is_integer() {
+			value.numerator.to_i128()
+		} else {
+			None
+		}
+	}
+
+	fn is_zero(&self, value: &BigRational) -> bool {
+		value.numerator.is_zero()
+	}
+
+	fn is_negative(&self, value: &BigRational) -> bool {
+		value.numerator.is_negative()
+	}
+
+	fn compare(&self, a: &BigRational, b: &BigRational) -> Option<Ordering> {
+		Some((&a.numerator * &b.denominator).cmp(&(&b.numerator * &a.denominator)))
+	}
+
+	fn neg(&self, value: &BigRational) -> Result<BigRational, NumericError> {
+		Ok(BigRational {
+			numerator: -&value.numerator,
+			denominator: value.denominator.clone(),
+		})
+	}
+
+	fn add(&self, a: &BigRational, b: &BigRational) -> Result<BigRational, NumericError> {
+		let numerator = &(&a.numerator * &b.denominator) + &(&b.numerator * &a.denominator);
+		Self::checked(BigRational::new(numerator, &a.denominator * &b.denominator))
+	}
+
+	fn sub(&self, a: &BigRational, b: &BigRational) -> Result<BigRational, NumericError> {
+		self.add(a, &self.neg(b)?)
+	}
+
+	fn mul(&self, a: &BigRational, b: &BigRational) -> Result<BigRational, NumericError> {
+		Self::checked(BigRational::new(&a.numerator * &b.numerator, &a.denominator * &b.denominator))
+	}
+
+	fn div(&self, a: &BigRational, b: &BigRational) -> Result<BigRational, NumericError> {
+		Self::checked(BigRational::new(&a.numerator * &b.denominator, &a.denominator * &b.numerator))
+	}
+
+	fn rem(&self, a: &BigRational, b: &BigRational) -> Result<BigRational, NumericError> {
+		let quotient = BigRational::from_integer(Self::truncated_quotient(a, b));
+		self.sub(a, &self.mul(b, &quotient)?)
+	}
+
+	fn pow(&self, base: &BigRational, exponent: &BigRational) -> Result<BigRational, NumericError> {
+		if !exponent.is_integer() {
+			return self.root_pow(base, exponent);
+		}
+
+		let one = BigInt::one();
+		if base.numerator.is_zero() || (base.is_integer() && base.numerator.abs() == one) {
+			// these never grow so any exponent will do
+			let odd = !exponent.numerator.is_even();
+			let result = if base.numerator.is_negative() && !odd {
+				base.numerator.abs()
+			} else {
+				base.numerator.clone()
+			};
+			let result = if exponent.numerator.is_zero() { one } else { result };
+			return Ok(BigRational::from_integer(result));
+		}
+
+		// the result has at least this many bits so we can give up before doing the work
+		let power = exponent.numerator.abs().to_u64().ok_or(NumericError::Overflow)?;
+		let bits = base.numerator.bits().max(base.denominator.bits()) - 1;
+		if bits.saturating_mul(power) > Self::MAX_BITS {
+			return Err(NumericError::Overflow);
+		}
+
+		let (numerator, denominator) = (base.numerator.pow(power), base.denominator.pow(power));
+		Self::checked(if exponent.numerator.is_negative() {
+			BigRational::new(denominator, numerator)
+		} else {
+			BigRational::new(numerator, denominator)
+		})
+	}
+
+	fn floor(&self, value: &BigRational) -> BigRational {
+		let floor = value.numerator.div_floor(&value.denominator).expect("BUG: fraction with a zero denominator");
+		BigRational::from_integer(floor)
+	}
+
+	fn constant(&self, _name: &str) -> Option<Result<BigRational, NumericError>> {
+		// `pi`, `e` and `tau` are all irrational
+		Some(Err(NumericError::Inexact))
+	}
+
+	fn function(&self, name: &str, args: &[BigRational]) -> Option<Result<BigRational, NumericError>> {
+		if !functions::arity(name).is_some_and(|arity| arity.accepts(args.len())) {
+			return None;
+		}
+
+		Some(match (name, args) {
+			("sqrt", [value]) => Self::root(value, 2),
+			("cbrt", [value]) => Self::root(value, 3),
+			("hypot", _) => args
+				.iter()
+				.try_fold(BigRational::from_integer(BigInt::zero()), |sum, value| self.add(&sum, &self.mul(value, value)?))
+				.and_then(|sum| Self::root(&sum, 2)),
+			// the angle of a negative number is `pi`
+			("arg", [value]) if value.numerator.is_negative() => Err(NumericError::Inexact),
+			// the few rational points of the others
+			("sin" | "tan" | "asin" | "atan" | "sinh" | "tanh", [value]) if value.numerator.is_zero() => Ok(value.clone()),
+			("cos" | "cosh" | "exp", [value]) if value.numerator.is_zero() => self.integer(1),
+			("ln" | "log" | "log2" | "acos", [value, ..]) if *value == BigRational::from_integer(BigInt::one()) => {
+				self.integer(0)
+			},
+			(
+				"sin" | "cos" | "tan" | "asin" | "acos" | "atan" | "atan2" | "sinh" | "cosh" | "tanh" | "exp" | "ln" | "log"
+				| "log2",
+				_,
+			) => Err(NumericError::Inexact),
+			_ => return None,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn rational(numerator: i64, denominator: i64) -> BigRational {
+		BigRational::new(BigInt::from(numerator), BigInt::from(denominator))
+	}
+
+	#[test]
+	fn normalize_display_test() {
+		assert_eq!(rational(2, 4).to_string(), "1/2");
+		assert_eq!(rational(3, -6).to_string(), "-1/2");
+		assert_eq!(rational(-4, -2).to_string(), "2");
+		assert_eq!(rational(0, -5).to_string(), "0");
+	}
+
+	#[test]
+	fn arithmetic_test() {
+		let exact = RationalMode;
+
+		assert_eq!(exact.add(&rational(1, 3), &rational(1, 6)), Ok(rational(1, 2)));
+		assert_eq!(exact.sub(&rational(1, 3), &rational(1, 2)), Ok(rational(-1, 6)));
+		assert_eq!(exact.mul(&rational(2, 3), &rational(9, 4)), Ok(rational(3, 2)));
+		assert_eq!(exact.div(&rational(2, 3), &rational(-4, 9)), Ok(rational(-3, 2)));
+		assert_eq!(exact.rem(&rational(7, 2), &rational(1, 1)), Ok(rational(1, 2)));
+		assert_eq!(exact.rem(&rational(-7, 1), &rational(3, 1)), Ok(rational(-1, 1)));
+		assert_eq!(exact.neg(&rational(1, 2)), Ok(rational(-1, 2)));
+		assert_eq!(exact.floor(&rational(-7, 2)), rational(-4, 1));
+		assert_eq!(exact.floor(&rational(7, 2)), rational(3, 1));
+		assert_eq!(exact.compare(&rational(1, 3), &rational(1, 2)), Some(Ordering::Less));
+	}
+
+	#[test]
+	fn pow_test() {
+		let exact = RationalMode;
+
+		assert_eq!(exact.pow(&rational(2, 3), &rational(3, 1)), Ok(rational(8, 27)));
+		assert_eq!(exact.pow(&rational(2, 3), &rational(-2, 1)), Ok(rational(9, 4)));
+		assert_eq!(exact.pow(&rational(-2, 1), &rational(-3, 1)), Ok(rational(-1, 8)));
+		assert_eq!(exact.pow(&rational(4, 1), &rational(1, 2)), Ok(rational(2, 1)));
+		assert_eq!(exact.pow(&rational(-1, 1), &rational(1_000_000_000_001, 1)), Ok(rational(-1, 1)));
+		assert_eq!(exact.pow(&rational(-1, 1), &rational(-4, 1)), Ok(rational(1, 1)));
+		assert_eq!(exact.pow(&rational(0, 1), &rational(0, 1)), Ok(rational(1, 1)));
+		assert_eq!(exact.pow(&rational(2, 1), &rational(1_000_000, 1)), Err(NumericError::Overflow));
+		assert_eq!(exact.pow(&rational(-8, 1), &rational(1, 3)), Err(NumericError::NotANumber));
+		assert_eq!(exact.pow(&rational(2, 1), &rational(65_535, 1)).map(|value| value.numerator.bits()), Ok(65_536));
+		assert_eq!(exact.pow(&rational(2, 1), &rational(65_536, 1)), Err(NumericError::Overflow));
+		assert_eq!(exact.pow(&rational(8, 27), &rational(2, 3)), Ok(rational(4, 9)));
+		assert_eq!(exact.pow(&rational(4, 9), &rational(-3, 2)), Ok(rational(27, 8)));
+		assert_eq!(exact.pow(&rational(2, 1), &rational(1, 2)), Err(NumericError::Inexact));
+		assert_eq!(exact.pow(&rational(9, 2), &rational(1, 2)), Err(NumericError::Inexact));
+	}
+
+	#[test]
+	fn function_test() {
+		let exact = RationalMode;
+		let call = |name, args: &[BigRational]| exact.function(name, args);
+
+		assert_eq!(call("sqrt", &[rational(9, 4)]), Some(Ok(rational(3, 2))));
+		assert_eq!(call("sqrt", &[rational(2, 1)]), Some(Err(NumericError::Inexact)));
+		assert_eq!(call("sqrt", &[rational(1, 1)]), Some(Ok(rational(1, 1))));
+		assert_eq!(call("sqrt", &[rational(-4, 1)]), Some(Err(NumericError::NotANumber)));
+		assert_eq!(call("cbrt", &[rational(-27, 8)]), Some(Ok(rational(-3, 2))));
+		assert_eq!(call("cbrt", &[rational(4, 1)]), Some(Err(NumericError::Inexact)));
+		assert_eq!(call("hypot", &[rational(3, 5), rational(4, 5)]), Some(Ok(rational(1, 1))));
+		assert_eq!(call("sin", &[rational(1, 1)]), Some(Err(NumericError::Inexact)));
+		assert_eq!(call("sin", &[rational(0, 1)]), Some(Ok(rational(0, 1))));
+		assert_eq!(call("exp", &[rational(0, 1)]), Some(Ok(rational(1, 1))));
+		assert_eq!(call("log", &[rational(1, 1), rational(3, 1)]), Some(Ok(rational(0, 1))));
+		assert_eq!(call("arg", &[rational(-1, 1)]), Some(Err(NumericError::Inexact)));
+		assert_eq!(call("abs", &[rational(-1, 1)]), None);
+		assert_eq!(exact.constant("pi"), Some(Err(NumericError::Inexact)));
+	}
+
+	#[test]
+	fn f64_test() {
+		let exact = RationalMode;
+
+		assert_eq!(exact.value(0.1), Ok(rational(1, 10)));
+		assert_eq!(exact.value(-2.5), Ok(rational(-5, 2)));
+		assert_eq!(exact.value(1e20).map(|value| value.to_string()), Ok(String::from("100000000000000000000")));
+		assert_eq!(exact.value(f64::INFINITY), Err(NumericError::Overflow));
+		assert_eq!(exact.value(f64::NAN), Err(NumericError::NotANumber));
+		assert_eq!(exact.to_f64(&rational(1, 3)), 1.0 / 3.0);
+		assert_eq!(exact.to_f64(&rational(-7, 2)), -3.5);
+		assert_eq!(exact.to_f64(&rational(0, 1)), 0.0);
+
+		// literals keep every digit an `f64` would drop
+		let literal = |digits| exact.literal(digits).map(|value| value.to_string());
+		assert_eq!(literal("123456789012345678901234567890"), Ok(String::from("123456789012345678901234567890")));
+		assert_eq!(literal("0.123456789012345678901"), Ok(String::from("123456789012345678901/1000000000000000000000")));
+		assert_eq!(literal("2.5e-20"), Ok(String::from("1/40000000000000000000")));
+		assert_eq!(literal("1e-99999999999999999999"), Err(NumericError::Overflow));
+
+		assert_eq!(exact.integer(-7), Ok(rational(-7, 1)));
+		assert_eq!(exact.to_integer(&rational(-7, 1)), Some(-7));
+		assert_eq!(exact.to_integer(&rational(7, 2)), None);
+
+		let huge = exact.pow(&rational(10, 1), &rational(400, 1)).unwrap();
+		assert_eq!(exact.to_f64(&huge), f64::INFINITY);
+		assert_eq!(exact.to_f64(&exact.div(&rational(1, 1), &huge).unwrap()), 0.0);
+	}
+}
@@ -0,0 +1,373 @@
+use std::{fmt, io};
+
+use ccalc::{
+	env::{Environment, UserFunction},
+	format::Format,
+	functions,
+	notation::Notation,
+	numeric::Numeric,
+	postfix::{Postfix, PostfixError},
+	report,
+	shunting::{ShuntingYard, ShuntingYardError, Span, Statement, Syntax},
+	value::Value,
+};
+
+use crate::editor::Editor;
+
+const HELP: &str = "\
+Enter an expression like `2 * (3 + 4)` or assign it with `x = 2 * 3`.
+Define a function with `f(x, y) = x^2 + y` and call it like `f(3, 4)`.
+Numbers can carry units like `3 km / 20 min` and be converted with `in km/h`.
+The last result is available as `ans`.
+
+Commands:
+  :vars   list all variables
+  :funcs  list all functions
+  :clear  remove all variables and functions
+  :help   show this help
+  :quit   leave the calculator";
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome<V = f64> {
+	Value(V),
+	Assignment(String, V),
+	Output(String),
+	Quit,
+}
+
+impl<V: fmt::Display> fmt::Display for Outcome<V> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Outcome::Value(value) => write!(f, "{value}"),
+			Outcome::Assignment(name, value) => write!(f, "{name} = {value}"),
+			Outcome::Output(output) => write!(f, "{output}"),
+			Outcome::Quit => Ok(()),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReplError {
+	Parse(ShuntingYardError),
+	Calc(PostfixError),
+	ConstantAssignment(String, Span),
+	BuiltinRedefinition(String, Span),
+	Redefinition(String, Span),
+	UnknownCommand(String),
+}
+
+impl fmt::Display for ReplError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ReplError::Parse(error) => write!(f, "{error}"),
+			ReplError::Calc(error) => write!(f, "{error}"),
+			ReplError::ConstantAssignment(name, _) => write!(f, "`{name}` is a constant and can't be assigned to"),
+			ReplError::BuiltinRedefinition(name, _) => write!(f, "`{name}` is a built-in function and can't be redefined"),
+			ReplError::Redefinition(name, _) => write!(f, "`{name}` is already defined, `:clear` removes it"),
+			ReplError::UnknownCommand(command) => write!(f, "unknown command `:{command}`, try `:help`"),
+		}
+	}
+}
+
+impl ReplError {
+	pub fn span(&self) -> Option<Span> {
+		match self {
+			ReplError::Parse(error) => error.span(),
+			ReplError::Calc(error) => Some(error.span()),
+			ReplError::ConstantAssignment(_, span)
+			| ReplError::BuiltinRedefinition(_, span)
+			| ReplError::Redefinition(_, span) => Some(*span),
+			ReplError::UnknownCommand(_) => None,
+		}
+	}
+}
+
+impl From<ShuntingYardError> for ReplError {
+	fn from(error: ShuntingYardError) -> Self {
+		ReplError::Parse(error)
+	}
+}
+
+impl From<PostfixError> for ReplError {
+	fn from(error: PostfixError) -> Self {
+		ReplError::Calc(error)
+	}
+}
+
+#[derive(Debug)]
+pub struct Repl<N: Numeric> {
+	numeric: N,
+	syntax: Syntax,
+	/// Only changes how results are printed, they are kept as values
+	format: Format,
+	env: Environment<Value<N::Value>>,
+}
+
+impl<N: Numeric> Repl<N> {
+	pub fn new(numeric: N, syntax: Syntax, format: Format) -> Self {
+		Self {
+			numeric,
+			syntax,
+			format,
+			env: Environment::new(),
+		}
+	}
+
+	pub fn set_recursion_limit(&mut self, limit: usize) {
+		self.env.recursion_limit = limit;
+	}
+
+	/// A result the way the calculator prints it
+	pub fn print(&self, value: &Value<N::Value>) -> String {
+		value.format(&self.numeric, self.format)
+	}
+
+	pub fn eval_line(&mut self, line: &str) -> Result<Outcome<Value<N::Value>>, ReplError> {
+		let line = line.trim();
+		if let Some(command) = line.strip_prefix(':') {
+			return self.command(command.trim());
+		}
+
+		match ShuntingYard::parse_statement(line, self.syntax)? {
+			Statement::Expression(ast) => {
+				let value = Postfix::calc(&ast, &self.env, &self.numeric)?;
+				self.env.set("ans", value.clone());
+				Ok(Outcome::Value(value))
+			},
+			Statement::Assignment(name, ast) => {
+				if functions::constant(&name.node).is_some() {
+					return Err(ReplError::ConstantAssignment(name.node, name.span));
+				}
+
+				let value = Postfix::calc(&ast, &self.env, &self.numeric)?;
+				self.env.set(&name.node, value.clone());
+				self.env.set("ans", value.clone());
+				Ok(Outcome::Assignment(name.node, value))
+			},
+			Statement::Definition(name, parameters, body) => {
+				if functions::is_builtin(&name.node) {
+					return Err(ReplError::BuiltinRedefinition(name.node, name.span));
+				}
+				if self.env.function(&name.node).is_some() {
+					return Err(ReplError::Redefinition(name.node, name.span));
+				}
+				// a parameter would hide the constant inside the body
+				if let Some(parameter) = parameters.iter().find(|parameter| functions::constant(&parameter.node).is_some()) {
+					return Err(ReplError::ConstantAssignment(parameter.node.clone(), parameter.span));
+				}
+
+				let function = UserFunction {
+					parameters: parameters.into_iter().map(|parameter| parameter.node).collect(),
+					body,
+				};
+				let signature = Self::signature(&name.node, &function);
+				self.env.define(&name.node, function);
+				Ok(Outcome::Output(signature))
+			},
+		}
+	}
+
+	fn signature(name: &str, function: &UserFunction) -> String {
+		format!("{name}({}) = {}", function.parameters.join(", "), Notation::Infix.print(&function.body))
+	}
+
+	fn command(&mut self, command: &str) -> Result<Outcome<Value<N::Value>>, ReplError> {
+		match command {
+			"vars" => {
+				let variables = self.env.variables();
+				if variables.is_empty() {
+					Ok(Outcome::Output(String::from("no variables")))
+				} else {
+					let lines =
+						variables.iter().map(|(name, value)| format!("{name} = {}", self.print(value))).collect::<Vec<String>>();
+					Ok(Outcome::Output(lines.join("\n")))
+				}
+			},
+			"funcs" => {
+				let functions = self.env.functions();
+				if functions.is_empty() {
+					Ok(Outcome::Output(String::from("no functions")))
+				} else {
+					let lines = functions.iter().map(|(name, function)| Self::signature(name, function)).collect::<Vec<String>>();
+					Ok(Outcome::Output(lines.join("\n")))
+				}
+			},
+			"clear" => {
+				self.env.clear();
+				Ok(Outcome::Output(String::from("variables and functions cleared")))
+			},
+			"help" => Ok(Outcome::Output(String::from(HELP))),
+			"quit" | "q" | "exit" => Ok(Outcome::Quit),
+			_ => Err(ReplError::UnknownCommand(command.to_string())),
+		}
+	}
+
+	pub fn run(&mut self) -> io::Result<()> {
+		let mut editor = Editor::new();
+
+		while let Some(line) = editor.read_line("> ")? {
+			if line.trim().is_empty() {
+				continue;
+			}
+
+			match self.eval_line(&line) {
+				Ok(Outcome::Quit) => break,
+				Ok(Outcome::Value(value)) => println!("{}", self.print(&value)),
+				Ok(Outcome::Assignment(name, value)) => println!("{name} = {}", self.print(&value)),
+				Ok(outcome) => println!("{outcome}"),
+				Err(error) => println!("{}", report::render(&error, error.span(), line.trim())),
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ccalc::numeric::FloatMode;
+
+	#[test]
+	fn eval_line_test() {
+		let mut repl = Repl::new(FloatMode, Syntax::default(), Format::default());
+
+		assert_eq!(repl.eval_line("x = 3 * 4"), Ok(Outcome::Assignment(String::from("x"), Value::Number(12.0))));
+		assert_eq!(repl.eval_line("x + 1"), Ok(Outcome::Value(Value::Number(13.0))));
+		assert_eq!(repl.eval_line("ans * 2"), Ok(Outcome::Value(Value::Number(26.0))));
+		assert_eq!(repl.eval_line("  y = -ans  "), Ok(Outcome::Assignment(String::from("y"), Value::Number(-26.0))));
+		assert_eq!(repl.eval_line("x = x + y"), Ok(Outcome::Assignment(String::from("x"), Value::Number(-14.0))));
+		assert_eq!(repl.eval_line("x"), Ok(Outcome::Value(Value::Number(-14.0))));
+	}
+
+	#[test]
+	fn eval_line_error_test() {
+		let mut repl = Repl::new(FloatMode, Syntax::default(), Format::default());
+
+		assert_eq!(
+			repl.eval_line("ans"),
+			Err(ReplError::Calc(PostfixError::UnknownIdentifier(String::from("ans"), Span::new(0, 3))))
+		);
+		assert_eq!(repl.eval_line("pi = 3"), Err(ReplError::ConstantAssignment(String::from("pi"), Span::new(0, 2))));
+		assert_eq!(repl.eval_line("1 = 3"), Err(ReplError::Parse(ShuntingYardError::MisplacedAssignment(Span::new(2, 3)))));
+		assert_eq!(repl.eval_line("x = 1 / 0"), Err(ReplError::Calc(PostfixError::DivisionByZero(Span::new(8, 9)))));
+		// a failed assignment doesn't leave anything behind
+		assert_eq!(
+			repl.eval_line("x"),
+			Err(ReplError::Calc(PostfixError::UnknownIdentifier(String::from("x"), Span::new(0, 1))))
+		);
+	}
+
+	#[test]
+	fn command_test() {
+		let mut repl = Repl::new(FloatMode, Syntax::default(), Format::default());
+
+		assert_eq!(repl.eval_line(":vars"), Ok(Outcome::Output(String::from("no variables"))));
+		repl.eval_line("b = 2").unwrap();
+		repl.eval_line("a = 1").unwrap();
+		assert_eq!(repl.eval_line(":vars"), Ok(Outcome::Output(String::from("a = 1\nans = 1\nb = 2"))));
+		assert_eq!(repl.eval_line(":clear"), Ok(Outcome::Output(String::from("variables and functions cleared"))));
+		assert_eq!(repl.eval_line(":vars"), Ok(Outcome::Output(String::from("no variables"))));
+		assert_eq!(repl.eval_line(":help"), Ok(Outcome::Output(String::from(HELP))));
+		assert_eq!(repl.eval_line(":quit"), Ok(Outcome::Quit));
+		assert_eq!(repl.eval_line(":nope"), Err(ReplError::UnknownCommand(String::from("nope"))));
+	}
+
+	#[test]
+	fn exact_mode_test() {
+		use ccalc::{decimal::DecimalMode, rational::RationalMode};
+
+		let mut repl = Repl::new(RationalMode, Syntax::default(), Format::default());
+		assert_eq!(repl.eval_line("x = 1/3").map(|outcome| outcome.to_string()), Ok(String::from("x = 1/3")));
+		assert_eq!(repl.eval_line("x + 1/6").map(|outcome| outcome.to_string()), Ok(String::from("1/2")));
+		assert_eq!(repl.eval_line("ans * 4").map(|outcome| outcome.to_string()), Ok(String::from("2")));
+
+		let mut repl = Repl::new(DecimalMode::default(), Syntax::default(), Format::default());
+		assert_eq!(repl.eval_line("0.1 + 0.2").map(|outcome| outcome.to_string()), Ok(String::from("0.3")));
+		assert_eq!(
+			repl.eval_line("ans / 0").map(|outcome| outcome.to_string()),
+			Err(ReplError::Calc(PostfixError::DivisionByZero(Span::new(6, 7))))
+		);
+	}
+
+	#[test]
+	fn integer_mode_test() {
+		use ccalc::{integer::IntegerMode, numeric::Radix};
+
+		let mut repl = Repl::new(
+			IntegerMode::default(),
+			Syntax {
+				caret_is_xor: true,
+				..Syntax::default()
+			},
+			Format {
+				radix: Radix::Hexadecimal,
+				..Format::default()
+			},
+		);
+		assert_eq!(
+			repl.eval_line("mask = 0xF0 ^ 0xFF"),
+			Ok(Outcome::Assignment(String::from("mask"), Value::Number(0x0F)))
+		);
+		assert_eq!(repl.eval_line("2 ** 10 | mask"), Ok(Outcome::Value(Value::Number(0x40F))));
+		assert_eq!(repl.eval_line(":vars"), Ok(Outcome::Output(String::from("ans = 0x40f\nmask = 0xf"))));
+		assert_eq!(repl.eval_line("7 / 2.5"), Err(ReplError::Calc(PostfixError::NotAnInteger(Span::new(4, 7)))));
+	}
+
+	#[test]
+	fn boolean_test() {
+		let mut repl = Repl::new(FloatMode, Syntax::default(), Format::default());
+
+		assert_eq!(repl.eval_line("hot = 85 > 80"), Ok(Outcome::Assignment(String::from("hot"), Value::Bool(true))));
+		assert_eq!(repl.eval_line("hot && !ans ? 1 : 2"), Ok(Outcome::Value(Value::Number(2.0))));
+		assert_eq!(repl.eval_line(":vars"), Ok(Outcome::Output(String::from("ans = 2\nhot = true"))));
+		assert_eq!(repl.eval_line("hot + 1"), Err(ReplError::Calc(PostfixError::ExpectedNumber(Span::new(0, 3)))));
+	}
+
+	#[test]
+	fn function_test() {
+		let mut repl = Repl::new(FloatMode, Syntax::default(), Format::default());
+
+		assert_eq!(repl.eval_line(":funcs"), Ok(Outcome::Output(String::from("no functions"))));
+		assert_eq!(repl.eval_line("f(x, y) = (x^2) + y"), Ok(Outcome::Output(String::from("f(x, y) = x^2 + y"))));
+		assert_eq!(repl.eval_line("f(3, 4)"), Ok(Outcome::Value(Value::Number(13.0))));
+		assert_eq!(repl.eval_line("y = f(ans, 0)"), Ok(Outcome::Assignment(String::from("y"), Value::Number(169.0))));
+		repl.eval_line("fib(n) = n < 2 ? n : fib(n - 1) + fib(n - 2)").unwrap();
+		assert_eq!(repl.eval_line("fib(15)"), Ok(Outcome::Value(Value::Number(610.0))));
+		assert_eq!(
+			repl.eval_line(":funcs"),
+			Ok(Outcome::Output(String::from("f(x, y) = x^2 + y\nfib(n) = n < 2 ? n : fib(n - 1) + fib(n - 2)")))
+		);
+
+		assert_eq!(repl.eval_line("f(x) = x"), Err(ReplError::Redefinition(String::from("f"), Span::new(0, 1))));
+		assert_eq!(
+			repl.eval_line("sqrt(x) = x"),
+			Err(ReplError::BuiltinRedefinition(String::from("sqrt"), Span::new(0, 4)))
+		);
+		assert_eq!(
+			repl.eval_line("g(x, e) = x ^ e"),
+			Err(ReplError::ConstantAssignment(String::from("e"), Span::new(5, 6)))
+		);
+
+		repl.set_recursion_limit(10);
+		assert_eq!(repl.eval_line("fib(10)"), Ok(Outcome::Value(Value::Number(55.0))));
+		assert_eq!(
+			repl.eval_line("fib(11)"),
+			Err(ReplError::Calc(PostfixError::InFunction(
+				String::from("fib"),
+				Box::new(PostfixError::RecursionLimit(10, Span::new(21, 31))),
+				Span::new(0, 7)
+			)))
+		);
+
+		repl.eval_line(":clear").unwrap();
+		assert_eq!(repl.eval_line("f(x) = -x"), Ok(Outcome::Output(String::from("f(x) = -x"))));
+	}
+
+	#[test]
+	fn outcome_display_test() {
+		assert_eq!(Outcome::Value(1.5).to_string(), "1.5");
+		assert_eq!(Outcome::Assignment(String::from("x"), 2.0).to_string(), "x = 2");
+		assert_eq!(Outcome::<f64>::Quit.to_string(), "");
+	}
+}
@@ -0,0 +1,169 @@
+use std::fmt;
+
+use crate::shunting::Span;
+
+/// Renders an error message followed by the expression with the offending part underlined
+///
+/// ```text
+/// Error: missing operand after an operator at column 3
+///   1 +
+///     ^
+/// ```
+pub fn render(message: impl fmt::Display, span: Option<Span>, source: &str) -> String {
+	match span {
+		Some(span) => format!("Error: {message} at column {}\n{}", column(source, span), underline(source, span)),
+		None => format!("Error: {message}"),
+	}
+}
+
+/// Like `render` for one line of a script where the span is within that line
+pub fn render_line(message: impl fmt::Display, span: Option<Span>, source: &str, line: usize) -> String {
+	match span {
+		Some(span) => {
+			format!("Error: {message} at line {line}, column {}\n{}", column(source, span), underline(source, span))
+		},
+		None => format!("Error: {message} at line {line}"),
+	}
+}
+
+/// The column a span starts at, counting characters from 1
+pub fn column(source: &str, span: Span) -> usize {
+	// spans count bytes but the terminal shows characters
+	source.get(..span.start).unwrap_or(source).chars().count() + 1
+}
+
+fn underline(source: &str, span: Span) -> String {
+	let column = column(source, span) - 1;
+	let width = source.get(span.start..span.end).map(|text| text.chars().count()).unwrap_or_default().max(1);
+	format!("  {source}\n  {}{}", " ".repeat(column), "^".repeat(width))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		env::Environment,
+		numeric::FloatMode,
+		postfix::Postfix,
+		shunting::{ShuntingYard, Syntax},
+	};
+
+	fn report(expression: &str) -> String {
+		match ShuntingYard::parse(expression, Syntax::default()) {
+			Ok(ast) => match Postfix::calc(&ast, &Environment::new(), &FloatMode) {
+				Ok(result) => result.to_string(),
+				Err(error) => render(&error, Some(error.span()), expression),
+			},
+			Err(error) => render(&error, error.span(), expression),
+		}
+	}
+
+	#[test]
+	fn render_test() {
+		assert_eq!(render("oops", None, "1 + 2"), "Error: oops");
+		assert_eq!(render("oops", Some(Span::new(4, 5)), "1 + 2"), "Error: oops at column 5\n  1 + 2\n      ^");
+		// a span at the very end still gets a caret just after the last character
+		assert_eq!(render("oops", Some(Span::new(3, 3)), "1 +"), "Error: oops at column 4\n  1 +\n     ^");
+		// columns count characters rather than bytes
+		assert_eq!(render("oops", Some(Span::new(5, 6)), "π + $"), "Error: oops at column 5\n  π + $\n      ^");
+
+		assert_eq!(render_line("oops", None, "1 + 2", 3), "Error: oops at line 3");
+		assert_eq!(
+			render_line("oops", Some(Span::new(2, 5)), "x = y; 1", 12),
+			"Error: oops at line 12, column 3\n  x = y; 1\n    ^^^"
+		);
+	}
+
+	#[test]
+	fn syntax_error_snapshot_test() {
+		assert_eq!(
+			report("2 * (3 $ 4)"),
+			"\
+Error: unexpected character `$` at column 8
+  2 * (3 $ 4)
+         ^"
+		);
+		assert_eq!(
+			report("(1 + 2"),
+			"\
+Error: mismatched parenthesis, `(` is never closed at column 1
+  (1 + 2
+  ^"
+		);
+		assert_eq!(
+			report("1 + 2)"),
+			"\
+Error: mismatched parenthesis, `)` was never opened at column 6
+  1 + 2)
+       ^"
+		);
+		assert_eq!(
+			report("1 +"),
+			"\
+Error: missing operand after an operator at column 3
+  1 +
+    ^"
+		);
+		assert_eq!(
+			report("1 2"),
+			"\
+Error: missing operator before this operand at column 3
+  1 2
+    ^"
+		);
+		assert_eq!(
+			report("max(1,)"),
+			"\
+Error: missing function argument at column 7
+  max(1,)
+        ^"
+		);
+		assert_eq!(
+			report("4 * () - 1"),
+			"\
+Error: nothing inside the parentheses at column 5
+  4 * () - 1
+      ^^"
+		);
+		assert_eq!(report(" "), "Error: empty expression");
+	}
+
+	#[test]
+	fn calc_error_snapshot_test() {
+		assert_eq!(
+			report("1 / (2 - 2)"),
+			"\
+Error: division by zero at column 6
+  1 / (2 - 2)
+       ^^^^^"
+		);
+		assert_eq!(
+			report("10 % 0 + 1"),
+			"\
+Error: modulo by zero at column 6
+  10 % 0 + 1
+       ^"
+		);
+		assert_eq!(
+			report("1 + sqrt(4, 9)"),
+			"\
+Error: `sqrt` takes exactly 1 argument but was given 2 at column 5
+  1 + sqrt(4, 9)
+      ^^^^^^^^^^"
+		);
+		assert_eq!(
+			report("2 * foo"),
+			"\
+Error: unknown identifier `foo` at column 5
+  2 * foo
+      ^^^"
+		);
+		assert_eq!(
+			report("1 + 10^400"),
+			"\
+Error: the result is too large at column 5
+  1 + 10^400
+      ^^^^^^"
+		);
+	}
+}
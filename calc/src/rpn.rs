@@ -0,0 +1,325 @@
+use std::{fmt, io};
+
+use ccalc::{
+	env::Environment,
+	expr::Expr,
+	format::Format,
+	functions::{self, Arity},
+	numeric::Numeric,
+	postfix::{Postfix, PostfixError},
+	report,
+	shunting::{Operator, ShuntingYard, ShuntingYardError, Span, Spanned, Syntax, Token},
+	value::Value,
+};
+
+use crate::editor::Editor;
+
+const HELP: &str = "\
+Enter numbers and operators in postfix order like `3 4 2 10 - * +`.
+Operators and functions take their operands from the top of the stack and push the result,
+`neg` changes the sign and functions like `max` that take any number of values take two.
+
+Stack commands:
+  dup    copy the top value
+  swap   exchange the top two values
+  drop   remove the top value
+  clear  remove all values
+  roll   move the top value to the bottom
+
+Commands:
+  :help  show this help
+  :quit  leave the calculator";
+
+#[derive(Debug, PartialEq)]
+pub enum RpnError {
+	Parse(ShuntingYardError),
+	Calc(PostfixError),
+	/// An operator, function or command needs more values than the stack holds
+	NotEnoughOperands {
+		name: String,
+		needed: usize,
+		found: usize,
+		span: Span,
+	},
+	UnknownCommand(String),
+	/// A function like `sum` that calculates an expression over and over, which the stack only holds values of
+	HigherOrder(String, Span),
+}
+
+impl fmt::Display for RpnError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			RpnError::Parse(error) => write!(f, "{error}"),
+			RpnError::Calc(error) => write!(f, "{error}"),
+			RpnError::NotEnoughOperands {
+				name, needed, found, ..
+			} => {
+				let values = if *needed == 1 { "value" } else { "values" };
+				write!(f, "`{name}` needs {needed} {values} but the stack holds {found}")
+			},
+			RpnError::UnknownCommand(command) => write!(f, "unknown command `:{command}`, try `:help`"),
+			RpnError::HigherOrder(name, _) => write!(f, "`{name}` takes an expression, which postfix input can't write"),
+		}
+	}
+}
+
+impl RpnError {
+	pub fn span(&self) -> Option<Span> {
+		match self {
+			RpnError::Parse(error) => error.span(),
+			RpnError::Calc(error) => Some(error.span()),
+			RpnError::NotEnoughOperands { span, .. } | RpnError::HigherOrder(_, span) => Some(*span),
+			RpnError::UnknownCommand(_) => None,
+		}
+	}
+}
+
+impl From<ShuntingYardError> for RpnError {
+	fn from(error: ShuntingYardError) -> Self {
+		RpnError::Parse(error)
+	}
+}
+
+impl From<PostfixError> for RpnError {
+	fn from(error: PostfixError) -> Self {
+		RpnError::Calc(error)
+	}
+}
+
+/// A stack calculator for postfix input that keeps its stack from one line to the next
+#[derive(Debug)]
+pub struct Rpn<N: Numeric> {
+	numeric: N,
+	syntax: Syntax,
+	format: Format,
+	env: Environment<Value<N::Value>>,
+	/// The top of the stack is the last value
+	stack: Vec<Value<N::Value>>,
+}
+
+impl<N: Numeric> Rpn<N> {
+	pub fn new(numeric: N, syntax: Syntax, format: Format) -> Self {
+		Self {
+			numeric,
+			syntax,
+			format,
+			env: Environment::new(),
+			stack: Vec::new(),
+		}
+	}
+
+	pub fn set_recursion_limit(&mut self, limit: usize) {
+		self.env.recursion_limit = limit;
+	}
+
+	pub fn stack(&self) -> &[Value<N::Value>] {
+		&self.stack
+	}
+
+	/// Runs a line of postfix input, which leaves the stack as it was if anything in the line fails
+	pub fn eval_line(&mut self, line: &str) -> Result<(), RpnError> {
+		let mut stack = self.stack.clone();
+		for token in ShuntingYard::tokenize_postfix(line, self.syntax)? {
+			self.step(&mut stack, token)?;
+		}
+		self.stack = stack;
+		Ok(())
+	}
+
+	fn step(&self, stack: &mut Vec<Value<N::Value>>, token: Spanned<Token>) -> Result<(), RpnError> {
+		let span = token.span;
+		// the operands are named by their position, see `Postfix::apply`
+		let operand = |index: usize| Box::new(Spanned::new(Expr::Variable(index.to_string()), span));
+		let (name, expr, count) = match token.node {
+			Token::Number(number) => (String::new(), Expr::Number(number), 0),
+			Token::Integer(integer, radix) => (String::new(), Expr::Integer(integer, radix), 0),
+			Token::Decimal(digits) => (String::new(), Expr::Decimal(digits), 0),
+			Token::Quantity(number, symbol) => (String::new(), Expr::Quantity(number, symbol), 0),
+			Token::Imaginary(number) => (String::new(), Expr::Imaginary(number), 0),
+			Token::Bool(boolean) => (String::new(), Expr::Bool(boolean), 0),
+			Token::Identifier(name) if Self::command(&name).is_some() => {
+				let count = Self::command(&name).expect("BUG: checked by the guard");
+				let operands = Self::take(stack, &name, count, span)?;
+				match name.as_str() {
+					"dup" => stack.extend([operands[0].clone(), operands[0].clone()]),
+					"swap" => stack.extend(operands.into_iter().rev()),
+					"clear" => stack.clear(),
+					"roll" => stack.insert(0, operands[0].clone()),
+					// `drop` is done by taking the value
+					_ => {},
+				}
+				return Ok(());
+			},
+			Token::Identifier(name) if self.env.function(&name).is_none() && functions::is_higher_order(&name) => {
+				return Err(RpnError::HigherOrder(name, span));
+			},
+			Token::Identifier(name) => {
+				let count = match (self.env.function(&name), functions::arity(&name)) {
+					(Some(function), _) => Some(function.parameters.len()),
+					(None, Some(Arity::Exact(count) | Arity::Between(count, _))) => Some(count),
+					// functions of any number of values combine the top two
+					(None, Some(Arity::AtLeast(count))) => Some(count.max(2)),
+					(None, None) => None,
+				};
+				match count {
+					Some(count) => (name.clone(), Expr::Call(name, (0..count).map(|index| *operand(index)).collect()), count),
+					None => (name.clone(), Expr::Variable(name), 0),
+				}
+			},
+			Token::Operator(operator) => {
+				let name = operator.to_string();
+				match operator {
+					Operator::Conditional => (String::from("?"), Expr::Conditional(operand(0), operand(1), operand(2)), 3),
+					operator if operator.is_prefix() => (name, Expr::Unary(operator, operand(0)), 1),
+					operator => (name, Expr::Binary(operator, operand(0), operand(1)), 2),
+				}
+			},
+			token => unreachable!("postfix input has no `{token}` tokens"),
+		};
+
+		let operands = Self::take(stack, &name, count, span)?;
+		stack.push(Postfix::apply(&Spanned::new(expr, span), operands, &self.env, &self.numeric)?);
+		Ok(())
+	}
+
+	// how many values a stack command works on
+	fn command(name: &str) -> Option<usize> {
+		match name {
+			"dup" | "drop" | "roll" => Some(1),
+			"swap" => Some(2),
+			"clear" => Some(0),
+			_ => None,
+		}
+	}
+
+	// the top values in the order they were pushed
+	fn take(
+		stack: &mut Vec<Value<N::Value>>,
+		name: &str,
+		count: usize,
+		span: Span,
+	) -> Result<Vec<Value<N::Value>>, RpnError> {
+		if stack.len() < count {
+			return Err(RpnError::NotEnoughOperands {
+				name: name.to_string(),
+				needed: count,
+				found: stack.len(),
+				span,
+			});
+		}
+		Ok(stack.split_off(stack.len() - count))
+	}
+
+	/// The stack from the bottom to the top, numbered like on a HP calculator where `1` is the top
+	pub fn levels(&self) -> String {
+		let width = self.stack.len().to_string().len();
+		self
+			.stack
+			.iter()
+			.enumerate()
+			.map(|(index, value)| {
+				let level = self.stack.len() - index;
+				format!("{level:>width$}: {}", value.format(&self.numeric, self.format))
+			})
+			.collect::<Vec<String>>()
+			.join("\n")
+	}
+
+	/// The values left on the stack from the bottom to the top, one per line
+	pub fn results(&self) -> String {
+		self.stack.iter().map(|value| value.format(&self.numeric, self.format)).collect::<Vec<String>>().join("\n")
+	}
+
+	pub fn run(&mut self) -> io::Result<()> {
+		let mut editor = Editor::new();
+
+		while let Some(line) = editor.read_line("> ")? {
+			let result = match line.trim().strip_prefix(':').map(str::trim) {
+				Some("quit" | "q" | "exit") => break,
+				Some("help") => {
+					println!("{HELP}");
+					continue;
+				},
+				Some(command) => Err(RpnError::UnknownCommand(command.to_string())),
+				None => self.eval_line(&line),
+			};
+			match result {
+				// the whole stack is shown after every line
+				Ok(()) if !self.stack.is_empty() => println!("{}", self.levels()),
+				Ok(()) => {},
+				Err(error) => println!("{}", report::render(&error, error.span(), &line)),
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ccalc::numeric::FloatMode;
+
+	fn rpn(lines: &[&str]) -> Result<Vec<Value>, RpnError> {
+		let mut rpn = Rpn::new(FloatMode, Syntax::default(), Format::default());
+		for line in lines {
+			rpn.eval_line(line)?;
+		}
+		Ok(rpn.stack.clone())
+	}
+
+	#[test]
+	fn eval_line_test() {
+		assert_eq!(rpn(&["3 4 2 10 - * +"]), Ok(vec![Value::Number(-29.0)]));
+		assert_eq!(rpn(&["2 3 ^", "1 +"]), Ok(vec![Value::Number(9.0)]));
+		assert_eq!(rpn(&["-3 neg 4"]), Ok(vec![Value::Number(3.0), Value::Number(4.0)]));
+		assert_eq!(rpn(&["pi 2 / sin"]), Ok(vec![Value::Number(1.0)]));
+		assert_eq!(rpn(&["1 5 3 max"]), Ok(vec![Value::Number(1.0), Value::Number(5.0)]));
+		assert_eq!(rpn(&["100 log", "8 2 atan2 0 >"]), Ok(vec![Value::Number(2.0), Value::Bool(true)]));
+		assert_eq!(rpn(&["1 2 < 10 20 ?"]), Ok(vec![Value::Number(10.0)]));
+		assert_eq!(rpn(&["3 km * 2 *", "m"]).map(|stack| stack[0].to_string()), Ok(String::from("6000 m")));
+	}
+
+	#[test]
+	fn stack_commands_test() {
+		let numbers = |numbers: &[f64]| Ok(numbers.iter().copied().map(Value::Number).collect::<Vec<Value>>());
+
+		assert_eq!(rpn(&["2 dup *"]), numbers(&[4.0]));
+		assert_eq!(rpn(&["1 2 swap -"]), numbers(&[1.0]));
+		assert_eq!(rpn(&["1 2 drop"]), numbers(&[1.0]));
+		assert_eq!(rpn(&["1 2 3 clear 4"]), numbers(&[4.0]));
+		assert_eq!(rpn(&["1 2 3 roll"]), numbers(&[3.0, 1.0, 2.0]));
+	}
+
+	#[test]
+	fn error_test() {
+		let mut rpn = Rpn::new(FloatMode, Syntax::default(), Format::default());
+		rpn.eval_line("1 2").unwrap();
+
+		// a failing line leaves the stack as it was before the line
+		assert_eq!(
+			rpn.eval_line("3 + + +"),
+			Err(RpnError::NotEnoughOperands {
+				name: String::from("+"),
+				needed: 2,
+				found: 1,
+				span: Span::new(6, 7),
+			})
+		);
+		assert_eq!(rpn.eval_line("0 /"), Err(RpnError::Calc(PostfixError::DivisionByZero(Span::new(2, 3)))));
+		assert_eq!(
+			rpn.eval_line("swap x"),
+			Err(RpnError::Calc(PostfixError::UnknownIdentifier(String::from("x"), Span::new(5, 6))))
+		);
+		assert_eq!(rpn.eval_line("1 (2+3)"), Err(RpnError::Parse(ShuntingYardError::UnexpectedWord(Span::new(2, 7)))));
+		assert_eq!(rpn.eval_line("1 1.2.3"), Err(RpnError::Parse(ShuntingYardError::InvalidNumber(Span::new(2, 7)))));
+		assert_eq!(rpn.eval_line("3 4 integrate"), Err(RpnError::HigherOrder(String::from("integrate"), Span::new(4, 13))));
+		assert_eq!(
+			rpn.eval_line("3 4 sum").map_err(|error| error.to_string()),
+			Err(String::from("`sum` takes an expression, which postfix input can't write"))
+		);
+		assert_eq!(rpn.stack, vec![Value::Number(1.0), Value::Number(2.0)]);
+		assert_eq!(rpn.levels(), "2: 1\n1: 2");
+		assert_eq!(rpn.results(), "1\n2");
+	}
+}
@@ -0,0 +1,310 @@
+use std::io::{self, BufRead, Write};
+
+use ccalc::{numeric::Numeric, report, shunting::Span, value::Value};
+
+use crate::repl::{Outcome, Repl, ReplError};
+
+/// How the results of a script are written
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Output {
+	/// The value of every expression on its own line, assignments and definitions are quiet
+	#[default]
+	Plain,
+	/// A JSON object for every statement like `{"line":1,"value":3}`, values JSON has no numbers for like `1/3`
+	/// or `[1, 2]` are written as strings
+	Json,
+}
+
+/// How a script ended, which decides the exit code
+#[derive(Debug, PartialEq)]
+pub enum Status {
+	Success,
+	/// The last result was `false`, like a single expression that is a false comparison
+	False,
+	/// A statement failed, whether or not the script went on after it
+	Failed,
+}
+
+/// Runs statements separated by new lines or `;` where `#` starts a comment
+///
+/// Statements are evaluated like those of the REPL so variables and functions last until the end of the script.
+pub struct Script<N: Numeric> {
+	repl: Repl<N>,
+	output: Output,
+	continue_on_error: bool,
+}
+
+impl<N: Numeric> Script<N> {
+	pub fn new(repl: Repl<N>, output: Output, continue_on_error: bool) -> Self {
+		Self {
+			repl,
+			output,
+			continue_on_error,
+		}
+	}
+
+	pub fn run(&mut self, input: impl BufRead, out: &mut impl Write) -> io::Result<Status> {
+		let mut status = Status::Success;
+		let mut failed = false;
+
+		for (index, line) in input.lines().enumerate() {
+			let line = line?;
+			for (offset, statement) in statements(&line) {
+				match self.repl.eval_line(statement) {
+					Ok(Outcome::Quit) => return Ok(if failed { Status::Failed } else { status }),
+					Ok(outcome) => {
+						status = match outcome {
+							Outcome::Value(Value::Bool(false)) | Outcome::Assignment(_, Value::Bool(false)) => Status::False,
+							_ => Status::Success,
+						};
+						// only commands print something for a statement that isn't an expression
+						let quiet =
+							self.output == Output::Plain && matches!(outcome, Outcome::Output(_)) && !statement.starts_with(':');
+						if !quiet {
+							self.write(out, index + 1, outcome)?;
+						}
+					},
+					Err(error) => {
+						failed = true;
+						self.write_error(out, index + 1, &line, offset, &error)?;
+						if !self.continue_on_error {
+							return Ok(Status::Failed);
+						}
+					},
+				}
+			}
+		}
+
+		Ok(if failed { Status::Failed } else { status })
+	}
+
+	fn write(&self, out: &mut impl Write, line: usize, outcome: Outcome<Value<N::Value>>) -> io::Result<()> {
+		match (self.output, outcome) {
+			(Output::Plain, Outcome::Value(value)) => writeln!(out, "{}", self.repl.print(&value)),
+			(Output::Plain, Outcome::Output(output)) => writeln!(out, "{output}"),
+			(Output::Plain, _) => Ok(()),
+			(Output::Json, Outcome::Value(value)) => {
+				writeln!(out, r#"{{"line":{line},"value":{}}}"#, self.json_value(&value))
+			},
+			(Output::Json, Outcome::Assignment(name, value)) => {
+				writeln!(out, r#"{{"line":{line},"name":{},"value":{}}}"#, json(&name), self.json_value(&value))
+			},
+			(Output::Json, Outcome::Output(output)) => writeln!(out, r#"{{"line":{line},"output":{}}}"#, json(&output)),
+			(Output::Json, Outcome::Quit) => Ok(()),
+		}
+	}
+
+	// booleans and numbers JSON can read stay what they are, anything else is the string it is printed as
+	fn json_value(&self, value: &Value<N::Value>) -> String {
+		let printed = self.repl.print(value);
+		match value {
+			Value::Bool(boolean) => boolean.to_string(),
+			Value::Number(_) if is_json_number(&printed) => printed,
+			_ => json(&printed),
+		}
+	}
+
+	// the span of the error is within the statement, which starts at the offset of the line
+	fn write_error(
+		&self,
+		out: &mut impl Write,
+		line: usize,
+		source: &str,
+		offset: usize,
+		error: &ReplError,
+	) -> io::Result<()> {
+		let span = error.span().map(|span| Span::new(span.start + offset, span.end + offset));
+		match (self.output, span) {
+			(Output::Plain, span) => writeln!(out, "{}", report::render_line(error, span, source, line)),
+			(Output::Json, Some(span)) => writeln!(
+				out,
+				r#"{{"line":{line},"column":{},"error":{}}}"#,
+				report::column(source, span),
+				json(&error.to_string())
+			),
+			(Output::Json, None) => writeln!(out, r#"{{"line":{line},"error":{}}}"#, json(&error.to_string())),
+		}
+	}
+}
+
+// the statements of a line without its comment, each trimmed and with where it starts in the line
+fn statements(line: &str) -> impl Iterator<Item = (usize, &str)> {
+	let code = line.split('#').next().unwrap_or_default();
+	code
+		.split(';')
+		.scan(0, |start, part| {
+			let offset = *start;
+			*start += part.len() + 1;
+			Some((offset + part.len() - part.trim_start().len(), part.trim()))
+		})
+		.filter(|(_, statement)| !statement.is_empty())
+}
+
+// whether the text is a number in JSON's grammar, which `1/3`, `0xff`, `.5`, `1_000` and `inf` aren't
+fn is_json_number(text: &str) -> bool {
+	let digits = |text: &str| text.find(|character: char| !character.is_ascii_digit()).unwrap_or(text.len());
+	let rest = text.strip_prefix('-').unwrap_or(text);
+	let integer = digits(rest);
+	if integer == 0 || (integer > 1 && rest.starts_with('0')) {
+		return false;
+	}
+	let mut rest = &rest[integer..];
+	if let Some(fraction) = rest.strip_prefix('.') {
+		let length = digits(fraction);
+		if length == 0 {
+			return false;
+		}
+		rest = &fraction[length..];
+	}
+	if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+		let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+		let length = digits(exponent);
+		if length == 0 {
+			return false;
+		}
+		rest = &exponent[length..];
+	}
+	rest.is_empty()
+}
+
+// a JSON string with the characters it can't hold escaped
+fn json(text: &str) -> String {
+	let mut escaped = String::with_capacity(text.len() + 2);
+	escaped.push('"');
+	for character in text.chars() {
+		match character {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			'\n' => escaped.push_str("\\n"),
+			'\t' => escaped.push_str("\\t"),
+			character if character.is_control() => escaped.push_str(&format!("\\u{:04x}", character as u32)),
+			character => escaped.push(character),
+		}
+	}
+	escaped.push('"');
+	escaped
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use ccalc::{format::Format, numeric::FloatMode, rational::RationalMode, shunting::Syntax};
+
+	fn run(source: &str, output: Output, continue_on_error: bool) -> (String, Status) {
+		let repl = Repl::new(FloatMode, Syntax::default(), Format::default());
+		let mut out = Vec::new();
+		let status = Script::new(repl, output, continue_on_error).run(source.as_bytes(), &mut out).unwrap();
+		(String::from_utf8(out).unwrap(), status)
+	}
+
+	#[test]
+	fn statements_test() {
+		let statements = |line| statements(line).collect::<Vec<(usize, &str)>>();
+
+		assert_eq!(statements("x = 1; y = 2;z"), [(0, "x = 1"), (7, "y = 2"), (13, "z")]);
+		assert_eq!(statements("  1 + 2  # three"), [(2, "1 + 2")]);
+		assert_eq!(statements("# only a comment; 1"), []);
+		assert_eq!(statements(" ; ;"), []);
+	}
+
+	#[test]
+	fn run_test() {
+		let source = "\
+# the area of a circle
+r = 2; area(r) = pi * r^2
+area(r) / pi
+r > 3
+";
+		assert_eq!(run(source, Output::Plain, false), (String::from("4\nfalse\n"), Status::False));
+		assert_eq!(
+			run(source, Output::Json, false),
+			(
+				String::from(
+					r#"{"line":2,"name":"r","value":2}
+{"line":2,"output":"area(r) = pi*r^2"}
+{"line":3,"value":4}
+{"line":4,"value":false}
+"#
+				),
+				Status::False
+			)
+		);
+		assert_eq!(run("1\n:quit\n1 / 0", Output::Plain, false), (String::from("1\n"), Status::Success));
+		assert_eq!(run("x = 1; :vars", Output::Plain, false), (String::from("ans = 1\nx = 1\n"), Status::Success));
+	}
+
+	#[test]
+	fn run_error_test() {
+		let source = "x = 1\ny = x / 0; x + 1\n:nope\nx";
+
+		assert_eq!(
+			run(source, Output::Plain, false),
+			(String::from("Error: division by zero at line 2, column 9\n  y = x / 0; x + 1\n          ^\n"), Status::Failed)
+		);
+		assert_eq!(
+			run(source, Output::Plain, true),
+			(
+				String::from(
+					"\
+Error: division by zero at line 2, column 9
+  y = x / 0; x + 1
+          ^
+2
+Error: unknown command `:nope`, try `:help` at line 3
+1
+"
+				),
+				Status::Failed
+			)
+		);
+		assert_eq!(
+			run(source, Output::Json, true),
+			(
+				String::from(
+					r#"{"line":1,"name":"x","value":1}
+{"line":2,"column":9,"error":"division by zero"}
+{"line":2,"value":2}
+{"line":3,"error":"unknown command `:nope`, try `:help`"}
+{"line":4,"value":1}
+"#
+				),
+				Status::Failed
+			)
+		);
+	}
+
+	#[test]
+	fn json_test() {
+		assert_eq!(json("x = 1"), r#""x = 1""#);
+		assert_eq!(json("a \"b\" \\ c\nd\u{1}"), r#""a \"b\" \\ c\nd\u0001""#);
+
+		for number in ["0", "-2", "0.5", "1.25e-3", "6.02E+23"] {
+			assert!(is_json_number(number), "{number}");
+		}
+		for text in [
+			"", "-", "01", ".5", "1.", "1e", "1/3", "0xff", "1_000", "inf", "NaN", "3+4i",
+		] {
+			assert!(!is_json_number(text), "{text}");
+		}
+
+		// values JSON has no numbers for are strings
+		let (out, _) = run("-1.5; 2 m; [1, 2]; 1 < 2", Output::Json, false);
+		assert_eq!(
+			out,
+			r#"{"line":1,"value":-1.5}
+{"line":1,"value":"2 m"}
+{"line":1,"value":"[1, 2]"}
+{"line":1,"value":true}
+"#
+		);
+		let repl = Repl::new(RationalMode, Syntax::default(), Format::default());
+		let mut out = Vec::new();
+		Script::new(repl, Output::Json, false).run("x = 1/3; 3x".as_bytes(), &mut out).unwrap();
+		assert_eq!(
+			String::from_utf8(out).unwrap(),
+			r#"{"line":1,"name":"x","value":"1/3"}
+{"line":1,"value":1}
+"#
+		);
+	}
+}
@@ -1,11 +1,45 @@
 use std::fmt;
 
-#[derive(Debug, PartialEq)]
+use crate::{
+	expr::{Ast, Expr},
+	numeric::{self, Radix},
+	units::{self, Symbol},
+};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
 	Plus,
 	Minus,
 	Times,
 	Divided,
+	Modulo,
+	Power,
+	Negate,
+	BitAnd,
+	BitOr,
+	BitXor,
+	BitNot,
+	ShiftLeft,
+	ShiftRight,
+	Equal,
+	NotEqual,
+	Less,
+	LessEqual,
+	Greater,
+	GreaterEqual,
+	And,
+	Or,
+	Not,
+	/// The ternary `condition ? then : otherwise` which takes three operands
+	Conditional,
+	/// `quantity in unit`, also written with `to`
+	Convert,
+	/// The dot product of two vectors
+	Dot,
+	/// The matrix product `a @ b` where `*` multiplies element by element
+	MatrixProduct,
+	/// A value and how far off it may be like `9.81 ± 0.02`, also written `+/-`
+	PlusMinus,
 }
 
 impl fmt::Display for Operator {
@@ -15,134 +49,1255 @@ impl fmt::Display for Operator {
 			Operator::Minus => write!(f, "-"),
 			Operator::Times => write!(f, "*"),
 			Operator::Divided => write!(f, "/"),
+			Operator::Modulo => write!(f, "%"),
+			Operator::Power => write!(f, "^"),
+			Operator::Negate => write!(f, "-"),
+			Operator::BitAnd => write!(f, "&"),
+			Operator::BitOr => write!(f, "|"),
+			Operator::BitXor => write!(f, "^"),
+			Operator::BitNot => write!(f, "~"),
+			Operator::ShiftLeft => write!(f, "<<"),
+			Operator::ShiftRight => write!(f, ">>"),
+			Operator::Equal => write!(f, "=="),
+			Operator::NotEqual => write!(f, "!="),
+			Operator::Less => write!(f, "<"),
+			Operator::LessEqual => write!(f, "<="),
+			Operator::Greater => write!(f, ">"),
+			Operator::GreaterEqual => write!(f, ">="),
+			Operator::And => write!(f, "&&"),
+			Operator::Or => write!(f, "||"),
+			Operator::Not => write!(f, "!"),
+			Operator::Conditional => write!(f, "?:"),
+			Operator::Convert => write!(f, "in"),
+			Operator::Dot => write!(f, "."),
+			Operator::MatrixProduct => write!(f, "@"),
+			Operator::PlusMinus => write!(f, "±"),
 		}
 	}
 }
 
-#[derive(Debug, PartialEq)]
+impl Operator {
+	// everything but the power binds like in C, loosest below the arithmetic ones, and a conversion takes the
+	// whole calculation on its left
+	pub fn precedence(&self) -> u8 {
+		match self {
+			Operator::Conditional => 1,
+			Operator::Convert => 2,
+			Operator::Or => 3,
+			Operator::And => 4,
+			Operator::BitOr => 5,
+			Operator::BitXor => 6,
+			Operator::BitAnd => 7,
+			Operator::Equal | Operator::NotEqual => 8,
+			Operator::Less | Operator::LessEqual | Operator::Greater | Operator::GreaterEqual => 9,
+			Operator::ShiftLeft | Operator::ShiftRight => 10,
+			Operator::Plus | Operator::Minus | Operator::PlusMinus => 11,
+			Operator::Times | Operator::Divided | Operator::Modulo | Operator::Dot | Operator::MatrixProduct => 12,
+			Operator::Negate | Operator::BitNot | Operator::Not => 13,
+			Operator::Power => 14,
+		}
+	}
+
+	// `2^3^2` is `2^(3^2)` while `8/4/2` is `(8/4)/2`, `a ? b : c ? d : e` nests to the right too
+	pub fn is_right_associative(&self) -> bool {
+		matches!(self, Operator::Power | Operator::Conditional) || self.is_prefix()
+	}
+
+	// prefix operators have no left operand so they must never pop what came before them
+	pub fn is_prefix(&self) -> bool {
+		matches!(self, Operator::Negate | Operator::BitNot | Operator::Not)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Token {
 	Number(f64),
+	/// A literal written with a radix prefix like `0xFF` or one too large for an `f64` to hold exactly
+	Integer(u64, Radix),
+	/// The digits of a decimal literal with more of them than an `f64` holds like `0.12345678901234567890`
+	Decimal(String),
+	/// A number followed by a unit like `3 km`
+	Quantity(f64, Symbol),
+	/// A number right before an `i` like `4i`
+	Imaginary(f64),
+	Bool(bool),
+	Identifier(String),
+	Function(String),
+	/// A function call in postfix notation with the number of arguments it takes from the stack
+	Call(String, usize),
+	/// A vector literal in postfix notation with the number of elements it takes from the stack
+	Vector(usize),
 	Operator(Operator),
 	OpenParenthesis,
 	CloseParenthesis,
+	OpenBracket,
+	CloseBracket,
+	Comma,
+	Equals,
+	/// The `?` of a conditional, it turns into the operator once its `:` is found
+	Question,
+	Colon,
+	/// The product of two operands written next to each other like `2x` or `(a)(b)`
+	ImplicitTimes,
+	/// A `%` after a number that makes it a percentage rather than the remainder operator
+	Percent,
 }
 
 impl fmt::Display for Token {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
 			Token::Number(number) => write!(f, "{number}"),
+			Token::Integer(integer, radix) => write!(f, "{}", radix.format(*integer as u128)),
+			Token::Decimal(digits) => write!(f, "{digits}"),
+			Token::Quantity(number, symbol) => write!(f, "{number} {symbol}"),
+			Token::Imaginary(number) => write!(f, "{number}i"),
+			Token::Bool(boolean) => write!(f, "{boolean}"),
+			Token::Identifier(name) | Token::Function(name) => write!(f, "{name}"),
+			Token::Call(name, arity) => write!(f, "{name}/{arity}"),
+			Token::Vector(length) => write!(f, "[{length}]"),
 			Token::Operator(operator) => write!(f, "{operator}"),
 			Token::OpenParenthesis => write!(f, "("),
 			Token::CloseParenthesis => write!(f, ")"),
+			Token::OpenBracket => write!(f, "["),
+			Token::CloseBracket => write!(f, "]"),
+			Token::Comma => write!(f, ","),
+			Token::Equals => write!(f, "="),
+			Token::Question => write!(f, "?"),
+			Token::Colon => write!(f, ":"),
+			Token::ImplicitTimes => write!(f, "*"),
+			Token::Percent => write!(f, "%"),
 		}
 	}
 }
 
+/// Byte offsets into the expression with an exclusive end
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Span {
+	pub start: usize,
+	pub end: usize,
+}
+
+impl Span {
+	pub fn new(start: usize, end: usize) -> Self {
+		Self { start, end }
+	}
+
+	/// The smallest span covering both
+	pub fn to(self, other: Span) -> Span {
+		Span::new(self.start.min(other.start), self.end.max(other.end))
+	}
+
+	/// The same span in a text with `offset` more bytes before it
+	pub fn shifted(self, offset: usize) -> Span {
+		Span::new(self.start + offset, self.end + offset)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+	pub node: T,
+	pub span: Span,
+}
+
+impl<T> Spanned<T> {
+	pub fn new(node: T, span: Span) -> Self {
+		Self { node, span }
+	}
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ShuntingYardError {
-	ItemNotRecognized,
+	UnexpectedCharacter(char, Span),
+	InvalidNumber(Span),
+	NumberTooLarge(Span),
+	CommaOutsideFunction(Span),
+	MisplacedAssignment(Span),
+	InvalidParameter(Span),
+	DuplicateParameter(String, Span),
+	UnmatchedOpenParenthesis(Span),
+	UnmatchedCloseParenthesis(Span),
+	UnmatchedOpenBracket(Span),
+	UnmatchedCloseBracket(Span),
+	EmptyParentheses(Span),
+	MissingOperand(Span),
+	MissingOperator(Span),
+	MissingArgument(Span),
+	MissingColon(Span),
+	UnmatchedColon(Span),
+	/// A word of postfix input that is more than a single operand or operator
+	UnexpectedWord(Span),
+	/// More tokens than the syntax allows, the span is the first one too many
+	TooManyTokens(usize, Span),
+	/// Nested deeper than the syntax allows, the span is the operator or call that went too deep
+	TooDeep(usize, Span),
+	EmptyExpression,
+}
+
+impl ShuntingYardError {
+	pub fn span(&self) -> Option<Span> {
+		match self {
+			ShuntingYardError::UnexpectedCharacter(_, span)
+			| ShuntingYardError::InvalidNumber(span)
+			| ShuntingYardError::NumberTooLarge(span)
+			| ShuntingYardError::CommaOutsideFunction(span)
+			| ShuntingYardError::MisplacedAssignment(span)
+			| ShuntingYardError::InvalidParameter(span)
+			| ShuntingYardError::DuplicateParameter(_, span)
+			| ShuntingYardError::UnmatchedOpenParenthesis(span)
+			| ShuntingYardError::UnmatchedCloseParenthesis(span)
+			| ShuntingYardError::UnmatchedOpenBracket(span)
+			| ShuntingYardError::UnmatchedCloseBracket(span)
+			| ShuntingYardError::EmptyParentheses(span)
+			| ShuntingYardError::MissingOperand(span)
+			| ShuntingYardError::MissingOperator(span)
+			| ShuntingYardError::MissingArgument(span)
+			| ShuntingYardError::MissingColon(span)
+			| ShuntingYardError::UnexpectedWord(span)
+			| ShuntingYardError::TooManyTokens(_, span)
+			| ShuntingYardError::TooDeep(_, span)
+			| ShuntingYardError::UnmatchedColon(span) => Some(*span),
+			ShuntingYardError::EmptyExpression => None,
+		}
+	}
+}
+
+impl fmt::Display for ShuntingYardError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ShuntingYardError::UnexpectedCharacter(character, _) => write!(f, "unexpected character `{character}`"),
+			ShuntingYardError::InvalidNumber(_) => write!(f, "invalid number"),
+			ShuntingYardError::NumberTooLarge(_) => write!(f, "the number is too large"),
+			ShuntingYardError::CommaOutsideFunction(_) => write!(f, "comma outside of a function call or vector"),
+			ShuntingYardError::MisplacedAssignment(_) => write!(f, "assignments have to look like `name = expression`"),
+			ShuntingYardError::InvalidParameter(_) => write!(f, "function parameters have to be names separated by commas"),
+			ShuntingYardError::DuplicateParameter(name, _) => write!(f, "parameter `{name}` appears more than once"),
+			ShuntingYardError::UnmatchedOpenParenthesis(_) => write!(f, "mismatched parenthesis, `(` is never closed"),
+			ShuntingYardError::UnmatchedCloseParenthesis(_) => write!(f, "mismatched parenthesis, `)` was never opened"),
+			ShuntingYardError::UnmatchedOpenBracket(_) => write!(f, "mismatched bracket, `[` is never closed"),
+			ShuntingYardError::UnmatchedCloseBracket(_) => write!(f, "mismatched bracket, `]` was never opened"),
+			ShuntingYardError::EmptyParentheses(_) => write!(f, "nothing inside the parentheses"),
+			ShuntingYardError::MissingOperand(_) => write!(f, "missing operand after an operator"),
+			ShuntingYardError::MissingOperator(_) => write!(f, "missing operator before this operand"),
+			ShuntingYardError::MissingArgument(_) => write!(f, "missing function argument"),
+			ShuntingYardError::MissingColon(_) => write!(f, "`?` is missing its `:`"),
+			ShuntingYardError::UnmatchedColon(_) => write!(f, "`:` without a `?` before it"),
+			ShuntingYardError::UnexpectedWord(_) => write!(f, "expected a number, a name or an operator"),
+			ShuntingYardError::TooManyTokens(limit, _) => write!(f, "the expression is longer than {limit} tokens"),
+			ShuntingYardError::TooDeep(limit, _) => write!(f, "the expression is nested deeper than {limit} levels"),
+			ShuntingYardError::EmptyExpression => write!(f, "empty expression"),
+		}
+	}
+}
+
+pub type Tokens = Vec<Spanned<Token>>;
+
+// the words of postfix input that are operators, `^` depends on the syntax
+const POSTFIX_OPERATORS: &[(&str, Operator)] = &[
+	("+", Operator::Plus),
+	("-", Operator::Minus),
+	("*", Operator::Times),
+	("/", Operator::Divided),
+	("%", Operator::Modulo),
+	("^", Operator::Power),
+	("**", Operator::Power),
+	("neg", Operator::Negate),
+	("&", Operator::BitAnd),
+	("|", Operator::BitOr),
+	("~", Operator::BitNot),
+	("<<", Operator::ShiftLeft),
+	(">>", Operator::ShiftRight),
+	("==", Operator::Equal),
+	("!=", Operator::NotEqual),
+	("<", Operator::Less),
+	("<=", Operator::LessEqual),
+	(">", Operator::Greater),
+	(">=", Operator::GreaterEqual),
+	("&&", Operator::And),
+	("||", Operator::Or),
+	("!", Operator::Not),
+	("?", Operator::Conditional),
+	("±", Operator::PlusMinus),
+	("+/-", Operator::PlusMinus),
+];
+
+/// Grammar choices that depend on how the expression is going to be calculated
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Syntax {
+	/// `^` is an exclusive or like in C rather than a power, which is still written `**`
+	pub caret_is_xor: bool,
+	/// `inf` and `nan` are numbers rather than names
+	pub non_finite: bool,
+	/// Rejects expressions with more tokens than this, which bounds the memory a parse takes
+	pub max_tokens: Option<usize>,
+	/// Rejects expressions nested deeper than this, which bounds the recursion of everything walking the tree
+	pub max_depth: Option<usize>,
 }
 
-pub type Ast = Vec<Token>;
+#[derive(Debug, PartialEq)]
+pub enum Statement {
+	Assignment(Spanned<String>, Ast),
+	/// `name(parameters) = body`
+	Definition(Spanned<String>, Vec<Spanned<String>>, Ast),
+	Expression(Ast),
+}
 
 pub struct ShuntingYard {}
 
 impl ShuntingYard {
+	/// Trees deeper than this would overflow the stack of the passes walking them, whatever the syntax allows
+	pub const MAX_DEPTH: usize = 1_000;
+
+	// a `?` waiting for its `:` already binds like the conditional it will become, a product without a `*` binds
+	// like a sign so `1/2x` is `1/(2x)` while `2x^2` is still `2(x^2)`
 	fn precedence(token: &Token) -> u8 {
 		match token {
-			Token::Operator(Operator::Plus) | Token::Operator(Operator::Minus) => 1,
-			Token::Operator(Operator::Times) | Token::Operator(Operator::Divided) => 2,
+			Token::Operator(operator) => operator.precedence(),
+			Token::Question => Operator::Conditional.precedence(),
+			Token::ImplicitTimes => Operator::Negate.precedence(),
 			_ => 0,
 		}
 	}
 
-	pub fn infix_to_postfix(expression: &str) -> Result<Ast, ShuntingYardError> {
-		let mut output_stack: Ast = Vec::new();
-		let mut operator_stack: Ast = Vec::new();
+	fn is_right_associative(token: &Token) -> bool {
+		match token {
+			Token::Operator(operator) => operator.is_right_associative(),
+			Token::Question => true,
+			_ => false,
+		}
+	}
+
+	fn is_prefix(token: &Token) -> bool {
+		matches!(token, Token::Operator(operator) if operator.is_prefix())
+	}
+
+	// a sign is unary when there is nothing on its left it could be subtracted from
+	fn expects_operand(previous: Option<&Token>) -> bool {
+		matches!(
+			previous,
+			None
+				| Some(
+					Token::Operator(_)
+						| Token::ImplicitTimes
+						| Token::OpenParenthesis
+						| Token::OpenBracket
+						| Token::Comma
+						| Token::Equals
+						| Token::Question
+						| Token::Colon
+				)
+		)
+	}
+
+	// `max(1, 2)` has a function token sitting right below its opening parenthesis on the operator stack
+	fn in_function_call(operator_stack: &Tokens) -> bool {
+		matches!(
+			operator_stack.iter().rev().nth(1),
+			Some(Spanned {
+				node: Token::Function(_),
+				..
+			})
+		)
+	}
+
+	// the elements of `[1, 2]` are separated by commas like arguments
+	fn in_vector(operator_stack: &Tokens) -> bool {
+		matches!(
+			operator_stack.last(),
+			Some(Spanned {
+				node: Token::OpenBracket,
+				..
+			})
+		)
+	}
+
+	pub fn parse(expression: &str, syntax: Syntax) -> Result<Ast, ShuntingYardError> {
+		Self::postfix_to_tree(Self::infix_to_postfix(expression, syntax)?, syntax)
+	}
+
+	pub fn infix_to_postfix(expression: &str, syntax: Syntax) -> Result<Tokens, ShuntingYardError> {
+		Self::tokens_to_postfix(Self::tokenize(expression, syntax)?)
+	}
+
+	/// Splits postfix input like `3 4 2 10 - * +` into operands and operators at the whitespace between them
+	///
+	/// Names stay identifiers whether they are variables, functions or commands. `-3` is a negative number
+	/// while `neg` negates the top of the stack.
+	pub fn tokenize_postfix(expression: &str, syntax: Syntax) -> Result<Tokens, ShuntingYardError> {
+		let mut tokens = Tokens::new();
+		let mut rest = expression;
+
+		while let Some(start) = rest.find(|item: char| !item.is_whitespace()) {
+			let length = rest[start..].find(char::is_whitespace).unwrap_or(rest.len() - start);
+			let offset = expression.len() - rest.len() + start;
+			let word = &rest[start..start + length];
+			let span = Span::new(offset, offset + length);
+			rest = &rest[start + length..];
+
+			let operator = match word {
+				"^" if syntax.caret_is_xor => Some(Operator::BitXor),
+				_ => POSTFIX_OPERATORS.iter().find(|(name, _)| *name == word).map(|(_, operator)| operator.clone()),
+			};
+			if let Some(operator) = operator {
+				tokens.push(Spanned::new(Token::Operator(operator), span));
+				continue;
+			}
+
+			let token = match Self::tokenize(word, syntax) {
+				Ok(word_tokens) => match word_tokens.into_iter().map(|token| token.node).collect::<Vec<Token>>().as_slice() {
+					[
+						operand @ (Token::Number(_)
+						| Token::Integer(..)
+						| Token::Decimal(_)
+						| Token::Quantity(..)
+						| Token::Imaginary(_)
+						| Token::Bool(_)
+						| Token::Identifier(_)),
+					] => operand.clone(),
+					[Token::Operator(Operator::Negate), Token::Number(number)] => Token::Number(-number),
+					[Token::Operator(Operator::Negate), Token::Decimal(digits)] => Token::Decimal(format!("-{digits}")),
+					[Token::Operator(Operator::Negate), Token::Imaginary(number)] => Token::Imaginary(-number),
+					[Token::Operator(Operator::Negate), Token::Quantity(number, symbol)] => {
+						Token::Quantity(-number, symbol.clone())
+					},
+					_ => return Err(ShuntingYardError::UnexpectedWord(span)),
+				},
+				// the lexer only reports these and their spans are moved from the word into the whole input
+				Err(ShuntingYardError::UnexpectedCharacter(character, at)) => {
+					return Err(ShuntingYardError::UnexpectedCharacter(character, at.shifted(offset)));
+				},
+				Err(ShuntingYardError::InvalidNumber(at)) => return Err(ShuntingYardError::InvalidNumber(at.shifted(offset))),
+				Err(ShuntingYardError::NumberTooLarge(at)) => {
+					return Err(ShuntingYardError::NumberTooLarge(at.shifted(offset)));
+				},
+				Err(error) => return Err(error),
+			};
+			tokens.push(Spanned::new(token, span));
+		}
+
+		Ok(tokens)
+	}
+
+	/// Parses `name = expression`, `name(parameters) = expression` or a plain expression
+	pub fn parse_statement(expression: &str, syntax: Syntax) -> Result<Statement, ShuntingYardError> {
+		let tokens = Self::tokenize(expression, syntax)?;
+		let equals = tokens.iter().position(|token| token.node == Token::Equals);
+
+		match tokens.as_slice() {
+			[
+				Spanned {
+					node: Token::Function(name),
+					span,
+				},
+				..,
+			] if equals.is_some() => {
+				let equals = equals.expect("BUG: checked by the guard");
+				let (parameters, body) = Self::parse_definition(&tokens[..equals], &tokens[equals..], syntax)?;
+				Ok(Statement::Definition(Spanned::new(name.clone(), *span), parameters, body))
+			},
+			[
+				Spanned {
+					node: Token::Identifier(name),
+					span,
+				},
+				equals @ Spanned {
+					node: Token::Equals, ..
+				},
+				rest @ ..,
+			] => {
+				if rest.is_empty() {
+					return Err(ShuntingYardError::MissingOperand(equals.span));
+				}
+				Ok(Statement::Assignment(
+					Spanned::new(name.clone(), *span),
+					Self::postfix_to_tree(Self::tokens_to_postfix(rest.to_vec())?, syntax)?,
+				))
+			},
+			_ => Ok(Statement::Expression(Self::postfix_to_tree(Self::tokens_to_postfix(tokens)?, syntax)?)),
+		}
+	}
+
+	/// Parses an equation like `2*x = 5` into its sides, an expression without `=` equals zero
+	pub fn parse_equation(expression: &str, syntax: Syntax) -> Result<(Ast, Ast), ShuntingYardError> {
+		let tokens = Self::tokenize(expression, syntax)?;
+		let mut equals = tokens.iter().enumerate().filter(|(_, token)| token.node == Token::Equals);
+
+		let (left, right) = match (equals.next(), equals.next()) {
+			(_, Some((_, second))) => return Err(ShuntingYardError::MisplacedAssignment(second.span)),
+			(Some((split, equals)), None) => {
+				if split == 0 || split == tokens.len() - 1 {
+					return Err(ShuntingYardError::MissingOperand(equals.span));
+				}
+				(tokens[..split].to_vec(), tokens[split + 1..].to_vec())
+			},
+			(None, None) => {
+				let end = tokens.last().map_or(0, |token| token.span.end);
+				(tokens, vec![Spanned::new(Token::Number(0.0), Span::new(end, end))])
+			},
+		};
+		Ok((
+			Self::postfix_to_tree(Self::tokens_to_postfix(left)?, syntax)?,
+			Self::postfix_to_tree(Self::tokens_to_postfix(right)?, syntax)?,
+		))
+	}
+
+	// the head is `name(a, b)` and the body starts with its `=`
+	fn parse_definition(
+		head: &[Spanned<Token>],
+		body: &[Spanned<Token>],
+		syntax: Syntax,
+	) -> Result<(Vec<Spanned<String>>, Ast), ShuntingYardError> {
+		let equals = &body[0];
+		let (tokens, close) = match head {
+			[
+				_,
+				Spanned {
+					node: Token::OpenParenthesis,
+					..
+				},
+				parameters @ ..,
+				Spanned {
+					node: Token::CloseParenthesis,
+					span: close,
+				},
+			] => (parameters, *close),
+			_ => return Err(ShuntingYardError::MisplacedAssignment(equals.span)),
+		};
+
+		let mut parameters: Vec<Spanned<String>> = Vec::new();
+		for (index, token) in tokens.iter().enumerate() {
+			match &token.node {
+				Token::Identifier(name) if index % 2 == 0 => {
+					if parameters.iter().any(|parameter| parameter.node == *name) {
+						return Err(ShuntingYardError::DuplicateParameter(name.clone(), token.span));
+					}
+					parameters.push(Spanned::new(name.clone(), token.span));
+				},
+				Token::Comma if index % 2 == 1 => {},
+				_ => return Err(ShuntingYardError::InvalidParameter(token.span)),
+			}
+		}
+		// `f(x,) = x` ends on a comma
+		if tokens.len() % 2 == 0 && !tokens.is_empty() {
+			return Err(ShuntingYardError::InvalidParameter(close));
+		}
+
+		if body.len() == 1 {
+			return Err(ShuntingYardError::MissingOperand(equals.span));
+		}
+		Ok((parameters, Self::postfix_to_tree(Self::tokens_to_postfix(body[1..].to_vec())?, syntax)?))
+	}
+
+	fn tokens_to_postfix(tokens: Tokens) -> Result<Tokens, ShuntingYardError> {
+		let mut output_stack: Tokens = Vec::new();
+		let mut operator_stack: Tokens = Vec::new();
+		// commas seen per open function call, used to count its arguments
+		let mut comma_counts: Vec<usize> = Vec::new();
+		// decides whether an operand or an operator has to come next
+		let mut previous: Option<Spanned<Token>> = None;
+
+		for token in Self::implicit_products(tokens) {
+			let expecting_operand = Self::expects_operand(previous.as_ref().map(|previous| &previous.node));
+			let span = token.span;
+			let current = token.clone();
+
+			match token.node {
+				Token::Number(_)
+				| Token::Integer(..)
+				| Token::Decimal(_)
+				| Token::Quantity(..)
+				| Token::Imaginary(_)
+				| Token::Bool(_)
+				| Token::Identifier(_) => {
+					if !expecting_operand {
+						return Err(ShuntingYardError::MissingOperator(span));
+					}
+					output_stack.push(token);
+				},
+				Token::Function(_) => {
+					if !expecting_operand {
+						return Err(ShuntingYardError::MissingOperator(span));
+					}
+					operator_stack.push(token);
+					comma_counts.push(0);
+				},
+				Token::Comma => {
+					match previous {
+						None => return Err(ShuntingYardError::CommaOutsideFunction(span)),
+						Some(Spanned {
+							node: Token::OpenParenthesis | Token::OpenBracket,
+							..
+						}) => return Err(ShuntingYardError::MissingArgument(span)),
+						_ if expecting_operand => return Err(Self::missing_operand(previous.as_ref(), span)),
+						_ => {},
+					}
+
+					while let Some(top_operator) = operator_stack.last() {
+						if matches!(top_operator.node, Token::OpenParenthesis | Token::OpenBracket) {
+							break;
+						}
+						Self::output_operator(&mut output_stack, operator_stack.pop().unwrap())?;
+					}
+
+					if !Self::in_function_call(&operator_stack) && !Self::in_vector(&operator_stack) {
+						return Err(ShuntingYardError::CommaOutsideFunction(span));
+					}
+					*comma_counts.last_mut().expect("BUG: function call without comma count") += 1;
+				},
+				Token::Call(..) | Token::Vector(_) => unreachable!("Calls and vectors are only created by the shunting yard"),
+				Token::Equals => return Err(ShuntingYardError::MisplacedAssignment(span)),
+				// it binds tighter than anything else so it goes straight to the operand before it
+				Token::Percent => {
+					if expecting_operand {
+						return Err(ShuntingYardError::MissingOperand(span));
+					}
+					output_stack.push(token);
+				},
+				node @ (Token::Operator(_) | Token::Question | Token::ImplicitTimes) => {
+					let operator = Spanned::new(node, span);
+					if expecting_operand && !Self::is_prefix(&operator.node) {
+						return Err(ShuntingYardError::MissingOperand(span));
+					}
+					// `1 ~2` has no operator between its operands since `~` only ever goes before one
+					if !expecting_operand && Self::is_prefix(&operator.node) {
+						return Err(ShuntingYardError::MissingOperator(span));
+					}
 
-		for token in Self::tokenize(expression)? {
-			match token {
-				Token::Number(_) => output_stack.push(token),
-				Token::Operator(operator) => {
-					let operator = Token::Operator(operator);
 					while let Some(top_operator) = operator_stack.last() {
-						if Self::precedence(&operator) <= Self::precedence(top_operator) {
-							output_stack.push(operator_stack.pop().unwrap());
+						let pops = if Self::is_right_associative(&operator.node) {
+							Self::precedence(&operator.node) < Self::precedence(&top_operator.node)
+						} else {
+							Self::precedence(&operator.node) <= Self::precedence(&top_operator.node)
+						};
+
+						if !Self::is_prefix(&operator.node) && pops {
+							Self::output_operator(&mut output_stack, operator_stack.pop().unwrap())?;
 						} else {
 							break;
 						}
 					}
 					operator_stack.push(operator);
 				},
+				Token::Colon => {
+					if expecting_operand {
+						return Err(Self::missing_operand(previous.as_ref(), span));
+					}
+
+					// everything since the `?` makes up the operand in the middle
+					loop {
+						match operator_stack.last() {
+							Some(Spanned {
+								node: Token::Question, ..
+							}) => break,
+							Some(Spanned {
+								node: Token::OpenParenthesis | Token::OpenBracket,
+								..
+							})
+							| None => return Err(ShuntingYardError::UnmatchedColon(span)),
+							Some(_) => Self::output_operator(&mut output_stack, operator_stack.pop().unwrap())?,
+						}
+					}
+					let question = operator_stack.pop().expect("BUG: `:` without a `?` to replace");
+					operator_stack.push(Spanned::new(Token::Operator(Operator::Conditional), question.span));
+				},
 				Token::OpenParenthesis => {
+					let after_function = matches!(
+						previous,
+						Some(Spanned {
+							node: Token::Function(_),
+							..
+						})
+					);
+					if !expecting_operand && !after_function {
+						return Err(ShuntingYardError::MissingOperator(span));
+					}
 					operator_stack.push(token);
 				},
 				Token::CloseParenthesis => {
-					while let Some(top_operator) = operator_stack.pop() {
-						if top_operator == Token::OpenParenthesis {
-							break;
-						} else {
-							output_stack.push(top_operator);
+					// `f()` is the only place where a closing parenthesis may follow without an operand
+					let empty_call = matches!(
+						previous,
+						Some(Spanned {
+							node: Token::OpenParenthesis,
+							..
+						})
+					) && Self::in_function_call(&operator_stack);
+					if previous.is_none() {
+						return Err(ShuntingYardError::UnmatchedCloseParenthesis(span));
+					}
+					if expecting_operand && !empty_call {
+						return Err(Self::missing_operand(previous.as_ref(), span));
+					}
+
+					loop {
+						match operator_stack.pop() {
+							Some(Spanned {
+								node: Token::OpenParenthesis,
+								..
+							}) => break,
+							Some(Spanned {
+								node: Token::OpenBracket,
+								..
+							})
+							| None => return Err(ShuntingYardError::UnmatchedCloseParenthesis(span)),
+							Some(top_operator) => Self::output_operator(&mut output_stack, top_operator)?,
 						}
 					}
+
+					if let Some(Spanned {
+						node: Token::Function(name),
+						span: name_span,
+					}) = operator_stack.pop_if(|top_operator| matches!(top_operator.node, Token::Function(_)))
+					{
+						let commas = comma_counts.pop().expect("BUG: function call without comma count");
+						// `f()` has no arguments while `f(a)` has one and every comma adds another
+						let arity = if empty_call { 0 } else { commas + 1 };
+						output_stack.push(Spanned::new(Token::Call(name, arity), name_span.to(span)));
+					}
+				},
+				Token::OpenBracket => {
+					if !expecting_operand {
+						return Err(ShuntingYardError::MissingOperator(span));
+					}
+					operator_stack.push(token);
+					comma_counts.push(0);
+				},
+				Token::CloseBracket => {
+					// `[]` is the empty vector
+					let empty = matches!(
+						previous,
+						Some(Spanned {
+							node: Token::OpenBracket,
+							..
+						})
+					);
+					if previous.is_none() {
+						return Err(ShuntingYardError::UnmatchedCloseBracket(span));
+					}
+					if expecting_operand && !empty {
+						return Err(Self::missing_operand(previous.as_ref(), span));
+					}
+
+					let open = loop {
+						match operator_stack.pop() {
+							Some(Spanned {
+								node: Token::OpenBracket,
+								span: open,
+							}) => break open,
+							Some(Spanned {
+								node: Token::OpenParenthesis,
+								..
+							})
+							| None => return Err(ShuntingYardError::UnmatchedCloseBracket(span)),
+							Some(top_operator) => Self::output_operator(&mut output_stack, top_operator)?,
+						}
+					};
+					let commas = comma_counts.pop().expect("BUG: vector without comma count");
+					let length = if empty { 0 } else { commas + 1 };
+					output_stack.push(Spanned::new(Token::Vector(length), open.to(span)));
 				},
 			}
+
+			previous = Some(current);
+		}
+
+		match previous {
+			None => return Err(ShuntingYardError::EmptyExpression),
+			Some(Spanned {
+				node: Token::Operator(_) | Token::Question | Token::Colon,
+				span,
+			}) => return Err(ShuntingYardError::MissingOperand(span)),
+			Some(_) => {},
 		}
 
 		while let Some(top_operator) = operator_stack.pop() {
-			output_stack.push(top_operator);
+			match top_operator.node {
+				Token::OpenParenthesis => return Err(ShuntingYardError::UnmatchedOpenParenthesis(top_operator.span)),
+				Token::OpenBracket => return Err(ShuntingYardError::UnmatchedOpenBracket(top_operator.span)),
+				_ => {},
+			}
+			Self::output_operator(&mut output_stack, top_operator)?;
 		}
 
 		Ok(output_stack)
 	}
 
-	fn tokenize(expression: &str) -> Result<Ast, ShuntingYardError> {
-		let mut tokens = Vec::new();
-		let mut items = expression.chars().peekable();
+	// a `?` that is still waiting when its operands are complete never gets its `:`, an implicit product is an
+	// ordinary one once its operands are settled
+	fn output_operator(output_stack: &mut Tokens, operator: Spanned<Token>) -> Result<(), ShuntingYardError> {
+		match operator.node {
+			Token::Question => return Err(ShuntingYardError::MissingColon(operator.span)),
+			Token::ImplicitTimes => output_stack.push(Spanned::new(Token::Operator(Operator::Times), operator.span)),
+			_ => output_stack.push(operator),
+		}
+		Ok(())
+	}
+
+	// `2x`, `3 sin(x)`, `2(a + b)` and `(a + b)(c + d)` multiply, a name right before parentheses is a call and
+	// two numbers in a row are still a missing operator
+	fn implicit_products(tokens: Tokens) -> Tokens {
+		let mut products = Tokens::with_capacity(tokens.len());
+		for token in tokens {
+			let multiplies = match products.last().map(|previous: &Spanned<Token>| &previous.node) {
+				Some(
+					Token::Number(_)
+					| Token::Integer(..)
+					| Token::Decimal(_)
+					| Token::Quantity(..)
+					| Token::Imaginary(_)
+					| Token::Identifier(_),
+				) => {
+					matches!(token.node, Token::Identifier(_) | Token::Function(_) | Token::OpenParenthesis)
+				},
+				Some(Token::CloseParenthesis) => matches!(
+					token.node,
+					Token::Number(_)
+						| Token::Integer(..)
+						| Token::Decimal(_)
+						| Token::Quantity(..)
+						| Token::Imaginary(_)
+						| Token::Identifier(_)
+						| Token::Function(_)
+						| Token::OpenParenthesis
+				),
+				_ => false,
+			};
+			if multiplies {
+				let end = products.last().expect("BUG: a product without a left operand").span.end;
+				products.push(Spanned::new(Token::ImplicitTimes, Span::new(end, end)));
+			}
+			products.push(token);
+		}
+		products
+	}
+
+	// the shunting yard already made sure every operator and call finds its operands
+	fn postfix_to_tree(postfix: Tokens, syntax: Syntax) -> Result<Ast, ShuntingYardError> {
+		let mut stack: Vec<Ast> = Vec::new();
+		// how deep each tree on the stack is, checked before a deeper one is built
+		let mut depths: Vec<usize> = Vec::new();
+		let pop = |stack: &mut Vec<Ast>| stack.pop().expect("BUG: postfix notation without enough operands");
+		// the right operand of a sum ends right before it so `a + b%` can be told from `a + b`
+		let mut after_percent = false;
+
+		for Spanned { node: token, span } in postfix {
+			let percent = token == Token::Percent;
+			let operands = match &token {
+				Token::Operator(Operator::Conditional) => 3,
+				Token::Operator(operator) if operator.is_prefix() => 1,
+				Token::Percent => 1,
+				Token::Operator(_) => 2,
+				Token::Call(_, arity) => *arity,
+				Token::Vector(length) => *length,
+				_ => 0,
+			};
+			let depth = depths.split_off(depths.len() - operands).into_iter().max().unwrap_or_default() + 1;
+			let limit = syntax.max_depth.map_or(Self::MAX_DEPTH, |limit| limit.min(Self::MAX_DEPTH));
+			if depth > limit {
+				return Err(ShuntingYardError::TooDeep(limit, span));
+			}
+			depths.push(depth);
+
+			let node = match token {
+				Token::Number(number) => Spanned::new(Expr::Number(number), span),
+				Token::Integer(integer, radix) => Spanned::new(Expr::Integer(integer, radix), span),
+				Token::Decimal(digits) => Spanned::new(Expr::Decimal(digits), span),
+				Token::Quantity(number, symbol) => Spanned::new(Expr::Quantity(number, symbol), span),
+				Token::Imaginary(number) => Spanned::new(Expr::Imaginary(number), span),
+				Token::Bool(boolean) => Spanned::new(Expr::Bool(boolean), span),
+				Token::Operator(Operator::Conditional) => {
+					let (otherwise, then, condition) = (pop(&mut stack), pop(&mut stack), pop(&mut stack));
+					let span = condition.span.to(otherwise.span);
+					Spanned::new(Expr::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)), span)
+				},
+				Token::Identifier(name) => Spanned::new(Expr::Variable(name), span),
+				Token::Operator(operator) if operator.is_prefix() => {
+					let operand = pop(&mut stack);
+					let span = span.to(operand.span);
+					Spanned::new(Expr::Unary(operator, Box::new(operand)), span)
+				},
+				Token::Percent => {
+					let (operand, hundred) = (pop(&mut stack), Spanned::new(Expr::Number(100.0), span));
+					let span = operand.span.to(span);
+					Spanned::new(Expr::Binary(Operator::Divided, Box::new(operand), Box::new(hundred)), span)
+				},
+				// like on a pocket calculator `a + b%` is `a + a*b/100`, `a ± b%` is off by that much of `a`
+				Token::Operator(operator @ (Operator::Plus | Operator::Minus | Operator::PlusMinus)) if after_percent => {
+					let (right, left) = (pop(&mut stack), pop(&mut stack));
+					let span = left.span.to(right.span);
+					let Expr::Binary(Operator::Divided, part, hundred) = right.node else {
+						unreachable!("A percentage is a division by 100")
+					};
+					let product = Spanned::new(Expr::Binary(Operator::Times, Box::new(left.clone()), part), right.span);
+					let share = Spanned::new(Expr::Binary(Operator::Divided, Box::new(product), hundred), right.span);
+					Spanned::new(Expr::Binary(operator, Box::new(left), Box::new(share)), span)
+				},
+				Token::Operator(operator) => {
+					let (right, left) = (pop(&mut stack), pop(&mut stack));
+					let span = left.span.to(right.span);
+					Spanned::new(Expr::Binary(operator, Box::new(left), Box::new(right)), span)
+				},
+				Token::Call(name, arity) => {
+					let args = stack.split_off(stack.len() - arity);
+					Spanned::new(Expr::Call(name, args), span)
+				},
+				Token::Vector(length) => Spanned::new(Expr::Vector(stack.split_off(stack.len() - length)), span),
+				Token::Function(_)
+				| Token::OpenParenthesis
+				| Token::CloseParenthesis
+				| Token::OpenBracket
+				| Token::CloseBracket
+				| Token::Comma
+				| Token::Equals
+				| Token::Question
+				| Token::Colon
+				| Token::ImplicitTimes => {
+					unreachable!("Postfix notations only contain operands, operators and calls")
+				},
+			};
+			stack.push(node);
+			after_percent = percent;
+		}
+
+		let tree = pop(&mut stack);
+		assert!(stack.is_empty(), "BUG: postfix notation with too many operands");
+		Ok(tree)
+	}
+
+	// an operand was expected where `span` is so we point at whatever left it dangling
+	fn missing_operand(previous: Option<&Spanned<Token>>, span: Span) -> ShuntingYardError {
+		match previous {
+			Some(Spanned {
+				node: Token::OpenParenthesis,
+				span: open_span,
+			}) => ShuntingYardError::EmptyParentheses(open_span.to(span)),
+			Some(Spanned { node: Token::Comma, .. }) => ShuntingYardError::MissingArgument(span),
+			Some(previous) => ShuntingYardError::MissingOperand(previous.span),
+			None => ShuntingYardError::MissingOperand(span),
+		}
+	}
+
+	// `0x`, `0o` and `0b` literals take every letter and digit after them so `0b102` is a single invalid number
+	fn radix_literal(expression: &str, start: usize) -> Option<Result<Spanned<Token>, ShuntingYardError>> {
+		let radix = match expression.get(start..start + 2)? {
+			"0x" | "0X" => Radix::Hexadecimal,
+			"0o" | "0O" => Radix::Octal,
+			"0b" | "0B" => Radix::Binary,
+			_ => return None,
+		};
+
+		let digits = &expression[start + 2..];
+		let length = digits.find(|digit: char| !digit.is_ascii_alphanumeric() && digit != '_').unwrap_or(digits.len());
+		let span = Span::new(start, start + 2 + length);
+		let digits = &digits[..length];
+		if !Self::separated(digits, u8::is_ascii_alphanumeric) {
+			return Some(Err(ShuntingYardError::InvalidNumber(span)));
+		}
+		Some(match u64::from_str_radix(&digits.replace('_', ""), radix.base()) {
+			Ok(integer) if length > 0 => Ok(Spanned::new(Token::Integer(integer, radix), span)),
+			_ => Err(ShuntingYardError::InvalidNumber(span)),
+		})
+	}
+
+	// a separator `_` has to sit between two digits
+	fn separated(part: &str, digit: fn(&u8) -> bool) -> bool {
+		let bytes = part.as_bytes();
+		bytes.iter().enumerate().all(|(index, byte)| {
+			*byte != b'_' || (index > 0 && digit(&bytes[index - 1]) && bytes.get(index + 1).is_some_and(digit))
+		})
+	}
+
+	// digits with `_` between them, an optional fraction and an exponent like the one of `6.02e23`
+	fn decimal_literal(expression: &str, start: usize) -> Result<Spanned<Token>, ShuntingYardError> {
+		let rest = &expression[start..];
+		// everything that could be part of the number is taken so `1.2.3` is a single invalid number
+		let mut length = rest.find(|item: char| !item.is_ascii_digit() && item != '.' && item != '_').unwrap_or(rest.len());
+		// an `e` that starts a name is left alone so `2eV` is two electronvolts, `1e` and `1e+` are exponents
+		// without digits rather than a product with `e`
+		if let Some(exponent) = rest[length..].strip_prefix(['e', 'E']) {
+			let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+			if digits.starts_with(|item: char| item.is_ascii_digit()) {
+				let end = digits.find(|item: char| !item.is_ascii_digit() && item != '_').unwrap_or(digits.len());
+				length = rest.len() - digits.len() + end;
+			} else if digits.len() < exponent.len()
+				|| !exponent.starts_with(|item: char| item.is_alphanumeric() || item == '_')
+			{
+				return Err(ShuntingYardError::InvalidNumber(Span::new(start, start + rest.len() - digits.len())));
+			}
+		}
+
+		let span = Span::new(start, start + length);
+		let literal = &rest[..length];
+		let (mantissa, exponent) = literal.split_once(['e', 'E']).unwrap_or((literal, "0"));
+		let valid = mantissa.matches('.').count() <= 1
+			&& mantissa.contains(|item: char| item.is_ascii_digit())
+			&& Self::separated(mantissa, u8::is_ascii_digit)
+			&& Self::separated(exponent, u8::is_ascii_digit);
+		if !valid {
+			return Err(ShuntingYardError::InvalidNumber(span));
+		}
+
+		let digits = literal.replace('_', "");
+		// an `f64` only holds integers up to 2^53 exactly and about 16 digits of anything else, the exact modes get
+		// to see the digits of longer literals
+		let token = match digits.parse::<u64>() {
+			Ok(integer) if integer > 1 << f64::MANTISSA_DIGITS => Token::Integer(integer, Radix::Decimal),
+			_ => match digits.parse::<f64>() {
+				Ok(number) if number.is_infinite() => return Err(ShuntingYardError::NumberTooLarge(span)),
+				Ok(number) if numeric::decimal_digits(&digits) != numeric::decimal_digits(&number.to_string()) => {
+					Token::Decimal(digits)
+				},
+				Ok(number) => Token::Number(number),
+				Err(_) => return Err(ShuntingYardError::InvalidNumber(span)),
+			},
+		};
+		Ok(Spanned::new(token, span))
+	}
+
+	// a unit right after a number belongs to it, `5 m^2` is five square metres rather than 25 of them
+	fn unit_symbol(expression: &str, start: usize, syntax: Syntax) -> Option<(Symbol, usize)> {
+		let rest = &expression[start..];
+		let name_start = start + rest.len() - rest.trim_start_matches(' ').len();
+		let rest = &expression[name_start..];
+		if !rest.starts_with(|item: char| item.is_ascii_alphabetic()) {
+			return None;
+		}
+		let length = rest.find(|item: char| !item.is_ascii_alphanumeric() && item != '_').unwrap_or(rest.len());
+		let name = &rest[..length];
+		units::unit(name)?;
+
+		let mut end = name_start + length;
+		// `3 min(1, 2)` is a call of the function and not a number of minutes
+		if expression[end..].trim_start_matches(' ').starts_with('(') {
+			return None;
+		}
+
+		let mut power = 1;
+		let after = &expression[end..];
+		let exponent = after.strip_prefix("**").or_else(|| after.strip_prefix('^').filter(|_| !syntax.caret_is_xor));
+		if let Some(exponent) = exponent {
+			let sign = usize::from(exponent.starts_with('-'));
+			let digits = exponent[sign..].find(|item: char| !item.is_ascii_digit()).unwrap_or(exponent.len() - sign);
+			// anything else after the caret is an operator applied to the whole quantity
+			if let Ok(parsed) = exponent[..sign + digits].parse::<i16>() {
+				power = parsed.into();
+				end += after.len() - exponent.len() + sign + digits;
+			}
+		}
+
+		Some((
+			Symbol {
+				name: name.to_string(),
+				power,
+			},
+			end,
+		))
+	}
+
+	// a `%` a binary operator follows is a percentage, anything else is the remainder it always was; a sign is written
+	// against its number so `10% - 3` takes three from a tenth while `10%-3` and `10 % -3` are both `10 % -3`
+	fn is_percent(expression: &str, start: usize) -> bool {
+		let after = expression[start + 1..].trim_start();
+		match after.chars().next() {
+			None | Some(')' | ']' | ',' | '*' | '/' | '^' | '<' | '>' | '=' | '&' | '|' | '?' | ':' | '@' | '±') => true,
+			Some('+' | '-') => after.starts_with("+/-") || after[1..].starts_with(char::is_whitespace),
+			Some('!') => after.starts_with("!="),
+			_ => false,
+		}
+	}
+
+	fn tokenize(expression: &str, syntax: Syntax) -> Result<Tokens, ShuntingYardError> {
+		let mut tokens: Tokens = Vec::new();
+		let mut items = expression.char_indices().peekable();
+
+		while let Some(&(start, item)) = items.peek() {
+			let single = Span::new(start, start + item.len_utf8());
+			if let Some(limit) = syntax.max_tokens
+				&& tokens.len() >= limit
+				&& !item.is_whitespace()
+			{
+				return Err(ShuntingYardError::TooManyTokens(limit, single));
+			}
+			let previous = tokens.last().map(|token| &token.node);
 
-		while let Some(item) = items.peek() {
 			match item {
+				// a point that doesn't start a number like `.5` is the dot product
+				'.' if !expression[start + 1..].starts_with(|next: char| next.is_ascii_digit()) => {
+					tokens.push(Spanned::new(Token::Operator(Operator::Dot), single));
+					items.next();
+				},
+				'@' => {
+					tokens.push(Spanned::new(Token::Operator(Operator::MatrixProduct), single));
+					items.next();
+				},
 				'0'..='9' | '.' => {
-					let mut number = String::new();
-					while let Some(&next_item) = items.peek() {
-						if next_item.is_ascii_digit() || next_item == '.' {
-							number.push(next_item);
-							items.next();
-						} else {
-							break;
-						}
+					if let Some(literal) = Self::radix_literal(expression, start) {
+						let literal = literal?;
+						while items.next_if(|(next_start, _)| *next_start < literal.span.end).is_some() {}
+						tokens.push(literal);
+						continue;
 					}
-					tokens.push(Token::Number(number.parse().unwrap()));
+
+					let Spanned { node: token, span } = Self::decimal_literal(expression, start)?;
+					// an `i` that isn't the start of a longer name makes the number imaginary
+					let after = &expression[span.end..];
+					let imaginary =
+						after.starts_with('i') && !after[1..].starts_with(|item: char| item.is_ascii_alphanumeric() || item == '_');
+					// imaginary numbers and quantities are made of `f64` however many digits they were written with
+					let token = match token {
+						Token::Decimal(digits) if imaginary || Self::unit_symbol(expression, span.end, syntax).is_some() => {
+							Token::Number(digits.parse().expect("BUG: decimal literal that isn't an f64"))
+						},
+						token => token,
+					};
+					let (token, span) = match (token, Self::unit_symbol(expression, span.end, syntax)) {
+						(Token::Number(number), _) if imaginary => (Token::Imaginary(number), Span::new(start, span.end + 1)),
+						(Token::Integer(integer, _), _) if imaginary => {
+							(Token::Imaginary(integer as f64), Span::new(start, span.end + 1))
+						},
+						(Token::Number(number), Some((symbol, end))) => (Token::Quantity(number, symbol), Span::new(start, end)),
+						(token, _) => (token, span),
+					};
+					while items.next_if(|(next_start, _)| *next_start < span.end).is_some() {}
+					tokens.push(Spanned::new(token, span));
+				},
+				'+' if !Self::expects_operand(previous) && expression[start..].starts_with("+/-") => {
+					tokens.push(Spanned::new(Token::Operator(Operator::PlusMinus), Span::new(start, start + 3)));
+					while items.next_if(|(next_start, _)| *next_start < start + 3).is_some() {}
+				},
+				'±' => {
+					tokens.push(Spanned::new(Token::Operator(Operator::PlusMinus), single));
+					items.next();
 				},
 				'+' => {
-					tokens.push(Token::Operator(Operator::Plus));
+					// a unary plus changes nothing so it doesn't need a token
+					if !Self::expects_operand(previous) {
+						tokens.push(Spanned::new(Token::Operator(Operator::Plus), single));
+					}
 					items.next();
 				},
 				'-' => {
-					tokens.push(Token::Operator(Operator::Minus));
+					if Self::expects_operand(previous) {
+						tokens.push(Spanned::new(Token::Operator(Operator::Negate), single));
+					} else {
+						tokens.push(Spanned::new(Token::Operator(Operator::Minus), single));
+					}
 					items.next();
 				},
 				'*' => {
-					tokens.push(Token::Operator(Operator::Times));
 					items.next();
+					// `**` is an alias for `^`
+					if items.next_if(|(_, next_item)| *next_item == '*').is_some() {
+						tokens.push(Spanned::new(Token::Operator(Operator::Power), Span::new(start, start + 2)));
+					} else {
+						tokens.push(Spanned::new(Token::Operator(Operator::Times), single));
+					}
 				},
 				'/' => {
-					tokens.push(Token::Operator(Operator::Divided));
+					tokens.push(Spanned::new(Token::Operator(Operator::Divided), single));
+					items.next();
+				},
+				'%' => {
+					let token = if Self::is_percent(expression, start) {
+						Token::Percent
+					} else {
+						Token::Operator(Operator::Modulo)
+					};
+					tokens.push(Spanned::new(token, single));
+					items.next();
+				},
+				'^' => {
+					let operator = if syntax.caret_is_xor {
+						Operator::BitXor
+					} else {
+						Operator::Power
+					};
+					tokens.push(Spanned::new(Token::Operator(operator), single));
+					items.next();
+				},
+				'~' => {
+					tokens.push(Spanned::new(Token::Operator(Operator::BitNot), single));
+					items.next();
+				},
+				'&' | '|' | '<' | '>' | '=' | '!' => {
+					items.next();
+					let second = items.peek().map(|(_, next_item)| *next_item);
+					// the two character operators win over the ones made of their first character
+					let (token, length) = match (item, second) {
+						('&', Some('&')) => (Token::Operator(Operator::And), 2),
+						('&', _) => (Token::Operator(Operator::BitAnd), 1),
+						('|', Some('|')) => (Token::Operator(Operator::Or), 2),
+						('|', _) => (Token::Operator(Operator::BitOr), 1),
+						('<', Some('<')) => (Token::Operator(Operator::ShiftLeft), 2),
+						('<', Some('=')) => (Token::Operator(Operator::LessEqual), 2),
+						('<', _) => (Token::Operator(Operator::Less), 1),
+						('>', Some('>')) => (Token::Operator(Operator::ShiftRight), 2),
+						('>', Some('=')) => (Token::Operator(Operator::GreaterEqual), 2),
+						('>', _) => (Token::Operator(Operator::Greater), 1),
+						('=', Some('=')) => (Token::Operator(Operator::Equal), 2),
+						('=', _) => (Token::Equals, 1),
+						('!', Some('=')) => (Token::Operator(Operator::NotEqual), 2),
+						(_, _) => (Token::Operator(Operator::Not), 1),
+					};
+					if length == 2 {
+						items.next();
+					}
+					tokens.push(Spanned::new(token, Span::new(start, start + length)));
+				},
+				'?' => {
+					tokens.push(Spanned::new(Token::Question, single));
+					items.next();
+				},
+				':' => {
+					tokens.push(Spanned::new(Token::Colon, single));
+					items.next();
+				},
+				'a'..='z' | 'A'..='Z' | '_' => {
+					let mut name = String::new();
+					while let Some((_, next_item)) =
+						items.next_if(|(_, next_item)| next_item.is_ascii_alphanumeric() || *next_item == '_')
+					{
+						name.push(next_item);
+					}
+					let span = Span::new(start, start + name.len());
+
+					while items.next_if(|(_, next_item)| *next_item == ' ').is_some() {}
+					let token = match (name.as_str(), items.peek()) {
+						// `x in (km/h)` converts rather than calling a function
+						("in" | "to", _) => Token::Operator(Operator::Convert),
+						(_, Some((_, '('))) => Token::Function(name),
+						("true", _) => Token::Bool(true),
+						("false", _) => Token::Bool(false),
+						("inf", _) if syntax.non_finite => Token::Number(f64::INFINITY),
+						("nan", _) if syntax.non_finite => Token::Number(f64::NAN),
+						_ => Token::Identifier(name),
+					};
+					tokens.push(Spanned::new(token, span));
+				},
+				',' => {
+					tokens.push(Spanned::new(Token::Comma, single));
 					items.next();
 				},
 				'(' => {
-					tokens.push(Token::OpenParenthesis);
+					tokens.push(Spanned::new(Token::OpenParenthesis, single));
 					items.next();
 				},
 				')' => {
-					tokens.push(Token::CloseParenthesis);
+					tokens.push(Spanned::new(Token::CloseParenthesis, single));
+					items.next();
+				},
+				'[' => {
+					tokens.push(Spanned::new(Token::OpenBracket, single));
+					items.next();
+				},
+				']' => {
+					tokens.push(Spanned::new(Token::CloseBracket, single));
 					items.next();
 				},
 				' ' => {
 					// skipping whitespace
 					items.next();
 				},
-				_ => return Err(ShuntingYardError::ItemNotRecognized),
+				_ => return Err(ShuntingYardError::UnexpectedCharacter(item, single)),
 			}
 		}
 
@@ -153,19 +1308,42 @@ impl ShuntingYard {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::{notation::Notation, value::Value};
+
+	// spans are covered by their own tests so the others can compare plain tokens
+	fn strip(tokens: Tokens) -> Vec<Token> {
+		tokens.into_iter().map(|token| token.node).collect()
+	}
+
+	fn tokens(expression: &str) -> Result<Vec<Token>, ShuntingYardError> {
+		ShuntingYard::tokenize(expression, Syntax::default()).map(strip)
+	}
+
+	fn postfix(expression: &str) -> Result<Vec<Token>, ShuntingYardError> {
+		ShuntingYard::infix_to_postfix(expression, Syntax::default()).map(strip)
+	}
+
+	fn statement(expression: &str) -> Result<(Option<String>, String), ShuntingYardError> {
+		ShuntingYard::parse_statement(expression, Syntax::default()).map(|statement| match statement {
+			Statement::Assignment(name, ast) => (Some(name.node), Notation::Sexpr.print(&ast)),
+			Statement::Definition(name, parameters, ast) => {
+				let parameters = parameters.into_iter().map(|parameter| parameter.node).collect::<Vec<String>>();
+				(Some(format!("{}({})", name.node, parameters.join(", "))), Notation::Sexpr.print(&ast))
+			},
+			Statement::Expression(ast) => (None, Notation::Sexpr.print(&ast)),
+		})
+	}
+
+	fn tree(expression: &str) -> Result<String, ShuntingYardError> {
+		ShuntingYard::parse(expression, Syntax::default()).map(|ast| Notation::Sexpr.print(&ast))
+	}
 
 	#[test]
 	fn tokenize_test() {
+		assert_eq!(tokens("1 +2"), Ok(vec![Token::Number(1.0), Token::Operator(Operator::Plus), Token::Number(2.0)]));
+		assert_eq!(tokens("12 +2"), Ok(vec![Token::Number(12.0), Token::Operator(Operator::Plus), Token::Number(2.0)]));
 		assert_eq!(
-			ShuntingYard::tokenize("1 +2"),
-			Ok(vec![Token::Number(1.0), Token::Operator(Operator::Plus), Token::Number(2.0)])
-		);
-		assert_eq!(
-			ShuntingYard::tokenize("12 +2"),
-			Ok(vec![Token::Number(12.0), Token::Operator(Operator::Plus), Token::Number(2.0)])
-		);
-		assert_eq!(
-			ShuntingYard::tokenize("999 / 666"),
+			tokens("999 / 666"),
 			Ok(vec![
 				Token::Number(999.0),
 				Token::Operator(Operator::Divided),
@@ -173,7 +1351,7 @@ mod tests {
 			])
 		);
 		assert_eq!(
-			ShuntingYard::tokenize("9+(2*4)-80/0"),
+			tokens("9+(2*4)-80/0"),
 			Ok(vec![
 				Token::Number(9.0),
 				Token::Operator(Operator::Plus),
@@ -191,17 +1369,127 @@ mod tests {
 	}
 
 	#[test]
-	fn infix_to_postfix_test() {
+	fn tokenize_literals_test() {
+		assert_eq!(tokens("6.02e23"), Ok(vec![Token::Number(6.02e23)]));
+		assert_eq!(
+			tokens("1E-3 + 2.5e+2"),
+			Ok(vec![
+				Token::Number(1e-3),
+				Token::Operator(Operator::Plus),
+				Token::Number(250.0)
+			])
+		);
+		assert_eq!(tokens("1_000_000"), Ok(vec![Token::Number(1e6)]));
+		assert_eq!(tokens("0.000_1e1_0"), Ok(vec![Token::Number(1e6)]));
+		assert_eq!(tokens(".5"), Ok(vec![Token::Number(0.5)]));
+		assert_eq!(tokens("1e"), Err(ShuntingYardError::InvalidNumber(Span::new(0, 2))));
+		assert_eq!(tokens("1.5e- 3"), Err(ShuntingYardError::InvalidNumber(Span::new(0, 5))));
+		assert_eq!(tokens("(2e)"), Err(ShuntingYardError::InvalidNumber(Span::new(1, 3))));
+		assert_eq!(tokens("2 e"), Ok(vec![Token::Number(2.0), Token::Identifier(String::from("e"))]));
+		assert_eq!(tokens("9_007_199_254_740_993"), Ok(vec![Token::Integer(9007199254740993, Radix::Decimal)]));
+		// the digits an `f64` would lose are kept for the exact modes
 		assert_eq!(
-			ShuntingYard::infix_to_postfix("1 +2"),
-			Ok(vec![Token::Number(1.0), Token::Number(2.0), Token::Operator(Operator::Plus)])
+			tokens("123_456_789_012_345_678_901 + 0.1234567890123456789"),
+			Ok(vec![
+				Token::Decimal(String::from("123456789012345678901")),
+				Token::Operator(Operator::Plus),
+				Token::Decimal(String::from("0.1234567890123456789"))
+			])
 		);
+		assert_eq!(tokens("1.5e-400"), Ok(vec![Token::Decimal(String::from("1.5e-400"))]));
 		assert_eq!(
-			ShuntingYard::infix_to_postfix("1+  2"),
-			Ok(vec![Token::Number(1.0), Token::Number(2.0), Token::Operator(Operator::Plus)])
+			tokens("2eV"),
+			Ok(vec![Token::Quantity(
+				2.0,
+				Symbol {
+					name: String::from("eV"),
+					power: 1,
+				}
+			)])
 		);
+
+		// an `i` right after a number makes it imaginary unless it starts a name
 		assert_eq!(
-			ShuntingYard::infix_to_postfix(" 3 + 4 *  ( 2 - 10) "),
+			tokens("4i - 2.5e3i"),
+			Ok(vec![
+				Token::Imaginary(4.0),
+				Token::Operator(Operator::Minus),
+				Token::Imaginary(2500.0)
+			])
+		);
+		assert_eq!(tokens("2ix"), Ok(vec![Token::Number(2.0), Token::Identifier(String::from("ix"))]));
+
+		assert_eq!(tokens("inf"), Ok(vec![Token::Identifier(String::from("inf"))]));
+		let non_finite = |expression| {
+			let syntax = Syntax {
+				non_finite: true,
+				..Syntax::default()
+			};
+			ShuntingYard::tokenize(expression, syntax).map(strip)
+		};
+		assert_eq!(non_finite("-inf"), Ok(vec![Token::Operator(Operator::Negate), Token::Number(f64::INFINITY)]));
+		assert!(matches!(non_finite("nan").as_deref(), Ok([Token::Number(number)]) if number.is_nan()));
+
+		assert_eq!(tokens("1.2.3"), Err(ShuntingYardError::InvalidNumber(Span::new(0, 5))));
+		assert_eq!(tokens("2 * 1_"), Err(ShuntingYardError::InvalidNumber(Span::new(4, 6))));
+		assert_eq!(tokens("1__0"), Err(ShuntingYardError::InvalidNumber(Span::new(0, 4))));
+		assert_eq!(tokens("1._5"), Err(ShuntingYardError::InvalidNumber(Span::new(0, 4))));
+		assert_eq!(tokens("1e400"), Err(ShuntingYardError::NumberTooLarge(Span::new(0, 5))));
+		// a point without digits is the dot product
+		assert_eq!(tokens("."), Ok(vec![Token::Operator(Operator::Dot)]));
+	}
+
+	#[test]
+	fn tokenize_unary_test() {
+		assert_eq!(
+			tokens("-3 + 4"),
+			Ok(vec![
+				Token::Operator(Operator::Negate),
+				Token::Number(3.0),
+				Token::Operator(Operator::Plus),
+				Token::Number(4.0)
+			])
+		);
+		assert_eq!(
+			tokens("2 * -5"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Operator(Operator::Times),
+				Token::Operator(Operator::Negate),
+				Token::Number(5.0)
+			])
+		);
+		assert_eq!(
+			tokens("3--2"),
+			Ok(vec![
+				Token::Number(3.0),
+				Token::Operator(Operator::Minus),
+				Token::Operator(Operator::Negate),
+				Token::Number(2.0)
+			])
+		);
+		assert_eq!(
+			tokens("(2)-(-3)"),
+			Ok(vec![
+				Token::OpenParenthesis,
+				Token::Number(2.0),
+				Token::CloseParenthesis,
+				Token::Operator(Operator::Minus),
+				Token::OpenParenthesis,
+				Token::Operator(Operator::Negate),
+				Token::Number(3.0),
+				Token::CloseParenthesis
+			])
+		);
+		assert_eq!(tokens("+3 + +4"), Ok(vec![Token::Number(3.0), Token::Operator(Operator::Plus), Token::Number(4.0)]));
+	}
+
+	#[test]
+	fn infix_to_postfix_test() {
+		assert_eq!(postfix("1 +2"), Ok(vec![Token::Number(1.0), Token::Number(2.0), Token::Operator(Operator::Plus)]));
+		assert_eq!(postfix("1+  2"), Ok(vec![Token::Number(1.0), Token::Number(2.0), Token::Operator(Operator::Plus)]));
+		assert_eq!(
+			postfix(" 3 + 4 *  ( 2 - 10) "),
 			Ok(vec![
 				Token::Number(3.0),
 				Token::Number(4.0),
@@ -213,4 +1501,803 @@ mod tests {
 			])
 		);
 	}
+
+	#[test]
+	fn infix_to_postfix_implicit_test() {
+		assert_eq!(
+			postfix("2(3 + 4)"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Number(3.0),
+				Token::Number(4.0),
+				Token::Operator(Operator::Plus),
+				Token::Operator(Operator::Times),
+			])
+		);
+		assert_eq!(
+			postfix("1/2x"),
+			Ok(vec![
+				Token::Number(1.0),
+				Token::Number(2.0),
+				Token::Identifier(String::from("x")),
+				Token::Operator(Operator::Times),
+				Token::Operator(Operator::Divided),
+			])
+		);
+		assert_eq!(
+			postfix("200 + 15%"),
+			Ok(vec![
+				Token::Number(200.0),
+				Token::Number(15.0),
+				Token::Percent,
+				Token::Operator(Operator::Plus)
+			])
+		);
+
+		// a product without `*` binds tighter than division and looser than a power
+		assert_eq!(tree("1/2x"), Ok(String::from("(/ 1 (* 2 x))")));
+		assert_eq!(tree("1/2 x"), Ok(String::from("(/ 1 (* 2 x))")));
+		assert_eq!(tree("1/2*x"), Ok(String::from("(* (/ 1 2) x)")));
+		assert_eq!(tree("2x^2"), Ok(String::from("(* 2 (^ x 2))")));
+		assert_eq!(tree("-2x"), Ok(String::from("(* (- 2) x)")));
+		assert_eq!(tree("3pi r"), Ok(String::from("(* (* 3 pi) r)")));
+		assert_eq!(tree("2 sin(x)"), Ok(String::from("(* 2 (sin x))")));
+		assert_eq!(tree("(1 + 2)(3 + 4)"), Ok(String::from("(* (+ 1 2) (+ 3 4))")));
+		assert_eq!(tree("(1 + 2)3"), Ok(String::from("(* (+ 1 2) 3)")));
+		assert_eq!(tree("x^2(y)"), Ok(String::from("(* (^ x 2) y)")));
+		// a name right before parentheses is a call rather than a product
+		assert_eq!(tree("x(2)"), Ok(String::from("(x 2)")));
+		assert_eq!(tree("x (2)"), Ok(String::from("(x 2)")));
+
+		// a `%` that nothing follows is a percentage, a sum with one grows or shrinks its left side
+		assert_eq!(tree("200 + 15%"), Ok(String::from("(+ 200 (/ (* 200 15) 100))")));
+		assert_eq!(tree("200 - 15%"), Ok(String::from("(- 200 (/ (* 200 15) 100))")));
+		assert_eq!(tree("15% * 200"), Ok(String::from("(* (/ 15 100) 200)")));
+		assert_eq!(tree("200 * 15%"), Ok(String::from("(* 200 (/ 15 100))")));
+		assert_eq!(tree("2^50%"), Ok(String::from("(^ 2 (/ 50 100))")));
+		assert_eq!(tree("10% - 3"), Ok(String::from("(- (/ 10 100) 3)")));
+		assert_eq!(
+			tree("100 + 10% + 10%"),
+			Ok(String::from("(+ (+ 100 (/ (* 100 10) 100)) (/ (* (+ 100 (/ (* 100 10) 100)) 10) 100))"))
+		);
+		assert_eq!(tree("10% +/- 1"), Ok(String::from("(± (/ 10 100) 1)")));
+		// a `%` an operand or a sign against its number follows is a modulo however it is spaced
+		assert_eq!(tree("10 % 3"), Ok(String::from("(% 10 3)")));
+		assert_eq!(tree("10 % -3"), Ok(String::from("(% 10 (- 3))")));
+		assert_eq!(tree("10%-3"), Ok(String::from("(% 10 (- 3))")));
+		assert_eq!(tree("10 % (3)"), Ok(String::from("(% 10 3)")));
+	}
+
+	#[test]
+	fn tokenize_power_modulo_test() {
+		assert_eq!(
+			tokens("2^3 ** 4 % 5*6"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Operator(Operator::Power),
+				Token::Number(3.0),
+				Token::Operator(Operator::Power),
+				Token::Number(4.0),
+				Token::Operator(Operator::Modulo),
+				Token::Number(5.0),
+				Token::Operator(Operator::Times),
+				Token::Number(6.0)
+			])
+		);
+	}
+
+	#[test]
+	fn infix_to_postfix_associativity_test() {
+		assert_eq!(
+			postfix("2^3^2"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Number(3.0),
+				Token::Number(2.0),
+				Token::Operator(Operator::Power),
+				Token::Operator(Operator::Power),
+			])
+		);
+		assert_eq!(
+			postfix("8/4/2"),
+			Ok(vec![
+				Token::Number(8.0),
+				Token::Number(4.0),
+				Token::Operator(Operator::Divided),
+				Token::Number(2.0),
+				Token::Operator(Operator::Divided),
+			])
+		);
+		assert_eq!(
+			postfix("7 % 4 * 2"),
+			Ok(vec![
+				Token::Number(7.0),
+				Token::Number(4.0),
+				Token::Operator(Operator::Modulo),
+				Token::Number(2.0),
+				Token::Operator(Operator::Times),
+			])
+		);
+		assert_eq!(
+			postfix("-2^2"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Number(2.0),
+				Token::Operator(Operator::Power),
+				Token::Operator(Operator::Negate),
+			])
+		);
+		assert_eq!(
+			postfix("2*3^2"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Number(3.0),
+				Token::Number(2.0),
+				Token::Operator(Operator::Power),
+				Token::Operator(Operator::Times),
+			])
+		);
+	}
+
+	#[test]
+	fn infix_to_postfix_power_modulo_calc_test() {
+		use crate::{
+			env::Environment,
+			numeric::FloatMode,
+			postfix::{Postfix, PostfixError},
+		};
+
+		let calc = |expression| {
+			Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &Environment::new(), &FloatMode)
+		};
+
+		assert_eq!(calc("2^3^2"), Ok(Value::Number(512.0)));
+		assert_eq!(calc("2**3**2"), Ok(Value::Number(512.0)));
+		assert_eq!(calc("(2^3)^2"), Ok(Value::Number(64.0)));
+		assert_eq!(calc("-2^2"), Ok(Value::Number(-4.0)));
+		assert_eq!(calc("(-2)^2"), Ok(Value::Number(4.0)));
+		assert_eq!(calc("2^-1"), Ok(Value::Number(0.5)));
+		assert_eq!(calc("2^-1^2"), Ok(Value::Number(0.5)));
+		assert_eq!(calc("2 * 3^2"), Ok(Value::Number(18.0)));
+		assert_eq!(calc("4^0.5"), Ok(Value::Number(2.0)));
+		assert_eq!(calc("10 % 4"), Ok(Value::Number(2.0)));
+		assert_eq!(calc("-7 % 3"), Ok(Value::Number(-1.0)));
+		assert_eq!(calc("7.5 % 2"), Ok(Value::Number(1.5)));
+		assert_eq!(calc("1 + 10 % 4 * 3"), Ok(Value::Number(7.0)));
+		assert_eq!(calc("5 % 0"), Err(PostfixError::ModuloByZero(Span::new(4, 5))));
+		assert_eq!(calc("0^-1"), Err(PostfixError::DivisionByZero(Span::new(0, 4))));
+		assert_eq!(calc("0^0"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("40 + 50%"), Ok(Value::Number(60.0)));
+		assert_eq!(calc("80 - 25%"), Ok(Value::Number(60.0)));
+		assert_eq!(calc("50% * 8"), Ok(Value::Number(4.0)));
+		assert_eq!(calc("200 + 15%"), Ok(Value::Number(230.0)));
+		assert_eq!(calc("7%-3"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("50% + 5"), Ok(Value::Number(5.5)));
+		assert_eq!(calc("100 * 10% + 1"), Ok(Value::Number(11.0)));
+		assert_eq!(calc("100 + 10% + 10%"), Ok(Value::Number(121.0)));
+		assert_eq!(calc("1/2(2 + 2)"), Ok(Value::Number(0.125)));
+	}
+
+	#[test]
+	fn tokenize_functions_test() {
+		assert_eq!(
+			tokens("max(1, -pi) + sqrt (2)"),
+			Ok(vec![
+				Token::Function(String::from("max")),
+				Token::OpenParenthesis,
+				Token::Number(1.0),
+				Token::Comma,
+				Token::Operator(Operator::Negate),
+				Token::Identifier(String::from("pi")),
+				Token::CloseParenthesis,
+				Token::Operator(Operator::Plus),
+				Token::Function(String::from("sqrt")),
+				Token::OpenParenthesis,
+				Token::Number(2.0),
+				Token::CloseParenthesis
+			])
+		);
+		assert_eq!(
+			tokens("log_2 - e"),
+			Ok(vec![
+				Token::Identifier(String::from("log_2")),
+				Token::Operator(Operator::Minus),
+				Token::Identifier(String::from("e"))
+			])
+		);
+	}
+
+	#[test]
+	fn infix_to_postfix_functions_test() {
+		assert_eq!(
+			postfix("sqrt(2) * sin(pi/4)"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Call(String::from("sqrt"), 1),
+				Token::Identifier(String::from("pi")),
+				Token::Number(4.0),
+				Token::Operator(Operator::Divided),
+				Token::Call(String::from("sin"), 1),
+				Token::Operator(Operator::Times),
+			])
+		);
+		assert_eq!(
+			postfix("max(1, 2 + 3, min(4, 5))"),
+			Ok(vec![
+				Token::Number(1.0),
+				Token::Number(2.0),
+				Token::Number(3.0),
+				Token::Operator(Operator::Plus),
+				Token::Number(4.0),
+				Token::Number(5.0),
+				Token::Call(String::from("min"), 2),
+				Token::Call(String::from("max"), 3),
+			])
+		);
+		assert_eq!(postfix("f()"), Ok(vec![Token::Call(String::from("f"), 0)]));
+		assert_eq!(
+			postfix("f((1), (2))"),
+			Ok(vec![
+				Token::Number(1.0),
+				Token::Number(2.0),
+				Token::Call(String::from("f"), 2)
+			])
+		);
+		assert_eq!(postfix("1, 2"), Err(ShuntingYardError::CommaOutsideFunction(Span::new(1, 2))));
+		assert_eq!(postfix("f((1, 2))"), Err(ShuntingYardError::CommaOutsideFunction(Span::new(4, 5))));
+	}
+
+	#[test]
+	fn infix_to_postfix_functions_calc_test() {
+		use crate::{
+			env::Environment,
+			functions::Arity,
+			numeric::FloatMode,
+			postfix::{Postfix, PostfixError},
+		};
+
+		let calc = |expression| {
+			Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &Environment::new(), &FloatMode)
+		};
+
+		assert_eq!(calc("sqrt(2) * sin(pi/4) + max(1, 2, 3)"), Ok(Value::Number(4.0)));
+		assert_eq!(calc("2 * pi - tau"), Ok(Value::Number(0.0)));
+		assert_eq!(calc("ln(e)"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("-abs(-3)^2"), Ok(Value::Number(-9.0)));
+		assert_eq!(calc("hypot(3, 4) + min(2, -1)"), Ok(Value::Number(4.0)));
+		assert_eq!(calc("log(100) + log(8, 2)"), Ok(Value::Number(5.0)));
+		assert_eq!(calc("floor(2.5) + ceil(2.5) + round(2.5)"), Ok(Value::Number(8.0)));
+		assert_eq!(calc("exp(0)"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("foo(1)"), Err(PostfixError::UnknownFunction(String::from("foo"), Span::new(0, 6))));
+		assert_eq!(calc("2 * phi"), Err(PostfixError::UnknownIdentifier(String::from("phi"), Span::new(4, 7))));
+		assert_eq!(
+			calc("sqrt(1, 2)"),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("sqrt"),
+				expected: Arity::Exact(1),
+				found: 2,
+				span: Span::new(0, 10),
+			})
+		);
+		assert_eq!(
+			calc("min()"),
+			Err(PostfixError::ArityMismatch {
+				name: String::from("min"),
+				expected: Arity::AtLeast(1),
+				found: 0,
+				span: Span::new(0, 5),
+			})
+		);
+	}
+
+	#[test]
+	fn parse_statement_test() {
+		assert_eq!(statement("x = 3 * -4"), Ok((Some(String::from("x")), String::from("(* 3 (- 4))"))));
+		assert_eq!(statement("x * 2"), Ok((None, String::from("(* x 2)"))));
+		assert_eq!(statement("x = y = 2"), Err(ShuntingYardError::MisplacedAssignment(Span::new(6, 7))));
+		assert_eq!(statement("2 = 2"), Err(ShuntingYardError::MisplacedAssignment(Span::new(2, 3))));
+		assert_eq!(statement("x ="), Err(ShuntingYardError::MissingOperand(Span::new(2, 3))));
+		assert_eq!(postfix("x = 2"), Err(ShuntingYardError::MisplacedAssignment(Span::new(2, 3))));
+	}
+
+	#[test]
+	fn parse_equation_test() {
+		let equation = |expression| {
+			ShuntingYard::parse_equation(expression, Syntax::default())
+				.map(|(left, right)| (Notation::Sexpr.print(&left), Notation::Sexpr.print(&right)))
+		};
+
+		assert_eq!(equation("2*x^2 - 3*x = 5"), Ok((String::from("(- (* 2 (^ x 2)) (* 3 x))"), String::from("5"))));
+		assert_eq!(equation("sin(x)"), Ok((String::from("(sin x)"), String::from("0"))));
+		assert_eq!(equation("x = y = 2"), Err(ShuntingYardError::MisplacedAssignment(Span::new(6, 7))));
+		assert_eq!(equation("= 2"), Err(ShuntingYardError::MissingOperand(Span::new(0, 1))));
+		assert_eq!(equation("x ="), Err(ShuntingYardError::MissingOperand(Span::new(2, 3))));
+		assert_eq!(equation("x + = 1"), Err(ShuntingYardError::MissingOperand(Span::new(2, 3))));
+	}
+
+	#[test]
+	fn parse_definition_test() {
+		assert_eq!(statement("f(x, y) = x^2 + y"), Ok((Some(String::from("f(x, y)")), String::from("(+ (^ x 2) y)"))));
+		assert_eq!(statement("one() = 1"), Ok((Some(String::from("one()")), String::from("1"))));
+		assert_eq!(statement("f(x) == 2"), Ok((None, String::from("(== (f x) 2)"))));
+		assert_eq!(
+			ShuntingYard::parse_statement("area(r) = pi*r^2", Syntax::default()).map(|statement| match statement {
+				Statement::Definition(name, parameters, _) => (name.span, parameters[0].span),
+				_ => unreachable!(),
+			}),
+			Ok((Span::new(0, 4), Span::new(5, 6)))
+		);
+
+		assert_eq!(statement("f(x) ="), Err(ShuntingYardError::MissingOperand(Span::new(5, 6))));
+		assert_eq!(statement("f(1) = 2"), Err(ShuntingYardError::InvalidParameter(Span::new(2, 3))));
+		assert_eq!(statement("f(x y) = 2"), Err(ShuntingYardError::InvalidParameter(Span::new(4, 5))));
+		assert_eq!(statement("f(x,) = 2"), Err(ShuntingYardError::InvalidParameter(Span::new(4, 5))));
+		assert_eq!(statement("f(, x) = 2"), Err(ShuntingYardError::InvalidParameter(Span::new(2, 3))));
+		assert_eq!(
+			statement("f(x, x) = 2"),
+			Err(ShuntingYardError::DuplicateParameter(String::from("x"), Span::new(5, 6)))
+		);
+		assert_eq!(statement("f(x) + 1 = 2"), Err(ShuntingYardError::MisplacedAssignment(Span::new(9, 10))));
+		assert_eq!(statement("f(x) = y = 2"), Err(ShuntingYardError::MisplacedAssignment(Span::new(9, 10))));
+	}
+
+	#[test]
+	fn infix_to_postfix_unary_test() {
+		assert_eq!(
+			postfix("-3 + 4"),
+			Ok(vec![
+				Token::Number(3.0),
+				Token::Operator(Operator::Negate),
+				Token::Number(4.0),
+				Token::Operator(Operator::Plus),
+			])
+		);
+		assert_eq!(
+			postfix("2 * -5"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Number(5.0),
+				Token::Operator(Operator::Negate),
+				Token::Operator(Operator::Times),
+			])
+		);
+		assert_eq!(
+			postfix("--3"),
+			Ok(vec![
+				Token::Number(3.0),
+				Token::Operator(Operator::Negate),
+				Token::Operator(Operator::Negate),
+			])
+		);
+		assert_eq!(
+			postfix("-(2+3)"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Number(3.0),
+				Token::Operator(Operator::Plus),
+				Token::Operator(Operator::Negate),
+			])
+		);
+		assert_eq!(
+			postfix("-2 * 3"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Operator(Operator::Negate),
+				Token::Number(3.0),
+				Token::Operator(Operator::Times),
+			])
+		);
+	}
+
+	#[test]
+	fn infix_to_postfix_unary_calc_test() {
+		use crate::{env::Environment, numeric::FloatMode, postfix::Postfix};
+
+		let calc = |expression| {
+			Postfix::calc(&ShuntingYard::parse(expression, Syntax::default()).unwrap(), &Environment::new(), &FloatMode)
+		};
+
+		assert_eq!(calc("-3 + 4"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("2 * -5"), Ok(Value::Number(-10.0)));
+		assert_eq!(calc("--3"), Ok(Value::Number(3.0)));
+		assert_eq!(calc("---3"), Ok(Value::Number(-3.0)));
+		assert_eq!(calc("-(2+3)"), Ok(Value::Number(-5.0)));
+		assert_eq!(calc("3--2"), Ok(Value::Number(5.0)));
+		assert_eq!(calc("3 - -2"), Ok(Value::Number(5.0)));
+		assert_eq!(calc("3-+2"), Ok(Value::Number(1.0)));
+		assert_eq!(calc("-3 - 2"), Ok(Value::Number(-5.0)));
+		assert_eq!(calc("-3 * -3"), Ok(Value::Number(9.0)));
+		assert_eq!(calc("-6 / -(1 + 2)"), Ok(Value::Number(2.0)));
+		assert_eq!(calc("+3"), Ok(Value::Number(3.0)));
+		assert_eq!(calc("-+-3"), Ok(Value::Number(3.0)));
+		assert_eq!(calc("1 - (-(-1))"), Ok(Value::Number(0.0)));
+		assert_eq!(postfix("-"), Err(ShuntingYardError::MissingOperand(Span::new(0, 1))));
+		assert_eq!(postfix("3 * -"), Err(ShuntingYardError::MissingOperand(Span::new(4, 5))));
+	}
+
+	#[test]
+	fn tokenize_bitwise_test() {
+		assert_eq!(
+			tokens("0xFF & 0b1010 << 0o17"),
+			Ok(vec![
+				Token::Integer(255, Radix::Hexadecimal),
+				Token::Operator(Operator::BitAnd),
+				Token::Integer(10, Radix::Binary),
+				Token::Operator(Operator::ShiftLeft),
+				Token::Integer(15, Radix::Octal),
+			])
+		);
+		assert_eq!(
+			tokens("~a | b >> 1"),
+			Ok(vec![
+				Token::Operator(Operator::BitNot),
+				Token::Identifier(String::from("a")),
+				Token::Operator(Operator::BitOr),
+				Token::Identifier(String::from("b")),
+				Token::Operator(Operator::ShiftRight),
+				Token::Number(1.0),
+			])
+		);
+		// integers an `f64` would round keep all their digits
+		assert_eq!(tokens("9007199254740992"), Ok(vec![Token::Number(9007199254740992.0)]));
+		assert_eq!(tokens("9223372036854775807"), Ok(vec![Token::Integer(i64::MAX as u64, Radix::Decimal)]));
+
+		let caret = |syntax| ShuntingYard::tokenize("2 ^ 3", syntax).map(strip).unwrap()[1].clone();
+		assert_eq!(caret(Syntax::default()), Token::Operator(Operator::Power));
+		assert_eq!(
+			caret(Syntax {
+				caret_is_xor: true,
+				..Syntax::default()
+			}),
+			Token::Operator(Operator::BitXor)
+		);
+	}
+
+	#[test]
+	fn parse_bitwise_test() {
+		let tree = |expression| {
+			ShuntingYard::parse(
+				expression,
+				Syntax {
+					caret_is_xor: true,
+					..Syntax::default()
+				},
+			)
+			.map(|ast| Notation::Sexpr.print(&ast))
+		};
+
+		assert_eq!(tree("0xFF & 0b1010 << 2"), Ok(String::from("(& 0xff (<< 0b1010 2))")));
+		assert_eq!(tree("a | b ^ c & d"), Ok(String::from("(| a (^ b (& c d)))")));
+		assert_eq!(tree("1 << 2 + 3"), Ok(String::from("(<< 1 (+ 2 3))")));
+		assert_eq!(tree("~x ** 2 & -y"), Ok(String::from("(& (~ (^ x 2)) (- y))")));
+		assert_eq!(tree("1 >> 2 >> 3"), Ok(String::from("(>> (>> 1 2) 3)")));
+		// separators go between the digits of every radix
+		assert_eq!(tree("0xFF_FF"), Ok(String::from("0xffff")));
+		assert_eq!(tree("0o7_55"), Ok(String::from("0o755")));
+		assert_eq!(tree("0b1010_1010 | 1"), Ok(String::from("(| 0b10101010 1)")));
+	}
+
+	#[test]
+	fn bitwise_error_test() {
+		let error = |expression| ShuntingYard::infix_to_postfix(expression, Syntax::default()).unwrap_err();
+
+		assert_eq!(error("0x"), ShuntingYardError::InvalidNumber(Span::new(0, 2)));
+		assert_eq!(error("1 + 0b102"), ShuntingYardError::InvalidNumber(Span::new(4, 9)));
+		assert_eq!(error("0xFFFFFFFFFFFFFFFFF"), ShuntingYardError::InvalidNumber(Span::new(0, 19)));
+		assert_eq!(error("0x_FF"), ShuntingYardError::InvalidNumber(Span::new(0, 5)));
+		assert_eq!(error("0o7__7"), ShuntingYardError::InvalidNumber(Span::new(0, 6)));
+		assert_eq!(error("0b1_ + 1"), ShuntingYardError::InvalidNumber(Span::new(0, 4)));
+		assert_eq!(error("1 $ 2"), ShuntingYardError::UnexpectedCharacter('$', Span::new(2, 3)));
+		assert_eq!(error("1 & & 2"), ShuntingYardError::MissingOperand(Span::new(4, 5)));
+		assert_eq!(error("1 ~2"), ShuntingYardError::MissingOperator(Span::new(2, 3)));
+		assert_eq!(error("x~y"), ShuntingYardError::MissingOperator(Span::new(1, 2)));
+		assert_eq!(error("inf!k"), ShuntingYardError::MissingOperator(Span::new(3, 4)));
+	}
+
+	#[test]
+	fn tokenize_logical_test() {
+		assert_eq!(
+			tokens("a <= 1 && !b || c != true ? 2 : 3"),
+			Ok(vec![
+				Token::Identifier(String::from("a")),
+				Token::Operator(Operator::LessEqual),
+				Token::Number(1.0),
+				Token::Operator(Operator::And),
+				Token::Operator(Operator::Not),
+				Token::Identifier(String::from("b")),
+				Token::Operator(Operator::Or),
+				Token::Identifier(String::from("c")),
+				Token::Operator(Operator::NotEqual),
+				Token::Bool(true),
+				Token::Question,
+				Token::Number(2.0),
+				Token::Colon,
+				Token::Number(3.0),
+			])
+		);
+		assert_eq!(
+			tokens("1<2>=3==4>5&6|7"),
+			Ok(vec![
+				Token::Number(1.0),
+				Token::Operator(Operator::Less),
+				Token::Number(2.0),
+				Token::Operator(Operator::GreaterEqual),
+				Token::Number(3.0),
+				Token::Operator(Operator::Equal),
+				Token::Number(4.0),
+				Token::Operator(Operator::Greater),
+				Token::Number(5.0),
+				Token::Operator(Operator::BitAnd),
+				Token::Number(6.0),
+				Token::Operator(Operator::BitOr),
+				Token::Number(7.0),
+			])
+		);
+	}
+
+	#[test]
+	fn parse_logical_test() {
+		let tree = |expression| ShuntingYard::parse(expression, Syntax::default()).map(|ast| Notation::Sexpr.print(&ast));
+
+		assert_eq!(tree("cpu > 80 && mem < 90 ? 1 : 0"), Ok(String::from("(?: (&& (> cpu 80) (< mem 90)) 1 0)")));
+		assert_eq!(tree("a || b && c"), Ok(String::from("(|| a (&& b c))")));
+		assert_eq!(tree("!a == b"), Ok(String::from("(== (! a) b)")));
+		assert_eq!(tree("1 + 2 < 3 == x & 1 > 0"), Ok(String::from("(& (== (< (+ 1 2) 3) x) (> 1 0))")));
+		// the conditional groups to the right in both of its branches
+		assert_eq!(tree("a ? b : c ? d : e"), Ok(String::from("(?: a b (?: c d e))")));
+		assert_eq!(tree("a ? b ? c : d : e"), Ok(String::from("(?: a (?: b c d) e)")));
+		assert_eq!(tree("(a ? b : c) ? d : e"), Ok(String::from("(?: (?: a b c) d e)")));
+		assert_eq!(tree("max(a ? 1 : 2, 3)"), Ok(String::from("(max (?: a 1 2) 3)")));
+		assert_eq!(tree("true || false"), Ok(String::from("(|| true false)")));
+	}
+
+	#[test]
+	fn conditional_error_test() {
+		let error = |expression| ShuntingYard::parse(expression, Syntax::default()).unwrap_err();
+
+		assert_eq!(error("a ? b"), ShuntingYardError::MissingColon(Span::new(2, 3)));
+		assert_eq!(error("(a ? b) : c"), ShuntingYardError::MissingColon(Span::new(3, 4)));
+		assert_eq!(error("max(a ? b, c)"), ShuntingYardError::MissingColon(Span::new(6, 7)));
+		assert_eq!(error("a : b"), ShuntingYardError::UnmatchedColon(Span::new(2, 3)));
+		assert_eq!(error("a ? (b : c)"), ShuntingYardError::UnmatchedColon(Span::new(7, 8)));
+		assert_eq!(error("a ? : c"), ShuntingYardError::MissingOperand(Span::new(2, 3)));
+		assert_eq!(error("a ? b :"), ShuntingYardError::MissingOperand(Span::new(6, 7)));
+		assert_eq!(error("a && || b"), ShuntingYardError::MissingOperand(Span::new(5, 7)));
+		// `!` is never a factorial
+		assert_eq!(error("2!3"), ShuntingYardError::MissingOperator(Span::new(1, 2)));
+		assert_eq!(error("a !b"), ShuntingYardError::MissingOperator(Span::new(2, 3)));
+	}
+
+	#[test]
+	fn tokenize_units_test() {
+		let quantity = |number, name: &str, power| {
+			Token::Quantity(
+				number,
+				Symbol {
+					name: name.to_string(),
+					power,
+				},
+			)
+		};
+
+		assert_eq!(
+			tokens("3 km / 20min in km/h"),
+			Ok(vec![
+				quantity(3.0, "km", 1),
+				Token::Operator(Operator::Divided),
+				quantity(20.0, "min", 1),
+				Token::Operator(Operator::Convert),
+				Token::Identifier(String::from("km")),
+				Token::Operator(Operator::Divided),
+				Token::Identifier(String::from("h")),
+			])
+		);
+		assert_eq!(
+			tokens("5 m^2 + 2 s^-1 - 1 ft**3 to L"),
+			Ok(vec![
+				quantity(5.0, "m", 2),
+				Token::Operator(Operator::Plus),
+				quantity(2.0, "s", -1),
+				Token::Operator(Operator::Minus),
+				quantity(1.0, "ft", 3),
+				Token::Operator(Operator::Convert),
+				Token::Identifier(String::from("L")),
+			])
+		);
+		// only known units belong to the number and a caret without digits is an operator
+		assert_eq!(
+			tokens("2 x + 3 m^x"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Identifier(String::from("x")),
+				Token::Operator(Operator::Plus),
+				quantity(3.0, "m", 1),
+				Token::Operator(Operator::Power),
+				Token::Identifier(String::from("x")),
+			])
+		);
+		assert_eq!(
+			tokens("2 min(1)"),
+			Ok(vec![
+				Token::Number(2.0),
+				Token::Function(String::from("min")),
+				Token::OpenParenthesis,
+				Token::Number(1.0),
+				Token::CloseParenthesis,
+			])
+		);
+		// `^` is an exclusive or when it is the one for integers
+		assert_eq!(
+			ShuntingYard::tokenize(
+				"5 m^2",
+				Syntax {
+					caret_is_xor: true,
+					..Syntax::default()
+				}
+			)
+			.map(strip),
+			Ok(vec![
+				quantity(5.0, "m", 1),
+				Token::Operator(Operator::BitXor),
+				Token::Number(2.0)
+			])
+		);
+	}
+
+	#[test]
+	fn parse_depth_test() {
+		// the passes walking the tree recurse so even a syntax without limits has a deepest tree
+		let negations = |count| format!("{}1", "-".repeat(count));
+		assert!(ShuntingYard::parse(&negations(ShuntingYard::MAX_DEPTH - 1), Syntax::default()).is_ok());
+		assert_eq!(
+			ShuntingYard::parse(&negations(1_000_000), Syntax::default()),
+			Err(ShuntingYardError::TooDeep(ShuntingYard::MAX_DEPTH, Span::new(999_000, 999_001)))
+		);
+		assert_eq!(
+			ShuntingYard::parse(&format!("{}1", "2^".repeat(100_000)), Syntax::default()),
+			Err(ShuntingYardError::TooDeep(ShuntingYard::MAX_DEPTH, Span::new(198_001, 198_002)))
+		);
+		let lenient = Syntax {
+			max_depth: Some(usize::MAX),
+			..Syntax::default()
+		};
+		assert_eq!(
+			ShuntingYard::parse(&negations(ShuntingYard::MAX_DEPTH), lenient),
+			Err(ShuntingYardError::TooDeep(ShuntingYard::MAX_DEPTH, Span::new(0, 1)))
+		);
+	}
+
+	#[test]
+	fn parse_units_test() {
+		let tree = |expression| ShuntingYard::parse(expression, Syntax::default()).map(|ast| Notation::Sexpr.print(&ast));
+
+		assert_eq!(tree("3 km / 20 min in km/h"), Ok(String::from("(in (/ 3km 20min) (/ km h))")));
+		assert_eq!(tree("1 + 2 m to cm ? 1 : 2"), Ok(String::from("(?: (in (+ 1 2m) cm) 1 2)")));
+		assert_eq!(tree("a ? 2 m : 3 m in cm"), Ok(String::from("(?: a 2m (in 3m cm))")));
+		assert_eq!(tree("in"), Err(ShuntingYardError::MissingOperand(Span::new(0, 2))));
+	}
+
+	#[test]
+	fn tokenize_postfix_test() {
+		let postfix = |expression| ShuntingYard::tokenize_postfix(expression, Syntax::default()).map(strip);
+
+		assert_eq!(
+			postfix(" 3 -4  x ** neg max"),
+			Ok(vec![
+				Token::Number(3.0),
+				Token::Number(-4.0),
+				Token::Identifier(String::from("x")),
+				Token::Operator(Operator::Power),
+				Token::Operator(Operator::Negate),
+				Token::Identifier(String::from("max")),
+			])
+		);
+		assert_eq!(
+			ShuntingYard::tokenize_postfix(
+				"2 ^",
+				Syntax {
+					caret_is_xor: true,
+					..Syntax::default()
+				}
+			)
+			.map(strip),
+			Ok(vec![Token::Number(2.0), Token::Operator(Operator::BitXor)])
+		);
+		assert_eq!(postfix("1 2+"), Err(ShuntingYardError::UnexpectedWord(Span::new(2, 4))));
+		assert_eq!(postfix("1 2 $"), Err(ShuntingYardError::UnexpectedCharacter('$', Span::new(4, 5))));
+		assert_eq!(postfix("  1e999"), Err(ShuntingYardError::NumberTooLarge(Span::new(2, 7))));
+		assert_eq!(postfix(""), Ok(vec![]));
+	}
+
+	#[test]
+	fn span_test() {
+		let spans = |expression| {
+			ShuntingYard::tokenize(expression, Syntax::default())
+				.unwrap()
+				.into_iter()
+				.map(|token| (token.span.start, token.span.end))
+				.collect::<Vec<(usize, usize)>>()
+		};
+
+		assert_eq!(spans("12 + 3.5"), vec![(0, 2), (3, 4), (5, 8)]);
+		assert_eq!(spans("2 ** -x_1"), vec![(0, 1), (2, 4), (5, 6), (6, 9)]);
+		assert_eq!(spans("max (1,2)"), vec![(0, 3), (4, 5), (5, 6), (6, 7), (7, 8), (8, 9)]);
+		assert_eq!(spans("0x1f>>~0b1"), vec![(0, 4), (4, 6), (6, 7), (7, 10)]);
+		assert_eq!(spans("3 km^2/2  s in m"), vec![(0, 6), (6, 7), (7, 11), (12, 14), (15, 16)]);
+
+		let calls = ShuntingYard::infix_to_postfix("1 + max(2, 3)", Syntax::default()).unwrap();
+		// a call covers its name up to the closing parenthesis
+		assert_eq!(calls[3], Spanned::new(Token::Call(String::from("max"), 2), Span::new(4, 13)));
+	}
+
+	#[test]
+	fn parse_tree_test() {
+		assert_eq!(tree("1 + 2 * 3"), Ok(String::from("(+ 1 (* 2 3))")));
+		assert_eq!(tree("(1 + 2) * 3"), Ok(String::from("(* (+ 1 2) 3)")));
+		assert_eq!(tree("2 ^ 3 ^ -x"), Ok(String::from("(^ 2 (^ 3 (- x)))")));
+		assert_eq!(tree("max(1, sin(x), 3) - f()"), Ok(String::from("(- (max 1 (sin x) 3) (f))")));
+		assert_eq!(tree("1 +"), Err(ShuntingYardError::MissingOperand(Span::new(2, 3))));
+		assert_eq!(tree("2 * 9.81 ± 0.02 + 1"), Ok(String::from("(+ (± (* 2 9.81) 0.02) 1)")));
+		assert_eq!(tree("x +/- -1"), Ok(String::from("(± x (- 1))")));
+		assert_eq!(tree("10 ± 5%"), Ok(String::from("(± 10 (/ (* 10 5) 100))")));
+
+		// every node covers its operands so errors found while evaluating can point at them
+		let ast = ShuntingYard::parse("-(1 + 2) * sqrt(4)", Syntax::default()).unwrap();
+		assert_eq!(ast.span, Span::new(0, 18));
+		let Expr::Binary(Operator::Times, left, right) = ast.node else {
+			panic!("expected a multiplication but got {:?}", ast.node);
+		};
+		assert_eq!(left.span, Span::new(0, 7));
+		assert_eq!(right.span, Span::new(11, 18));
+	}
+
+	#[test]
+	fn parse_vector_test() {
+		assert_eq!(tree("[1, 2 + x, f(3)]"), Ok(String::from("(vector 1 (+ 2 x) (f 3))")));
+		assert_eq!(tree("[[1, 2], [3, 4]] @ v"), Ok(String::from("(@ (vector (vector 1 2) (vector 3 4)) v)")));
+		assert_eq!(tree("-[1] . [max(1, 2)] * 2"), Ok(String::from("(* (. (- (vector 1)) (vector (max 1 2))) 2)")));
+		assert_eq!(tree("[]"), Ok(String::from("(vector)")));
+		// a point followed by a digit is still a number
+		assert_eq!(tree("[.5].[1.]"), Ok(String::from("(. (vector 0.5) (vector 1))")));
+
+		let ast = ShuntingYard::parse("2 * [1, 2]", Syntax::default()).unwrap();
+		let Expr::Binary(_, _, vector) = ast.node else {
+			panic!("expected a multiplication but got {:?}", ast.node);
+		};
+		assert_eq!(vector.span, Span::new(4, 10));
+
+		let error = |expression| ShuntingYard::infix_to_postfix(expression, Syntax::default()).unwrap_err();
+		assert_eq!(error("[1, 2"), ShuntingYardError::UnmatchedOpenBracket(Span::new(0, 1)));
+		assert_eq!(error("1, 2]"), ShuntingYardError::CommaOutsideFunction(Span::new(1, 2)));
+		assert_eq!(error("1]"), ShuntingYardError::UnmatchedCloseBracket(Span::new(1, 2)));
+		assert_eq!(error("(1]"), ShuntingYardError::UnmatchedCloseBracket(Span::new(2, 3)));
+		assert_eq!(error("[1)"), ShuntingYardError::UnmatchedCloseParenthesis(Span::new(2, 3)));
+		assert_eq!(error("[1,]"), ShuntingYardError::MissingArgument(Span::new(3, 4)));
+		assert_eq!(error("[, 1]"), ShuntingYardError::MissingArgument(Span::new(1, 2)));
+		assert_eq!(error("[1 2]"), ShuntingYardError::MissingOperator(Span::new(3, 4)));
+		assert_eq!(error("2 [1]"), ShuntingYardError::MissingOperator(Span::new(2, 3)));
+	}
+
+	#[test]
+	fn syntax_error_test() {
+		let error = |expression| ShuntingYard::infix_to_postfix(expression, Syntax::default()).unwrap_err();
+
+		assert_eq!(error("1 $ 2"), ShuntingYardError::UnexpectedCharacter('$', Span::new(2, 3)));
+		assert_eq!(error("(1 + 2"), ShuntingYardError::UnmatchedOpenParenthesis(Span::new(0, 1)));
+		assert_eq!(error("1 + 2)"), ShuntingYardError::UnmatchedCloseParenthesis(Span::new(5, 6)));
+		assert_eq!(error(")"), ShuntingYardError::UnmatchedCloseParenthesis(Span::new(0, 1)));
+		assert_eq!(error("1 + () * 2"), ShuntingYardError::EmptyParentheses(Span::new(4, 6)));
+		assert_eq!(error("1 +"), ShuntingYardError::MissingOperand(Span::new(2, 3)));
+		assert_eq!(error("1 * / 2"), ShuntingYardError::MissingOperand(Span::new(4, 5)));
+		assert_eq!(error("(1 +)"), ShuntingYardError::MissingOperand(Span::new(3, 4)));
+		assert_eq!(error("1 2"), ShuntingYardError::MissingOperator(Span::new(2, 3)));
+		assert_eq!(error("x 2"), ShuntingYardError::MissingOperator(Span::new(2, 3)));
+		assert_eq!(error("(2) 3 4"), ShuntingYardError::MissingOperator(Span::new(6, 7)));
+		assert_eq!(error("% 5"), ShuntingYardError::MissingOperand(Span::new(0, 1)));
+		assert_eq!(error("f(1,)"), ShuntingYardError::MissingArgument(Span::new(4, 5)));
+		assert_eq!(error("f(, 1)"), ShuntingYardError::MissingArgument(Span::new(2, 3)));
+		assert_eq!(error("f(1,,2)"), ShuntingYardError::MissingArgument(Span::new(4, 5)));
+		assert_eq!(error(", 1"), ShuntingYardError::CommaOutsideFunction(Span::new(0, 1)));
+		assert_eq!(error("  "), ShuntingYardError::EmptyExpression);
+	}
 }
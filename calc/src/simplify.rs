@@ -0,0 +1,396 @@
+use crate::{
+	diff::Builder,
+	expr::{Ast, Expr},
+	notation::Notation,
+	shunting::Operator,
+};
+
+/// Every pass can open up rules for the nodes above it, this many are plenty for anything a derivative produces
+const MAX_PASSES: usize = 16;
+
+/// Rewrites an expression into a shorter one that calculates the same
+///
+/// Constants are folded, identities like `x*1` and `x^0` are removed and like terms and factors are collected so
+/// `x*x + 2*x*x` becomes `3*x^2`.
+pub fn simplify(ast: &Ast) -> Ast {
+	let mut ast = ast.clone();
+	for _ in 0..MAX_PASSES {
+		let simplified = pass(&ast);
+		if simplified == ast {
+			break;
+		}
+		ast = simplified;
+	}
+	ast
+}
+
+// children first so every rule sees operands that are already simplified
+fn pass(ast: &Ast) -> Ast {
+	let build = Builder(ast.span);
+	let node = match &ast.node {
+		Expr::Unary(operator, operand) => Expr::Unary(operator.clone(), Box::new(pass(operand))),
+		Expr::Binary(operator, left, right) => Expr::Binary(operator.clone(), Box::new(pass(left)), Box::new(pass(right))),
+		Expr::Call(name, args) => Expr::Call(name.clone(), args.iter().map(pass).collect()),
+		Expr::Vector(elements) => Expr::Vector(elements.iter().map(pass).collect()),
+		Expr::Conditional(condition, then, otherwise) => {
+			Expr::Conditional(Box::new(pass(condition)), Box::new(pass(then)), Box::new(pass(otherwise)))
+		},
+		Expr::Number(_)
+		| Expr::Integer(..)
+		| Expr::Decimal(_)
+		| Expr::Quantity(..)
+		| Expr::Imaginary(_)
+		| Expr::Bool(_)
+		| Expr::Variable(_) => return ast.clone(),
+	};
+	rewrite(build.node(node))
+}
+
+fn rewrite(ast: Ast) -> Ast {
+	let build = Builder(ast.span);
+
+	match &ast.node {
+		Expr::Unary(Operator::Negate, operand) => match (constant(operand), &operand.node) {
+			(Some(number), _) => build.number(-number),
+			(_, Expr::Unary(Operator::Negate, inner)) => (**inner).clone(),
+			// the sign goes into the coefficient so `-(3*x)` is `-3*x`
+			(_, Expr::Binary(Operator::Times | Operator::Divided, ..)) => product(&ast),
+			_ => ast,
+		},
+		Expr::Binary(Operator::Plus | Operator::Minus, ..) => sum(&ast),
+		Expr::Binary(Operator::Times, ..) => product(&ast),
+		Expr::Binary(Operator::Divided, numerator, denominator) => {
+			match (constant(numerator), constant(denominator)) {
+				(_, Some(1.0)) => (**numerator).clone(),
+				(Some(0.0), denominator) if denominator != Some(0.0) => build.number(0.0),
+				// only quotients that come out even are folded so nothing is rounded
+				(Some(numerator), Some(denominator)) if denominator != 0.0 && (numerator / denominator).fract() == 0.0 => {
+					build.number(numerator / denominator)
+				},
+				_ if key(numerator) == key(denominator) => build.number(1.0),
+				_ => product(&ast),
+			}
+		},
+		Expr::Binary(Operator::Power, base, exponent) => match (constant(base), constant(exponent)) {
+			(_, Some(0.0)) | (Some(1.0), _) => build.number(1.0),
+			(_, Some(1.0)) => (**base).clone(),
+			(Some(base), Some(exponent)) if exponent.fract() == 0.0 && base.powf(exponent).is_finite() => {
+				let power = base.powf(exponent);
+				if power.fract() == 0.0 { build.number(power) } else { ast }
+			},
+			(_, Some(exponent))
+				if exponent.fract() == 0.0
+					&& matches!(
+						base.node,
+						Expr::Binary(Operator::Times | Operator::Divided, ..) | Expr::Unary(Operator::Negate, _)
+					) =>
+			{
+				product(&ast)
+			},
+			_ => ast,
+		},
+		Expr::Call(name, args)
+			if name == "ln" && matches!(args.as_slice(), [arg] if arg.node == Expr::Variable(String::from("e"))) =>
+		{
+			build.number(1.0)
+		},
+		Expr::Conditional(condition, then, otherwise) => match condition.node {
+			Expr::Bool(true) => (**then).clone(),
+			Expr::Bool(false) => (**otherwise).clone(),
+			_ if key(then) == key(otherwise) => (**then).clone(),
+			_ => ast,
+		},
+		_ => ast,
+	}
+}
+
+fn constant(ast: &Ast) -> Option<f64> {
+	match ast.node {
+		Expr::Number(number) => Some(number),
+		Expr::Integer(integer, _) => Some(integer as f64),
+		_ => None,
+	}
+}
+
+// the same expression written with different spans or parentheses compares equal
+fn key(ast: &Ast) -> String {
+	Notation::Sexpr.print(ast)
+}
+
+// a sum is a list of terms with their coefficients and a constant, `2*x - 3 + x` is `3*x` and `-3`
+fn sum(ast: &Ast) -> Ast {
+	fn collect(ast: &Ast, sign: f64, terms: &mut Vec<(f64, Vec<Ast>)>, constant_term: &mut f64) {
+		match &ast.node {
+			Expr::Binary(Operator::Plus, left, right) => {
+				collect(left, sign, terms, constant_term);
+				collect(right, sign, terms, constant_term);
+			},
+			Expr::Binary(Operator::Minus, left, right) => {
+				collect(left, sign, terms, constant_term);
+				collect(right, -sign, terms, constant_term);
+			},
+			Expr::Unary(Operator::Negate, operand) => collect(operand, -sign, terms, constant_term),
+			_ => match constant(ast) {
+				Some(number) => *constant_term += sign * number,
+				None => {
+					let (coefficient, factors) = term(ast);
+					// factors can be in any order, `x*y` and `y*x` are like terms
+					let term = |factors: &[Ast]| {
+						let mut keys = factors.iter().map(key).collect::<Vec<String>>();
+						keys.sort();
+						keys
+					};
+					let this = term(&factors);
+					match terms.iter_mut().find(|(_, other)| term(other) == this) {
+						Some((total, _)) => *total += sign * coefficient,
+						None => terms.push((sign * coefficient, factors)),
+					}
+				},
+			},
+		}
+	}
+
+	let build = Builder(ast.span);
+	let (mut terms, mut constant_term) = (Vec::new(), 0.0);
+	collect(ast, 1.0, &mut terms, &mut constant_term);
+	terms.retain(|(coefficient, _)| *coefficient != 0.0);
+	if constant_term != 0.0 {
+		terms.push((constant_term, Vec::new()));
+	}
+
+	let mut terms = terms.into_iter();
+	let Some((coefficient, factors)) = terms.next() else {
+		return build.number(0.0);
+	};
+	// only the first term keeps its sign, the others are added or subtracted
+	terms.fold(rebuild_term(&build, coefficient, factors), |sum, (coefficient, factors)| {
+		let operator = if coefficient < 0.0 {
+			Operator::Minus
+		} else {
+			Operator::Plus
+		};
+		build.binary(operator, sum, rebuild_term(&build, coefficient.abs(), factors))
+	})
+}
+
+// the coefficient of a quotient is taken from its numerator so `-3*x/y` is `x/y` times `-3`
+fn term(ast: &Ast) -> (f64, Vec<Ast>) {
+	match &ast.node {
+		Expr::Binary(Operator::Divided, numerator, denominator) => {
+			let build = Builder(ast.span);
+			let (coefficient, factors) = factors(numerator);
+			(
+				coefficient,
+				vec![build.binary(Operator::Divided, rebuild_product(&build, 1.0, factors), (**denominator).clone())],
+			)
+		},
+		_ => factors(ast),
+	}
+}
+
+// puts the coefficient back into the numerator of a quotient
+fn rebuild_term(build: &Builder, coefficient: f64, factors: Vec<Ast>) -> Ast {
+	if let [
+		Ast {
+			node: Expr::Binary(Operator::Divided, numerator, denominator),
+			..
+		},
+	] = factors.as_slice()
+	{
+		let (_, numerator) = self::factors(numerator);
+		return build.binary(Operator::Divided, rebuild_product(build, coefficient, numerator), (**denominator).clone());
+	}
+	rebuild_product(build, coefficient, factors)
+}
+
+// the numeric coefficient of a product and its other factors in the order they were written
+fn factors(ast: &Ast) -> (f64, Vec<Ast>) {
+	fn collect(ast: &Ast, coefficient: &mut f64, factors: &mut Vec<Ast>) {
+		match &ast.node {
+			Expr::Binary(Operator::Times, left, right) => {
+				collect(left, coefficient, factors);
+				collect(right, coefficient, factors);
+			},
+			Expr::Unary(Operator::Negate, operand) => {
+				*coefficient = -*coefficient;
+				collect(operand, coefficient, factors);
+			},
+			_ => match constant(ast) {
+				Some(number) => *coefficient *= number,
+				None => factors.push(ast.clone()),
+			},
+		}
+	}
+
+	let (mut coefficient, mut factors) = (1.0, Vec::new());
+	collect(ast, &mut coefficient, &mut factors);
+	(coefficient, factors)
+}
+
+// bases with the exponents they are raised to
+type Powers = Vec<(Ast, f64)>;
+
+// a product as a coefficient over a divisor times powers, where the factors of denominators have negative exponents
+struct Factors {
+	coefficient: f64,
+	divisor: f64,
+	powers: Powers,
+}
+
+impl Factors {
+	fn collect(&mut self, ast: &Ast, exponent: f64) {
+		let whole = exponent.fract() == 0.0;
+		match &ast.node {
+			Expr::Binary(Operator::Times, left, right) => {
+				self.collect(left, exponent);
+				self.collect(right, exponent);
+			},
+			Expr::Binary(Operator::Divided, numerator, denominator) => {
+				self.collect(numerator, exponent);
+				self.collect(denominator, -exponent);
+			},
+			Expr::Unary(Operator::Negate, operand) if whole => {
+				if exponent % 2.0 != 0.0 {
+					self.coefficient = -self.coefficient;
+				}
+				self.collect(operand, exponent);
+			},
+			// `(x*y)^2` is `x^2*y^2` but `(x^2)^0.5` isn't `x` so only whole powers are taken apart
+			Expr::Binary(Operator::Power, base, power) if constant(power).is_some() => {
+				let power = constant(power).expect("BUG: checked by the guard");
+				if whole && power.fract() == 0.0 {
+					self.collect(base, exponent * power);
+				} else {
+					self.multiply((**base).clone(), exponent * power);
+				}
+			},
+			_ => match constant(ast) {
+				Some(number) if whole && exponent > 0.0 => self.coefficient *= number.powi(exponent as i32),
+				Some(number) if whole => self.divisor *= number.powi(-exponent as i32),
+				_ => self.multiply(ast.clone(), exponent),
+			},
+		}
+	}
+
+	fn multiply(&mut self, base: Ast, exponent: f64) {
+		match self.powers.iter_mut().find(|(other, _)| key(other) == key(&base)) {
+			Some((_, total)) => *total += exponent,
+			None => self.powers.push((base, exponent)),
+		}
+	}
+}
+
+// equal bases are collected into one power and quotients are pulled out, `2*x*(1/y)*x^2` is `2*x^3/y`
+fn product(ast: &Ast) -> Ast {
+	let build = Builder(ast.span);
+	let mut factors = Factors {
+		coefficient: 1.0,
+		divisor: 1.0,
+		powers: Vec::new(),
+	};
+	factors.collect(ast, 1.0);
+
+	let (mut coefficient, mut divisor) = (factors.coefficient, factors.divisor);
+	// a fraction of whole numbers is reduced to lowest terms
+	if coefficient.fract() == 0.0 && divisor.fract() == 0.0 && divisor != 0.0 {
+		let divisor_gcd = gcd(coefficient.abs(), divisor.abs()) * divisor.signum();
+		(coefficient, divisor) = (coefficient / divisor_gcd, divisor / divisor_gcd);
+	}
+
+	let power = |(base, exponent): (Ast, f64)| {
+		if exponent == 1.0 {
+			base
+		} else {
+			build.power(base, build.number(exponent))
+		}
+	};
+	let (numerator, denominator): (Powers, Powers) =
+		factors.powers.into_iter().filter(|(_, exponent)| *exponent != 0.0).partition(|(_, exponent)| *exponent > 0.0);
+	let numerator = rebuild_product(&build, coefficient, numerator.into_iter().map(power).collect());
+	if (denominator.is_empty() && divisor == 1.0) || coefficient == 0.0 {
+		return numerator;
+	}
+
+	let denominator = denominator.into_iter().map(|(base, exponent)| power((base, -exponent))).collect();
+	build.binary(Operator::Divided, numerator, rebuild_product(&build, divisor, denominator))
+}
+
+fn gcd(a: f64, b: f64) -> f64 {
+	if b == 0.0 { a } else { gcd(b, a % b) }
+}
+
+fn rebuild_product(build: &Builder, coefficient: f64, factors: Vec<Ast>) -> Ast {
+	if coefficient == 0.0 {
+		return build.number(0.0);
+	}
+
+	let mut factors = factors.into_iter();
+	let first = match factors.next() {
+		None => return build.number(coefficient),
+		Some(first) if coefficient == 1.0 => first,
+		Some(first) if coefficient == -1.0 => build.negate(first),
+		Some(first) => build.times(build.number(coefficient), first),
+	};
+	factors.fold(first, |product, factor| build.times(product, factor))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shunting::{ShuntingYard, Syntax};
+
+	fn simplified(expression: &str) -> String {
+		Notation::Infix.print(&simplify(&ShuntingYard::parse(expression, Syntax::default()).unwrap()))
+	}
+
+	#[test]
+	fn constant_folding_test() {
+		assert_eq!(simplified("1 + 2 * 3"), "7");
+		assert_eq!(simplified("2^10 - 24"), "1000");
+		assert_eq!(simplified("6 / 3 + x"), "x + 2");
+		assert_eq!(simplified("1 / 3"), "1/3");
+		assert_eq!(simplified("-(-(2))"), "2");
+		assert_eq!(simplified("sqrt(4)"), "sqrt(4)");
+		assert_eq!(simplified("ln(e)"), "1");
+	}
+
+	#[test]
+	fn identity_test() {
+		assert_eq!(simplified("x + 0"), "x");
+		assert_eq!(simplified("0 - x"), "-x");
+		assert_eq!(simplified("1 * x * 1"), "x");
+		assert_eq!(simplified("x * 0 + y"), "y");
+		assert_eq!(simplified("0 / x"), "0");
+		assert_eq!(simplified("x / 1"), "x");
+		assert_eq!(simplified("sin(x) / sin(x)"), "1");
+		assert_eq!(simplified("x ^ 1 + y ^ 0"), "x + 1");
+		assert_eq!(simplified("1 ^ x"), "1");
+		assert_eq!(simplified("--x"), "x");
+		assert_eq!(simplified("x > 0 ? y * 1 : y + 0"), "y");
+		assert_eq!(simplified("true ? x : y"), "x");
+	}
+
+	#[test]
+	fn like_terms_test() {
+		assert_eq!(simplified("x + x"), "2*x");
+		assert_eq!(simplified("2*x - x"), "x");
+		assert_eq!(simplified("x - x"), "0");
+		assert_eq!(simplified("x*x + 2*x*x"), "3*x^2");
+		assert_eq!(simplified("x * y * x^2"), "x^3*y");
+		assert_eq!(simplified("x * x^-1"), "1");
+		assert_eq!(simplified("3 - 2*y + x + y - 5"), "-y + x - 2");
+		assert_eq!(simplified("-(2*x) - 3*x"), "-5*x");
+		assert_eq!(simplified("sin(x)*2*cos(x) + cos(x)*sin(x)"), "3*sin(x)*cos(x)");
+		assert_eq!(simplified("-x * y"), "-x*y");
+		assert_eq!(simplified("2 * (1 / (x + 1)) * x"), "2*x/(x + 1)");
+		assert_eq!(simplified("x * (1 / x)"), "1");
+		assert_eq!(simplified("(x / y) * (y / z)"), "x/z");
+		assert_eq!(simplified("(2*x)^2 / (4*x)"), "x");
+		assert_eq!(simplified("-(3*x^2)"), "-3*x^2");
+		assert_eq!(simplified("(-x)^3 * x^0.5 * x^0.5"), "-x^4");
+		assert_eq!(simplified("(x^2)^0.5"), "(x^2)^0.5");
+		assert_eq!(simplified("6*x / (-4*y)"), "-3*x/(2*y)");
+		assert_eq!(simplified("1 - (x/2)^2"), "-x^2/4 + 1");
+		assert_eq!(simplified("x/y - 3*x/y"), "-2*x/y");
+	}
+}
@@ -0,0 +1,381 @@
+use crate::{
+	diff::{self, Builder},
+	env::Environment,
+	expr::{Ast, Expr},
+	matrix::Shape,
+	numeric::FloatMode,
+	postfix::{Postfix, PostfixError},
+	shunting::{Operator, Span},
+	value::Value,
+};
+
+/// Polynomials of a higher degree than this are solved numerically like any other expression
+const MAX_DEGREE: usize = 64;
+
+/// How often Brent's and Newton's method may refine a root
+const ITERATIONS: usize = 200;
+
+/// What solving an equation found
+#[derive(Debug, PartialEq)]
+pub enum Solution {
+	/// The real roots from the smallest to the largest, none when there aren't any
+	Roots(Vec<f64>),
+	/// Both sides are equal whatever the variable is
+	Identity,
+}
+
+/// Finds the values of a variable that make both sides of an equation equal
+///
+/// Linear and quadratic equations are solved exactly. Anything else is sampled over the range and every
+/// sign change is narrowed down with Brent's method, while roots that only touch zero are found with
+/// Newton's method from where the samples come closest to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Solver {
+	/// Where roots are searched for when the equation has to be solved numerically
+	pub range: (f64, f64),
+	/// How many pieces the range is split into
+	pub samples: usize,
+}
+
+impl Default for Solver {
+	fn default() -> Self {
+		Self {
+			range: (-1000.0, 1000.0),
+			samples: 20_000,
+		}
+	}
+}
+
+impl Solver {
+	pub fn solve(
+		&self,
+		left: &Ast,
+		right: &Ast,
+		variable: &str,
+		env: &Environment<Value>,
+	) -> Result<Solution, PostfixError> {
+		// `left = right` is `left - right = 0`
+		let build = Builder(Span::new(left.span.start, right.span.end));
+		let difference = build.binary(Operator::Minus, left.clone(), right.clone());
+
+		match Self::polynomial(&difference, variable, env) {
+			Some(coefficients) if coefficients.len() <= 3 => Ok(Self::closed_form(&coefficients)),
+			_ => self.numeric(&difference, variable, env),
+		}
+	}
+
+	// the coefficients from the constant term up with the leading zeros removed, `None` for anything that
+	// isn't a polynomial in the variable
+	fn polynomial(ast: &Ast, variable: &str, env: &Environment<Value>) -> Option<Vec<f64>> {
+		let polynomial = |ast: &Ast| Self::polynomial(ast, variable, env);
+		let constant = |ast: &Ast| match Postfix::calc(ast, env, &FloatMode) {
+			Ok(Value::Number(number)) if number.is_finite() => Some(number),
+			_ => None,
+		};
+
+		let mut coefficients = if !diff::depends_on(ast, variable) {
+			vec![constant(ast)?]
+		} else {
+			match &ast.node {
+				Expr::Variable(_) => vec![0.0, 1.0],
+				Expr::Unary(Operator::Negate, operand) => polynomial(operand)?.iter().map(|coefficient| -coefficient).collect(),
+				Expr::Binary(operator @ (Operator::Plus | Operator::Minus), left, right) => {
+					let (mut sum, right) = (polynomial(left)?, polynomial(right)?);
+					sum.resize(sum.len().max(right.len()), 0.0);
+					let sign = if *operator == Operator::Plus { 1.0 } else { -1.0 };
+					sum.iter_mut().zip(right).for_each(|(coefficient, other)| *coefficient += sign * other);
+					sum
+				},
+				Expr::Binary(Operator::Times, left, right) => Self::multiply(&polynomial(left)?, &polynomial(right)?)?,
+				Expr::Binary(Operator::Divided, left, right) if !diff::depends_on(right, variable) => {
+					let divisor = constant(right).filter(|divisor| *divisor != 0.0)?;
+					polynomial(left)?.iter().map(|coefficient| coefficient / divisor).collect()
+				},
+				Expr::Binary(Operator::Power, base, exponent) if !diff::depends_on(exponent, variable) => {
+					let exponent = constant(exponent).filter(|exponent| exponent.fract() == 0.0 && *exponent >= 0.0)?;
+					let base = polynomial(base)?;
+					let mut power = vec![1.0];
+					for _ in 0..exponent.min(MAX_DEGREE as f64) as usize {
+						power = Self::multiply(&power, &base)?;
+					}
+					power
+				},
+				_ => return None,
+			}
+		};
+
+		while coefficients.len() > 1 && coefficients.last() == Some(&0.0) {
+			coefficients.pop();
+		}
+		(coefficients.len() <= MAX_DEGREE + 1 && coefficients.iter().all(|coefficient| coefficient.is_finite()))
+			.then_some(coefficients)
+	}
+
+	fn multiply(a: &[f64], b: &[f64]) -> Option<Vec<f64>> {
+		if a.len() + b.len() - 1 > MAX_DEGREE + 1 {
+			return None;
+		}
+		let mut product = vec![0.0; a.len() + b.len() - 1];
+		for (i, x) in a.iter().enumerate() {
+			for (j, y) in b.iter().enumerate() {
+				product[i + j] += x * y;
+			}
+		}
+		Some(product)
+	}
+
+	// the roots of `c + b*x + a*x^2`
+	fn closed_form(coefficients: &[f64]) -> Solution {
+		let mut roots = match *coefficients {
+			[0.0] => return Solution::Identity,
+			[_] => Vec::new(),
+			[c, b] => vec![-c / b],
+			[c, b, a] => {
+				let discriminant = b * b - 4.0 * a * c;
+				if discriminant < 0.0 {
+					Vec::new()
+				} else if discriminant == 0.0 {
+					vec![-b / (2.0 * a)]
+				} else {
+					// the root that would subtract two numbers of about the same size comes from the other one
+					let q = -(b + discriminant.sqrt().copysign(b)) / 2.0;
+					vec![q / a, c / q]
+				}
+			},
+			_ => unreachable!("only polynomials up to the second degree have a closed form here"),
+		};
+		// `-0` is just zero
+		roots.iter_mut().for_each(|root| *root += 0.0);
+		roots.sort_by(f64::total_cmp);
+		Solution::Roots(roots)
+	}
+
+	fn numeric(&self, difference: &Ast, variable: &str, env: &Environment<Value>) -> Result<Solution, PostfixError> {
+		let value = |x: f64| match Postfix::calc_with(difference, variable, Value::Number(x), env, &FloatMode) {
+			// quantities are compared in their base units
+			Ok(Value::Quantity(quantity)) => Ok(quantity.magnitude),
+			Ok(Value::Number(number)) => Ok(number),
+			Ok(Value::Bool(_)) => Err(PostfixError::ExpectedNumber(difference.span)),
+			Ok(Value::Vector(elements)) => Err(PostfixError::ExpectedScalar(Shape::Vector(elements.len()), difference.span)),
+			Ok(Value::Matrix(matrix)) => Err(PostfixError::ExpectedScalar(matrix.shape(), difference.span)),
+			Err(error) => Err(error),
+		};
+		let f = |x: f64| value(x).ok().filter(|y| !y.is_nan());
+		// the derivative is only needed for roots without a sign change and not every function has one
+		let derivative = diff::derivative(difference, variable).ok();
+		let slope = |x: f64| {
+			let derivative = derivative.as_ref()?;
+			match Postfix::calc_with(derivative, variable, Value::Number(x), env, &FloatMode) {
+				Ok(Value::Number(slope)) => Some(slope).filter(|slope| slope.is_finite()),
+				_ => None,
+			}
+		};
+
+		let (low, high) = self.range;
+		let samples = self.samples.max(1);
+		let points = (0..=samples)
+			.map(|index| low + (high - low) * index as f64 / samples as f64)
+			.map(|x| (x, value(x)))
+			.collect::<Vec<(f64, Result<f64, PostfixError>)>>();
+		// an equation that can't be calculated anywhere is most likely wrong like `x + y = 1` without a `y`
+		if points.iter().all(|(_, y)| y.is_err()) {
+			let (_, error) = points.into_iter().next().expect("BUG: there is at least one sample");
+			return Err(error.expect_err("BUG: every sample failed"));
+		}
+		let points = points.into_iter().map(|(x, y)| (x, y.ok().filter(|y| !y.is_nan()))).collect::<Vec<_>>();
+
+		let mut roots = Vec::new();
+		for (index, window) in points.windows(2).enumerate() {
+			let [(a, Some(fa)), (b, Some(fb))] = *window else {
+				continue;
+			};
+			if fa == 0.0 {
+				roots.push(a);
+			} else if fa.signum() != fb.signum() && fb != 0.0 {
+				// a pole like the one of `1/x` changes the sign too but grows rather than shrinks towards it
+				if let Some(root) = Self::brent(&f, (a, fa), (b, fb))
+					&& f(root).is_some_and(|y| y.abs() <= fa.abs().min(fb.abs()))
+				{
+					roots.push(root);
+				}
+			} else if let Some(&(_, Some(fc))) = points.get(index + 2)
+				&& fb.abs() < fa.abs()
+				&& fb.abs() <= fc.abs()
+				&& fb.signum() == fc.signum()
+				&& let Some(root) = Self::newton(&f, &slope, b)
+				&& (low..=high).contains(&root)
+			{
+				roots.push(root);
+			}
+		}
+		if let Some(&(x, Some(y))) = points.last()
+			&& y == 0.0
+		{
+			roots.push(x);
+		}
+
+		roots.iter_mut().for_each(|root| *root += 0.0);
+		roots.sort_by(f64::total_cmp);
+		roots.dedup_by(|a, b| (*a - *b).abs() <= 1e-9 * a.abs().max(1.0));
+		Ok(Solution::Roots(roots))
+	}
+
+	// narrows down a root between two points where the function has opposite signs
+	fn brent(f: &impl Fn(f64) -> Option<f64>, (mut a, mut fa): (f64, f64), (mut b, mut fb): (f64, f64)) -> Option<f64> {
+		let (mut c, mut fc) = (a, fa);
+		let mut step = b - a;
+		let mut previous_step = step;
+
+		for _ in 0..ITERATIONS {
+			if fb.signum() == fc.signum() {
+				(c, fc) = (a, fa);
+				step = b - a;
+				previous_step = step;
+			}
+			// `b` is always the best guess so far
+			if fc.abs() < fb.abs() {
+				(a, fa) = (b, fb);
+				(b, fb) = (c, fc);
+				(c, fc) = (a, fa);
+			}
+
+			let tolerance = 2.0 * f64::EPSILON * b.abs() + f64::MIN_POSITIVE;
+			let middle = (c - b) / 2.0;
+			if middle.abs() <= tolerance || fb == 0.0 {
+				return Some(b);
+			}
+
+			if previous_step.abs() >= tolerance && fa.abs() > fb.abs() {
+				// inverse quadratic interpolation or the secant method when there are only two points
+				let s = fb / fa;
+				let (mut p, mut q) = if a == c {
+					(2.0 * middle * s, 1.0 - s)
+				} else {
+					let (q, r) = (fa / fc, fb / fc);
+					(s * (2.0 * middle * q * (q - r) - (b - a) * (r - 1.0)), (q - 1.0) * (r - 1.0) * (s - 1.0))
+				};
+				if p > 0.0 {
+					q = -q;
+				} else {
+					p = -p;
+				}
+				if 2.0 * p < (3.0 * middle * q - (tolerance * q).abs()).min((previous_step * q).abs()) {
+					previous_step = step;
+					step = p / q;
+				} else {
+					// the interpolation isn't converging fast enough so this step bisects
+					step = middle;
+					previous_step = step;
+				}
+			} else {
+				step = middle;
+				previous_step = step;
+			}
+
+			(a, fa) = (b, fb);
+			b += if step.abs() > tolerance {
+				step
+			} else {
+				tolerance.copysign(middle)
+			};
+			fb = f(b)?;
+		}
+
+		Some(b)
+	}
+
+	// follows the tangent from a guess, which finds roots like the one of `x^4` where the sign doesn't change
+	fn newton(f: &impl Fn(f64) -> Option<f64>, slope: &impl Fn(f64) -> Option<f64>, mut x: f64) -> Option<f64> {
+		for _ in 0..ITERATIONS {
+			let y = f(x)?;
+			if y == 0.0 {
+				return Some(x);
+			}
+			let next = x - y / slope(x).filter(|slope| *slope != 0.0)?;
+			if (next - x).abs() <= 4.0 * f64::EPSILON * x.abs() {
+				x = next;
+				break;
+			}
+			x = next;
+		}
+		f(x).filter(|y| y.abs() <= 1e-12).map(|_| x)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::f64::consts;
+
+	use super::*;
+	use crate::shunting::{ShuntingYard, Syntax};
+
+	fn roots(solver: Solver, equation: &str, env: &Environment<Value>) -> Vec<f64> {
+		match solve_with(solver, equation, env) {
+			Ok(Solution::Roots(roots)) => roots,
+			solution => panic!("`{equation}` has no roots but {solution:?}"),
+		}
+	}
+
+	fn assert_close(equation: &str, roots: &[f64], expected: &[f64]) {
+		assert_eq!(roots.len(), expected.len(), "`{equation}` has the roots {roots:?}");
+		for (root, expected) in roots.iter().zip(expected) {
+			assert!((root - expected).abs() <= 1e-9 * expected.abs().max(1.0), "`{equation}` has the roots {roots:?}");
+		}
+	}
+
+	fn solve_with(solver: Solver, equation: &str, env: &Environment<Value>) -> Result<Solution, PostfixError> {
+		let (left, right) = ShuntingYard::parse_equation(equation, Syntax::default()).unwrap();
+		solver.solve(&left, &right, "x", env)
+	}
+
+	fn solve(equation: &str) -> Result<Solution, PostfixError> {
+		solve_with(Solver::default(), equation, &Environment::new())
+	}
+
+	fn assert_roots(equation: &str, expected: &[f64]) {
+		assert_close(equation, &roots(Solver::default(), equation, &Environment::new()), expected);
+	}
+
+	#[test]
+	fn closed_form_test() {
+		assert_eq!(solve("2*x^2 - 3*x = 5"), Ok(Solution::Roots(vec![-1.0, 2.5])));
+		assert_eq!(solve("3 * (x - 4) = x / 2"), Ok(Solution::Roots(vec![4.8])));
+		assert_eq!(solve("(x + 1)^2 = 0"), Ok(Solution::Roots(vec![-1.0])));
+		assert_roots("x^2 = 2", &[-consts::SQRT_2, consts::SQRT_2]);
+		assert_eq!(solve("x^2 + x"), Ok(Solution::Roots(vec![-1.0, 0.0])));
+		assert_eq!(solve("x^2 + 1 = 0"), Ok(Solution::Roots(vec![])));
+		assert_eq!(solve("2 * (x + 1) = 2*x + 2"), Ok(Solution::Identity));
+		assert_eq!(solve("x = x + 1"), Ok(Solution::Roots(vec![])));
+		// the huge coefficients would cancel out in the textbook formula
+		assert_roots("x^2 + 1e9*x + 1 = 0", &[-1e9, -1e-9]);
+
+		let mut env = Environment::new();
+		env.set("a", Value::Number(2.0));
+		env.set("x", Value::Number(100.0));
+		assert_eq!(solve_with(Solver::default(), "a*x = 6", &env), Ok(Solution::Roots(vec![3.0])));
+	}
+
+	#[test]
+	fn numeric_test() {
+		assert_roots("x^3 - 2*x - 5 = 0", &[2.0945514815423265]);
+		assert_roots("(x - 1) * (x - 2) * (x - 3) = 0", &[1.0, 2.0, 3.0]);
+		assert_roots("exp(x) = 10", &[consts::LN_10]);
+		assert_roots("sqrt(x) = 3", &[9.0]);
+		assert_roots("x * ln(x) = 1", &[1.7632228343518968]);
+		assert_roots("(x - 1)^4 = 0", &[1.0]);
+		assert_roots("(x - 0.25)^4 = 0", &[0.25]);
+		assert_roots("1 / x = 0", &[]);
+
+		let solver = Solver {
+			range: (0.0, 3.0),
+			samples: 300,
+		};
+		let env = Environment::new();
+		let equation = "sin(x) = 0.5";
+		assert_close(equation, &roots(solver, equation, &env), &[consts::FRAC_PI_6, 5.0 * consts::FRAC_PI_6]);
+		assert_eq!(
+			solve_with(solver, "x + y = 1", &env),
+			Err(PostfixError::UnknownIdentifier(String::from("y"), Span::new(4, 5)))
+		);
+	}
+}
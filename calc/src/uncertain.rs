@@ -0,0 +1,347 @@
+use std::{cmp::Ordering, f64::consts, fmt};
+
+use crate::{
+	format::Format,
+	functions,
+	numeric::{FloatMode, Numeric, NumericError},
+};
+
+/// A measured value with the standard deviation of its error
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Uncertain {
+	pub value: f64,
+	pub sigma: f64,
+}
+
+impl Uncertain {
+	pub const fn new(value: f64, sigma: f64) -> Self {
+		Self { value, sigma }
+	}
+
+	pub const fn exact(value: f64) -> Self {
+		Self::new(value, 0.0)
+	}
+
+	// to first order the errors of independent operands add up in quadrature, each of them weighted by the slope of
+	// the result along its operand, given here as `(slope, sigma)`
+	fn propagated(value: f64, terms: &[(f64, f64)]) -> Self {
+		let sigma =
+			terms.iter().filter(|(_, sigma)| *sigma != 0.0).map(|(slope, sigma)| slope * sigma).fold(0.0, f64::hypot);
+		Self::new(value, sigma)
+	}
+
+	fn is_finite(&self) -> bool {
+		self.value.is_finite() && self.sigma.is_finite()
+	}
+
+	// `19.62 ± 0.98` with the error rounded to two significant digits and the value to the same place, both written
+	// by `part`
+	fn written(&self, part: impl Fn(String) -> String) -> String {
+		if self.sigma == 0.0 || self.value.is_nan() {
+			return part(self.value.to_string());
+		}
+		if !self.is_finite() {
+			return format!("{} ± {}", part(self.value.to_string()), part(self.sigma.to_string()));
+		}
+
+		let place = self.sigma.log10().floor() as i32 - 1;
+		let (value, sigma) = if place < 0 {
+			let decimals = place.unsigned_abs() as usize;
+			(format!("{:.decimals$}", self.value), format!("{:.decimals$}", self.sigma))
+		} else {
+			let rounded = |number: f64| ((number / 10f64.powi(place)).round() * 10f64.powi(place)).to_string();
+			(rounded(self.value), rounded(self.sigma))
+		};
+		format!("{} ± {}", part(value), part(sigma))
+	}
+}
+
+impl fmt::Display for Uncertain {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.written(|part| part))
+	}
+}
+
+/// Gaussian error propagation to first order where every value has a standard deviation
+///
+/// The errors of the operands of an operation are taken to be independent, so `x - x` has an error unless `x` is
+/// exact. Comparisons only look at the values.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UncertainMode;
+
+impl UncertainMode {
+	// only a finite calculation turning infinite is an overflow like in `FloatMode`
+	fn checked(result: Uncertain, operands: &[Uncertain]) -> Result<Uncertain, NumericError> {
+		if !result.is_finite()
+			&& !result.value.is_nan()
+			&& !result.sigma.is_nan()
+			&& operands.iter().all(Uncertain::is_finite)
+		{
+			Err(NumericError::Overflow)
+		} else {
+			Ok(result)
+		}
+	}
+
+	// the partial derivatives of a built-in function, `nan` where it has none like `abs` at zero or `floor` at an
+	// integer
+	fn slopes(name: &str, args: &[f64]) -> Vec<f64> {
+		let none = f64::NAN;
+		match (name, args) {
+			("sin", [x]) => vec![x.cos()],
+			("cos", [x]) => vec![-x.sin()],
+			("tan", [x]) => vec![1.0 / x.cos().powi(2)],
+			("asin", [x]) => vec![1.0 / (1.0 - x * x).sqrt()],
+			("acos", [x]) => vec![-1.0 / (1.0 - x * x).sqrt()],
+			("atan", [x]) => vec![1.0 / (1.0 + x * x)],
+			("atan2", [y, x]) => {
+				let square = x * x + y * y;
+				vec![x / square, -y / square]
+			},
+			("sinh", [x]) => vec![x.cosh()],
+			("cosh", [x]) => vec![x.sinh()],
+			("tanh", [x]) => vec![1.0 / x.cosh().powi(2)],
+			("sqrt", [x]) => vec![0.5 / x.sqrt()],
+			("cbrt", [x]) => vec![1.0 / (3.0 * x.cbrt().powi(2))],
+			("exp", [x]) => vec![x.exp()],
+			("ln", [x]) => vec![1.0 / x],
+			("log", [x]) => vec![1.0 / (x * consts::LN_10)],
+			("log", [x, base]) => vec![1.0 / (x * base.ln()), -x.ln() / (base * base.ln().powi(2))],
+			("log2", [x]) => vec![1.0 / (x * consts::LN_2)],
+			("abs" | "arg", [x]) if *x == 0.0 => vec![none],
+			("abs", [x]) => vec![x.signum()],
+			("arg" | "im", [_]) => vec![0.0],
+			("conj" | "re", [_]) => vec![1.0],
+			// flat but where they jump
+			("floor" | "ceil", [x]) => vec![if x.fract() == 0.0 { none } else { 0.0 }],
+			("round", [x]) => vec![if x.fract().abs() == 0.5 { none } else { 0.0 }],
+			("min" | "max", _) => {
+				let chosen = functions::implementation(name).map_or(none, |(_, call)| call(args));
+				let ties = args.iter().filter(|arg| **arg == chosen).count();
+				args
+					.iter()
+					.map(|arg| match *arg == chosen {
+						true if ties > 1 => none,
+						true => 1.0,
+						false => 0.0,
+					})
+					.collect()
+			},
+			("hypot", _) => {
+				let hypot = args.iter().fold(0.0, |hypot: f64, arg| hypot.hypot(*arg));
+				args.iter().map(|arg| arg / hypot).collect()
+			},
+			// only real at multiples of half a turn, which a changing angle leaves right away
+			("rect", [_, angle]) => vec![angle.cos(), none],
+			_ => vec![none; args.len()],
+		}
+	}
+}
+
+impl Numeric for UncertainMode {
+	type Value = Uncertain;
+
+	const EXACT: bool = false;
+
+	fn value(&self, number: f64) -> Result<Uncertain, NumericError> {
+		FloatMode.value(number).map(Uncertain::exact)
+	}
+
+	fn unchecked(&self, number: f64) -> Result<Uncertain, NumericError> {
+		Ok(Uncertain::exact(number))
+	}
+
+	fn to_f64(&self, value: &Uncertain) -> f64 {
+		value.value
+	}
+
+	fn integer(&self, integer: i128) -> Result<Uncertain, NumericError> {
+		Ok(Uncertain::exact(integer as f64))
+	}
+
+	fn to_integer(&self, value: &Uncertain) -> Option<i128> {
+		FloatMode.to_integer(&value.value).filter(|_| value.sigma == 0.0)
+	}
+
+	/// A value of zero has no first order error after a division by it whatever its sigma
+	fn is_zero(&self, value: &Uncertain) -> bool {
+		value.value == 0.0
+	}
+
+	fn is_negative(&self, value: &Uncertain) -> bool {
+		value.value < 0.0
+	}
+
+	fn compare(&self, a: &Uncertain, b: &Uncertain) -> Option<Ordering> {
+		a.value.partial_cmp(&b.value)
+	}
+
+	fn neg(&self, value: &Uncertain) -> Result<Uncertain, NumericError> {
+		Ok(Uncertain::new(-value.value, value.sigma))
+	}
+
+	fn add(&self, a: &Uncertain, b: &Uncertain) -> Result<Uncertain, NumericError> {
+		Self::checked(Uncertain::propagated(a.value + b.value, &[(1.0, a.sigma), (1.0, b.sigma)]), &[*a, *b])
+	}
+
+	fn sub(&self, a: &Uncertain, b: &Uncertain) -> Result<Uncertain, NumericError> {
+		Self::checked(Uncertain::propagated(a.value - b.value, &[(1.0, a.sigma), (-1.0, b.sigma)]), &[*a, *b])
+	}
+
+	fn mul(&self, a: &Uncertain, b: &Uncertain) -> Result<Uncertain, NumericError> {
+		Self::checked(Uncertain::propagated(a.value * b.value, &[(b.value, a.sigma), (a.value, b.sigma)]), &[*a, *b])
+	}
+
+	fn div(&self, a: &Uncertain, b: &Uncertain) -> Result<Uncertain, NumericError> {
+		let quotient = a.value / b.value;
+		let terms = [(1.0 / b.value, a.sigma), (-quotient / b.value, b.sigma)];
+		Self::checked(Uncertain::propagated(quotient, &terms), &[*a, *b])
+	}
+
+	fn rem(&self, a: &Uncertain, b: &Uncertain) -> Result<Uncertain, NumericError> {
+		let terms = [(1.0, a.sigma), (-(a.value / b.value).trunc(), b.sigma)];
+		Self::checked(Uncertain::propagated(a.value % b.value, &terms), &[*a, *b])
+	}
+
+	fn pow(&self, base: &Uncertain, exponent: &Uncertain) -> Result<Uncertain, NumericError> {
+		let power = base.value.powf(exponent.value);
+		let terms = [
+			(exponent.value * base.value.powf(exponent.value - 1.0), base.sigma),
+			(power * base.value.ln(), exponent.sigma),
+		];
+		Self::checked(Uncertain::propagated(power, &terms), &[*base, *exponent])
+	}
+
+	/// Exact since it doesn't change for small errors
+	fn floor(&self, value: &Uncertain) -> Uncertain {
+		Uncertain::exact(value.value.floor())
+	}
+
+	fn format_with(&self, value: &Uncertain, format: Format) -> String {
+		if value.sigma == 0.0 {
+			format.decimal(&self.format(value, format.radix))
+		} else {
+			value.written(|part| format.decimal(&part))
+		}
+	}
+
+	fn plus_minus(&self, value: &Uncertain, error: &Uncertain) -> Option<Result<Uncertain, NumericError>> {
+		let uncertain = Uncertain::new(value.value, value.sigma.hypot(error.value));
+		Some(Self::checked(uncertain, &[*value, *error]))
+	}
+
+	fn parts(&self, value: &Uncertain) -> Vec<f64> {
+		vec![value.value, value.sigma]
+	}
+
+	/// The errors of an integrand come from the same measurements everywhere so they add up as they are
+	fn joined(&self, areas: &[f64]) -> Result<Uncertain, NumericError> {
+		Ok(Uncertain::new(FloatMode.value(areas[0])?, FloatMode.value(areas[1])?.abs()))
+	}
+
+	fn function(&self, name: &str, args: &[Uncertain]) -> Option<Result<Uncertain, NumericError>> {
+		// exact arguments go through `f64` like in `FloatMode`
+		if args.iter().all(|arg| arg.sigma == 0.0) {
+			return None;
+		}
+		let (_, call) = functions::implementation(name).filter(|(arity, _)| arity.accepts(args.len()))?;
+
+		let values = args.iter().map(|arg| arg.value).collect::<Vec<f64>>();
+		let value = call(&values);
+		let terms = Self::slopes(name, &values)
+			.into_iter()
+			.zip(args)
+			.map(|(slope, arg)| (slope, arg.sigma))
+			.collect::<Vec<(f64, f64)>>();
+		// only the slopes along arguments with an error matter
+		if !value.is_nan() && terms.iter().any(|(slope, sigma)| *sigma != 0.0 && !slope.is_finite()) {
+			return Some(Err(NumericError::NotDifferentiable));
+		}
+		Some(Self::checked(Uncertain::propagated(value, &terms), args))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_close(actual: Uncertain, expected: Uncertain) {
+		let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * b.abs().max(1.0);
+		assert!(
+			close(actual.value, expected.value) && close(actual.sigma, expected.sigma),
+			"{actual:?} is not {expected:?}"
+		);
+	}
+
+	#[test]
+	fn display_test() {
+		assert_eq!(Uncertain::new(19.62, 0.981_814_6).to_string(), "19.62 ± 0.98");
+		assert_eq!(Uncertain::new(9.81, 0.02).to_string(), "9.810 ± 0.020");
+		assert_eq!(Uncertain::new(1234.5, 56.0).to_string(), "1235 ± 56");
+		assert_eq!(Uncertain::new(12345.0, 678.0).to_string(), "12350 ± 680");
+		assert_eq!(Uncertain::exact(2.5).to_string(), "2.5");
+		assert_eq!(Uncertain::new(1.0, f64::INFINITY).to_string(), "1 ± inf");
+
+		let format = Format {
+			digits: Some(2),
+			..Format::default()
+		};
+		assert_eq!(UncertainMode.format_with(&Uncertain::new(19.62, 0.98), format), "20 ± 0.98");
+	}
+
+	#[test]
+	fn propagation_test() {
+		let mode = UncertainMode;
+		let (g, t) = (Uncertain::new(9.81, 0.02), Uncertain::new(2.0, 0.1));
+
+		assert_close(mode.mul(&g, &t).unwrap(), Uncertain::new(19.62, 0.04f64.hypot(0.981)));
+		assert_close(mode.add(&g, &t).unwrap(), Uncertain::new(11.81, 0.02f64.hypot(0.1)));
+		assert_close(mode.sub(&g, &t).unwrap(), Uncertain::new(7.81, 0.02f64.hypot(0.1)));
+		assert_close(mode.div(&Uncertain::new(1.0, 0.1), &t).unwrap(), Uncertain::new(0.5, 0.05f64.hypot(0.025)));
+		assert_close(mode.pow(&t, &Uncertain::exact(2.0)).unwrap(), Uncertain::new(4.0, 0.4));
+		assert_close(mode.pow(&Uncertain::exact(2.0), &t).unwrap(), Uncertain::new(4.0, 0.4 * 2f64.ln()));
+		assert_close(mode.neg(&t).unwrap(), Uncertain::new(-2.0, 0.1));
+		assert_eq!(mode.floor(&Uncertain::new(2.5, 0.1)), Uncertain::exact(2.0));
+		assert_close(
+			mode.plus_minus(&t, &Uncertain::exact(-0.2)).unwrap().unwrap(),
+			Uncertain::new(2.0, 0.1f64.hypot(0.2)),
+		);
+		assert_eq!(mode.mul(&Uncertain::new(1e300, 1.0), &Uncertain::exact(1e10)), Err(NumericError::Overflow));
+
+		assert_eq!(mode.compare(&g, &t), Some(Ordering::Greater));
+		assert_eq!(mode.to_integer(&Uncertain::exact(3.0)), Some(3));
+		assert_eq!(mode.to_integer(&Uncertain::new(3.0, 0.1)), None);
+	}
+
+	#[test]
+	fn function_test() {
+		let call =
+			|name, args: &[Uncertain]| UncertainMode.function(name, args).expect("the function should be uncertain").unwrap();
+
+		assert_close(call("sin", &[Uncertain::new(0.0, 0.1)]), Uncertain::new(0.0, 0.1));
+		assert_close(call("sqrt", &[Uncertain::new(4.0, 0.4)]), Uncertain::new(2.0, 0.1));
+		assert_close(call("ln", &[Uncertain::new(2.0, 0.2)]), Uncertain::new(2f64.ln(), 0.1));
+		assert_close(call("hypot", &[Uncertain::new(3.0, 0.5), Uncertain::exact(4.0)]), Uncertain::new(5.0, 0.3));
+		assert_close(call("max", &[Uncertain::new(3.0, 0.5), Uncertain::new(1.0, 0.2)]), Uncertain::new(3.0, 0.5));
+		assert_close(
+			call("atan2", &[Uncertain::new(1.0, 0.1), Uncertain::exact(1.0)]),
+			Uncertain::new(consts::FRAC_PI_4, 0.05),
+		);
+		assert_close(call("log", &[Uncertain::new(100.0, 1.0)]), Uncertain::new(2.0, 0.01 / consts::LN_10));
+		assert_close(call("abs", &[Uncertain::new(-2.0, 0.1)]), Uncertain::new(2.0, 0.1));
+		assert_eq!(call("floor", &[Uncertain::new(2.5, 0.1)]), Uncertain::exact(2.0));
+		// where the functions jump or turn or their slope is infinite there's nothing to carry the error
+		let jump = |name, args: &[Uncertain]| UncertainMode.function(name, args);
+		assert_eq!(jump("floor", &[Uncertain::new(2.0, 0.1)]), Some(Err(NumericError::NotDifferentiable)));
+		assert_eq!(jump("round", &[Uncertain::new(1.5, 1.0)]), Some(Err(NumericError::NotDifferentiable)));
+		assert_eq!(jump("sqrt", &[Uncertain::new(0.0, 1.0)]), Some(Err(NumericError::NotDifferentiable)));
+		assert_eq!(jump("abs", &[Uncertain::new(0.0, 1.0)]), Some(Err(NumericError::NotDifferentiable)));
+		assert_eq!(
+			jump("min", &[Uncertain::new(1.0, 0.1), Uncertain::new(1.0, 0.2)]),
+			Some(Err(NumericError::NotDifferentiable))
+		);
+		assert!(jump("sqrt", &[Uncertain::new(-1.0, 0.1)]).unwrap().unwrap().value.is_nan());
+		assert_eq!(UncertainMode.function("sqrt", &[Uncertain::exact(4.0)]), None);
+		assert_eq!(UncertainMode.function("transpose", &[Uncertain::new(4.0, 1.0)]), None);
+	}
+}
@@ -0,0 +1,421 @@
+use std::{f64::consts, fmt};
+
+use crate::{
+	expr::{Ast, Expr},
+	numeric::{Numeric, NumericError},
+	postfix::PostfixError,
+	shunting::Operator,
+};
+
+/// Symbols of the SI base units in the order their exponents are stored in a `Dimension`
+const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+// no exponent may grow beyond this so a dimension never overflows
+const MAX_EXPONENT: i64 = 1_000_000;
+
+/// The exponents of the SI base units making up a physical dimension, velocity is `m^1 s^-1`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Dimension([i32; 7]);
+
+impl Dimension {
+	pub const NONE: Dimension = Dimension([0; 7]);
+
+	// `None` when an exponent is out of range
+	fn checked(exponents: [i64; 7]) -> Option<Dimension> {
+		let mut dimension = Dimension::NONE;
+		for (power, exponent) in dimension.0.iter_mut().zip(exponents) {
+			if exponent.abs() > MAX_EXPONENT {
+				return None;
+			}
+			*power = exponent as i32;
+		}
+		Some(dimension)
+	}
+
+	pub fn is_dimensionless(&self) -> bool {
+		*self == Self::NONE
+	}
+
+	pub fn checked_mul(self, other: Dimension) -> Option<Dimension> {
+		Self::checked(std::array::from_fn(|index| i64::from(self.0[index]) + i64::from(other.0[index])))
+	}
+
+	pub fn checked_div(self, other: Dimension) -> Option<Dimension> {
+		Self::checked(std::array::from_fn(|index| i64::from(self.0[index]) - i64::from(other.0[index])))
+	}
+
+	/// `None` when an exponent wouldn't be an integer like for the square root of a length
+	pub fn pow(self, exponent: f64) -> Option<Dimension> {
+		if self.is_dimensionless() {
+			return Some(self);
+		}
+
+		let mut exponents = [0; 7];
+		for (power, base) in exponents.iter_mut().zip(self.0) {
+			let product = f64::from(base) * exponent;
+			if product.fract() != 0.0 || product.abs() > MAX_EXPONENT as f64 {
+				return None;
+			}
+			*power = product as i64;
+		}
+		Self::checked(exponents)
+	}
+
+	/// `None` when an exponent isn't divisible by the degree of the root
+	pub fn root(self, degree: i32) -> Option<Dimension> {
+		if self.0.iter().any(|power| power % degree != 0) {
+			return None;
+		}
+		Some(Dimension(self.0.map(|power| power / degree)))
+	}
+}
+
+// named units read better than their base units so `kg*m/s^2` is written as `N`
+const DERIVED_NAMES: &[&str] = &["N", "J", "W", "Pa", "C", "V", "ohm", "F", "Wb", "T", "H"];
+
+impl fmt::Display for Dimension {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some(name) = DERIVED_NAMES.iter().find(|name| unit(name).is_some_and(|unit| unit.dimension == *self)) {
+			return write!(f, "{name}");
+		}
+
+		let power = |symbol: &str, power: i32| {
+			if power == 1 {
+				symbol.to_string()
+			} else {
+				format!("{symbol}^{power}")
+			}
+		};
+		let numerator = BASE_UNITS
+			.iter()
+			.zip(self.0)
+			.filter(|(_, exponent)| *exponent > 0)
+			.map(|(symbol, exponent)| power(symbol, exponent))
+			.collect::<Vec<String>>();
+		// without a numerator the exponents stay negative instead of writing `1/s`
+		let sign = if numerator.is_empty() { -1 } else { 1 };
+		let denominator = BASE_UNITS
+			.iter()
+			.zip(self.0)
+			.filter(|(_, exponent)| *exponent < 0)
+			.map(|(symbol, exponent)| power(symbol, -exponent * sign))
+			.collect::<Vec<String>>();
+
+		match (numerator.as_slice(), denominator.as_slice()) {
+			([], []) => Ok(()),
+			([], denominator) => write!(f, "{}", denominator.join("*")),
+			(numerator, []) => write!(f, "{}", numerator.join("*")),
+			(numerator, [denominator]) => write!(f, "{}/{denominator}", numerator.join("*")),
+			(numerator, denominator) => write!(f, "{}/({})", numerator.join("*"), denominator.join("*")),
+		}
+	}
+}
+
+/// `factor / divisor` of the SI base units of a dimension
+///
+/// Keeping the divisor apart leaves units like `km/h` as the integers `1000 / 3600` so converting between them
+/// doesn't round more than it has to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Unit {
+	pub factor: f64,
+	pub divisor: f64,
+	pub dimension: Dimension,
+}
+
+impl Unit {
+	const fn new(factor: f64, divisor: f64, exponents: [i32; 7]) -> Self {
+		Self {
+			factor,
+			divisor,
+			dimension: Dimension(exponents),
+		}
+	}
+
+	fn mul(self, other: Unit) -> Option<Unit> {
+		Some(Unit {
+			factor: self.factor * other.factor,
+			divisor: self.divisor * other.divisor,
+			dimension: self.dimension.checked_mul(other.dimension)?,
+		})
+	}
+
+	fn div(self, other: Unit) -> Option<Unit> {
+		Some(Unit {
+			factor: self.factor * other.divisor,
+			divisor: self.divisor * other.factor,
+			dimension: self.dimension.checked_div(other.dimension)?,
+		})
+	}
+
+	/// The magnitude in SI base units of a value given in this unit
+	pub fn magnitude<N: Numeric>(&self, numeric: &N, value: &N::Value) -> Result<N::Value, NumericError> {
+		numeric.div(&numeric.mul(value, &numeric.value(self.factor)?)?, &numeric.value(self.divisor)?)
+	}
+
+	/// How much of this unit a magnitude in SI base units is
+	pub fn amount<N: Numeric>(&self, numeric: &N, magnitude: &N::Value) -> Result<N::Value, NumericError> {
+		numeric.div(&numeric.mul(magnitude, &numeric.value(self.divisor)?)?, &numeric.value(self.factor)?)
+	}
+
+	/// `None` when the exponents of the dimension get out of range
+	pub fn powi(self, power: i32) -> Option<Unit> {
+		let unit = Unit {
+			factor: self.factor.powi(power.abs()),
+			divisor: self.divisor.powi(power.abs()),
+			dimension: self.dimension.pow(f64::from(power))?,
+		};
+		if power < 0 {
+			Some(Unit {
+				factor: unit.divisor,
+				divisor: unit.factor,
+				..unit
+			})
+		} else {
+			Some(unit)
+		}
+	}
+}
+
+struct Definition {
+	name: &'static str,
+	unit: Unit,
+	/// Whether the SI prefixes like the `k` of `km` can be put in front of it
+	prefixed: bool,
+}
+
+const fn si(name: &'static str, factor: f64, divisor: f64, exponents: [i32; 7]) -> Definition {
+	Definition {
+		name,
+		unit: Unit::new(factor, divisor, exponents),
+		prefixed: true,
+	}
+}
+
+const fn other(name: &'static str, factor: f64, divisor: f64, exponents: [i32; 7]) -> Definition {
+	Definition {
+		name,
+		unit: Unit::new(factor, divisor, exponents),
+		prefixed: false,
+	}
+}
+
+// exponents of `m`, `kg`, `s`, `A`, `K`, `mol` and `cd`
+const LENGTH: [i32; 7] = [1, 0, 0, 0, 0, 0, 0];
+const MASS: [i32; 7] = [0, 1, 0, 0, 0, 0, 0];
+const TIME: [i32; 7] = [0, 0, 1, 0, 0, 0, 0];
+const AREA: [i32; 7] = [2, 0, 0, 0, 0, 0, 0];
+const VOLUME: [i32; 7] = [3, 0, 0, 0, 0, 0, 0];
+const SPEED: [i32; 7] = [1, 0, -1, 0, 0, 0, 0];
+const FORCE: [i32; 7] = [1, 1, -2, 0, 0, 0, 0];
+const PRESSURE: [i32; 7] = [-1, 1, -2, 0, 0, 0, 0];
+const ENERGY: [i32; 7] = [2, 1, -2, 0, 0, 0, 0];
+const POWER: [i32; 7] = [2, 1, -3, 0, 0, 0, 0];
+const DIMENSIONLESS: [i32; 7] = [0; 7];
+
+const UNITS: &[Definition] = &[
+	// SI base units, the kilogram is a prefixed gram
+	si("m", 1.0, 1.0, LENGTH),
+	si("g", 1.0, 1000.0, MASS),
+	si("s", 1.0, 1.0, TIME),
+	si("A", 1.0, 1.0, [0, 0, 0, 1, 0, 0, 0]),
+	si("K", 1.0, 1.0, [0, 0, 0, 0, 1, 0, 0]),
+	si("mol", 1.0, 1.0, [0, 0, 0, 0, 0, 1, 0]),
+	si("cd", 1.0, 1.0, [0, 0, 0, 0, 0, 0, 1]),
+	// derived SI units
+	si("Hz", 1.0, 1.0, [0, 0, -1, 0, 0, 0, 0]),
+	si("N", 1.0, 1.0, FORCE),
+	si("Pa", 1.0, 1.0, PRESSURE),
+	si("J", 1.0, 1.0, ENERGY),
+	si("W", 1.0, 1.0, POWER),
+	si("C", 1.0, 1.0, [0, 0, 1, 1, 0, 0, 0]),
+	si("V", 1.0, 1.0, [2, 1, -3, -1, 0, 0, 0]),
+	si("ohm", 1.0, 1.0, [2, 1, -3, -2, 0, 0, 0]),
+	si("F", 1.0, 1.0, [-2, -1, 4, 2, 0, 0, 0]),
+	si("Wb", 1.0, 1.0, [2, 1, -2, -1, 0, 0, 0]),
+	si("T", 1.0, 1.0, [0, 1, -2, -1, 0, 0, 0]),
+	si("H", 1.0, 1.0, [2, 1, -2, -2, 0, 0, 0]),
+	// common units outside of SI
+	si("L", 1.0, 1000.0, VOLUME),
+	si("l", 1.0, 1000.0, VOLUME),
+	si("t", 1000.0, 1.0, MASS),
+	si("bar", 100_000.0, 1.0, PRESSURE),
+	si("eV", 1.602176634e-19, 1.0, ENERGY),
+	si("Wh", 3600.0, 1.0, ENERGY),
+	si("cal", 4.184, 1.0, ENERGY),
+	other("min", 60.0, 1.0, TIME),
+	other("h", 3600.0, 1.0, TIME),
+	other("day", 86_400.0, 1.0, TIME),
+	other("week", 604_800.0, 1.0, TIME),
+	other("year", 31_557_600.0, 1.0, TIME),
+	other("ha", 10_000.0, 1.0, AREA),
+	other("atm", 101_325.0, 1.0, PRESSURE),
+	other("rad", 1.0, 1.0, DIMENSIONLESS),
+	other("deg", consts::PI, 180.0, DIMENSIONLESS),
+	// imperial and US customary units
+	other("inch", 254.0, 10_000.0, LENGTH),
+	other("ft", 3048.0, 10_000.0, LENGTH),
+	other("yd", 9144.0, 10_000.0, LENGTH),
+	other("mi", 1_609_344.0, 1000.0, LENGTH),
+	other("nmi", 1852.0, 1.0, LENGTH),
+	other("acre", 40_468_564_224.0, 10_000_000.0, AREA),
+	other("gal", 3_785_411_784.0, 1e12, VOLUME),
+	other("oz", 28_349_523_125.0, 1e12, MASS),
+	other("lb", 45_359_237.0, 100_000_000.0, MASS),
+	other("mph", 1_609_344.0, 3_600_000.0, SPEED),
+	other("knot", 1852.0, 3600.0, SPEED),
+	other("lbf", 4.448_221_615_260_5, 1.0, FORCE),
+	other("psi", 6_894.757_293_168_361, 1.0, PRESSURE),
+	other("hp", 745.699_871_582_270_2, 1.0, POWER),
+];
+
+// big prefixes multiply and small ones divide so both stay integers
+const PREFIXES: &[(&str, f64, f64)] = &[
+	("T", 1e12, 1.0),
+	("G", 1e9, 1.0),
+	("M", 1e6, 1.0),
+	("k", 1e3, 1.0),
+	("h", 1e2, 1.0),
+	("d", 1.0, 1e1),
+	("c", 1.0, 1e2),
+	("m", 1.0, 1e3),
+	("u", 1.0, 1e6),
+	("n", 1.0, 1e9),
+	("p", 1.0, 1e12),
+	("f", 1.0, 1e15),
+];
+
+/// Looks up a unit by its symbol, units win over prefixed ones so `min` is a minute and not a milli-inch
+pub fn unit(name: &str) -> Option<Unit> {
+	if let Some(definition) = UNITS.iter().find(|definition| definition.name == name) {
+		return Some(definition.unit);
+	}
+
+	PREFIXES.iter().find_map(|(prefix, factor, divisor)| {
+		let name = name.strip_prefix(prefix)?;
+		let definition = UNITS.iter().find(|definition| definition.prefixed && definition.name == name)?;
+		Some(Unit::new(definition.unit.factor * factor, definition.unit.divisor * divisor, definition.unit.dimension.0))
+	})
+}
+
+/// A unit written right after a number like the `km` of `3 km` or the `m^2` of `5 m^2`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+	pub name: String,
+	pub power: i32,
+}
+
+impl Symbol {
+	/// `None` when the power takes the exponents of the dimension out of range
+	pub fn unit(&self) -> Option<Unit> {
+		unit(&self.name).expect("BUG: symbol of an unknown unit").powi(self.power)
+	}
+}
+
+impl fmt::Display for Symbol {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if self.power == 1 {
+			write!(f, "{}", self.name)
+		} else {
+			write!(f, "{}^{}", self.name, self.power)
+		}
+	}
+}
+
+/// The unit on the right of `in` like the `km/h` of `x in km/h`, only units, numbers and the operators
+/// combining them are allowed
+pub fn target(ast: &Ast) -> Result<Unit, PostfixError> {
+	let unit = compose(ast)?;
+	// converting into `0 m` would divide by zero
+	if unit.factor == 0.0 || unit.divisor == 0.0 || !unit.factor.is_finite() || !unit.divisor.is_finite() {
+		return Err(PostfixError::NotAUnit(ast.span));
+	}
+	Ok(unit)
+}
+
+fn compose(ast: &Ast) -> Result<Unit, PostfixError> {
+	let out_of_range = || PostfixError::InvalidUnitPower(ast.span);
+
+	match &ast.node {
+		Expr::Variable(name) => unit(name).ok_or_else(|| PostfixError::UnknownUnit(name.clone(), ast.span)),
+		Expr::Number(number) => Ok(Unit::new(*number, 1.0, DIMENSIONLESS)),
+		Expr::Quantity(number, symbol) => {
+			Unit::new(*number, 1.0, DIMENSIONLESS).mul(symbol.unit().ok_or_else(out_of_range)?).ok_or_else(out_of_range)
+		},
+		Expr::Binary(Operator::Times, left, right) => compose(left)?.mul(compose(right)?).ok_or_else(out_of_range),
+		Expr::Binary(Operator::Divided, left, right) => compose(left)?.div(compose(right)?).ok_or_else(out_of_range),
+		Expr::Binary(Operator::Power, base, exponent) => {
+			let power = match &exponent.node {
+				Expr::Number(power) => *power,
+				Expr::Unary(Operator::Negate, power) => match power.node {
+					Expr::Number(power) => -power,
+					_ => return Err(PostfixError::NotAUnit(exponent.span)),
+				},
+				_ => return Err(PostfixError::NotAUnit(exponent.span)),
+			};
+			if power.fract() != 0.0 || power.abs() > MAX_EXPONENT as f64 {
+				return Err(out_of_range());
+			}
+			compose(base)?.powi(power as i32).ok_or_else(out_of_range)
+		},
+		_ => Err(PostfixError::NotAUnit(ast.span)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::shunting::{ShuntingYard, Span, Syntax};
+
+	fn target(expression: &str) -> Result<Unit, PostfixError> {
+		super::target(&ShuntingYard::parse(expression, Syntax::default()).unwrap())
+	}
+
+	#[test]
+	fn unit_test() {
+		assert_eq!(unit("m"), Some(Unit::new(1.0, 1.0, LENGTH)));
+		assert_eq!(unit("km"), Some(Unit::new(1000.0, 1.0, LENGTH)));
+		assert_eq!(unit("kg"), Some(Unit::new(1000.0, 1000.0, MASS)));
+		assert_eq!(unit("ms"), Some(Unit::new(1.0, 1000.0, TIME)));
+		assert_eq!(unit("min"), Some(Unit::new(60.0, 1.0, TIME)));
+		assert_eq!(unit("kWh"), Some(Unit::new(3_600_000.0, 1.0, ENERGY)));
+		assert_eq!(unit("kft"), None);
+		assert_eq!(unit("x"), None);
+		assert_eq!(unit("k"), None);
+	}
+
+	#[test]
+	fn target_test() {
+		assert_eq!(target("km/h"), Ok(Unit::new(1000.0, 3600.0, SPEED)));
+		assert_eq!(target("m/s^2"), Ok(Unit::new(1.0, 1.0, [1, 0, -2, 0, 0, 0, 0])));
+		assert_eq!(target("s^-1"), Ok(Unit::new(1.0, 1.0, [0, 0, -1, 0, 0, 0, 0])));
+		assert_eq!(target("kg*m^2"), Ok(Unit::new(1000.0, 1000.0, [2, 1, 0, 0, 0, 0, 0])));
+		assert_eq!(target("100 m"), Ok(Unit::new(100.0, 1.0, LENGTH)));
+		assert_eq!(target("furlong"), Err(PostfixError::UnknownUnit(String::from("furlong"), Span::new(0, 7))));
+		assert_eq!(target("m + s"), Err(PostfixError::NotAUnit(Span::new(0, 5))));
+		assert_eq!(target("m^x"), Err(PostfixError::NotAUnit(Span::new(2, 3))));
+	}
+
+	#[test]
+	fn dimension_test() {
+		let dimension = |exponents| Dimension(exponents);
+
+		assert_eq!(dimension(LENGTH).to_string(), "m");
+		assert_eq!(dimension(SPEED).to_string(), "m/s");
+		assert_eq!(dimension(FORCE).to_string(), "N");
+		assert_eq!(dimension(POWER).to_string(), "W");
+		assert_eq!(dimension([0, 0, -1, 0, 0, 0, 0]).to_string(), "s^-1");
+		assert_eq!(dimension([0, 0, -2, 0, 0, 0, 0]).to_string(), "s^-2");
+		assert_eq!(dimension([3, 0, 0, 0, 0, 0, 0]).to_string(), "m^3");
+		assert_eq!(dimension([1, 1, -1, 0, 0, 0, 0]).to_string(), "m*kg/s");
+		assert_eq!(dimension([0, 1, -2, 0, -1, 0, 0]).to_string(), "kg/(s^2*K)");
+
+		assert_eq!(dimension(AREA).pow(0.5), Some(dimension(LENGTH)));
+		assert_eq!(dimension(LENGTH).pow(0.5), None);
+		assert_eq!(dimension(LENGTH).pow(f64::INFINITY), None);
+		assert_eq!(Dimension::NONE.pow(0.5), Some(Dimension::NONE));
+		assert_eq!(dimension(VOLUME).root(3), Some(dimension(LENGTH)));
+		assert_eq!(dimension(AREA).root(3), None);
+		assert_eq!(dimension(SPEED).checked_mul(dimension(TIME)), Some(dimension(LENGTH)));
+		assert_eq!(dimension(LENGTH).checked_div(dimension(SPEED)), Some(dimension(TIME)));
+	}
+}
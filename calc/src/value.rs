@@ -0,0 +1,83 @@
+use std::fmt;
+
+use crate::{format::Format, matrix::Matrix, numeric::Numeric, units::Dimension};
+
+/// What an expression evaluates to, a number of the numeric mode in use or the result of a comparison
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<N = f64> {
+	Number(N),
+	Bool(bool),
+	Quantity(Quantity<N>),
+	Vector(Vec<N>),
+	Matrix(Matrix<N>),
+}
+
+/// A number with physical units
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity<N = f64> {
+	/// The magnitude in SI base units whatever unit it was written in
+	pub magnitude: N,
+	pub dimension: Dimension,
+	/// The magnitude in the unit it was converted to with `in` and how that unit was written
+	pub converted: Option<(N, String)>,
+}
+
+impl<N> Value<N> {
+	/// A number with units, or a plain number when the units cancel out
+	pub fn quantity(magnitude: N, dimension: Dimension) -> Self {
+		if dimension.is_dimensionless() {
+			Value::Number(magnitude)
+		} else {
+			Value::Quantity(Quantity {
+				magnitude,
+				dimension,
+				converted: None,
+			})
+		}
+	}
+
+	/// Prints numbers in the radix where the mode supports it and decimals in the style asked for
+	pub fn format<M: Numeric<Value = N>>(&self, numeric: &M, format: Format) -> String {
+		let number = |number| numeric.format_with(number, format);
+		match self {
+			Value::Number(value) => number(value),
+			Value::Bool(boolean) => boolean.to_string(),
+			Value::Quantity(Quantity {
+				converted: Some((magnitude, unit)),
+				..
+			}) => format!("{} {unit}", number(magnitude)),
+			Value::Quantity(quantity) => format!("{} {}", number(&quantity.magnitude), quantity.dimension),
+			Value::Vector(elements) => brackets(elements.iter().map(number)),
+			Value::Matrix(matrix) => brackets((0..matrix.rows()).map(|row| brackets(matrix.row(row).iter().map(number)))),
+		}
+	}
+}
+
+// `[1, 2, 3]`
+fn brackets(elements: impl Iterator<Item = String>) -> String {
+	format!("[{}]", elements.collect::<Vec<String>>().join(", "))
+}
+
+impl<N> From<N> for Value<N> {
+	fn from(number: N) -> Self {
+		Value::Number(number)
+	}
+}
+
+impl<N: fmt::Display> fmt::Display for Value<N> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Value::Number(number) => write!(f, "{number}"),
+			Value::Bool(boolean) => write!(f, "{boolean}"),
+			Value::Quantity(Quantity {
+				converted: Some((magnitude, unit)),
+				..
+			}) => write!(f, "{magnitude} {unit}"),
+			Value::Quantity(quantity) => write!(f, "{} {}", quantity.magnitude, quantity.dimension),
+			Value::Vector(elements) => write!(f, "{}", brackets(elements.iter().map(N::to_string))),
+			Value::Matrix(matrix) => {
+				write!(f, "{}", brackets((0..matrix.rows()).map(|row| brackets(matrix.row(row).iter().map(N::to_string)))))
+			},
+		}
+	}
+}
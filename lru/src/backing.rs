@@ -0,0 +1,363 @@
+use crate::LruCache;
+
+/// A persistent store sitting behind a [`PersistentCache`]
+pub trait Backing<K, V> {
+	type Error;
+
+	fn load(&mut self, key: &K) -> Result<Option<V>, Self::Error>;
+	fn store(&mut self, key: &K, value: &V) -> Result<(), Self::Error>;
+	fn remove(&mut self, key: &K) -> Result<(), Self::Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WriteMode {
+	/// every write goes to the backing store before it lands in the cache
+	WriteThrough,
+	/// writes only mark the entry dirty and reach the backing store on eviction, `flush` or drop
+	WriteBack,
+}
+
+#[derive(Debug, PartialEq)]
+struct Entry<V> {
+	value: V,
+	dirty: bool,
+}
+
+#[derive(Debug)]
+pub struct PersistentCache<K, V, B>
+where
+	K: Clone + Eq + std::hash::Hash,
+	B: Backing<K, V>,
+{
+	cache: LruCache<K, Entry<V>>,
+	backing: B,
+	mode: WriteMode,
+}
+
+impl<K, V, B> PersistentCache<K, V, B>
+where
+	K: Clone + Eq + std::hash::Hash,
+	B: Backing<K, V>,
+{
+	pub fn new(capacity: usize, backing: B, mode: WriteMode) -> Self {
+		Self {
+			cache: LruCache::new(capacity),
+			backing,
+			mode,
+		}
+	}
+
+	// a dirty entry about to be evicted is stored first so that a failing store leaves the cache untouched
+	fn make_room(&mut self, key: &K) -> Result<(), B::Error> {
+		if self.cache.len() == self.cache.capacity()
+			&& !self.cache.contains(key)
+			&& let Some((lru_key, entry)) = self.cache.peek_lru_mut()
+			&& entry.dirty
+		{
+			self.backing.store(lru_key, &entry.value)?;
+			entry.dirty = false;
+		}
+
+		Ok(())
+	}
+
+	pub fn read(&mut self, key: &K) -> Result<Option<&V>, B::Error> {
+		if !self.cache.contains(key) {
+			match self.backing.load(key)? {
+				Some(value) => {
+					self.make_room(key)?;
+					self.cache.write(key.clone(), Entry { value, dirty: false });
+				},
+				None => return Ok(None),
+			}
+		}
+
+		Ok(self.cache.read(key).map(|entry| &entry.value))
+	}
+
+	pub fn write(&mut self, key: K, value: V) -> Result<(), B::Error> {
+		self.make_room(&key)?;
+
+		let dirty = match self.mode {
+			WriteMode::WriteThrough => {
+				self.backing.store(&key, &value)?;
+				false
+			},
+			WriteMode::WriteBack => true,
+		};
+		self.cache.write(key, Entry { value, dirty });
+
+		Ok(())
+	}
+
+	pub fn delete(&mut self, key: &K) -> Result<(), B::Error> {
+		self.backing.remove(key)?;
+		// the key may only live in the backing store which is not an error here
+		let _ = self.cache.delete(key);
+
+		Ok(())
+	}
+
+	pub fn flush(&mut self) -> Result<(), B::Error> {
+		for (key, entry) in self.cache.entries_mut() {
+			if entry.dirty {
+				self.backing.store(key, &entry.value)?;
+				entry.dirty = false;
+			}
+		}
+
+		Ok(())
+	}
+
+	pub fn is_dirty(&self, key: &K) -> bool {
+		self.cache.map.get(key).and_then(|index| self.cache.items[*index].as_ref()).is_some_and(|node| node.value.dirty)
+	}
+
+	pub fn backing(&self) -> &B {
+		&self.backing
+	}
+
+	pub fn mode(&self) -> WriteMode {
+		self.mode
+	}
+
+	pub fn len(&self) -> usize {
+		self.cache.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.cache.is_empty()
+	}
+}
+
+impl<K, V, B> Drop for PersistentCache<K, V, B>
+where
+	K: Clone + Eq + std::hash::Hash,
+	B: Backing<K, V>,
+{
+	fn drop(&mut self) {
+		// nobody is left to hand an error to so this is a best effort
+		let _ = self.flush();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::{
+		cell::{Cell, RefCell},
+		collections::HashMap,
+		rc::Rc,
+	};
+
+	#[derive(Debug, PartialEq)]
+	enum MemoryError {
+		Offline,
+	}
+
+	// clones share the same rows so tests can look inside after the cache took ownership
+	#[derive(Debug, Clone, Default)]
+	struct MemoryBacking {
+		rows: Rc<RefCell<HashMap<i32, &'static str>>>,
+		stores: Rc<Cell<usize>>,
+		offline: Rc<Cell<bool>>,
+	}
+
+	impl MemoryBacking {
+		fn row(&self, key: i32) -> Option<&'static str> {
+			self.rows.borrow().get(&key).copied()
+		}
+	}
+
+	impl Backing<i32, &'static str> for MemoryBacking {
+		type Error = MemoryError;
+
+		fn load(&mut self, key: &i32) -> Result<Option<&'static str>, Self::Error> {
+			if self.offline.get() {
+				return Err(MemoryError::Offline);
+			}
+			Ok(self.rows.borrow().get(key).copied())
+		}
+
+		fn store(&mut self, key: &i32, value: &&'static str) -> Result<(), Self::Error> {
+			if self.offline.get() {
+				return Err(MemoryError::Offline);
+			}
+			self.stores.set(self.stores.get() + 1);
+			self.rows.borrow_mut().insert(*key, value);
+			Ok(())
+		}
+
+		fn remove(&mut self, key: &i32) -> Result<(), Self::Error> {
+			if self.offline.get() {
+				return Err(MemoryError::Offline);
+			}
+			self.rows.borrow_mut().remove(key);
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn write_through_stores_every_write_test() {
+		let backing = MemoryBacking::default();
+		let mut cache = PersistentCache::new(2, backing.clone(), WriteMode::WriteThrough);
+
+		assert_eq!(cache.write(1, "one"), Ok(()));
+		assert_eq!(backing.row(1), Some("one"));
+		assert!(!cache.is_dirty(&1));
+
+		assert_eq!(cache.write(1, "uno"), Ok(()));
+		assert_eq!(backing.row(1), Some("uno"));
+		assert_eq!(backing.stores.get(), 2);
+
+		assert_eq!(cache.write(2, "two"), Ok(()));
+		assert_eq!(cache.write(3, "three"), Ok(()));
+		assert_eq!(cache.len(), 2);
+		assert_eq!(backing.stores.get(), 4);
+		assert_eq!(backing.row(1), Some("uno"));
+	}
+
+	#[test]
+	fn write_through_failed_store_skips_cache_test() {
+		let backing = MemoryBacking::default();
+		let mut cache = PersistentCache::new(2, backing.clone(), WriteMode::WriteThrough);
+
+		backing.offline.set(true);
+		assert_eq!(cache.write(1, "one"), Err(MemoryError::Offline));
+		assert!(cache.is_empty());
+		assert_eq!(backing.row(1), None);
+	}
+
+	#[test]
+	fn write_back_stores_on_eviction_test() {
+		let backing = MemoryBacking::default();
+		let mut cache = PersistentCache::new(2, backing.clone(), WriteMode::WriteBack);
+
+		assert_eq!(cache.write(1, "one"), Ok(()));
+		assert_eq!(cache.write(2, "two"), Ok(()));
+		assert_eq!(cache.write(1, "uno"), Ok(()));
+		assert!(cache.is_dirty(&1));
+		assert!(cache.is_dirty(&2));
+		assert_eq!(backing.stores.get(), 0);
+
+		// 2 is least recently used and gets evicted
+		assert_eq!(cache.write(3, "three"), Ok(()));
+		assert_eq!(backing.stores.get(), 1);
+		assert_eq!(backing.row(2), Some("two"));
+		assert_eq!(backing.row(1), None);
+		assert_eq!(backing.row(3), None);
+	}
+
+	#[test]
+	fn write_back_clean_eviction_does_not_store_test() {
+		let backing = MemoryBacking::default();
+		backing.rows.borrow_mut().insert(1, "one");
+		let mut cache = PersistentCache::new(1, backing.clone(), WriteMode::WriteBack);
+
+		assert_eq!(cache.read(&1), Ok(Some(&"one")));
+		assert!(!cache.is_dirty(&1));
+		assert_eq!(cache.write(2, "two"), Ok(()));
+		assert_eq!(backing.stores.get(), 0);
+	}
+
+	#[test]
+	fn write_back_flush_test() {
+		let backing = MemoryBacking::default();
+		let mut cache = PersistentCache::new(3, backing.clone(), WriteMode::WriteBack);
+
+		assert_eq!(cache.write(1, "one"), Ok(()));
+		assert_eq!(cache.write(2, "two"), Ok(()));
+		assert_eq!(cache.flush(), Ok(()));
+		assert!(!cache.is_dirty(&1));
+		assert!(!cache.is_dirty(&2));
+		assert_eq!(backing.row(1), Some("one"));
+		assert_eq!(backing.row(2), Some("two"));
+		assert_eq!(backing.stores.get(), 2);
+
+		// clean entries are not stored again
+		assert_eq!(cache.flush(), Ok(()));
+		assert_eq!(backing.stores.get(), 2);
+	}
+
+	#[test]
+	fn write_back_flushes_on_drop_test() {
+		let backing = MemoryBacking::default();
+		{
+			let mut cache = PersistentCache::new(3, backing.clone(), WriteMode::WriteBack);
+			assert_eq!(cache.write(1, "one"), Ok(()));
+			assert_eq!(cache.write(2, "two"), Ok(()));
+			assert_eq!(backing.stores.get(), 0);
+		}
+		assert_eq!(backing.row(1), Some("one"));
+		assert_eq!(backing.row(2), Some("two"));
+	}
+
+	#[test]
+	fn write_back_failed_eviction_keeps_dirty_entry_test() {
+		let backing = MemoryBacking::default();
+		let mut cache = PersistentCache::new(1, backing.clone(), WriteMode::WriteBack);
+
+		assert_eq!(cache.write(1, "one"), Ok(()));
+		backing.offline.set(true);
+		assert_eq!(cache.write(2, "two"), Err(MemoryError::Offline));
+		assert_eq!(cache.len(), 1);
+		assert!(cache.is_dirty(&1));
+		assert_eq!(cache.flush(), Err(MemoryError::Offline));
+
+		backing.offline.set(false);
+		assert_eq!(cache.write(2, "two"), Ok(()));
+		assert_eq!(backing.row(1), Some("one"));
+		assert!(cache.is_dirty(&2));
+	}
+
+	#[test]
+	fn read_miss_loads_from_backing_test() {
+		let backing = MemoryBacking::default();
+		backing.rows.borrow_mut().insert(1, "one");
+		let mut cache = PersistentCache::new(2, backing.clone(), WriteMode::WriteThrough);
+
+		assert_eq!(cache.read(&1), Ok(Some(&"one")));
+		assert_eq!(cache.len(), 1);
+		assert_eq!(cache.read(&2), Ok(None));
+		assert_eq!(cache.len(), 1);
+
+		// served from the cache from now on
+		backing.offline.set(true);
+		assert_eq!(cache.read(&1), Ok(Some(&"one")));
+		assert_eq!(cache.read(&2), Err(MemoryError::Offline));
+	}
+
+	#[test]
+	fn read_miss_evicts_dirty_entry_test() {
+		let backing = MemoryBacking::default();
+		backing.rows.borrow_mut().insert(2, "two");
+		let mut cache = PersistentCache::new(1, backing.clone(), WriteMode::WriteBack);
+
+		assert_eq!(cache.write(1, "one"), Ok(()));
+		assert_eq!(cache.read(&2), Ok(Some(&"two")));
+		assert_eq!(backing.row(1), Some("one"));
+	}
+
+	#[test]
+	fn delete_test() {
+		let backing = MemoryBacking::default();
+		backing.rows.borrow_mut().insert(3, "three");
+		let mut cache = PersistentCache::new(2, backing.clone(), WriteMode::WriteBack);
+
+		assert_eq!(cache.write(1, "one"), Ok(()));
+		assert_eq!(cache.delete(&1), Ok(()));
+		assert!(cache.is_empty());
+		assert_eq!(cache.flush(), Ok(()));
+		assert_eq!(backing.row(1), None);
+
+		// only in the backing store
+		assert_eq!(cache.delete(&3), Ok(()));
+		assert_eq!(backing.row(3), None);
+
+		assert_eq!(cache.write(4, "four"), Ok(()));
+		backing.offline.set(true);
+		assert_eq!(cache.delete(&4), Err(MemoryError::Offline));
+		assert_eq!(cache.len(), 1);
+		assert_eq!(cache.mode(), WriteMode::WriteBack);
+	}
+}
@@ -0,0 +1,411 @@
+use std::collections::VecDeque;
+
+use crate::LruCache;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<K, V> {
+	Insert { key: K, value: V },
+	Update { key: K, value: V },
+	Promote { key: K },
+	Evict { key: K },
+	Delete { key: K },
+	Clear,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeEvent<K, V> {
+	pub seq: u64,
+	pub change: Change<K, V>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ApplyError {
+	/// an event was skipped or replayed, the replica has to be restored from a snapshot of the primary
+	OutOfOrder { expected: u64, found: u64 },
+	/// the event does not fit the replicas state so it no longer mirrors the primary
+	Diverged,
+}
+
+/// The entries of a cache from the least to the most recently used one, taken before the change `seq`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot<K, V> {
+	pub seq: u64,
+	pub entries: Vec<(K, V)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ChangeLog<K, V> {
+	events: VecDeque<ChangeEvent<K, V>>,
+	capacity: usize,
+	// captured at construction so only caches with a change log need `V: Clone`
+	pub(crate) clone_value: fn(&V) -> V,
+}
+
+impl<K, V> ChangeLog<K, V> {
+	pub(crate) fn push(&mut self, event: ChangeEvent<K, V>) {
+		if self.events.len() == self.capacity {
+			self.events.pop_front();
+		}
+		self.events.push_back(event);
+	}
+}
+
+impl<K, V> LruCache<K, V>
+where
+	K: Clone + Eq + std::hash::Hash,
+{
+	/// A cache that keeps the last `log_capacity` changes around for replicas to pick up
+	pub fn with_change_log(capacity: usize, log_capacity: usize) -> Self
+	where
+		V: Clone,
+	{
+		if log_capacity == 0 {
+			panic!("Log capacity must be greater than 0");
+		}
+
+		let mut cache = Self::new(capacity);
+		cache.change_log = Some(ChangeLog {
+			events: VecDeque::with_capacity(log_capacity),
+			capacity: log_capacity,
+			clone_value: V::clone,
+		});
+		cache
+	}
+
+	/// Sequence number the next change will get
+	pub fn next_seq(&self) -> u64 {
+		self.seq
+	}
+
+	pub fn changes(&self) -> impl Iterator<Item = &ChangeEvent<K, V>> {
+		self.change_log.iter().flat_map(|change_log| change_log.events.iter())
+	}
+
+	pub fn drain_changes(&mut self) -> impl Iterator<Item = ChangeEvent<K, V>> {
+		self.change_log.iter_mut().flat_map(|change_log| change_log.events.drain(..))
+	}
+
+	/// Everything a replica needs to start over after it missed changes, the ones from `seq` on apply to it
+	pub fn snapshot(&self) -> Snapshot<K, V>
+	where
+		V: Clone,
+	{
+		let mut entries = self.iter().map(|(key, value)| (key.clone(), value.clone())).collect::<Vec<_>>();
+		entries.reverse();
+		Snapshot { seq: self.seq, entries }
+	}
+
+	/// Replaces the contents of a replica with a snapshot of the primary, replicas of this one have to be
+	/// restored in turn since the changes it logged so far are dropped
+	pub fn restore(&mut self, snapshot: Snapshot<K, V>) -> Result<(), ApplyError> {
+		if snapshot.entries.len() > self.capacity {
+			return Err(ApplyError::Diverged);
+		}
+
+		// rebuilding the entries isn't a change of its own
+		let change_log = self.change_log.take();
+		self.clear();
+		for (key, value) in snapshot.entries {
+			self.write(key, value);
+		}
+		self.change_log = change_log;
+		if let Some(change_log) = self.change_log.as_mut() {
+			change_log.events.clear();
+		}
+		self.seq = snapshot.seq;
+
+		Ok(())
+	}
+
+	/// Replays a change from another cache of the same capacity, events have to arrive in sequence
+	pub fn apply(&mut self, event: ChangeEvent<K, V>) -> Result<(), ApplyError> {
+		if event.seq != self.seq {
+			return Err(ApplyError::OutOfOrder {
+				expected: self.seq,
+				found: event.seq,
+			});
+		}
+
+		match event.change {
+			Change::Insert { key, value } => {
+				// the primary always logs an eviction before inserting into a full cache
+				if self.contains(&key) || self.len == self.capacity {
+					return Err(ApplyError::Diverged);
+				}
+				self.write(key, value);
+			},
+			Change::Update { key, value } => {
+				if !self.contains(&key) {
+					return Err(ApplyError::Diverged);
+				}
+				self.write(key, value);
+			},
+			Change::Promote { key } => {
+				self.read(&key).ok_or(ApplyError::Diverged)?;
+			},
+			Change::Evict { key } => {
+				let lru_key = self.head.map(|index| &self.items[index].as_ref().expect("BUG: head node not found").key);
+				if lru_key != Some(&key) {
+					return Err(ApplyError::Diverged);
+				}
+				self.record(|_| Change::Evict { key: key.clone() });
+				self.remove(&key).map_err(|_| ApplyError::Diverged)?;
+			},
+			Change::Delete { key } => {
+				self.delete(&key).map_err(|_| ApplyError::Diverged)?;
+			},
+			Change::Clear => self.clear(),
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// xorshift so the operation sequences are random but reproducible
+	struct Rng(u64);
+
+	impl Rng {
+		fn next(&mut self, bound: u64) -> u64 {
+			self.0 ^= self.0 << 13;
+			self.0 ^= self.0 >> 7;
+			self.0 ^= self.0 << 17;
+			self.0 % bound
+		}
+	}
+
+	fn snapshot(cache: &LruCache<u64, u64>) -> Vec<(u64, u64)> {
+		cache.iter().map(|(key, value)| (*key, *value)).collect()
+	}
+
+	fn snapshot_str(cache: &LruCache<u64, &'static str>) -> Vec<(u64, &'static str)> {
+		cache.iter().map(|(key, value)| (*key, *value)).collect()
+	}
+
+	#[test]
+	fn change_log_events_test() {
+		let mut cache = LruCache::with_change_log(2, 16);
+
+		cache.write("a", 1);
+		cache.write("b", 2);
+		cache.read(&"a");
+		cache.read(&"missing");
+		cache.write("a", 10);
+		cache.write("c", 3);
+		assert_eq!(cache.delete(&"a"), Ok(()));
+		assert_eq!(cache.delete(&"a"), Err(crate::DeleteError::NotFound));
+		cache.clear();
+
+		assert_eq!(
+			cache.drain_changes().collect::<Vec<_>>(),
+			vec![
+				ChangeEvent {
+					seq: 0,
+					change: Change::Insert { key: "a", value: 1 }
+				},
+				ChangeEvent {
+					seq: 1,
+					change: Change::Insert { key: "b", value: 2 }
+				},
+				ChangeEvent {
+					seq: 2,
+					change: Change::Promote { key: "a" }
+				},
+				ChangeEvent {
+					seq: 3,
+					change: Change::Update { key: "a", value: 10 }
+				},
+				ChangeEvent {
+					seq: 4,
+					change: Change::Evict { key: "b" }
+				},
+				ChangeEvent {
+					seq: 5,
+					change: Change::Insert { key: "c", value: 3 }
+				},
+				ChangeEvent {
+					seq: 6,
+					change: Change::Delete { key: "a" }
+				},
+				ChangeEvent {
+					seq: 7,
+					change: Change::Clear
+				},
+			]
+		);
+		assert_eq!(cache.changes().count(), 0);
+		assert_eq!(cache.next_seq(), 8);
+	}
+
+	#[test]
+	fn change_log_disabled_test() {
+		let mut cache = LruCache::new(2);
+
+		cache.write(1, "one");
+		cache.write(2, "two");
+		assert_eq!(cache.changes().count(), 0);
+		assert_eq!(cache.next_seq(), 2);
+	}
+
+	#[test]
+	fn change_log_bounded_test() {
+		let mut cache = LruCache::with_change_log(3, 2);
+
+		cache.write(1, "one");
+		cache.write(2, "two");
+		cache.write(3, "three");
+		assert_eq!(cache.changes().map(|event| event.seq).collect::<Vec<_>>(), vec![1, 2]);
+
+		// the replica missed the first event and has to notice
+		let mut replica = LruCache::new(3);
+		let events = cache.drain_changes().collect::<Vec<_>>();
+		assert_eq!(replica.apply(events[0].clone()), Err(ApplyError::OutOfOrder { expected: 0, found: 1 }));
+		assert!(replica.is_empty());
+	}
+
+	#[test]
+	fn restore_after_overflow_test() {
+		let mut primary = LruCache::with_change_log(3, 3);
+		let mut replica = LruCache::with_change_log(3, 4);
+
+		for key in 1..=5 {
+			primary.write(key, key * 10);
+		}
+		primary.read(&3);
+
+		// the log only kept the last three changes so the replica can't catch up by applying them
+		let events = primary.drain_changes().collect::<Vec<_>>();
+		assert_eq!(replica.apply(events[0].clone()), Err(ApplyError::OutOfOrder { expected: 0, found: 5 }));
+
+		let taken = primary.snapshot();
+		assert_eq!(taken.seq, 8);
+		assert_eq!(taken.entries, vec![(4, 40), (5, 50), (3, 30)]);
+		replica.write(9, 90);
+		assert_eq!(replica.restore(taken), Ok(()));
+		assert_eq!(snapshot(&replica), snapshot(&primary));
+		assert_eq!(replica.next_seq(), primary.next_seq());
+		assert_eq!(replica.changes().count(), 0);
+
+		// the changes after the snapshot apply as usual, the least recently used entry is evicted first
+		primary.write(6, 60);
+		primary.read(&5);
+		for event in primary.drain_changes() {
+			assert_eq!(replica.apply(event), Ok(()));
+		}
+		assert_eq!(snapshot(&replica), vec![(5, 50), (6, 60), (3, 30)]);
+		assert_eq!(snapshot(&replica), snapshot(&primary));
+
+		let mut small = LruCache::new(2);
+		assert_eq!(small.restore(primary.snapshot()), Err(ApplyError::Diverged));
+	}
+
+	#[should_panic]
+	#[test]
+	fn zero_log_capacity_test() {
+		LruCache::<i32, i32>::with_change_log(1, 0);
+	}
+
+	#[test]
+	fn apply_diverged_test() {
+		let mut replica = LruCache::new(1);
+
+		assert_eq!(
+			replica.apply(ChangeEvent {
+				seq: 0,
+				change: Change::Promote { key: 1 }
+			}),
+			Err(ApplyError::Diverged)
+		);
+		replica.write(1, "one");
+		assert_eq!(
+			replica.apply(ChangeEvent {
+				seq: 1,
+				change: Change::Insert { key: 2, value: "two" }
+			}),
+			Err(ApplyError::Diverged)
+		);
+		assert_eq!(
+			replica.apply(ChangeEvent {
+				seq: 1,
+				change: Change::Evict { key: 2 }
+			}),
+			Err(ApplyError::Diverged)
+		);
+		assert_eq!(snapshot_str(&replica), vec![(1, "one")]);
+	}
+
+	#[test]
+	fn replica_converges_test() {
+		for seed in 1..=20u64 {
+			let mut rng = Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+			let capacity = 1 + rng.next(5) as usize;
+			let mut primary = LruCache::with_change_log(capacity, 8);
+			let mut replica = LruCache::new(capacity);
+
+			for round in 0..500 {
+				let key = rng.next(10);
+				match rng.next(20) {
+					0..=9 => primary.write(key, round),
+					10..=15 => {
+						primary.read(&key);
+					},
+					16..=18 => {
+						let _ = primary.delete(&key);
+					},
+					_ => primary.clear(),
+				}
+
+				// replicate in small batches so the bounded log never overflows
+				if round % 4 == 0 {
+					for event in primary.drain_changes() {
+						assert_eq!(replica.apply(event), Ok(()));
+					}
+					assert_eq!(snapshot(&replica), snapshot(&primary));
+				}
+			}
+
+			for event in primary.drain_changes() {
+				assert_eq!(replica.apply(event), Ok(()));
+			}
+			assert_eq!(snapshot(&replica), snapshot(&primary));
+			assert_eq!(replica.len(), primary.len());
+			assert_eq!(replica.next_seq(), primary.next_seq());
+		}
+	}
+
+	#[test]
+	fn chained_replica_test() {
+		let mut primary = LruCache::with_change_log(2, 16);
+		let mut middle = LruCache::with_change_log(2, 16);
+		let mut last = LruCache::new(2);
+
+		primary.write(1, 1);
+		primary.write(2, 2);
+		primary.read(&1);
+		primary.write(3, 3);
+
+		for event in primary.drain_changes() {
+			assert_eq!(middle.apply(event), Ok(()));
+		}
+		for event in middle.drain_changes() {
+			assert_eq!(last.apply(event), Ok(()));
+		}
+		assert_eq!(snapshot(&last), vec![(3, 3), (1, 1)]);
+	}
+
+	#[test]
+	fn iter_test() {
+		let mut cache = LruCache::new(3);
+
+		cache.write(1, 1);
+		cache.write(2, 2);
+		cache.write(3, 3);
+		cache.read(&1);
+		assert_eq!(snapshot(&cache), vec![(1, 1), (3, 3), (2, 2)]);
+		cache.clear();
+		assert_eq!(snapshot(&cache), vec![]);
+	}
+}
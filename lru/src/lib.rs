@@ -1,5 +1,13 @@
 use std::collections::HashMap;
 
+mod backing;
+mod change_log;
+
+pub use backing::{Backing, PersistentCache, WriteMode};
+pub use change_log::{ApplyError, Change, ChangeEvent, Snapshot};
+
+use change_log::ChangeLog;
+
 #[derive(Debug, PartialEq)]
 struct Node<K, V> {
 	key: K,
@@ -25,6 +33,8 @@ where
 	tail: Option<usize>,
 	len: usize,
 	capacity: usize,
+	change_log: Option<ChangeLog<K, V>>,
+	seq: u64,
 }
 
 impl<K, V> LruCache<K, V>
@@ -48,6 +58,19 @@ where
 			tail: None,
 			len: 0,
 			capacity,
+			change_log: None,
+			seq: 0,
+		}
+	}
+
+	// every mutation takes a sequence number, the change itself is only built when someone is listening
+	fn record(&mut self, change: impl FnOnce(fn(&V) -> V) -> Change<K, V>) {
+		let seq = self.seq;
+		self.seq += 1;
+
+		if let Some(change_log) = self.change_log.as_mut() {
+			let change = change(change_log.clone_value);
+			change_log.push(ChangeEvent { seq, change });
 		}
 	}
 
@@ -116,11 +139,16 @@ where
 		let tail = self.tail;
 
 		// UPDATE PATH
-		if let Some(new_tail) = self.map.get(&key) {
+		if let Some(new_tail) = self.map.get(&key).copied() {
+			self.record(|clone_value| Change::Update {
+				key: key.clone(),
+				value: clone_value(&value),
+			});
+
 			// update value
-			self.items[*new_tail].as_mut().expect("BUG: node from map not found").value = value;
+			self.items[new_tail].as_mut().expect("BUG: node from map not found").value = value;
 
-			self.move_to_tail(*new_tail);
+			self.move_to_tail(new_tail);
 		// EVICTION PATH
 		} else if self.len == self.capacity {
 			// get previous head node
@@ -134,7 +162,13 @@ where
 			let new_head_node = head_node.next;
 
 			// remove old mapping
-			self.map.remove(key_to_remove);
+			let (evicted_key, _) = self.map.remove_entry(key_to_remove).expect("BUG: head node key not in map");
+
+			self.record(|_| Change::Evict { key: evicted_key });
+			self.record(|clone_value| Change::Insert {
+				key: key.clone(),
+				value: clone_value(&value),
+			});
 
 			// overwrite new node to where the last head node was
 			// Note: We clone here assuming that if a key is used that is expensive to clone they would use Arc to make it cheaper
@@ -162,6 +196,11 @@ where
 			}
 		// INSERTION PATH
 		} else {
+			self.record(|clone_value| Change::Insert {
+				key: key.clone(),
+				value: clone_value(&value),
+			});
+
 			// add new node to items with key
 			let idx = self.free_slots.pop().expect("BUG: no free slots");
 
@@ -197,11 +236,21 @@ where
 	pub fn read(&mut self, key: &K) -> Option<&V> {
 		let index = self.map.get(key).copied()?;
 
+		self.record(|_| Change::Promote { key: key.clone() });
 		self.move_to_tail(index);
 		Some(&self.items[index].as_ref().expect("BUG: node not found").value)
 	}
 
 	pub fn delete(&mut self, key: &K) -> Result<(), DeleteError> {
+		if !self.map.contains_key(key) {
+			return Err(DeleteError::NotFound);
+		}
+
+		self.record(|_| Change::Delete { key: key.clone() });
+		self.remove(key)
+	}
+
+	fn remove(&mut self, key: &K) -> Result<(), DeleteError> {
 		let index = match self.map.get(key).copied() {
 			Some(idx) => idx,
 			None => return Err(DeleteError::NotFound),
@@ -247,6 +296,7 @@ where
 	}
 
 	pub fn clear(&mut self) {
+		self.record(|_| Change::Clear);
 		self.items.iter_mut().for_each(|slot| *slot = None);
 		self.map.clear();
 		self.free_slots.clear();
@@ -263,6 +313,34 @@ where
 	pub fn is_empty(&self) -> bool {
 		self.len == 0
 	}
+
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	pub fn contains(&self, key: &K) -> bool {
+		self.map.contains_key(key)
+	}
+
+	/// Iterates from the most recently used entry to the least recently used one without touching the order
+	pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+		std::iter::successors(self.tail, |index| self.items[*index].as_ref().expect("BUG: node not found").prev).map(
+			|index| {
+				let node = self.items[index].as_ref().expect("BUG: node not found");
+				(&node.key, &node.value)
+			},
+		)
+	}
+
+	// the entry the next insertion into a full cache would evict, without touching the order
+	fn peek_lru_mut(&mut self) -> Option<(&K, &mut V)> {
+		let node = self.items[self.head?].as_mut().expect("BUG: head node not found");
+		Some((&node.key, &mut node.value))
+	}
+
+	fn entries_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+		self.items.iter_mut().flatten().map(|node| (&node.key, &mut node.value))
+	}
 }
 
 #[cfg(test)]